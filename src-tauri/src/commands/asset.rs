@@ -0,0 +1,81 @@
+// Asset Commands
+//
+// Tauri command handlers for the vault's content-addressed asset store
+
+use crate::models::{AssetGcReport, AssetInfo, AssetUsage, AuditOperation};
+use crate::services::{AssetService, AuditService};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Add a file, or base64 data named `file_name`, to the asset store
+///
+/// Returns the existing asset if the same content was imported before.
+#[tauri::command]
+pub async fn import_asset(
+    app_handle: AppHandle,
+    vault_path: String,
+    file_path: Option<String>,
+    base64_data: Option<String>,
+    file_name: Option<String>,
+) -> Result<AssetInfo, String> {
+    let path = Path::new(&vault_path);
+
+    let asset = match (&file_path, base64_data) {
+        (Some(file_path), _) => AssetService::import_file(path, Path::new(file_path)),
+        (None, Some(data)) => {
+            let content = BASE64
+                .decode(&data)
+                .map_err(|e| format!("Failed to decode base64: {}", e))?;
+            AssetService::import_data(path, &content, file_name.as_deref().unwrap_or_default())
+        }
+        (None, None) => return Err("Either file_path or base64_data is required".to_string()),
+    }
+    .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::AssetImport,
+        json!({ "hash": asset.hash, "source": file_path.or(file_name) }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(asset)
+}
+
+/// Find the blob an `asset://<sha256>` URI refers to in the open vault
+///
+/// The frontend loads it by passing the returned path to `convertFileSrc`.
+#[tauri::command]
+pub async fn resolve_asset(vault_path: String, uri: String) -> Result<AssetInfo, String> {
+    AssetService::resolve(Path::new(&vault_path), &uri).map_err(|e| e.to_string())
+}
+
+/// List assets with how many nodes refer to each
+#[tauri::command]
+pub async fn list_assets(vault_path: String) -> Result<Vec<AssetUsage>, String> {
+    AssetService::list(Path::new(&vault_path)).map_err(|e| e.to_string())
+}
+
+/// Remove assets no node refers to
+#[tauri::command]
+pub async fn gc_assets(app_handle: AppHandle, vault_path: String) -> Result<AssetGcReport, String> {
+    let path = Path::new(&vault_path);
+
+    let report = AssetService::gc(path).map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::AssetGc,
+        json!({
+            "removed": report.removed.iter().map(|a| &a.hash).collect::<Vec<_>>(),
+            "freed_bytes": report.freed_bytes,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(report)
+}
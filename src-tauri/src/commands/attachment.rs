@@ -0,0 +1,52 @@
+// Attachment Commands
+//
+// Tauri command handlers for evidence attachments and their
+// chain-of-custody manifest
+
+use crate::models::{AttachmentManifest, AttachmentRecord, AttachmentVerification, AuditOperation};
+use crate::services::{AttachmentService, AuditService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Import a file into a canvas as evidence linked to a node
+#[tauri::command]
+pub async fn import_attachment(
+    app_handle: AppHandle,
+    canvas_path: String,
+    file_path: String,
+    node_id: String,
+) -> Result<AttachmentRecord, String> {
+    let path = Path::new(&canvas_path);
+
+    let record = AttachmentService::import(path, Path::new(&file_path), &node_id)
+        .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::AttachmentImport,
+        json!({
+            "attachment_id": record.id,
+            "node_ids": [record.node_id],
+            "path": record.path,
+            "original_path": record.original_path,
+            "sha256": record.sha256,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(record)
+}
+
+/// Get the chain-of-custody manifest of a canvas
+#[tauri::command]
+pub async fn list_attachments(canvas_path: String) -> Result<AttachmentManifest, String> {
+    AttachmentService::manifest(Path::new(&canvas_path)).map_err(|e| e.to_string())
+}
+
+/// Re-hash a canvas's attachments and flag any that changed
+#[tauri::command]
+pub async fn verify_attachments(canvas_path: String) -> Result<AttachmentVerification, String> {
+    AttachmentService::verify(Path::new(&canvas_path)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,23 @@
+// Audit Commands
+//
+// Tauri command handlers for the vault audit log
+
+use crate::models::{AuditEntry, AuditQuery, AuditVerification};
+use crate::services::AuditService;
+use std::path::Path;
+
+/// Check a vault's audit log for tampering
+#[tauri::command]
+pub async fn verify_audit_log(vault_path: String) -> Result<AuditVerification, String> {
+    AuditService::verify(Path::new(&vault_path)).map_err(|e| e.to_string())
+}
+
+/// Query a vault's audit log by canvas, operation and time range
+#[tauri::command]
+pub async fn query_audit_log(
+    vault_path: String,
+    query: Option<AuditQuery>,
+) -> Result<Vec<AuditEntry>, String> {
+    AuditService::query(Path::new(&vault_path), &query.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
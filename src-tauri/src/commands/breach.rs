@@ -0,0 +1,95 @@
+// Breach Commands
+//
+// Tauri command handlers for offline breached-password checks
+
+use crate::events::{EventEmitter, WorkspaceChangeType};
+use crate::models::{AuditOperation, BreachCheckReport, BreachDataset};
+use crate::services::{AuditService, BreachService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Get the breached-password dataset configured for a vault
+#[tauri::command]
+pub async fn get_breach_dataset(vault_path: String) -> Result<Option<BreachDataset>, String> {
+    BreachService::dataset(Path::new(&vault_path)).map_err(|e| e.to_string())
+}
+
+/// Use a local Pwned Passwords-style dataset for a vault's password checks
+#[tauri::command]
+pub async fn set_breach_dataset(
+    app_handle: AppHandle,
+    vault_path: String,
+    dataset_path: String,
+) -> Result<BreachDataset, String> {
+    let path = Path::new(&vault_path);
+
+    let dataset =
+        BreachService::set_dataset(path, Path::new(&dataset_path)).map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::BreachDatasetUpdate,
+        json!({ "path": dataset.path, "format": dataset.format }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(dataset)
+}
+
+/// Stop using a breached-password dataset for a vault
+#[tauri::command]
+pub async fn clear_breach_dataset(app_handle: AppHandle, vault_path: String) -> Result<(), String> {
+    let path = Path::new(&vault_path);
+
+    BreachService::clear_dataset(path).map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::BreachDatasetUpdate,
+        json!({ "path": null }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(())
+}
+
+/// Check credential passwords against the vault's breached-password dataset
+#[tauri::command]
+pub async fn check_breached_passwords(
+    app_handle: AppHandle,
+    canvas_path: String,
+    node_ids: Option<Vec<String>>,
+    expected_revision: Option<u64>,
+) -> Result<BreachCheckReport, String> {
+    let path = Path::new(&canvas_path);
+
+    let report = BreachService::check(path, node_ids.as_deref(), expected_revision)
+        .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::PasswordsCheck,
+        json!({
+            "revision": report.revision.as_ref().map(|r| r.revision),
+            "checked": report.checked,
+            "breached_node_ids": report.breached.iter().map(|b| &b.node_id).collect::<Vec<_>>(),
+            "updated_node_ids": report.updated_node_ids,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    if !report.updated_node_ids.is_empty() {
+        EventEmitter::new(&app_handle).nodes_changed(
+            &canvas_path,
+            WorkspaceChangeType::NodesUpdated,
+            report.updated_node_ids.clone(),
+        );
+    }
+
+    Ok(report)
+}
@@ -3,13 +3,19 @@
 // Tauri command handlers for canvas operations
 
 use crate::events::EventEmitter;
-use crate::models::{CanvasInfo, CanvasUIState};
-use crate::services::{CanvasService, HistoryService, StateService, VaultService};
+use crate::models::{AuditOperation, CanvasInfo, CanvasUIState, Tlp};
+use crate::services::{
+    AuditService, CanvasService, FolderService, HistoryService, StateService, TemplateService,
+    VaultService,
+};
+use serde_json::json;
+use std::collections::HashMap;
 use std::path::Path;
 use tauri::AppHandle;
 use tauri_plugin_fs::FsExt;
 
-/// Create a new canvas in a vault
+/// Create a new canvas in a vault, optionally from a template and inside a folder
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
 pub async fn create_canvas(
     app_handle: AppHandle,
@@ -17,12 +23,54 @@ pub async fn create_canvas(
     vault_id: String,
     name: String,
     description: Option<String>,
+    template_id: Option<String>,
+    variables: Option<HashMap<String, String>>,
+    folder: Option<String>,
 ) -> Result<CanvasInfo, String> {
-    let vault = Path::new(&vault_path);
-    let canvases_dir = vault.join("canvases");
+    create(
+        &app_handle,
+        &vault_path,
+        &vault_id,
+        &name,
+        description.as_deref(),
+        template_id.as_deref(),
+        variables.unwrap_or_default(),
+        folder.as_deref(),
+    )
+}
 
-    let canvas = CanvasService::create(&canvases_dir, &vault_id, &name, description.as_deref())
-        .map_err(|e| e.to_string())?;
+/// Create a canvas and register it like `create_canvas` does, for commands
+/// that create one before filling it
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create(
+    app_handle: &AppHandle,
+    vault_path: &str,
+    vault_id: &str,
+    name: &str,
+    description: Option<&str>,
+    template_id: Option<&str>,
+    variables: HashMap<String, String>,
+    folder: Option<&str>,
+) -> Result<CanvasInfo, String> {
+    let vault = Path::new(vault_path);
+    let canvases_dir =
+        FolderService::folder_dir(vault, folder.unwrap_or("")).map_err(|e| e.to_string())?;
+
+    let canvas = match template_id {
+        Some(template_id) => {
+            let template = TemplateService::get(vault, template_id).map_err(|e| e.to_string())?;
+            CanvasService::create_from_template(
+                &canvases_dir,
+                vault_id,
+                name,
+                description,
+                &template,
+                &variables,
+            )
+        }
+        None => CanvasService::create(&canvases_dir, vault_id, name, description),
+    }
+    .map_err(|e| e.to_string())?;
 
     // Allow canvas directory in fs scope for state persistence (recursive includes .mosaic and all subdirs)
     let canvas_path = Path::new(&canvas.path);
@@ -30,7 +78,7 @@ pub async fn create_canvas(
 
     // Track in history
     HistoryService::track_canvas(
-        &app_handle,
+        app_handle,
         canvas.id.clone(),
         canvas.vault_id.clone(),
         canvas.name.clone(),
@@ -39,11 +87,19 @@ pub async fn create_canvas(
     .map_err(|e| e.to_string())?;
 
     // Update state
-    StateService::update_last_opened(&app_handle, None, Some(canvas.id.clone()))
+    StateService::update_last_opened(app_handle, None, Some(canvas.id.clone()))
         .map_err(|e| e.to_string())?;
 
+    // Audit
+    AuditService::log_canvas(
+        &canvas,
+        AuditOperation::CanvasCreate,
+        json!({ "name": canvas.name }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(app_handle, e));
+
     // Emit event
-    let emitter = EventEmitter::new(&app_handle);
+    let emitter = EventEmitter::new(app_handle);
     emitter.canvas_created(&canvas.id, &canvas.path, &canvas.name, &canvas.vault_id);
 
     Ok(canvas)
@@ -107,6 +163,14 @@ pub async fn rename_canvas(
     )
     .map_err(|e| e.to_string())?;
 
+    // Audit
+    AuditService::log_canvas(
+        &canvas,
+        AuditOperation::CanvasRename,
+        json!({ "name": canvas.name }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     emitter.canvas_updated(&canvas.id, &canvas.path, &canvas.name, &canvas.vault_id);
@@ -130,6 +194,12 @@ pub async fn delete_canvas(app_handle: AppHandle, canvas_path: String) -> Result
         let _ = HistoryService::remove_canvas(&app_handle, id);
     }
 
+    // Audit
+    if let Some(c) = &canvas {
+        AuditService::log_canvas(c, AuditOperation::CanvasDelete, json!({ "name": c.name }))
+            .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+    }
+
     // Emit event
     if let Some(c) = canvas {
         let emitter = EventEmitter::new(&app_handle);
@@ -139,6 +209,190 @@ pub async fn delete_canvas(app_handle: AppHandle, canvas_path: String) -> Result
     Ok(())
 }
 
+/// Duplicate a canvas within its vault
+#[tauri::command]
+pub async fn duplicate_canvas(
+    app_handle: AppHandle,
+    canvas_path: String,
+    new_name: Option<String>,
+    regenerate_ids: Option<bool>,
+) -> Result<CanvasInfo, String> {
+    let canvas = CanvasService::duplicate(
+        Path::new(&canvas_path),
+        new_name.as_deref(),
+        regenerate_ids.unwrap_or(false),
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Allow new canvas directory in fs scope
+    let _ = app_handle
+        .fs_scope()
+        .allow_directory(Path::new(&canvas.path), true);
+
+    // Track in history
+    let _ = HistoryService::track_canvas(
+        &app_handle,
+        canvas.id.clone(),
+        canvas.vault_id.clone(),
+        canvas.name.clone(),
+        canvas.path.clone(),
+    );
+
+    // Audit
+    AuditService::log_canvas(
+        &canvas,
+        AuditOperation::CanvasDuplicate,
+        json!({ "source": canvas_path }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    emitter.canvas_created(&canvas.id, &canvas.path, &canvas.name, &canvas.vault_id);
+
+    Ok(canvas)
+}
+
+/// Copy a canvas into another vault
+#[tauri::command]
+pub async fn copy_canvas_to_vault(
+    app_handle: AppHandle,
+    canvas_path: String,
+    target_vault_path: String,
+    new_name: Option<String>,
+    regenerate_ids: Option<bool>,
+) -> Result<CanvasInfo, String> {
+    let target_vault = Path::new(&target_vault_path);
+    let vault = VaultService::open_migrated(target_vault).map_err(|e| e.to_string())?;
+
+    let canvas = CanvasService::copy_to_vault(
+        Path::new(&canvas_path),
+        &target_vault.join("canvases"),
+        &vault.id,
+        new_name.as_deref(),
+        regenerate_ids.unwrap_or(false),
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Allow new canvas directory in fs scope
+    let _ = app_handle
+        .fs_scope()
+        .allow_directory(Path::new(&canvas.path), true);
+
+    // Track in history
+    let _ = HistoryService::track_canvas(
+        &app_handle,
+        canvas.id.clone(),
+        canvas.vault_id.clone(),
+        canvas.name.clone(),
+        canvas.path.clone(),
+    );
+
+    // Audit
+    AuditService::log(
+        Path::new(&canvas_path),
+        AuditOperation::CanvasCopy,
+        json!({ "destination": canvas.path }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+    AuditService::log_canvas(
+        &canvas,
+        AuditOperation::CanvasCreate,
+        json!({ "source": canvas_path }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    emitter.canvas_created(&canvas.id, &canvas.path, &canvas.name, &canvas.vault_id);
+
+    Ok(canvas)
+}
+
+/// Move a canvas into another vault
+#[tauri::command]
+pub async fn move_canvas(
+    app_handle: AppHandle,
+    canvas_path: String,
+    target_vault_path: String,
+) -> Result<CanvasInfo, String> {
+    let path = Path::new(&canvas_path);
+    let target_vault = Path::new(&target_vault_path);
+
+    let source = CanvasService::open(path).map_err(|e| e.to_string())?;
+    let vault = VaultService::open_migrated(target_vault).map_err(|e| e.to_string())?;
+
+    let canvas = CanvasService::move_to_vault(path, &target_vault.join("canvases"), &vault.id)
+        .map_err(|e| e.to_string())?;
+
+    // Allow new canvas directory in fs scope
+    let _ = app_handle
+        .fs_scope()
+        .allow_directory(Path::new(&canvas.path), true);
+
+    // Update history
+    let _ = HistoryService::relocate_canvas(
+        &app_handle,
+        &canvas.id,
+        canvas.vault_id.clone(),
+        canvas.path.clone(),
+    );
+
+    // Audit
+    if canvas.path != source.path {
+        AuditService::log_canvas(
+            &source,
+            AuditOperation::CanvasMove,
+            json!({ "destination": canvas.path }),
+        )
+        .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+        if canvas.vault_id != source.vault_id {
+            AuditService::log_canvas(
+                &canvas,
+                AuditOperation::CanvasMove,
+                json!({ "source": source.path }),
+            )
+            .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+        }
+    }
+
+    // Emit events (removed from the source vault, added to the target)
+    if canvas.path != source.path {
+        let emitter = EventEmitter::new(&app_handle);
+        emitter.canvas_deleted(&source.id, &source.vault_id);
+        emitter.canvas_created(&canvas.id, &canvas.path, &canvas.name, &canvas.vault_id);
+    }
+
+    Ok(canvas)
+}
+
+/// Set or clear a canvas's TLP classification
+#[tauri::command]
+pub async fn update_canvas_classification(
+    app_handle: AppHandle,
+    canvas_path: String,
+    classification: Option<Tlp>,
+) -> Result<CanvasInfo, String> {
+    let path = Path::new(&canvas_path);
+
+    let canvas =
+        CanvasService::update_classification(path, classification).map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log_canvas(
+        &canvas,
+        AuditOperation::CanvasUpdate,
+        json!({ "classification": canvas.classification }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    emitter.canvas_updated(&canvas.id, &canvas.path, &canvas.name, &canvas.vault_id);
+
+    Ok(canvas)
+}
+
 /// Update canvas tags
 #[tauri::command]
 pub async fn update_canvas_tags(
@@ -150,6 +404,14 @@ pub async fn update_canvas_tags(
 
     let canvas = CanvasService::update_tags(path, tags).map_err(|e| e.to_string())?;
 
+    // Audit
+    AuditService::log_canvas(
+        &canvas,
+        AuditOperation::CanvasUpdate,
+        json!({ "tags": canvas.tags }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     emitter.canvas_updated(&canvas.id, &canvas.path, &canvas.name, &canvas.vault_id);
@@ -169,6 +431,14 @@ pub async fn update_canvas_description(
     let canvas =
         CanvasService::update_description(path, &description).map_err(|e| e.to_string())?;
 
+    // Audit
+    AuditService::log_canvas(
+        &canvas,
+        AuditOperation::CanvasUpdate,
+        json!({ "description": canvas.description }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     emitter.canvas_updated(&canvas.id, &canvas.path, &canvas.name, &canvas.vault_id);
@@ -0,0 +1,94 @@
+// Encryption Commands
+//
+// Tauri command handlers for passphrase-encrypted vaults
+
+use crate::events::EventEmitter;
+use crate::models::{AuditOperation, VaultConversionReport, VaultLockStatus};
+use crate::services::{AuditService, EncryptionService, VaultService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Get whether a vault is encrypted and unlocked
+#[tauri::command]
+pub async fn get_vault_lock_status(vault_path: String) -> Result<VaultLockStatus, String> {
+    Ok(EncryptionService::status(Path::new(&vault_path)))
+}
+
+/// Unlock an encrypted vault (auto-locks after `auto_lock_minutes` idle, 0 = never)
+#[tauri::command]
+pub async fn unlock_vault(
+    app_handle: AppHandle,
+    vault_path: String,
+    passphrase: String,
+    auto_lock_minutes: Option<u32>,
+) -> Result<VaultLockStatus, String> {
+    let path = Path::new(&vault_path);
+
+    let status = EncryptionService::unlock(path, &passphrase, auto_lock_minutes)
+        .map_err(|e| e.to_string())?;
+    EncryptionService::start_auto_lock(&app_handle);
+
+    // Emit event
+    if let Ok(Some(vault)) = VaultService::get_info(path) {
+        let emitter = EventEmitter::new(&app_handle);
+        emitter.vault_unlocked(&vault.id, &vault.path, &vault.name);
+    }
+
+    Ok(status)
+}
+
+/// Lock an encrypted vault, dropping its key from memory
+#[tauri::command]
+pub async fn lock_vault(
+    app_handle: AppHandle,
+    vault_path: String,
+) -> Result<VaultLockStatus, String> {
+    let path = Path::new(&vault_path);
+
+    let status = EncryptionService::lock(path);
+
+    // Emit event
+    EncryptionService::emit_locked(&app_handle, path);
+
+    Ok(status)
+}
+
+/// Encrypt (`enabled`) or decrypt an existing vault in place
+#[tauri::command]
+pub async fn set_vault_encryption(
+    app_handle: AppHandle,
+    vault_path: String,
+    passphrase: String,
+    enabled: bool,
+    auto_lock_minutes: Option<u32>,
+) -> Result<VaultConversionReport, String> {
+    let path = Path::new(&vault_path);
+
+    let report = if enabled {
+        EncryptionService::enable(path, &passphrase, auto_lock_minutes)
+    } else {
+        EncryptionService::disable(path, &passphrase)
+    }
+    .map_err(|e| e.to_string())?;
+
+    if enabled {
+        EncryptionService::start_auto_lock(&app_handle);
+    }
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::VaultEncryption,
+        json!({ "enabled": enabled }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    if let Ok(Some(vault)) = VaultService::get_info(path) {
+        let emitter = EventEmitter::new(&app_handle);
+        emitter.vault_updated(&vault.id, &vault.path, &vault.name);
+    }
+
+    Ok(report)
+}
@@ -2,8 +2,13 @@
 //
 // Tauri command handlers for export operations
 
+use crate::models::{AuditOperation, CanvasExport, ExportOptions};
+use crate::services::{AuditService, ExportService};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde_json::json;
 use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
 
 /// Save PNG image from base64 data
 #[tauri::command]
@@ -23,14 +28,22 @@ pub async fn save_png(file_path: String, base64_data: String) -> Result<bool, St
 /// 
 /// This renders HTML/CSS/foreignObject correctly by using a real browser engine.
 /// Scale factor controls the device pixel ratio for high-DPI output.
+/// The canvas's classification limit and redaction rules are applied to the
+/// SVG text, and the export is recorded in its vault's audit log.
 #[tauri::command]
 pub async fn svg_to_png_headless(
+    app_handle: AppHandle,
     svg_content: String,
     file_path: String,
     scale: f32,
+    canvas_path: String,
+    options: Option<ExportOptions>,
 ) -> Result<bool, String> {
     use headless_chrome::{Browser, LaunchOptions};
     use headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption;
+
+    let options = options.unwrap_or_default();
+    let svg_content = prepare_svg(svg_content, &canvas_path, &options, false)?;
     
     // Extract dimensions from SVG
     let width: u32 = extract_svg_dimension(&svg_content, "width").unwrap_or(1920);
@@ -111,9 +124,39 @@ pub async fn svg_to_png_headless(
     fs::write(&file_path, &screenshot_data)
         .map_err(|e| format!("Failed to write PNG file: {}", e))?;
     
+    // Audit
+    log_image_export(&app_handle, &canvas_path, &file_path, &options);
+
     Ok(true)
 }
 
+/// Helper to apply export options (classification, redaction) to SVG before
+/// rendering, optionally stamping the classification banner
+fn prepare_svg(
+    svg_content: String,
+    canvas_path: &str,
+    options: &ExportOptions,
+    stamp: bool,
+) -> Result<String, String> {
+    ExportService::prepare_svg(Path::new(canvas_path), svg_content, options, stamp)
+        .map_err(|e| e.to_string())
+}
+
+/// Helper to record an image export of a canvas in its vault's audit log
+fn log_image_export(
+    app_handle: &AppHandle,
+    canvas_path: &str,
+    file_path: &str,
+    options: &ExportOptions,
+) {
+    AuditService::log(
+        Path::new(canvas_path),
+        AuditOperation::Export,
+        json!({ "format": "png", "destination": file_path, "options": options }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(app_handle, e));
+}
+
 /// Helper to extract width/height from SVG attributes
 fn extract_svg_dimension(svg: &str, attr: &str) -> Option<u32> {
     let pattern = format!(r#"{}="([^"]+)""#, attr);
@@ -130,15 +173,23 @@ fn extract_svg_dimension(svg: &str, attr: &str) -> Option<u32> {
 /// This uses resvg for high-quality rendering without browser canvas limits.
 /// Generates pure SVG (no foreignObject/HTML) from the graph model data,
 /// which can be rendered perfectly by resvg at any scale.
+/// Export options are applied as in `svg_to_png_headless`, and the highest
+/// TLP classification is stamped as a banner.
 #[tauri::command]
 pub async fn svg_to_png(
+    app_handle: AppHandle,
     svg_content: String,
     file_path: String,
     scale: f32,
+    canvas_path: String,
+    options: Option<ExportOptions>,
 ) -> Result<bool, String> {
     use resvg::tiny_skia::Pixmap;
     use resvg::usvg::{Options, Transform, Tree, fontdb};
 
+    let options = options.unwrap_or_default();
+    let svg_content = prepare_svg(svg_content, &canvas_path, &options, true)?;
+
     // Set up font database with system fonts
     let mut fontdb = fontdb::Database::new();
     fontdb.load_system_fonts();
@@ -149,12 +200,12 @@ pub async fn svg_to_png(
     fontdb.set_monospace_family("Courier New");
 
     // Parse SVG with font database
-    let options = Options {
+    let render_options = Options {
         fontdb: std::sync::Arc::new(fontdb),
         ..Default::default()
     };
     
-    let tree = Tree::from_str(&svg_content, &options)
+    let tree = Tree::from_str(&svg_content, &render_options)
         .map_err(|e| format!("Failed to parse SVG: {}", e))?;
 
     // Get original size
@@ -204,5 +255,46 @@ pub async fn svg_to_png(
     fs::write(&file_path, &png_data)
         .map_err(|e| format!("Failed to write PNG file: {}", e))?;
 
+    // Audit
+    log_image_export(&app_handle, &canvas_path, &file_path, &options);
+
     Ok(true)
 }
+
+/// Export a canvas's metadata and workspace as JSON
+///
+/// Sensitive fields stay encrypted unless `options.reveal_secrets` is set;
+/// `options.redaction_profile` is applied before writing.
+#[tauri::command]
+pub async fn export_canvas_json(
+    app_handle: AppHandle,
+    canvas_path: String,
+    file_path: String,
+    options: Option<ExportOptions>,
+) -> Result<CanvasExport, String> {
+    let path = Path::new(&canvas_path);
+    let options = options.unwrap_or_default();
+
+    let export = ExportService::export_json(path, Path::new(&file_path), &options)
+        .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::Export,
+        json!({ "format": "json", "destination": file_path, "options": options }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(export)
+}
+
+/// Canvas data for rendering an image export, with secrets and redaction applied
+#[tauri::command]
+pub async fn prepare_canvas_export(
+    canvas_path: String,
+    options: Option<ExportOptions>,
+) -> Result<CanvasExport, String> {
+    ExportService::build(Path::new(&canvas_path), &options.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
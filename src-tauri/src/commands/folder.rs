@@ -0,0 +1,198 @@
+// Folder Commands
+//
+// Tauri command handlers for the canvas folder hierarchy
+
+use crate::events::EventEmitter;
+use crate::models::{AuditOperation, CanvasFolder, CanvasInfo};
+use crate::services::{AuditService, FolderService, HistoryService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+use tauri_plugin_fs::FsExt;
+
+/// Get the folder tree of a vault
+#[tauri::command]
+pub async fn get_canvas_folder_tree(vault_path: String) -> Result<CanvasFolder, String> {
+    FolderService::tree(Path::new(&vault_path)).map_err(|e| e.to_string())
+}
+
+/// Create a canvas folder ("" parent for the top level)
+#[tauri::command]
+pub async fn create_canvas_folder(
+    app_handle: AppHandle,
+    vault_path: String,
+    parent: String,
+    name: String,
+) -> Result<CanvasFolder, String> {
+    let folder =
+        FolderService::create(Path::new(&vault_path), &parent, &name).map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        Path::new(&vault_path),
+        AuditOperation::FolderCreate,
+        json!({ "folder": folder.path }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    emitter.folder_created(&vault_path, &folder.path);
+
+    Ok(folder)
+}
+
+/// Rename a canvas folder
+#[tauri::command]
+pub async fn rename_canvas_folder(
+    app_handle: AppHandle,
+    vault_path: String,
+    folder: String,
+    new_name: String,
+) -> Result<CanvasFolder, String> {
+    let renamed = FolderService::rename(Path::new(&vault_path), &folder, &new_name)
+        .map_err(|e| e.to_string())?;
+
+    // Canvases inside moved along with the folder
+    relocate_history(&app_handle, &vault_path, &renamed.path);
+
+    // Audit
+    AuditService::log(
+        Path::new(&vault_path),
+        AuditOperation::FolderRename,
+        json!({ "folder": folder, "destination": renamed.path }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    emitter.folder_updated(&vault_path, &renamed.path, &folder);
+
+    Ok(renamed)
+}
+
+/// Move a canvas folder under another folder ("" for the top level)
+#[tauri::command]
+pub async fn move_canvas_folder(
+    app_handle: AppHandle,
+    vault_path: String,
+    folder: String,
+    new_parent: String,
+) -> Result<CanvasFolder, String> {
+    let moved = FolderService::move_folder(Path::new(&vault_path), &folder, &new_parent)
+        .map_err(|e| e.to_string())?;
+
+    // Canvases inside moved along with the folder
+    relocate_history(&app_handle, &vault_path, &moved.path);
+
+    // Audit
+    AuditService::log(
+        Path::new(&vault_path),
+        AuditOperation::FolderMove,
+        json!({ "folder": folder, "destination": moved.path }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    emitter.folder_updated(&vault_path, &moved.path, &folder);
+
+    Ok(moved)
+}
+
+/// Delete a canvas folder (non-empty folders require `recursive`)
+#[tauri::command]
+pub async fn delete_canvas_folder(
+    app_handle: AppHandle,
+    vault_path: String,
+    folder: String,
+    recursive: Option<bool>,
+) -> Result<(), String> {
+    let canvas_ids =
+        FolderService::delete(Path::new(&vault_path), &folder, recursive.unwrap_or(false))
+            .map_err(|e| e.to_string())?;
+
+    // Remove deleted canvases from history
+    for id in &canvas_ids {
+        let _ = HistoryService::remove_canvas(&app_handle, id);
+    }
+
+    // Audit
+    AuditService::log(
+        Path::new(&vault_path),
+        AuditOperation::FolderDelete,
+        json!({ "folder": folder, "canvas_ids": canvas_ids }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    emitter.folder_deleted(&vault_path, &folder);
+
+    Ok(())
+}
+
+/// List canvases in a folder ("" for the top level)
+#[tauri::command]
+pub async fn list_canvases_in_folder(
+    vault_path: String,
+    folder: String,
+    recursive: Option<bool>,
+) -> Result<Vec<CanvasInfo>, String> {
+    FolderService::list_canvases(Path::new(&vault_path), &folder, recursive.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+/// Move a canvas into a folder of its vault ("" for the top level)
+#[tauri::command]
+pub async fn move_canvas_to_folder(
+    app_handle: AppHandle,
+    vault_path: String,
+    canvas_path: String,
+    folder: String,
+) -> Result<CanvasInfo, String> {
+    let canvas =
+        FolderService::move_canvas(Path::new(&vault_path), Path::new(&canvas_path), &folder)
+            .map_err(|e| e.to_string())?;
+
+    // Allow new canvas directory in fs scope
+    let _ = app_handle
+        .fs_scope()
+        .allow_directory(Path::new(&canvas.path), true);
+
+    // Update history
+    let _ = HistoryService::relocate_canvas(
+        &app_handle,
+        &canvas.id,
+        canvas.vault_id.clone(),
+        canvas.path.clone(),
+    );
+
+    // Audit
+    AuditService::log_canvas(
+        &canvas,
+        AuditOperation::CanvasMove,
+        json!({ "source": canvas_path, "folder": folder }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    emitter.canvas_updated(&canvas.id, &canvas.path, &canvas.name, &canvas.vault_id);
+
+    Ok(canvas)
+}
+
+/// Point history entries at the new paths of canvases under a moved folder
+fn relocate_history(app_handle: &AppHandle, vault_path: &str, folder: &str) {
+    if let Ok(canvases) = FolderService::list_canvases(Path::new(vault_path), folder, true) {
+        for canvas in canvases {
+            let _ = HistoryService::relocate_canvas(
+                app_handle,
+                &canvas.id,
+                canvas.vault_id.clone(),
+                canvas.path.clone(),
+            );
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// GeoIP Commands
+//
+// Tauri command handlers for offline IP geolocation and ASN enrichment
+
+use crate::events::{EventEmitter, WorkspaceChangeType};
+use crate::models::{AuditOperation, GeoIpConfig, GeoIpEnrichment, GeoIpInfo};
+use crate::services::{AuditService, GeoIpService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Get the GeoIP databases configured for a vault
+#[tauri::command]
+pub async fn get_geoip_databases(vault_path: String) -> Result<GeoIpConfig, String> {
+    GeoIpService::config(Path::new(&vault_path)).map_err(|e| e.to_string())
+}
+
+/// Set the City and ASN .mmdb databases used for a vault's lookups
+#[tauri::command]
+pub async fn set_geoip_databases(
+    app_handle: AppHandle,
+    vault_path: String,
+    city_db: Option<String>,
+    asn_db: Option<String>,
+) -> Result<GeoIpConfig, String> {
+    let path = Path::new(&vault_path);
+
+    let config = GeoIpService::set_databases(
+        path,
+        city_db.as_deref().map(Path::new),
+        asn_db.as_deref().map(Path::new),
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::GeoIpDatabasesUpdate,
+        json!({ "city_db": config.city_db, "asn_db": config.asn_db }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(config)
+}
+
+/// Look an IP address up in a vault's GeoIP databases
+#[tauri::command]
+pub async fn lookup_ip(vault_path: String, ip: String) -> Result<GeoIpInfo, String> {
+    GeoIpService::lookup(Path::new(&vault_path), &ip).map_err(|e| e.to_string())
+}
+
+/// Annotate router and domain nodes with location and network owner
+#[tauri::command]
+pub async fn enrich_ip_nodes(
+    app_handle: AppHandle,
+    canvas_path: String,
+    node_ids: Option<Vec<String>>,
+    create_nodes: Option<bool>,
+    expected_revision: Option<u64>,
+) -> Result<GeoIpEnrichment, String> {
+    let path = Path::new(&canvas_path);
+
+    let enrichment = GeoIpService::enrich(
+        path,
+        node_ids.as_deref(),
+        create_nodes.unwrap_or(false),
+        expected_revision,
+    )
+    .map_err(|e| e.to_string())?;
+    let node_ids: Vec<String> = enrichment.nodes.iter().map(|n| n.id.clone()).collect();
+    let edge_ids: Vec<String> = enrichment.edges.iter().map(|e| e.id.clone()).collect();
+
+    let Some(revision) = &enrichment.revision else {
+        return Ok(enrichment);
+    };
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::GeoIpEnrich,
+        json!({
+            "revision": revision.revision,
+            "updated_node_ids": enrichment.updated_node_ids,
+            "added_node_ids": node_ids,
+            "added_edge_ids": edge_ids,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    if !node_ids.is_empty() {
+        emitter.nodes_changed(&canvas_path, WorkspaceChangeType::NodesAdded, node_ids);
+    }
+    if !enrichment.updated_node_ids.is_empty() {
+        emitter.nodes_changed(
+            &canvas_path,
+            WorkspaceChangeType::NodesUpdated,
+            enrichment.updated_node_ids.clone(),
+        );
+    }
+    if !edge_ids.is_empty() {
+        emitter.edges_changed(&canvas_path, WorkspaceChangeType::EdgesAdded, edge_ids);
+    }
+
+    Ok(enrichment)
+}
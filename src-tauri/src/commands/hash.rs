@@ -0,0 +1,191 @@
+// Hash Commands
+//
+// Tauri command handlers for attachment hashing, hash identification and
+// local hash sets
+
+use crate::events::{EventEmitter, WorkspaceChangeType};
+use crate::models::{
+    AuditOperation, FileHashes, HashAlgorithm, HashIdentification, HashNodes, HashSetInfo,
+    HashSetKind, HashSetMatch, HashSetReport,
+};
+use crate::services::{AuditService, HashService, HashSetService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// MD5, SHA-1, SHA-256 and SHA-512 of an attachment
+#[tauri::command]
+pub async fn compute_attachment_hashes(
+    canvas_path: String,
+    attachment_id: String,
+) -> Result<FileHashes, String> {
+    HashService::compute_attachment(Path::new(&canvas_path), &attachment_id)
+        .map(|(_, hashes)| hashes)
+        .map_err(|e| e.to_string())
+}
+
+/// Create or update hash nodes for an attachment's digests
+#[tauri::command]
+pub async fn create_hash_nodes(
+    app_handle: AppHandle,
+    canvas_path: String,
+    attachment_id: String,
+    algorithms: Option<Vec<HashAlgorithm>>,
+    expected_revision: Option<u64>,
+) -> Result<HashNodes, String> {
+    let path = Path::new(&canvas_path);
+
+    let result = HashService::hash_nodes(
+        path,
+        &attachment_id,
+        algorithms.as_deref(),
+        expected_revision,
+    )
+    .map_err(|e| e.to_string())?;
+    if result.nodes.is_empty() && result.edges.is_empty() && result.updated_node_ids.is_empty() {
+        return Ok(result);
+    }
+    let node_ids: Vec<String> = result.nodes.iter().map(|n| n.id.clone()).collect();
+    let edge_ids: Vec<String> = result.edges.iter().map(|e| e.id.clone()).collect();
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::HashNodesCreate,
+        json!({
+            "revision": result.revision.revision,
+            "attachment_id": attachment_id,
+            "added_node_ids": node_ids,
+            "updated_node_ids": result.updated_node_ids,
+            "added_edge_ids": edge_ids,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    if !node_ids.is_empty() {
+        emitter.nodes_changed(&canvas_path, WorkspaceChangeType::NodesAdded, node_ids);
+    }
+    if !result.updated_node_ids.is_empty() {
+        emitter.nodes_changed(
+            &canvas_path,
+            WorkspaceChangeType::NodesUpdated,
+            result.updated_node_ids.clone(),
+        );
+    }
+    if !edge_ids.is_empty() {
+        emitter.edges_changed(&canvas_path, WorkspaceChangeType::EdgesAdded, edge_ids);
+    }
+
+    Ok(result)
+}
+
+/// Guess the algorithm of a pasted hash value
+#[tauri::command]
+pub async fn identify_hash(value: String) -> Result<HashIdentification, String> {
+    Ok(HashService::identify(&value))
+}
+
+/// Import a hash list (plain text or NSRL-style CSV) into a vault
+#[tauri::command]
+pub async fn import_hash_set(
+    app_handle: AppHandle,
+    vault_path: String,
+    file_path: String,
+    name: Option<String>,
+    kind: HashSetKind,
+) -> Result<HashSetInfo, String> {
+    let path = Path::new(&vault_path);
+
+    let info = HashSetService::import(path, Path::new(&file_path), name.as_deref(), kind)
+        .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::HashSetImport,
+        json!({
+            "hash_set_id": info.id,
+            "name": info.name,
+            "kind": info.kind,
+            "source": info.source,
+            "count": info.count,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(info)
+}
+
+/// List hash sets in a vault
+#[tauri::command]
+pub async fn list_hash_sets(vault_path: String) -> Result<Vec<HashSetInfo>, String> {
+    HashSetService::list(Path::new(&vault_path)).map_err(|e| e.to_string())
+}
+
+/// Delete a hash set
+#[tauri::command]
+pub async fn delete_hash_set(
+    app_handle: AppHandle,
+    vault_path: String,
+    hash_set_id: String,
+) -> Result<(), String> {
+    let path = Path::new(&vault_path);
+
+    let info = HashSetService::delete(path, &hash_set_id).map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::HashSetDelete,
+        json!({ "hash_set_id": hash_set_id, "name": info.name }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(())
+}
+
+/// Look hashes up in a vault's hash sets
+#[tauri::command]
+pub async fn check_hashes(
+    vault_path: String,
+    hashes: Vec<String>,
+) -> Result<Vec<HashSetMatch>, String> {
+    HashSetService::lookup(Path::new(&vault_path), &hashes).map_err(|e| e.to_string())
+}
+
+/// Fill in the threat level of a canvas's hash nodes from the vault's hash sets
+#[tauri::command]
+pub async fn apply_hash_sets(
+    app_handle: AppHandle,
+    canvas_path: String,
+    expected_revision: Option<u64>,
+) -> Result<HashSetReport, String> {
+    let path = Path::new(&canvas_path);
+
+    let report = HashSetService::apply(path, expected_revision).map_err(|e| e.to_string())?;
+
+    if let Some(revision) = &report.revision {
+        // Audit
+        AuditService::log(
+            path,
+            AuditOperation::HashSetsApply,
+            json!({
+                "revision": revision.revision,
+                "checked": report.checked,
+                "updated_node_ids": report.updated_node_ids,
+            }),
+        )
+        .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+        // Emit event
+        EventEmitter::new(&app_handle).nodes_changed(
+            &canvas_path,
+            WorkspaceChangeType::NodesUpdated,
+            report.updated_node_ids.clone(),
+        );
+    }
+
+    Ok(report)
+}
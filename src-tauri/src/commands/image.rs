@@ -0,0 +1,109 @@
+// Image Commands
+//
+// Tauri command handlers for image import, downscaled variants and
+// perceptual similarity search
+
+use crate::events::{EventEmitter, WorkspaceChangeType};
+use crate::models::{
+    AuditOperation, ImageHashIndex, ImageImport, ImageVariantReport, PerceptualHashKind,
+    SimilarImageMatch,
+};
+use crate::services::{image_hash_service, AuditService, ImageHashService, ImageService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Import an image into a node, generating its thumbnail and preview
+#[tauri::command]
+pub async fn import_image(
+    app_handle: AppHandle,
+    canvas_path: String,
+    file_path: String,
+    node_id: String,
+    expected_revision: Option<u64>,
+) -> Result<ImageImport, String> {
+    let path = Path::new(&canvas_path);
+
+    let import = ImageService::import(path, Path::new(&file_path), &node_id, expected_revision)
+        .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::AttachmentImport,
+        json!({
+            "attachment_id": import.attachment.id,
+            "node_ids": [node_id],
+            "path": import.attachment.path,
+            "original_path": import.attachment.original_path,
+            "sha256": import.attachment.sha256,
+            "revision": import.revision.revision,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    emitter.nodes_changed(
+        &canvas_path,
+        WorkspaceChangeType::NodesUpdated,
+        vec![node_id],
+    );
+
+    Ok(import)
+}
+
+/// Regenerate thumbnails and previews for every image in a vault
+#[tauri::command]
+pub async fn regenerate_image_variants(
+    app_handle: AppHandle,
+    vault_path: String,
+) -> Result<ImageVariantReport, String> {
+    let path = Path::new(&vault_path);
+
+    let report = ImageService::regenerate(path).map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::ImageVariantsRegenerate,
+        json!({
+            "processed": report.processed,
+            "updated_nodes": report.updated_nodes,
+            "failed": report.failed.len(),
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(report)
+}
+
+/// Refresh the perceptual hash index of every image referenced in a vault
+#[tauri::command]
+pub async fn index_image_hashes(vault_path: String) -> Result<ImageHashIndex, String> {
+    ImageHashService::index(Path::new(&vault_path)).map_err(|e| e.to_string())
+}
+
+/// Find images in other canvases that look like images in the vault
+///
+/// `max_distance` is the number of differing hash bits (out of 64) still
+/// counted as a match. The saved index is searched unless `refresh` is set.
+#[tauri::command]
+pub async fn find_similar_images(
+    vault_path: String,
+    canvas_path: Option<String>,
+    node_id: Option<String>,
+    max_distance: Option<u32>,
+    algorithm: Option<PerceptualHashKind>,
+    refresh: Option<bool>,
+) -> Result<Vec<SimilarImageMatch>, String> {
+    ImageHashService::find_similar(
+        Path::new(&vault_path),
+        canvas_path.as_deref().map(Path::new),
+        node_id.as_deref(),
+        max_distance.unwrap_or(image_hash_service::DEFAULT_MAX_DISTANCE),
+        algorithm.unwrap_or_default(),
+        refresh.unwrap_or(false),
+    )
+    .map_err(|e| e.to_string())
+}
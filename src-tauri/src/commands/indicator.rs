@@ -0,0 +1,95 @@
+// Indicator Commands
+//
+// Tauri command handlers for indicator-of-compromise extraction and
+// normalization
+
+use crate::core::normalize;
+use crate::events::{EventEmitter, WorkspaceChangeType};
+use crate::models::{AuditOperation, Indicator, IndicatorKind, IndicatorNodes};
+use crate::services::{AuditService, IndicatorService, NormalizeService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Find indicators in pasted text
+#[tauri::command]
+pub async fn extract_indicators(text: String) -> Result<Vec<Indicator>, String> {
+    Ok(IndicatorService::extract(&text))
+}
+
+/// Find indicators in the text of a note or code node
+#[tauri::command]
+pub async fn extract_node_indicators(
+    canvas_path: String,
+    node_id: String,
+) -> Result<Vec<Indicator>, String> {
+    IndicatorService::extract_from_node(Path::new(&canvas_path), &node_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Add domain, hash, credential and link nodes for the indicators in a node
+#[tauri::command]
+pub async fn create_indicator_nodes(
+    app_handle: AppHandle,
+    canvas_path: String,
+    node_id: String,
+    kinds: Option<Vec<IndicatorKind>>,
+    expected_revision: Option<u64>,
+) -> Result<IndicatorNodes, String> {
+    let path = Path::new(&canvas_path);
+
+    let created =
+        IndicatorService::materialize(path, &node_id, kinds.as_deref(), expected_revision)
+            .map_err(|e| e.to_string())?;
+    if created.edges.is_empty() {
+        return Ok(created);
+    }
+    let node_ids: Vec<String> = created.nodes.iter().map(|n| n.id.clone()).collect();
+    let edge_ids: Vec<String> = created.edges.iter().map(|e| e.id.clone()).collect();
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::IndicatorsMaterialize,
+        json!({
+            "revision": created.revision.revision,
+            "source_node_id": node_id,
+            "indicators": created.indicators.len(),
+            "added_node_ids": node_ids,
+            "added_edge_ids": edge_ids,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    if !node_ids.is_empty() {
+        emitter.nodes_changed(&canvas_path, WorkspaceChangeType::NodesAdded, node_ids);
+    }
+    if !edge_ids.is_empty() {
+        emitter.edges_changed(&canvas_path, WorkspaceChangeType::EdgesAdded, edge_ids);
+    }
+
+    Ok(created)
+}
+
+/// Canonical form of an indicator, or `None` if the value is not valid
+#[tauri::command]
+pub async fn normalize_indicator(
+    kind: IndicatorKind,
+    value: String,
+) -> Result<Option<String>, String> {
+    Ok(NormalizeService::normalize(kind, &value))
+}
+
+/// Defang an indicator for safe sharing ("hxxps://evil[.]com")
+#[tauri::command]
+pub async fn defang_indicator(value: String) -> Result<String, String> {
+    Ok(normalize::defang(&value))
+}
+
+/// Undo defanging in a text ("hxxps://evil[.]com" becomes "https://evil.com")
+#[tauri::command]
+pub async fn refang_text(text: String) -> Result<String, String> {
+    Ok(normalize::refang(&text))
+}
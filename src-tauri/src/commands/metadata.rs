@@ -0,0 +1,60 @@
+// Metadata Commands
+//
+// Tauri command handlers for attachment metadata extraction
+
+use crate::events::{EventEmitter, WorkspaceChangeType};
+use crate::models::{AuditOperation, FileMetadata, MetadataFindings};
+use crate::services::{AuditService, MetadataService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Read EXIF, XMP, PDF or Office metadata from an attachment
+#[tauri::command]
+pub async fn extract_metadata(
+    canvas_path: String,
+    attachment_id: String,
+) -> Result<FileMetadata, String> {
+    MetadataService::extract_attachment(Path::new(&canvas_path), &attachment_id)
+        .map(|(_, metadata)| metadata)
+        .map_err(|e| e.to_string())
+}
+
+/// Add map and person nodes for an attachment's GPS position and authors
+#[tauri::command]
+pub async fn materialize_metadata(
+    app_handle: AppHandle,
+    canvas_path: String,
+    attachment_id: String,
+    expected_revision: Option<u64>,
+) -> Result<MetadataFindings, String> {
+    let path = Path::new(&canvas_path);
+
+    let findings = MetadataService::materialize(path, &attachment_id, expected_revision)
+        .map_err(|e| e.to_string())?;
+    if findings.nodes.is_empty() {
+        return Ok(findings);
+    }
+    let node_ids: Vec<String> = findings.nodes.iter().map(|n| n.id.clone()).collect();
+    let edge_ids: Vec<String> = findings.edges.iter().map(|e| e.id.clone()).collect();
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::MetadataMaterialize,
+        json!({
+            "revision": findings.revision.revision,
+            "attachment_id": attachment_id,
+            "added_node_ids": node_ids,
+            "added_edge_ids": edge_ids,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    emitter.nodes_changed(&canvas_path, WorkspaceChangeType::NodesAdded, node_ids);
+    emitter.edges_changed(&canvas_path, WorkspaceChangeType::EdgesAdded, edge_ids);
+
+    Ok(findings)
+}
@@ -0,0 +1,134 @@
+// Migration Commands
+//
+// Tauri command handlers for schema migrations
+
+use crate::events::EventEmitter;
+use crate::models::{AuditOperation, MigrationReport, VaultMigrationReport};
+use crate::services::{AuditService, CanvasService, MigrationService, VaultService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Migrate vault.json to the latest schema (or report pending steps with `dry_run`)
+#[tauri::command]
+pub async fn migrate_vault(
+    app_handle: AppHandle,
+    vault_path: String,
+    dry_run: Option<bool>,
+) -> Result<MigrationReport, String> {
+    let path = Path::new(&vault_path);
+    let dry_run = dry_run.unwrap_or(false);
+
+    let report = MigrationService::run_vault(path, dry_run).map_err(|e| e.to_string())?;
+
+    // Audit
+    if !dry_run && report.has_changes() {
+        AuditService::log(
+            path,
+            AuditOperation::VaultMigrate,
+            json!({ "backup_path": report.backup_path }),
+        )
+        .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+    }
+
+    // Emit event
+    if !dry_run && report.has_changes() {
+        if let Ok(vault) = VaultService::open(path) {
+            let emitter = EventEmitter::new(&app_handle);
+            emitter.vault_updated(&vault.id, &vault.path, &vault.name);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Migrate a canvas's files to the latest schema (or report pending steps with `dry_run`)
+#[tauri::command]
+pub async fn migrate_canvas(
+    app_handle: AppHandle,
+    canvas_path: String,
+    dry_run: Option<bool>,
+) -> Result<MigrationReport, String> {
+    let path = Path::new(&canvas_path);
+    let dry_run = dry_run.unwrap_or(false);
+
+    let report = MigrationService::run_canvas(path, dry_run).map_err(|e| e.to_string())?;
+
+    // Audit
+    if !dry_run && report.has_changes() {
+        AuditService::log(
+            path,
+            AuditOperation::CanvasMigrate,
+            json!({ "backup_path": report.backup_path }),
+        )
+        .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+    }
+
+    // Emit event
+    if !dry_run && report.has_changes() {
+        if let Ok(canvas) = CanvasService::open(path) {
+            let emitter = EventEmitter::new(&app_handle);
+            emitter.canvas_updated(&canvas.id, &canvas.path, &canvas.name, &canvas.vault_id);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Migrate vault.json and every canvas in the vault, reporting per canvas
+#[tauri::command]
+pub async fn migrate_vault_all(
+    app_handle: AppHandle,
+    vault_path: String,
+    dry_run: Option<bool>,
+) -> Result<VaultMigrationReport, String> {
+    let path = Path::new(&vault_path);
+    let dry_run = dry_run.unwrap_or(false);
+
+    let report = MigrationService::run_vault_all(path, dry_run).map_err(|e| e.to_string())?;
+
+    // Audit
+    if !dry_run {
+        for result in &report.canvases {
+            if let Some(canvas_report) = result.report.as_ref().filter(|r| r.has_changes()) {
+                AuditService::log(
+                    Path::new(&result.canvas_path),
+                    AuditOperation::CanvasMigrate,
+                    json!({ "backup_path": canvas_report.backup_path }),
+                )
+                .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+            }
+        }
+        if report.vault.has_changes() {
+            AuditService::log(
+                path,
+                AuditOperation::VaultMigrate,
+                json!({ "backup_path": report.vault.backup_path }),
+            )
+            .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+        }
+    }
+
+    // Emit events
+    if !dry_run {
+        let emitter = EventEmitter::new(&app_handle);
+
+        if report.vault.has_changes() {
+            if let Ok(vault) = VaultService::open(path) {
+                emitter.vault_updated(&vault.id, &vault.path, &vault.name);
+            }
+        }
+
+        let migrated = report
+            .canvases
+            .iter()
+            .filter(|c| c.report.as_ref().is_some_and(|r| r.has_changes()));
+        for result in migrated {
+            if let Ok(canvas) = CanvasService::open(Path::new(&result.canvas_path)) {
+                emitter.canvas_updated(&canvas.id, &canvas.path, &canvas.name, &canvas.vault_id);
+            }
+        }
+    }
+
+    Ok(report)
+}
@@ -3,19 +3,55 @@
 // Tauri command handlers - thin wrappers around services
 // These are the entry points from the frontend
 
+pub mod asset;
+pub mod attachment;
+pub mod audit;
+pub mod breach;
 pub mod canvas;
 pub mod config;
+pub mod encryption;
 pub mod export;
+pub mod folder;
+pub mod geoip;
+pub mod hash;
 pub mod history;
+pub mod image;
+pub mod indicator;
+pub mod metadata;
+pub mod migration;
+pub mod package;
+pub mod redaction;
+pub mod scan;
+pub mod secret;
 pub mod state;
+pub mod template;
+pub mod transform;
 pub mod vault;
 pub mod workspace;
 
 // Re-export all commands for easy registration
+pub use asset::*;
+pub use attachment::*;
+pub use audit::*;
+pub use breach::*;
 pub use canvas::*;
 pub use config::*;
+pub use encryption::*;
 pub use export::*;
+pub use folder::*;
+pub use geoip::*;
+pub use hash::*;
 pub use history::*;
+pub use image::*;
+pub use indicator::*;
+pub use metadata::*;
+pub use migration::*;
+pub use package::*;
+pub use redaction::*;
+pub use scan::*;
+pub use secret::*;
 pub use state::*;
+pub use template::*;
+pub use transform::*;
 pub use vault::*;
 pub use workspace::*;
@@ -0,0 +1,59 @@
+// Package Commands
+//
+// Tauri command handlers for signed evidence packages
+
+use crate::core::get_data_dir;
+use crate::models::{
+    AuditOperation, ExportOptions, PackageManifest, PackageVerification, SigningIdentity,
+};
+use crate::services::{AuditService, PackageService};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Get the public key packages are signed with, to share with recipients
+#[tauri::command]
+pub async fn get_signing_identity(app_handle: AppHandle) -> Result<SigningIdentity, String> {
+    let data_dir = get_data_dir(&app_handle).map_err(|e| e.to_string())?;
+    PackageService::identity(&data_dir).map_err(|e| e.to_string())
+}
+
+/// Export canvases, their attachments and the audit log as a signed package
+#[tauri::command]
+pub async fn export_evidence_package(
+    app_handle: AppHandle,
+    canvas_paths: Vec<String>,
+    file_path: String,
+    options: Option<ExportOptions>,
+) -> Result<PackageManifest, String> {
+    let data_dir = get_data_dir(&app_handle).map_err(|e| e.to_string())?;
+    let paths: Vec<PathBuf> = canvas_paths.iter().map(PathBuf::from).collect();
+    let options = options.unwrap_or_default();
+
+    let manifest = PackageService::export(&data_dir, &paths, Path::new(&file_path), &options)
+        .map_err(|e| e.to_string())?;
+
+    // Audit
+    for path in &paths {
+        AuditService::log(
+            path,
+            AuditOperation::Export,
+            json!({ "format": "package", "destination": file_path, "options": options }),
+        )
+        .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+    }
+
+    Ok(manifest)
+}
+
+/// Check a package's signature and file hashes before opening it
+///
+/// `trusted_signers` are the fingerprints (or public keys) the sender
+/// shared out of band.
+#[tauri::command]
+pub async fn verify_package(
+    package_path: String,
+    trusted_signers: Vec<String>,
+) -> Result<PackageVerification, String> {
+    PackageService::verify(Path::new(&package_path), &trusted_signers).map_err(|e| e.to_string())
+}
@@ -0,0 +1,86 @@
+// Redaction Commands
+//
+// Tauri command handlers for export redaction profiles
+
+use crate::models::{AuditOperation, ExportOptions, RedactionProfile, RedactionReport};
+use crate::services::{AuditService, ExportService, RedactionService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// List redaction profiles in a vault
+#[tauri::command]
+pub async fn list_redaction_profiles(vault_path: String) -> Result<Vec<RedactionProfile>, String> {
+    RedactionService::list(Path::new(&vault_path)).map_err(|e| e.to_string())
+}
+
+/// Get a redaction profile
+#[tauri::command]
+pub async fn get_redaction_profile(
+    vault_path: String,
+    profile_id: String,
+) -> Result<RedactionProfile, String> {
+    RedactionService::get(Path::new(&vault_path), &profile_id).map_err(|e| e.to_string())
+}
+
+/// Create (empty ID) or update a redaction profile
+#[tauri::command]
+pub async fn save_redaction_profile(
+    app_handle: AppHandle,
+    vault_path: String,
+    profile: RedactionProfile,
+) -> Result<RedactionProfile, String> {
+    let path = Path::new(&vault_path);
+
+    let profile = RedactionService::save(path, profile).map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::RedactionProfileSave,
+        json!({ "profile_id": profile.id, "name": profile.name }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(profile)
+}
+
+/// Delete a redaction profile
+#[tauri::command]
+pub async fn delete_redaction_profile(
+    app_handle: AppHandle,
+    vault_path: String,
+    profile_id: String,
+) -> Result<(), String> {
+    let path = Path::new(&vault_path);
+
+    RedactionService::delete(path, &profile_id).map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::RedactionProfileDelete,
+        json!({ "profile_id": profile_id }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(())
+}
+
+/// Report what a profile would redact from a canvas export
+#[tauri::command]
+pub async fn preview_redaction(
+    canvas_path: String,
+    profile_id: String,
+    reveal_secrets: Option<bool>,
+) -> Result<RedactionReport, String> {
+    let options = ExportOptions {
+        reveal_secrets: reveal_secrets.unwrap_or(false),
+        redaction_profile: Some(profile_id),
+        ..Default::default()
+    };
+
+    ExportService::prepare(Path::new(&canvas_path), &options)
+        .map(|(_, report)| report.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,104 @@
+// Scan Commands
+//
+// Tauri command handlers for importing network scans onto canvases
+
+use crate::events::{EventEmitter, WorkspaceChangeType};
+use crate::models::{AuditOperation, ScanImport};
+use crate::services::{AuditService, ScanService};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Import an Nmap XML report onto a canvas
+#[tauri::command]
+pub async fn import_nmap_scan(
+    app_handle: AppHandle,
+    canvas_path: String,
+    file_path: String,
+    expected_revision: Option<u64>,
+) -> Result<ScanImport, String> {
+    import(&app_handle, &canvas_path, &file_path, expected_revision)
+}
+
+/// Create a canvas in a vault (optionally inside a folder) and import an
+/// Nmap XML report onto it
+#[tauri::command]
+pub async fn import_nmap_scan_to_new_canvas(
+    app_handle: AppHandle,
+    vault_path: String,
+    vault_id: String,
+    name: String,
+    file_path: String,
+    folder: Option<String>,
+) -> Result<ScanImport, String> {
+    // Read the report first so a bad file leaves no empty canvas behind
+    let content = std::fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+    ScanService::parse_nmap(&content).map_err(|e| e.to_string())?;
+
+    let canvas = super::canvas::create(
+        &app_handle,
+        &vault_path,
+        &vault_id,
+        &name,
+        None,
+        None,
+        HashMap::new(),
+        folder.as_deref(),
+    )?;
+
+    let mut scan = import(&app_handle, &canvas.path, &file_path, None)?;
+    scan.canvas = Some(canvas);
+    Ok(scan)
+}
+
+fn import(
+    app_handle: &AppHandle,
+    canvas_path: &str,
+    file_path: &str,
+    expected_revision: Option<u64>,
+) -> Result<ScanImport, String> {
+    let path = Path::new(canvas_path);
+
+    let scan = ScanService::import_nmap(path, Path::new(file_path), expected_revision)
+        .map_err(|e| e.to_string())?;
+    let node_ids: Vec<String> = scan.nodes.iter().map(|n| n.id.clone()).collect();
+    let edge_ids: Vec<String> = scan.edges.iter().map(|e| e.id.clone()).collect();
+
+    let Some(revision) = &scan.revision else {
+        return Ok(scan);
+    };
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::ScanImport,
+        json!({
+            "revision": revision.revision,
+            "file_path": file_path,
+            "hosts": scan.hosts.len(),
+            "updated_node_ids": scan.updated_node_ids,
+            "added_node_ids": node_ids,
+            "added_edge_ids": edge_ids,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(app_handle);
+    if !node_ids.is_empty() {
+        emitter.nodes_changed(canvas_path, WorkspaceChangeType::NodesAdded, node_ids);
+    }
+    if !scan.updated_node_ids.is_empty() {
+        emitter.nodes_changed(
+            canvas_path,
+            WorkspaceChangeType::NodesUpdated,
+            scan.updated_node_ids.clone(),
+        );
+    }
+    if !edge_ids.is_empty() {
+        emitter.edges_changed(canvas_path, WorkspaceChangeType::EdgesAdded, edge_ids);
+    }
+
+    Ok(scan)
+}
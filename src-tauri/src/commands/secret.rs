@@ -0,0 +1,59 @@
+// Secret Commands
+//
+// Tauri command handlers for field-level secrets in node data
+
+use crate::models::{AuditOperation, SecretFieldConfig};
+use crate::services::{AuditService, SecretService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Reveal the plaintext of a sensitive node field (None if absent)
+#[tauri::command]
+pub async fn reveal_secret(
+    canvas_path: String,
+    node_id: String,
+    field: String,
+) -> Result<Option<String>, String> {
+    let path = Path::new(&canvas_path);
+
+    let value = SecretService::reveal(path, &node_id, &field).map_err(|e| e.to_string())?;
+
+    // Audit; the plaintext is only handed out once the reveal is on record
+    AuditService::log(
+        path,
+        AuditOperation::SecretReveal,
+        json!({ "node_ids": [node_id], "field": field }),
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(value)
+}
+
+/// Get which node fields a vault stores encrypted
+#[tauri::command]
+pub async fn get_secret_fields(vault_path: String) -> Result<SecretFieldConfig, String> {
+    SecretService::config(Path::new(&vault_path)).map_err(|e| e.to_string())
+}
+
+/// Set which node fields a vault stores encrypted
+#[tauri::command]
+pub async fn set_secret_fields(
+    app_handle: AppHandle,
+    vault_path: String,
+    config: SecretFieldConfig,
+) -> Result<SecretFieldConfig, String> {
+    let path = Path::new(&vault_path);
+
+    SecretService::set_config(path, &config).map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::SecretFieldsUpdate,
+        json!({ "fields": config.fields }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(config)
+}
@@ -0,0 +1,74 @@
+// Template Commands
+//
+// Tauri command handlers for canvas templates
+
+use crate::models::{AuditOperation, CanvasTemplate, TemplateInfo};
+use crate::services::{AuditService, TemplateService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Save a canvas as a template in its vault
+#[tauri::command]
+pub async fn save_canvas_as_template(
+    app_handle: AppHandle,
+    vault_path: String,
+    canvas_path: String,
+    name: String,
+    description: Option<String>,
+) -> Result<TemplateInfo, String> {
+    let template = TemplateService::save_from_canvas(
+        Path::new(&vault_path),
+        Path::new(&canvas_path),
+        &name,
+        description.as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        Path::new(&canvas_path),
+        AuditOperation::TemplateSave,
+        json!({ "template_id": template.id, "name": template.name }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(template)
+}
+
+/// List templates in a vault
+#[tauri::command]
+pub async fn list_templates(vault_path: String) -> Result<Vec<TemplateInfo>, String> {
+    TemplateService::list(Path::new(&vault_path)).map_err(|e| e.to_string())
+}
+
+/// Get a template with its full workspace
+#[tauri::command]
+pub async fn get_template(
+    vault_path: String,
+    template_id: String,
+) -> Result<CanvasTemplate, String> {
+    TemplateService::get(Path::new(&vault_path), &template_id).map_err(|e| e.to_string())
+}
+
+/// Delete a template
+#[tauri::command]
+pub async fn delete_template(
+    app_handle: AppHandle,
+    vault_path: String,
+    template_id: String,
+) -> Result<(), String> {
+    let path = Path::new(&vault_path);
+
+    TemplateService::delete(path, &template_id).map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::TemplateDelete,
+        json!({ "template_id": template_id }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+// Transform Commands
+//
+// Tauri command handlers for enrichment transforms
+
+use crate::events::{EventEmitter, WorkspaceChangeType};
+use crate::models::{AuditOperation, TransformConfig, TransformInfo, TransformResult};
+use crate::services::{AuditService, TransformService};
+use serde_json::json;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// List transforms available in a vault, optionally for one node type
+#[tauri::command]
+pub async fn list_transforms(
+    vault_path: String,
+    node_type: Option<String>,
+) -> Result<Vec<TransformInfo>, String> {
+    TransformService::list(Path::new(&vault_path), node_type.as_deref()).map_err(|e| e.to_string())
+}
+
+/// Get the HTTP transforms configured for a vault
+#[tauri::command]
+pub async fn get_transform_config(vault_path: String) -> Result<TransformConfig, String> {
+    TransformService::config(Path::new(&vault_path)).map_err(|e| e.to_string())
+}
+
+/// Replace the HTTP transforms configured for a vault; an empty header value
+/// keeps the one already stored
+#[tauri::command]
+pub async fn set_transform_config(
+    app_handle: AppHandle,
+    vault_path: String,
+    config: TransformConfig,
+) -> Result<TransformConfig, String> {
+    let path = Path::new(&vault_path);
+
+    TransformService::set_config(path, &config).map_err(|e| e.to_string())?;
+    let config = TransformService::config(path).map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::TransformsUpdate,
+        json!({
+            "transforms": config
+                .http
+                .iter()
+                .map(|t| json!({ "id": t.info.id, "endpoint": t.endpoint }))
+                .collect::<Vec<_>>(),
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(config)
+}
+
+/// Run a transform on a node and add the nodes and edges it returns
+#[tauri::command]
+pub async fn run_transform(
+    app_handle: AppHandle,
+    canvas_path: String,
+    transform_id: String,
+    node_id: String,
+    expected_revision: Option<u64>,
+) -> Result<TransformResult, String> {
+    let path = Path::new(&canvas_path);
+
+    // Providers block on the network, so they run off the async runtime
+    let result = {
+        let (canvas_path, transform_id, node_id) =
+            (canvas_path.clone(), transform_id.clone(), node_id.clone());
+        tauri::async_runtime::spawn_blocking(move || {
+            TransformService::run(
+                Path::new(&canvas_path),
+                &transform_id,
+                &node_id,
+                expected_revision,
+            )
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?
+    };
+    if result.nodes.is_empty() && result.edges.is_empty() {
+        return Ok(result);
+    }
+    let node_ids: Vec<String> = result.nodes.iter().map(|n| n.id.clone()).collect();
+    let edge_ids: Vec<String> = result.edges.iter().map(|e| e.id.clone()).collect();
+
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::TransformRun,
+        json!({
+            "revision": result.revision.revision,
+            "transform_id": transform_id,
+            "node_ids": [node_id],
+            "added_node_ids": node_ids,
+            "added_edge_ids": edge_ids,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    if !node_ids.is_empty() {
+        emitter.nodes_changed(&canvas_path, WorkspaceChangeType::NodesAdded, node_ids);
+    }
+    if !edge_ids.is_empty() {
+        emitter.edges_changed(&canvas_path, WorkspaceChangeType::EdgesAdded, edge_ids);
+    }
+
+    Ok(result)
+}
@@ -3,8 +3,9 @@
 // Tauri command handlers for vault operations
 
 use crate::events::EventEmitter;
-use crate::models::VaultInfo;
-use crate::services::{HistoryService, StateService, VaultService};
+use crate::models::{AuditOperation, VaultInfo};
+use crate::services::{AuditService, HistoryService, StateService, VaultService, WatcherService};
+use serde_json::json;
 use std::path::Path;
 use tauri::AppHandle;
 
@@ -34,6 +35,17 @@ pub async fn create_vault(
     StateService::update_last_opened(&app_handle, Some(vault.id.clone()), None)
         .map_err(|e| e.to_string())?;
 
+    // Watch for external changes
+    let _ = WatcherService::start(&app_handle, vault_path);
+
+    // Audit
+    AuditService::log(
+        vault_path,
+        AuditOperation::VaultCreate,
+        json!({ "name": vault.name }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     emitter.vault_created(&vault.id, &vault.path, &vault.name);
@@ -61,6 +73,9 @@ pub async fn open_vault(app_handle: AppHandle, path: String) -> Result<VaultInfo
     StateService::update_last_opened(&app_handle, Some(vault.id.clone()), None)
         .map_err(|e| e.to_string())?;
 
+    // Watch for external changes
+    let _ = WatcherService::start(&app_handle, vault_path);
+
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     emitter.vault_opened(&vault.id, &vault.path, &vault.name);
@@ -88,6 +103,14 @@ pub async fn rename_vault(
     )
     .map_err(|e| e.to_string())?;
 
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::VaultRename,
+        json!({ "name": vault.name }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     emitter.vault_updated(&vault.id, &vault.path, &vault.name);
@@ -106,6 +129,14 @@ pub async fn update_vault_description(
 
     let vault = VaultService::update_description(path, &description).map_err(|e| e.to_string())?;
 
+    // Audit
+    AuditService::log(
+        path,
+        AuditOperation::VaultUpdate,
+        json!({ "description": description }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     emitter.vault_updated(&vault.id, &vault.path, &vault.name);
@@ -124,3 +155,16 @@ pub async fn is_valid_vault(path: String) -> Result<bool, String> {
 pub async fn get_vault_info(path: String) -> Result<Option<VaultInfo>, String> {
     VaultService::get_info(Path::new(&path)).map_err(|e| e.to_string())
 }
+
+/// Start watching a vault for external changes (replaces the current watch)
+#[tauri::command]
+pub async fn watch_vault(app_handle: AppHandle, vault_path: String) -> Result<(), String> {
+    WatcherService::start(&app_handle, Path::new(&vault_path)).map_err(|e| e.to_string())
+}
+
+/// Stop watching the current vault
+#[tauri::command]
+pub async fn unwatch_vault(app_handle: AppHandle) -> Result<(), String> {
+    WatcherService::stop(&app_handle);
+    Ok(())
+}
@@ -3,8 +3,12 @@
 // Tauri command handlers for workspace data operations
 
 use crate::events::{EventEmitter, WorkspaceChangeType};
-use crate::models::{WorkspaceData, WorkspaceEdge, WorkspaceNode};
-use crate::services::WorkspaceService;
+use crate::models::{
+    AuditOperation, Tlp, WorkspaceConflict, WorkspaceData, WorkspaceEdge, WorkspaceNode,
+    WorkspaceRevision,
+};
+use crate::services::{AuditService, NodeFileService, WorkspaceService};
+use serde_json::json;
 use std::path::Path;
 use tauri::AppHandle;
 
@@ -24,19 +28,48 @@ pub async fn load_workspace(
 }
 
 /// Save workspace data
+///
+/// With `expected_revision`, fails with a `conflict` error if the workspace
+/// was saved elsewhere since that revision was loaded.
 #[tauri::command]
 pub async fn save_workspace(
     app_handle: AppHandle,
     canvas_path: String,
     data: WorkspaceData,
-) -> Result<(), String> {
-    WorkspaceService::save(Path::new(&canvas_path), &data).map_err(|e| e.to_string())?;
+    expected_revision: Option<u64>,
+) -> Result<WorkspaceRevision, String> {
+    let revision =
+        WorkspaceService::save_checked(Path::new(&canvas_path), &data, expected_revision)
+            .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        Path::new(&canvas_path),
+        AuditOperation::WorkspaceSave,
+        json!({
+            "revision": revision.revision,
+            "node_ids": data.nodes.iter().map(|n| &n.id).collect::<Vec<_>>(),
+            "edge_ids": data.edges.iter().map(|e| &e.id).collect::<Vec<_>>(),
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
 
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     emitter.workspace_saved(&canvas_path);
 
-    Ok(())
+    Ok(revision)
+}
+
+/// Get the local and on-disk versions of a workspace after a save conflict
+#[tauri::command]
+pub async fn get_workspace_conflict(
+    canvas_path: String,
+    local: WorkspaceData,
+    expected_revision: Option<u64>,
+) -> Result<WorkspaceConflict, String> {
+    WorkspaceService::conflict(Path::new(&canvas_path), local, expected_revision)
+        .map_err(|e| e.to_string())
 }
 
 /// Update nodes only
@@ -45,16 +78,27 @@ pub async fn update_nodes(
     app_handle: AppHandle,
     canvas_path: String,
     nodes: Vec<WorkspaceNode>,
-) -> Result<(), String> {
+    expected_revision: Option<u64>,
+) -> Result<WorkspaceRevision, String> {
     let node_ids: Vec<String> = nodes.iter().map(|n| n.id.clone()).collect();
 
-    WorkspaceService::update_nodes(Path::new(&canvas_path), nodes).map_err(|e| e.to_string())?;
+    let revision =
+        WorkspaceService::update_nodes(Path::new(&canvas_path), nodes, expected_revision)
+            .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        Path::new(&canvas_path),
+        AuditOperation::NodesUpdate,
+        json!({ "revision": revision.revision, "node_ids": node_ids }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
 
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     emitter.nodes_changed(&canvas_path, WorkspaceChangeType::NodesUpdated, node_ids);
 
-    Ok(())
+    Ok(revision)
 }
 
 /// Update edges only
@@ -63,16 +107,27 @@ pub async fn update_edges(
     app_handle: AppHandle,
     canvas_path: String,
     edges: Vec<WorkspaceEdge>,
-) -> Result<(), String> {
+    expected_revision: Option<u64>,
+) -> Result<WorkspaceRevision, String> {
     let edge_ids: Vec<String> = edges.iter().map(|e| e.id.clone()).collect();
 
-    WorkspaceService::update_edges(Path::new(&canvas_path), edges).map_err(|e| e.to_string())?;
+    let revision =
+        WorkspaceService::update_edges(Path::new(&canvas_path), edges, expected_revision)
+            .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        Path::new(&canvas_path),
+        AuditOperation::EdgesUpdate,
+        json!({ "revision": revision.revision, "edge_ids": edge_ids }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
 
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     emitter.edges_changed(&canvas_path, WorkspaceChangeType::EdgesUpdated, edge_ids);
 
-    Ok(())
+    Ok(revision)
 }
 
 /// Add a single node
@@ -81,16 +136,26 @@ pub async fn add_node(
     app_handle: AppHandle,
     canvas_path: String,
     node: WorkspaceNode,
-) -> Result<(), String> {
+    expected_revision: Option<u64>,
+) -> Result<WorkspaceRevision, String> {
     let node_id = node.id.clone();
 
-    WorkspaceService::add_node(Path::new(&canvas_path), node).map_err(|e| e.to_string())?;
+    let revision = WorkspaceService::add_node(Path::new(&canvas_path), node, expected_revision)
+        .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        Path::new(&canvas_path),
+        AuditOperation::NodeAdd,
+        json!({ "revision": revision.revision, "node_ids": [node_id] }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
 
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     emitter.nodes_changed(&canvas_path, WorkspaceChangeType::NodesAdded, vec![node_id]);
 
-    Ok(())
+    Ok(revision)
 }
 
 /// Remove a single node
@@ -99,8 +164,19 @@ pub async fn remove_node(
     app_handle: AppHandle,
     canvas_path: String,
     node_id: String,
-) -> Result<(), String> {
-    WorkspaceService::remove_node(Path::new(&canvas_path), &node_id).map_err(|e| e.to_string())?;
+    expected_revision: Option<u64>,
+) -> Result<WorkspaceRevision, String> {
+    let revision =
+        WorkspaceService::remove_node(Path::new(&canvas_path), &node_id, expected_revision)
+            .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        Path::new(&canvas_path),
+        AuditOperation::NodeRemove,
+        json!({ "revision": revision.revision, "node_ids": [node_id] }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
 
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
@@ -110,7 +186,43 @@ pub async fn remove_node(
         vec![node_id],
     );
 
-    Ok(())
+    Ok(revision)
+}
+
+/// Set or clear the TLP classification of nodes
+#[tauri::command]
+pub async fn classify_nodes(
+    app_handle: AppHandle,
+    canvas_path: String,
+    node_ids: Vec<String>,
+    classification: Option<Tlp>,
+    expected_revision: Option<u64>,
+) -> Result<WorkspaceRevision, String> {
+    let revision = WorkspaceService::classify_nodes(
+        Path::new(&canvas_path),
+        &node_ids,
+        classification,
+        expected_revision,
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        Path::new(&canvas_path),
+        AuditOperation::NodesClassify,
+        json!({
+            "revision": revision.revision,
+            "node_ids": node_ids,
+            "classification": classification,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    // Emit event
+    let emitter = EventEmitter::new(&app_handle);
+    emitter.nodes_changed(&canvas_path, WorkspaceChangeType::NodesUpdated, node_ids);
+
+    Ok(revision)
 }
 
 /// Add a single edge
@@ -119,16 +231,26 @@ pub async fn add_edge(
     app_handle: AppHandle,
     canvas_path: String,
     edge: WorkspaceEdge,
-) -> Result<(), String> {
+    expected_revision: Option<u64>,
+) -> Result<WorkspaceRevision, String> {
     let edge_id = edge.id.clone();
 
-    WorkspaceService::add_edge(Path::new(&canvas_path), edge).map_err(|e| e.to_string())?;
+    let revision = WorkspaceService::add_edge(Path::new(&canvas_path), edge, expected_revision)
+        .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        Path::new(&canvas_path),
+        AuditOperation::EdgeAdd,
+        json!({ "revision": revision.revision, "edge_ids": [edge_id] }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
 
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     emitter.edges_changed(&canvas_path, WorkspaceChangeType::EdgesAdded, vec![edge_id]);
 
-    Ok(())
+    Ok(revision)
 }
 
 /// Remove a single edge
@@ -137,8 +259,19 @@ pub async fn remove_edge(
     app_handle: AppHandle,
     canvas_path: String,
     edge_id: String,
-) -> Result<(), String> {
-    WorkspaceService::remove_edge(Path::new(&canvas_path), &edge_id).map_err(|e| e.to_string())?;
+    expected_revision: Option<u64>,
+) -> Result<WorkspaceRevision, String> {
+    let revision =
+        WorkspaceService::remove_edge(Path::new(&canvas_path), &edge_id, expected_revision)
+            .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        Path::new(&canvas_path),
+        AuditOperation::EdgeRemove,
+        json!({ "revision": revision.revision, "edge_ids": [edge_id] }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
 
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
@@ -148,7 +281,7 @@ pub async fn remove_edge(
         vec![edge_id],
     );
 
-    Ok(())
+    Ok(revision)
 }
 
 /// Batch update nodes and edges
@@ -160,16 +293,35 @@ pub async fn batch_update_workspace(
     nodes_to_remove: Vec<String>,
     edges_to_add: Vec<WorkspaceEdge>,
     edges_to_remove: Vec<String>,
-) -> Result<(), String> {
-    WorkspaceService::batch_update(
+    expected_revision: Option<u64>,
+) -> Result<WorkspaceRevision, String> {
+    let added_node_ids: Vec<String> = nodes_to_add.iter().map(|n| n.id.clone()).collect();
+    let added_edge_ids: Vec<String> = edges_to_add.iter().map(|e| e.id.clone()).collect();
+
+    let revision = WorkspaceService::batch_update(
         Path::new(&canvas_path),
         nodes_to_add,
         nodes_to_remove.clone(),
         edges_to_add,
         edges_to_remove.clone(),
+        expected_revision,
     )
     .map_err(|e| e.to_string())?;
 
+    // Audit
+    AuditService::log(
+        Path::new(&canvas_path),
+        AuditOperation::WorkspaceBatch,
+        json!({
+            "revision": revision.revision,
+            "added_node_ids": added_node_ids,
+            "removed_node_ids": nodes_to_remove,
+            "added_edge_ids": added_edge_ids,
+            "removed_edge_ids": edges_to_remove,
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
     // Emit event
     let emitter = EventEmitter::new(&app_handle);
     if !nodes_to_remove.is_empty() {
@@ -187,5 +339,77 @@ pub async fn batch_update_workspace(
         );
     }
 
+    Ok(revision)
+}
+
+/// Read a file of a canvas (node, edge, manifest or state file), decrypted
+/// if the vault is encrypted
+#[tauri::command]
+pub async fn read_canvas_file(
+    canvas_path: String,
+    relative_path: String,
+) -> Result<Option<String>, String> {
+    NodeFileService::read_file(Path::new(&canvas_path), &relative_path).map_err(|e| e.to_string())
+}
+
+/// Write a file of a canvas, encrypted if the vault is encrypted
+#[tauri::command]
+pub async fn write_canvas_file(
+    app_handle: AppHandle,
+    canvas_path: String,
+    relative_path: String,
+    content: String,
+) -> Result<(), String> {
+    NodeFileService::write_file(Path::new(&canvas_path), &relative_path, &content)
+        .map_err(|e| e.to_string())?;
+
+    // Audit; edge files carry their edge ID, anything else is canvas-wide
+    let mut segments = relative_path.split(['/', '\\']);
+    let (operation, details) = match (segments.next(), segments.next()) {
+        (Some("edges"), Some(edge_id)) => (
+            AuditOperation::EdgesUpdate,
+            json!({ "edge_ids": [edge_id], "path": relative_path }),
+        ),
+        _ => (
+            AuditOperation::WorkspaceSave,
+            json!({ "path": relative_path }),
+        ),
+    };
+    AuditService::log(Path::new(&canvas_path), operation, details)
+        .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
+    Ok(())
+}
+
+/// Write a node's content and/or properties.json, sealing sensitive fields
+#[tauri::command]
+pub async fn write_node_files(
+    app_handle: AppHandle,
+    canvas_path: String,
+    node_id: String,
+    node_type: String,
+    content: Option<String>,
+    properties: Option<String>,
+) -> Result<(), String> {
+    NodeFileService::write_node_files(
+        Path::new(&canvas_path),
+        &node_id,
+        &node_type,
+        content.as_deref(),
+        properties.as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Audit
+    AuditService::log(
+        Path::new(&canvas_path),
+        AuditOperation::NodesUpdate,
+        json!({
+            "node_ids": [node_id],
+            "path": format!("nodes/{}/data", node_id),
+        }),
+    )
+    .unwrap_or_else(|e| AuditService::report_failure(&app_handle, e));
+
     Ok(())
 }
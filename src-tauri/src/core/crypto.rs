@@ -0,0 +1,436 @@
+// MosaicFlow Vault Encryption
+//
+// Passphrase-derived keys and file encryption for encrypted vaults. Unlocked
+// keys live only in memory; core::fs encrypts and decrypts transparently.
+
+use super::error::MosaicError;
+use super::result::MosaicResult;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+/// Prefix marking an encrypted file
+pub const MAGIC: &[u8] = b"MFENC1\n";
+
+/// Default Argon2id cost: 64 MiB, 3 passes, 1 lane
+pub const DEFAULT_MEMORY_KIB: u32 = 64 * 1024;
+pub const DEFAULT_ITERATIONS: u32 = 3;
+pub const DEFAULT_PARALLELISM: u32 = 1;
+
+/// Weakest Argon2id cost accepted from a header: 19 MiB, 2 passes
+pub const MIN_MEMORY_KIB: u32 = 19 * 1024;
+pub const MIN_ITERATIONS: u32 = 2;
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+const SALT_LEN: usize = 16;
+
+/// Symmetric vault key, wiped from memory on drop
+pub type VaultKey = Zeroizing<[u8; KEY_LEN]>;
+
+/// Generate a random salt for key derivation
+pub fn random_salt() -> Vec<u8> {
+    let mut salt = vec![0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+/// Generate a random symmetric key
+pub fn random_key() -> VaultKey {
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    OsRng.fill_bytes(key.as_mut());
+    key
+}
+
+/// Key from raw bytes (must be exactly 32 bytes)
+pub fn key_from_bytes(bytes: &[u8]) -> MosaicResult<VaultKey> {
+    let bytes: [u8; KEY_LEN] = bytes
+        .try_into()
+        .map_err(|_| MosaicError::invalid_format("Invalid key length"))?;
+    Ok(Zeroizing::new(bytes))
+}
+
+/// Derive a vault key from a passphrase with Argon2id
+pub fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+) -> MosaicResult<VaultKey> {
+    let params = Params::new(memory_kib, iterations, parallelism, Some(KEY_LEN))
+        .map_err(|e| MosaicError::invalid_format(format!("Invalid KDF parameters: {}", e)))?;
+
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| MosaicError::invalid_format(format!("Key derivation failed: {}", e)))?;
+
+    Ok(key)
+}
+
+/// Reject Argon2id costs below the minimums, so a tampered header cannot
+/// downgrade key derivation
+pub fn check_kdf_params(memory_kib: u32, iterations: u32) -> MosaicResult<()> {
+    if memory_kib < MIN_MEMORY_KIB || iterations < MIN_ITERATIONS {
+        return Err(MosaicError::invalid_format(format!(
+            "KDF parameters below minimum ({} KiB, {} passes)",
+            MIN_MEMORY_KIB, MIN_ITERATIONS
+        )));
+    }
+    Ok(())
+}
+
+/// Encrypt data as MAGIC || nonce || ciphertext
+///
+/// `aad` is authenticated but not stored; decryption needs the same value.
+pub fn encrypt(key: &VaultKey, aad: &[u8], plaintext: &[u8]) -> MosaicResult<Vec<u8>> {
+    let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| MosaicError::io_error("Encryption failed"))?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypt data produced by `encrypt` with the same `aad`
+pub fn decrypt(key: &VaultKey, aad: &[u8], data: &[u8]) -> MosaicResult<Vec<u8>> {
+    let body = data
+        .strip_prefix(MAGIC)
+        .filter(|body| body.len() >= NONCE_LEN)
+        .ok_or_else(|| MosaicError::invalid_format("Not an encrypted file"))?;
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+
+    XChaCha20Poly1305::new(key.as_ref().into())
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| MosaicError::decryption_failed("authentication failed"))
+}
+
+/// Associated data binding a vault file to its place in the vault: the
+/// '/'-separated path relative to the vault root
+///
+/// A file copied or swapped onto another path no longer decrypts.
+pub fn file_aad(root: &Path, path: &Path) -> Vec<u8> {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+        .into_bytes()
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn hmac(key: &[u8], message: &[u8]) -> HmacSha256 {
+    let mut mac =
+        <HmacSha256 as KeyInit>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac
+}
+
+/// HMAC-SHA256 of `message` under `key`, as lowercase hex
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> String {
+    format!("{:x}", hmac(key, message).finalize().into_bytes())
+}
+
+/// Check a hex HMAC-SHA256 of `message` in constant time
+pub fn verify_hmac_sha256(key: &[u8], message: &[u8], expected: &str) -> bool {
+    let tag: Option<Vec<u8>> = (0..expected.len())
+        .step_by(2)
+        .map(|i| {
+            expected
+                .get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        })
+        .collect();
+
+    tag.is_some_and(|tag| hmac(key, message).verify_slice(&tag).is_ok())
+}
+
+/// Whether data starts with the encrypted file marker
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+// ============================================================================
+// Keyring of unlocked vaults
+// ============================================================================
+
+struct UnlockedVault {
+    key: VaultKey,
+    auto_lock: Option<Duration>,
+    last_used: Instant,
+}
+
+fn keyring() -> &'static Mutex<HashMap<PathBuf, UnlockedVault>> {
+    static KEYRING: OnceLock<Mutex<HashMap<PathBuf, UnlockedVault>>> = OnceLock::new();
+    KEYRING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn normalize(root: &Path) -> PathBuf {
+    root.canonicalize().unwrap_or_else(|_| root.to_path_buf())
+}
+
+/// Keep a vault key in memory until `lock` or `auto_lock` of inactivity
+pub fn unlock(root: &Path, key: VaultKey, auto_lock: Option<Duration>) {
+    if let Ok(mut ring) = keyring().lock() {
+        ring.insert(
+            normalize(root),
+            UnlockedVault {
+                key,
+                auto_lock,
+                last_used: Instant::now(),
+            },
+        );
+    }
+}
+
+/// Forget a vault key; returns whether the vault was unlocked
+pub fn lock(root: &Path) -> bool {
+    keyring()
+        .lock()
+        .is_ok_and(|mut ring| ring.remove(&normalize(root)).is_some())
+}
+
+/// Whether the key of a vault is held in memory
+pub fn is_unlocked(root: &Path) -> bool {
+    keyring()
+        .lock()
+        .is_ok_and(|ring| ring.contains_key(&normalize(root)))
+}
+
+/// Inactivity timeout of an unlocked vault
+pub fn auto_lock_timeout(root: &Path) -> Option<Duration> {
+    let ring = keyring().lock().ok()?;
+    ring.get(&normalize(root)).and_then(|v| v.auto_lock)
+}
+
+/// Lock every vault idle for longer than its timeout; returns their roots
+pub fn lock_expired() -> Vec<PathBuf> {
+    let Ok(mut ring) = keyring().lock() else {
+        return vec![];
+    };
+
+    let now = Instant::now();
+    let expired: Vec<PathBuf> = ring
+        .iter()
+        .filter(|(_, v)| {
+            v.auto_lock
+                .is_some_and(|timeout| now.duration_since(v.last_used) >= timeout)
+        })
+        .map(|(root, _)| root.clone())
+        .collect();
+
+    for root in &expired {
+        ring.remove(root);
+    }
+    expired
+}
+
+/// Key of an unlocked vault, counting the lookup as activity
+pub fn key_for(root: &Path) -> Option<VaultKey> {
+    let mut ring = keyring().lock().ok()?;
+    let unlocked = ring.get_mut(&normalize(root))?;
+    unlocked.last_used = Instant::now();
+    Some(unlocked.key.clone())
+}
+
+/// Root of the encrypted vault containing `path`, if any
+pub fn encrypted_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(".mosaicflow").join("encryption.json").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// vault.json and the encryption header must stay readable while locked
+pub fn stays_plaintext(root: &Path, path: &Path) -> bool {
+    path == root.join("vault.json") || path == root.join(".mosaicflow").join("encryption.json")
+}
+
+/// Encrypt file contents if `path` lies in an encrypted vault
+pub(crate) fn encode(path: &Path, plaintext: Vec<u8>) -> MosaicResult<Vec<u8>> {
+    let Some(root) = encrypted_root(path).filter(|root| !stays_plaintext(root, path)) else {
+        return Ok(plaintext);
+    };
+
+    let key = key_for(&root).ok_or_else(|| MosaicError::vault_locked(&root.to_string_lossy()))?;
+    encrypt(&key, &file_aad(&root, path), &plaintext)
+}
+
+/// Whether the header of an encrypted vault records a conversion in progress
+fn converting(root: &Path) -> bool {
+    let Some(header) = std::fs::read(root.join(".mosaicflow").join("encryption.json"))
+        .ok()
+        .and_then(|data| serde_json::from_slice::<serde_json::Value>(&data).ok())
+    else {
+        return false;
+    };
+    let field = |name: &str| header.get(name).and_then(|v| v.as_str());
+
+    // The flag only counts when it was set by someone holding the key
+    let (Some(verifier), Some(mac)) = (field("verifier"), field("converting_mac")) else {
+        return false;
+    };
+    header.get("converting").and_then(|v| v.as_bool()) == Some(true)
+        && key_for(root)
+            .is_some_and(|key| verify_hmac_sha256(&key[..], &conversion_message(verifier), mac))
+}
+
+/// MAC that marks a conversion as started by the key holder
+pub fn conversion_mac(key: &VaultKey, verifier: &str) -> String {
+    hmac_sha256(&key[..], &conversion_message(verifier))
+}
+
+fn conversion_message(verifier: &str) -> Vec<u8> {
+    format!("converting:{}", verifier).into_bytes()
+}
+
+/// Decrypt file contents if they are encrypted
+///
+/// Plain files outside encrypted vaults are returned as-is. Inside one they
+/// are only accepted while encryption is being enabled or disabled, so a
+/// file planted without the marker is not read as vault content.
+pub(crate) fn decode(path: &Path, data: Vec<u8>) -> MosaicResult<Vec<u8>> {
+    let root = encrypted_root(path).filter(|root| !stays_plaintext(root, path));
+
+    if !is_encrypted(&data) {
+        return match root {
+            Some(root) if !converting(&root) => {
+                Err(MosaicError::decryption_failed(&path.to_string_lossy()))
+            }
+            _ => Ok(data),
+        };
+    }
+
+    let root = root.ok_or_else(|| MosaicError::decryption_failed(&path.to_string_lossy()))?;
+    let key = key_for(&root).ok_or_else(|| MosaicError::vault_locked(&root.to_string_lossy()))?;
+
+    decrypt(&key, &file_aad(&root, path), &data)
+        .map_err(|_| MosaicError::decryption_failed(&path.to_string_lossy()))
+}
+
+/// Re-seal the files under `to` that were just renamed from `from`, since
+/// their associated data names the old path
+///
+/// Renames between vaults are left alone; those go through copy + delete.
+pub(crate) fn rebind(from: &Path, to: &Path) -> MosaicResult<()> {
+    let (Some(old_root), Some(new_root)) = (encrypted_root(from), encrypted_root(to)) else {
+        return Ok(());
+    };
+    if old_root != new_root || file_aad(&old_root, from) == file_aad(&new_root, to) {
+        return Ok(());
+    }
+
+    let key =
+        key_for(&new_root).ok_or_else(|| MosaicError::vault_locked(&new_root.to_string_lossy()))?;
+    let files = if to.is_dir() {
+        super::fs::list_files_recursive(to)?
+    } else {
+        vec![to.to_path_buf()]
+    };
+
+    let mut rewritten = Vec::new();
+    for file in files {
+        let data = std::fs::read(&file)?;
+        if !is_encrypted(&data) {
+            continue;
+        }
+        let old_path = from.join(file.strip_prefix(to).unwrap_or(Path::new("")));
+
+        let plain = decrypt(&key, &file_aad(&old_root, &old_path), &data)
+            .map_err(|_| MosaicError::decryption_failed(&file.to_string_lossy()))?;
+        rewritten.push((
+            file.clone(),
+            encrypt(&key, &file_aad(&new_root, &file), &plain)?,
+        ));
+    }
+
+    for (file, data) in rewritten {
+        super::fs::write_raw(&file, &data)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231 test cases 1 and 6 (key longer than a block)
+        assert_eq!(
+            hmac_sha256(&[0x0b; 20], b"Hi There"),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            ),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+
+        let tag = hmac_sha256(b"key", b"message");
+        assert!(verify_hmac_sha256(b"key", b"message", &tag));
+        assert!(!verify_hmac_sha256(b"key", b"massage", &tag));
+        assert!(!verify_hmac_sha256(b"key", b"message", &tag[..62]));
+        assert!(!verify_hmac_sha256(b"key", b"message", "not hex"));
+    }
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let key = derive_key("correct horse", &random_salt(), 1024, 1, 1).unwrap();
+        let sealed = encrypt(&key, b"nodes/a/data/content", b"secret").unwrap();
+
+        assert!(is_encrypted(&sealed));
+        assert_eq!(
+            decrypt(&key, b"nodes/a/data/content", &sealed).unwrap(),
+            b"secret"
+        );
+        assert!(decrypt(&key, b"nodes/b/data/content", &sealed).is_err());
+
+        let other = derive_key("wrong horse", &random_salt(), 1024, 1, 1).unwrap();
+        assert!(decrypt(&other, b"nodes/a/data/content", &sealed).is_err());
+    }
+
+    #[test]
+    fn test_file_aad() {
+        let root = Path::new("/vault");
+        assert_eq!(
+            file_aad(root, Path::new("/vault/canvases/a/workspace.json")),
+            b"canvases/a/workspace.json"
+        );
+    }
+
+    #[test]
+    fn test_kdf_minimums() {
+        assert!(check_kdf_params(DEFAULT_MEMORY_KIB, DEFAULT_ITERATIONS).is_ok());
+        assert!(check_kdf_params(1024, DEFAULT_ITERATIONS).is_err());
+        assert!(check_kdf_params(DEFAULT_MEMORY_KIB, 1).is_err());
+    }
+}
@@ -29,6 +29,7 @@ pub enum ErrorCode {
     // Data errors
     InvalidJson,
     InvalidFormat,
+    InvalidInput,
     MigrationFailed,
 
     // Vault errors
@@ -41,10 +42,25 @@ pub enum ErrorCode {
     CanvasAlreadyExists,
     InvalidCanvas,
 
+    // Encryption errors
+    VaultLocked,
+    InvalidPassphrase,
+    DecryptionFailed,
+
+    // Folder errors
+    FolderNotFound,
+    FolderNotEmpty,
+
     // State errors
     StateNotFound,
     StateSaveFailed,
 
+    // Concurrency errors
+    Conflict,
+
+    // Classification errors
+    ClassificationExceeded,
+
     // Generic
     Unknown,
 }
@@ -80,6 +96,58 @@ impl MosaicError {
         Self::new(ErrorCode::InvalidJson, format!("JSON error: {}", err))
     }
 
+    pub fn invalid_format(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidFormat, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn migration_failed(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::MigrationFailed, message)
+    }
+
+    pub fn vault_locked(path: &str) -> Self {
+        Self::new(ErrorCode::VaultLocked, format!("Vault is locked: {}", path))
+    }
+
+    pub fn invalid_passphrase() -> Self {
+        Self::new(ErrorCode::InvalidPassphrase, "Invalid passphrase")
+    }
+
+    pub fn decryption_failed(path: &str) -> Self {
+        Self::new(
+            ErrorCode::DecryptionFailed,
+            format!("Could not decrypt: {}", path),
+        )
+    }
+
+    pub fn folder_not_found(path: &str) -> Self {
+        Self::new(
+            ErrorCode::FolderNotFound,
+            format!("Folder not found: {}", path),
+        )
+    }
+
+    pub fn folder_not_empty(path: &str) -> Self {
+        Self::new(
+            ErrorCode::FolderNotEmpty,
+            format!("Folder is not empty: {}", path),
+        )
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn classification_exceeded(level: &str, limit: &str) -> Self {
+        Self::new(
+            ErrorCode::ClassificationExceeded,
+            format!("Content marked {} exceeds sharing level {}", level, limit),
+        )
+    }
+
     pub fn vault_not_found(path: &str) -> Self {
         Self::new(
             ErrorCode::VaultNotFound,
@@ -87,6 +155,13 @@ impl MosaicError {
         )
     }
 
+    pub fn vault_not_migrated(path: &str) -> Self {
+        Self::new(
+            ErrorCode::InvalidVault,
+            format!("Vault must be migrated first: {}", path),
+        )
+    }
+
     pub fn canvas_not_found(path: &str) -> Self {
         Self::new(
             ErrorCode::CanvasNotFound,
@@ -126,6 +201,6 @@ impl From<serde_json::Error> for MosaicError {
 // For Tauri command return
 impl From<MosaicError> for String {
     fn from(err: MosaicError) -> Self {
-        serde_json::to_string(&err).unwrap_or_else(|_| err.message)
+        serde_json::to_string(&err).unwrap_or(err.message)
     }
 }
@@ -3,35 +3,33 @@
 // Centralized file operations - ALL I/O goes through here
 
 use serde::{de::DeserializeOwned, Serialize};
+use std::cmp::Ordering;
 use std::fs;
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
 use std::path::Path;
 
+use super::error::MosaicError;
 use super::result::MosaicResult;
+use super::{crypto, write_log};
 
 /// Read and parse JSON file
 pub fn read_json<T: DeserializeOwned>(path: &Path) -> MosaicResult<T> {
-    let content = fs::read_to_string(path)?;
-    let data = serde_json::from_str(&content)?;
+    let content = read_bytes(path)?;
+    let data = serde_json::from_slice(&content)?;
     Ok(data)
 }
 
 /// Write data as pretty JSON to file
 pub fn write_json<T: Serialize>(path: &Path, data: &T) -> MosaicResult<()> {
-    let content = serde_json::to_string_pretty(data)?;
-
-    // Ensure parent directory exists
-    if let Some(parent) = path.parent() {
-        ensure_dir(parent)?;
-    }
-
-    fs::write(path, content)?;
-    Ok(())
+    let content = serde_json::to_vec_pretty(data)?;
+    write_bytes(path, &content)
 }
 
 /// Ensure directory exists, create if not
 pub fn ensure_dir(path: &Path) -> MosaicResult<()> {
     if !path.exists() {
         fs::create_dir_all(path)?;
+        write_log::record(path);
     }
     Ok(())
 }
@@ -49,19 +47,40 @@ pub fn file_exists(path: &Path) -> bool {
 /// Remove file
 pub fn remove_file(path: &Path) -> MosaicResult<()> {
     fs::remove_file(path)?;
+    write_log::record(path);
+    Ok(())
+}
+
+/// Set a file's modification time to now
+pub fn touch(path: &Path) -> MosaicResult<()> {
+    fs::File::options()
+        .append(true)
+        .open(path)?
+        .set_modified(std::time::SystemTime::now())?;
+    write_log::record(path);
     Ok(())
 }
 
 /// Remove directory and all contents
 pub fn remove_dir_all(path: &Path) -> MosaicResult<()> {
+    // The watcher sees a delete for every file inside
+    for file in list_files_recursive(path)? {
+        write_log::record(&file);
+    }
     fs::remove_dir_all(path)?;
+    write_log::record(path);
     Ok(())
 }
 
 /// Rename/move file or directory
+///
+/// Encrypted files are bound to their path, so they are re-sealed for the
+/// new one.
 pub fn rename(from: &Path, to: &Path) -> MosaicResult<()> {
     fs::rename(from, to)?;
-    Ok(())
+    write_log::record(from);
+    write_log::record(to);
+    crypto::rebind(from, to)
 }
 
 /// List directory entries
@@ -93,47 +112,205 @@ pub fn list_subdirs(path: &Path) -> MosaicResult<Vec<std::path::PathBuf>> {
     Ok(entries)
 }
 
+/// List all files below a directory, recursively
+pub fn list_files_recursive(path: &Path) -> MosaicResult<Vec<std::path::PathBuf>> {
+    let mut files = Vec::new();
+
+    for entry in list_dir(path)? {
+        if entry.is_dir() {
+            files.extend(list_files_recursive(&entry)?);
+        } else {
+            files.push(entry);
+        }
+    }
+
+    Ok(files)
+}
+
 /// Copy file
+///
+/// Encrypted files are bound to their path and vault key, so they are
+/// re-sealed for the new path and vault.
 pub fn copy_file(from: &Path, to: &Path) -> MosaicResult<u64> {
+    if crypto::encrypted_root(from).is_some() || crypto::encrypted_root(to).is_some() {
+        let content = read_bytes(from)?;
+        write_bytes(to, &content)?;
+        return Ok(content.len() as u64);
+    }
+
     // Ensure parent directory exists
     if let Some(parent) = to.parent() {
         ensure_dir(parent)?;
     }
 
     let bytes = fs::copy(from, to)?;
+    write_log::record(to);
+    Ok(bytes)
+}
+
+/// Recursively copy a directory, skipping any path in `exclude`
+pub fn copy_dir_all(from: &Path, to: &Path, exclude: &[std::path::PathBuf]) -> MosaicResult<u64> {
+    ensure_dir(to)?;
+
+    let mut bytes = 0;
+    for entry in fs::read_dir(from)? {
+        let path = entry?.path();
+        if exclude.contains(&path) {
+            continue;
+        }
+
+        let target = to.join(path.file_name().unwrap_or_default());
+        bytes += if path.is_dir() {
+            copy_dir_all(&path, &target, exclude)?
+        } else {
+            copy_file(&path, &target)?
+        };
+    }
+
     Ok(bytes)
 }
 
 /// Read file as string
 pub fn read_string(path: &Path) -> MosaicResult<String> {
-    let content = fs::read_to_string(path)?;
-    Ok(content)
+    let content = read_bytes(path)?;
+    String::from_utf8(content).map_err(|e| MosaicError::invalid_format(e.to_string()))
 }
 
 /// Write string to file
 pub fn write_string(path: &Path, content: &str) -> MosaicResult<()> {
+    write_bytes(path, content.as_bytes())
+}
+
+/// Append a string to a file, creating it if missing
+///
+/// Files in encrypted vaults are sealed whole, so those are replaced
+/// atomically instead.
+pub fn append_string(path: &Path, content: &str) -> MosaicResult<()> {
+    if crypto::encrypted_root(path).is_some() {
+        let mut existing = if path.exists() {
+            read_bytes(path)?
+        } else {
+            vec![]
+        };
+        existing.extend_from_slice(content.as_bytes());
+        return write_bytes_atomic(path, &existing);
+    }
+
     // Ensure parent directory exists
     if let Some(parent) = path.parent() {
         ensure_dir(parent)?;
     }
 
-    fs::write(path, content)?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    file.write_all(content.as_bytes())?;
+    write_log::record(path);
     Ok(())
 }
 
-/// Read file as bytes
+/// Read file as bytes (decrypted if in an unlocked encrypted vault)
 pub fn read_bytes(path: &Path) -> MosaicResult<Vec<u8>> {
+    crypto::decode(path, read_raw(path)?)
+}
+
+/// Write bytes to file (encrypted if in an encrypted vault)
+pub fn write_bytes(path: &Path, content: &[u8]) -> MosaicResult<()> {
+    let content = crypto::encode(path, content.to_vec())?;
+    write_raw(path, &content)
+}
+
+/// Replace a file's bytes atomically (encrypted if in an encrypted vault)
+pub fn write_bytes_atomic(path: &Path, content: &[u8]) -> MosaicResult<()> {
+    let content = crypto::encode(path, content.to_vec())?;
+    write_raw_atomic(path, &content)
+}
+
+/// Read file bytes exactly as stored, bypassing vault encryption
+pub fn read_raw(path: &Path) -> MosaicResult<Vec<u8>> {
     let content = fs::read(path)?;
     Ok(content)
 }
 
-/// Write bytes to file
-pub fn write_bytes(path: &Path, content: &[u8]) -> MosaicResult<()> {
+/// Write bytes exactly as given, bypassing vault encryption
+pub fn write_raw(path: &Path, content: &[u8]) -> MosaicResult<()> {
     // Ensure parent directory exists
     if let Some(parent) = path.parent() {
         ensure_dir(parent)?;
     }
 
     fs::write(path, content)?;
+    write_log::record(path);
+    Ok(())
+}
+
+/// Binary search of a plain text file whose lines are sorted by key (the
+/// text before the first ':'), reading only the lines it compares
+///
+/// Keys compare case-insensitively, so hex lists sorted in either case
+/// work. Returns the matching line, trimmed.
+pub fn search_sorted(path: &Path, key: &str) -> MosaicResult<Option<String>> {
+    let key = key.to_ascii_uppercase();
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    let mut line = String::new();
+
+    // The line for `key`, if present, starts in [low, high)
+    let mut low = 0;
+    let mut high = reader.get_ref().metadata()?.len();
+    while low < high {
+        let mid = low + (high - low) / 2;
+
+        // First line starting at or after `mid`
+        let mut start = mid;
+        if mid > 0 {
+            reader.seek(SeekFrom::Start(mid - 1))?;
+            line.clear();
+            start = mid - 1 + reader.read_line(&mut line)? as u64;
+        } else {
+            reader.seek(SeekFrom::Start(0))?;
+        }
+        if start >= high {
+            high = mid;
+            continue;
+        }
+
+        line.clear();
+        let len = reader.read_line(&mut line)? as u64;
+        let trimmed = line.trim();
+        let line_key = trimmed.split(':').next().unwrap_or_default();
+        if line_key.is_empty() {
+            // Blank line or trailing garbage: look before it
+            high = mid;
+            continue;
+        }
+        match line_key.to_ascii_uppercase().cmp(&key) {
+            Ordering::Equal => return Ok(Some(trimmed.to_string())),
+            Ordering::Less => low = start + len,
+            Ordering::Greater => high = mid,
+        }
+    }
+
+    Ok(None)
+}
+
+/// Replace a file's bytes via a temp file in the same directory and a
+/// rename, so readers see either the old or the new contents
+pub fn write_raw_atomic(path: &Path, content: &[u8]) -> MosaicResult<()> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| MosaicError::io_error(format!("Not a file: {}", path.display())))?;
+    let temp = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        super::generate_uuid()
+    ));
+
+    write_raw(&temp, content)?;
+    if let Err(e) = fs::rename(&temp, path) {
+        let _ = fs::remove_file(&temp);
+        return Err(e.into());
+    }
+    write_log::record(path);
     Ok(())
 }
@@ -0,0 +1,386 @@
+// Indicator Normalization
+//
+// Canonical forms of domains, IP addresses, URLs, emails, phone numbers and
+// hashes, so the same indicator typed differently ("Example.COM." and
+// "example.com") compares equal, plus defang/refang helpers
+
+use regex::Regex;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::OnceLock;
+
+/// Common ways of defanging URLs, domains and addresses
+fn defanged() -> &'static Regex {
+    static DEFANGED: OnceLock<Regex> = OnceLock::new();
+    DEFANGED.get_or_init(|| {
+        Regex::new(concat!(
+            r"(?i)hxxp|h\*\*p|fxp://|\[\.\]|\(\.\)|\{\.\}|\[dot\]|\(dot\)",
+            r"|\[:\]|\[://\]|\[/\]|\[@\]|\[at\]|\(at\)",
+        ))
+        .unwrap()
+    })
+}
+
+/// Lowercase ASCII (punycode) domain without trailing dot
+pub fn domain(input: &str) -> Option<String> {
+    let refanged = refang(input.trim());
+    let name = refanged.trim_end_matches('.');
+    if name.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+
+    let ascii = idna::domain_to_ascii(name).ok()?;
+    let valid = ascii.len() <= 253
+        && ascii.contains('.')
+        && ascii.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        });
+    valid.then_some(ascii)
+}
+
+/// Unicode form of a domain for display ("bücher.de" for "xn--bcher-kva.de")
+pub fn domain_to_unicode(domain: &str) -> String {
+    idna::domain_to_unicode(domain).0
+}
+
+/// Registrable domain per the Public Suffix List ("example.co.uk" for
+/// "www.Example.co.uk")
+pub fn registrable_domain(input: &str) -> Option<String> {
+    let domain = domain(input)?;
+    psl::domain_str(&domain).map(str::to_string)
+}
+
+/// Canonical IPv4 or IPv6 address; IPv6 is compressed and lowercase,
+/// brackets and IPv4-mapped forms ("::ffff:10.0.0.1") are unwrapped
+pub fn ip(input: &str) -> Option<String> {
+    let refanged = refang(input.trim());
+    let address: IpAddr = refanged
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()?;
+
+    let address = match address {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(address, IpAddr::V4),
+        v4 => v4,
+    };
+    Some(address.to_string())
+}
+
+/// Network in CIDR notation with host bits cleared ("10.0.0.0/8" for
+/// "10.1.2.3/8"); a bare address becomes a /32 or /128
+pub fn cidr(input: &str) -> Option<String> {
+    let refanged = refang(input.trim());
+    let (address, prefix) = match refanged.split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix.trim().parse::<u32>().ok()?)),
+        None => (refanged.as_str(), None),
+    };
+
+    match ip(address)?.parse::<IpAddr>().ok()? {
+        IpAddr::V4(v4) => {
+            let prefix = prefix.unwrap_or(32);
+            let mask = u32::MAX
+                .checked_shl(32u32.checked_sub(prefix)?)
+                .unwrap_or(0);
+            Some(format!(
+                "{}/{}",
+                Ipv4Addr::from(u32::from(v4) & mask),
+                prefix
+            ))
+        }
+        IpAddr::V6(v6) => {
+            let prefix = prefix.unwrap_or(128);
+            let mask = u128::MAX
+                .checked_shl(128u32.checked_sub(prefix)?)
+                .unwrap_or(0);
+            Some(format!(
+                "{}/{}",
+                Ipv6Addr::from(u128::from(v6) & mask),
+                prefix
+            ))
+        }
+    }
+}
+
+/// URL with lowercase scheme and punycode host, without default port,
+/// fragment or trailing dot on the host; "example.com/a" is read as http
+///
+/// Input without a scheme is only taken as a URL when it starts with a
+/// domain or IP address.
+pub fn url(input: &str) -> Option<String> {
+    let refanged = refang(input.trim());
+    let with_scheme = if refanged.contains("://") {
+        refanged
+    } else {
+        let authority = &refanged[..refanged.find(['/', '?', '#']).unwrap_or(refanged.len())];
+        let host = match authority.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') && port.parse::<u16>().is_ok() => host,
+            _ => authority,
+        };
+        domain(host).or_else(|| ip(host))?;
+        format!("http://{}", refanged)
+    };
+
+    let mut url = url::Url::parse(&with_scheme).ok()?;
+    let host = url.host_str()?.to_string();
+    if host.ends_with('.') {
+        url.set_host(Some(host.trim_end_matches('.'))).ok()?;
+    }
+    url.set_fragment(None);
+    Some(url.to_string())
+}
+
+/// Lowercase email address with a normalized domain
+pub fn email(input: &str) -> Option<String> {
+    let refanged = refang(input.trim());
+    let address = refanged.strip_prefix("mailto:").unwrap_or(&refanged);
+    let (local, host) = address.rsplit_once('@')?;
+    if local.is_empty() || local.contains(|c: char| c.is_whitespace() || c == '@') {
+        return None;
+    }
+
+    Some(format!("{}@{}", local.to_lowercase(), domain(host)?))
+}
+
+/// E.164 form ("+15551234567") of a number written with its country code
+/// ("+1 (555) 123-4567", "001 555 123 4567")
+///
+/// Numbers without a country code are ambiguous and yield `None`.
+pub fn phone(input: &str) -> Option<String> {
+    let trimmed = input.trim();
+    let trimmed = trimmed.strip_prefix("tel:").unwrap_or(trimmed);
+    let international = trimmed
+        .strip_prefix('+')
+        .or_else(|| trimmed.strip_prefix("00"))?;
+    if !international
+        .chars()
+        .all(|c| c.is_ascii_digit() || " ().-/".contains(c))
+    {
+        return None;
+    }
+
+    let digits: String = international.chars().filter(char::is_ascii_digit).collect();
+    ((8..=15).contains(&digits.len()) && !digits.starts_with('0')).then(|| format!("+{}", digits))
+}
+
+/// Lowercase hex digest
+pub fn hash(input: &str) -> Option<String> {
+    let hex = input.trim();
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    (!hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit())).then(|| hex.to_lowercase())
+}
+
+/// Lowercase colon-separated MAC address ("aa:bb:cc:dd:ee:ff")
+pub fn mac(input: &str) -> Option<String> {
+    let hex: String = input
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if hex.len() != 12 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let pairs: Vec<&str> = (0..12).step_by(2).map(|i| &hex[i..i + 2]).collect();
+    Some(pairs.join(":").to_lowercase())
+}
+
+/// Make an indicator safe to paste where it could be clicked or resolved:
+/// "hxxps://evil[.]com/a.php", "user[@]evil[.]com"
+///
+/// Only the host part of a URL is defanged.
+pub fn defang(value: &str) -> String {
+    let value = refang(value);
+    let defang_host = |host: &str| host.replace('.', "[.]").replace('@', "[@]");
+
+    let Some((scheme, rest)) = value.split_once("://") else {
+        return defang_host(&value);
+    };
+    let scheme = match scheme.to_ascii_lowercase().as_str() {
+        "http" => "hxxp".to_string(),
+        "https" => "hxxps".to_string(),
+        "ftp" => "fxp".to_string(),
+        _ => scheme.to_string(),
+    };
+    let (host, path) = rest.split_at(rest.find(['/', '?', '#']).unwrap_or(rest.len()));
+    format!("{}://{}{}", scheme, defang_host(host), path)
+}
+
+/// Undo defanging ("hxxp://evil[.]com" becomes "http://evil.com")
+pub fn refang(text: &str) -> String {
+    refang_mapped(text).0
+}
+
+/// Undo defanging; also returns, for each byte of the result (and its
+/// end), the matching byte offset in `text`
+pub fn refang_mapped(text: &str) -> (String, Vec<usize>) {
+    let mut refanged = String::with_capacity(text.len());
+    let mut offsets = Vec::with_capacity(text.len() + 1);
+    let mut last = 0;
+
+    for m in defanged().find_iter(text) {
+        refanged.push_str(&text[last..m.start()]);
+        offsets.extend(last..m.start());
+
+        let replacement = match m.as_str().to_ascii_lowercase().as_str() {
+            "hxxp" | "h**p" => "http",
+            "fxp://" => "ftp://",
+            "[:]" => ":",
+            "[://]" => "://",
+            "[/]" => "/",
+            "[@]" | "[at]" | "(at)" => "@",
+            _ => ".",
+        };
+        refanged.push_str(replacement);
+        offsets.extend(std::iter::repeat_n(m.start(), replacement.len()));
+        last = m.end();
+    }
+    refanged.push_str(&text[last..]);
+    offsets.extend(last..=text.len());
+
+    (refanged, offsets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain() {
+        assert_eq!(domain("Example.COM.").as_deref(), Some("example.com"));
+        assert_eq!(domain("evil[.]example").as_deref(), Some("evil.example"));
+        assert_eq!(domain("bücher.de").as_deref(), Some("xn--bcher-kva.de"));
+        assert_eq!(domain_to_unicode("xn--bcher-kva.de"), "bücher.de");
+        assert_eq!(
+            registrable_domain("www.Example.co.uk").as_deref(),
+            Some("example.co.uk")
+        );
+        assert_eq!(domain("localhost"), None);
+        assert_eq!(domain("10.0.0.1"), None);
+        assert_eq!(domain("-bad.example"), None);
+        assert_eq!(domain("a..example"), None);
+    }
+
+    #[test]
+    fn test_ip() {
+        assert_eq!(ip(" 10.0.0.1 ").as_deref(), Some("10.0.0.1"));
+        assert_eq!(ip("10[.]0[.]0[.]1").as_deref(), Some("10.0.0.1"));
+        assert_eq!(ip("[2001:DB8:0:0::1]").as_deref(), Some("2001:db8::1"));
+        assert_eq!(ip("::ffff:10.0.0.1").as_deref(), Some("10.0.0.1"));
+        assert_eq!(ip("10.0.0.256"), None);
+        assert_eq!(ip("example.com"), None);
+    }
+
+    #[test]
+    fn test_cidr() {
+        assert_eq!(cidr("10.1.2.3/8").as_deref(), Some("10.0.0.0/8"));
+        assert_eq!(cidr("10.1.2.3").as_deref(), Some("10.1.2.3/32"));
+        assert_eq!(cidr("10.1.2.3/0").as_deref(), Some("0.0.0.0/0"));
+        assert_eq!(cidr("2001:db8::1/32").as_deref(), Some("2001:db8::/32"));
+        assert_eq!(cidr("2001:db8::1").as_deref(), Some("2001:db8::1/128"));
+        assert_eq!(cidr("10.0.0.0/33"), None);
+        assert_eq!(cidr("10.0.0.0/x"), None);
+    }
+
+    #[test]
+    fn test_url() {
+        assert_eq!(
+            url("HTTPS://Example.COM.:443/a?b=1#frag").as_deref(),
+            Some("https://example.com/a?b=1")
+        );
+        assert_eq!(
+            url("hxxp://evil[.]example/x.php").as_deref(),
+            Some("http://evil.example/x.php")
+        );
+        assert_eq!(
+            url("example.com/a").as_deref(),
+            Some("http://example.com/a")
+        );
+        assert_eq!(
+            url("10.0.0.1:8080/admin").as_deref(),
+            Some("http://10.0.0.1:8080/admin")
+        );
+        assert_eq!(url("just some words"), None);
+        assert_eq!(url("notes/readme"), None);
+        assert_eq!(url("user@example.com"), None);
+        assert_eq!(url("mailto:user@example.com"), None);
+    }
+
+    #[test]
+    fn test_email() {
+        assert_eq!(
+            email("mailto:John.Doe@Example.COM").as_deref(),
+            Some("john.doe@example.com")
+        );
+        assert_eq!(
+            email("user[@]evil[.]example").as_deref(),
+            Some("user@evil.example")
+        );
+        assert_eq!(email("@example.com"), None);
+        assert_eq!(email("user@localhost"), None);
+        assert_eq!(email("no address"), None);
+    }
+
+    #[test]
+    fn test_phone() {
+        assert_eq!(phone("+1 (555) 123-4567").as_deref(), Some("+15551234567"));
+        assert_eq!(phone("001 555 123 4567").as_deref(), Some("+15551234567"));
+        assert_eq!(
+            phone("tel:+44 20 7946 0958").as_deref(),
+            Some("+442079460958")
+        );
+        assert_eq!(phone("555 123 4567"), None);
+        assert_eq!(phone("+1 555 CALL NOW"), None);
+        assert_eq!(phone("+0 555 123 4567"), None);
+        assert_eq!(phone("+1234"), None);
+    }
+
+    #[test]
+    fn test_mac() {
+        assert_eq!(
+            mac("AA-BB-CC-DD-EE-FF").as_deref(),
+            Some("aa:bb:cc:dd:ee:ff")
+        );
+        assert_eq!(mac("aabb.ccdd.eeff").as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+        assert_eq!(mac("aa:bb:cc:dd:ee"), None);
+        assert_eq!(mac("gg:bb:cc:dd:ee:ff"), None);
+    }
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash(" 0xDEADbeef ").as_deref(), Some("deadbeef"));
+        assert_eq!(hash(""), None);
+        assert_eq!(hash("xyz"), None);
+    }
+
+    #[test]
+    fn test_defang() {
+        assert_eq!(
+            defang("https://evil.example/a.php?x=1.2"),
+            "hxxps://evil[.]example/a.php?x=1.2"
+        );
+        assert_eq!(defang("user@evil.example"), "user[@]evil[.]example");
+        assert_eq!(defang("10.0.0.1"), "10[.]0[.]0[.]1");
+        // Already defanged input is not defanged twice
+        assert_eq!(defang("evil[.]example"), "evil[.]example");
+    }
+
+    #[test]
+    fn test_refang() {
+        assert_eq!(
+            refang("hxxps[://]evil[dot]example[/]a"),
+            "https://evil.example/a"
+        );
+        assert_eq!(refang("user(at)evil(.)example"), "user@evil.example");
+        assert_eq!(refang("H**P://x{.}y"), "http://x.y");
+        assert_eq!(
+            refang(&defang("ftp://files.example/f")),
+            "ftp://files.example/f"
+        );
+    }
+}
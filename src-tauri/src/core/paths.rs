@@ -2,7 +2,8 @@
 //
 // Centralized path handling and name sanitization
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use tauri::Manager;
 
 use super::error::MosaicError;
@@ -28,7 +29,7 @@ pub fn get_data_dir(app_handle: &tauri::AppHandle) -> MosaicResult<PathBuf> {
     let config_dir = app_handle
         .path()
         .app_config_dir()
-        .map_err(|e| MosaicError::io_error(e))?;
+        .map_err(MosaicError::io_error)?;
 
     let data_dir = config_dir.join("data");
     super::fs::ensure_dir(&data_dir)?;
@@ -36,12 +37,36 @@ pub fn get_data_dir(app_handle: &tauri::AppHandle) -> MosaicResult<PathBuf> {
     Ok(data_dir)
 }
 
+/// App data directory for code that runs without an AppHandle (set once at
+/// startup by `init_data_dir`)
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Resolve the app data directory and remember it for `data_dir`
+pub fn init_data_dir(app_handle: &tauri::AppHandle) -> MosaicResult<PathBuf> {
+    let data_dir = get_data_dir(app_handle)?;
+    set_data_dir(data_dir.clone());
+    Ok(data_dir)
+}
+
+/// Remember the app data directory (the first call wins)
+pub fn set_data_dir(data_dir: PathBuf) {
+    let _ = DATA_DIR.set(data_dir);
+}
+
+/// App data directory remembered at startup
+pub fn data_dir() -> MosaicResult<PathBuf> {
+    DATA_DIR
+        .get()
+        .cloned()
+        .ok_or_else(|| MosaicError::io_error("App data directory not initialized"))
+}
+
 /// Get the app config file path
 pub fn get_config_path(app_handle: &tauri::AppHandle) -> MosaicResult<PathBuf> {
     let config_dir = app_handle
         .path()
         .app_config_dir()
-        .map_err(|e| MosaicError::io_error(e))?;
+        .map_err(MosaicError::io_error)?;
 
     super::fs::ensure_dir(&config_dir)?;
     Ok(config_dir.join("config.json"))
@@ -55,17 +80,49 @@ pub struct VaultPaths {
     pub assets: PathBuf,
     pub attachments: PathBuf,
     pub config: PathBuf,
+    pub backups: PathBuf,
+    pub templates: PathBuf,
+    /// Encryption header; present only in encrypted vaults
+    pub encryption_json: PathBuf,
+    /// Where older versions kept the field-level secrets key; it now lives
+    /// in the app data directory
+    pub secrets_key: PathBuf,
+    /// Which node fields are stored as secrets
+    pub secrets_json: PathBuf,
+    /// Redaction profiles applied to exports
+    pub redaction: PathBuf,
+    /// Hash-chained audit log (one JSON entry per line)
+    pub audit_log: PathBuf,
+    /// Sequence number and hash of the newest audit entry
+    pub audit_head: PathBuf,
+    /// Perceptual hashes of images referenced from nodes
+    pub image_index: PathBuf,
+    /// Imported hash sets (known-good lists and block lists)
+    pub hash_sets: PathBuf,
+    /// HTTP enrichment transforms
+    pub transforms: PathBuf,
 }
 
 impl VaultPaths {
-    pub fn from_root(root: &PathBuf) -> Self {
+    pub fn from_root(root: &Path) -> Self {
         Self {
-            root: root.clone(),
+            root: root.to_path_buf(),
             vault_json: root.join("vault.json"),
             canvases: root.join("canvases"),
             assets: root.join("assets"),
             attachments: root.join("attachments"),
             config: root.join(".mosaicflow"),
+            backups: root.join(".mosaicflow").join("backups"),
+            templates: root.join(".mosaicflow").join("templates"),
+            encryption_json: root.join(".mosaicflow").join("encryption.json"),
+            secrets_key: root.join(".mosaicflow").join("secrets.key"),
+            secrets_json: root.join(".mosaicflow").join("secrets.json"),
+            redaction: root.join(".mosaicflow").join("redaction"),
+            audit_log: root.join(".mosaicflow").join("audit.log"),
+            audit_head: root.join(".mosaicflow").join("audit-head.json"),
+            image_index: root.join(".mosaicflow").join("image-index.json"),
+            hash_sets: root.join(".mosaicflow").join("hashsets"),
+            transforms: root.join(".mosaicflow").join("transforms.json"),
         }
     }
 
@@ -74,6 +131,11 @@ impl VaultPaths {
         self.vault_json.exists()
     }
 
+    /// Check if this vault is encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption_json.exists()
+    }
+
     /// Create all required directories
     pub fn create_all(&self) -> MosaicResult<()> {
         super::fs::ensure_dir(&self.root)?;
@@ -92,25 +154,36 @@ pub struct CanvasPaths {
     pub meta_json: PathBuf,
     pub state_json: PathBuf,
     pub workspace_json: PathBuf,
+    /// Legacy v1 metadata file (replaced by .mosaic/meta.json)
+    pub canvas_json: PathBuf,
     pub nodes: PathBuf,
     pub edges: PathBuf,
     pub images: PathBuf,
     pub attachments: PathBuf,
+    /// Chain-of-custody manifest for imported evidence
+    pub attachments_json: PathBuf,
+    /// Revision counter and content hash of the node files
+    pub revision_json: PathBuf,
+    pub backups: PathBuf,
 }
 
 impl CanvasPaths {
-    pub fn from_root(root: &PathBuf) -> Self {
+    pub fn from_root(root: &Path) -> Self {
         let mosaic = root.join(".mosaic");
         Self {
-            root: root.clone(),
+            root: root.to_path_buf(),
             mosaic: mosaic.clone(),
             meta_json: mosaic.join("meta.json"),
             state_json: mosaic.join("state.json"),
             workspace_json: root.join("workspace.json"),
+            canvas_json: root.join("canvas.json"),
             nodes: root.join("nodes"),
             edges: root.join("edges"),
             images: root.join("images"),
             attachments: root.join("attachments"),
+            attachments_json: mosaic.join("attachments.json"),
+            revision_json: mosaic.join("revision.json"),
+            backups: mosaic.join("backups"),
         }
     }
 
@@ -121,7 +194,18 @@ impl CanvasPaths {
 
     /// Check if this is a valid canvas directory (v1 format)
     pub fn is_valid_v1(&self) -> bool {
-        self.root.join("canvas.json").exists()
+        self.canvas_json.exists()
+    }
+
+    /// Check if this is a canvas directory in either format
+    pub fn is_canvas(&self) -> bool {
+        self.is_valid_v2() || self.is_valid_v1()
+    }
+
+    /// Whether the directory holds canvas files at all, even if its
+    /// metadata is missing; such a directory is a broken canvas, not a folder
+    pub fn has_canvas_files(&self) -> bool {
+        self.is_canvas() || self.mosaic.is_dir() || self.workspace_json.exists()
     }
 
     /// Create all required directories
@@ -136,6 +220,68 @@ impl CanvasPaths {
     }
 }
 
+/// Root of the vault containing `path` (nearest ancestor with vault.json)
+pub fn find_vault_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join("vault.json").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Canvas directories under `canvases_dir`, descending into folders
+///
+/// Any non-hidden subdirectory without canvas files is treated as a folder;
+/// broken canvases are returned too, for callers to open or skip.
+pub fn list_canvas_dirs(canvases_dir: &Path) -> MosaicResult<Vec<PathBuf>> {
+    let mut canvases = Vec::new();
+
+    for dir in super::fs::list_subdirs(canvases_dir)? {
+        if is_hidden(&dir) {
+            continue;
+        }
+        if CanvasPaths::from_root(&dir).has_canvas_files() {
+            canvases.push(dir);
+        } else {
+            canvases.extend(list_canvas_dirs(&dir)?);
+        }
+    }
+
+    Ok(canvases)
+}
+
+/// Folder of a canvas relative to its vault's canvases directory ("" at top level)
+pub fn canvas_folder(canvas_path: &Path) -> String {
+    let Some(parent) = canvas_path.parent() else {
+        return String::new();
+    };
+
+    parent
+        .ancestors()
+        .find(|dir| {
+            dir.file_name().is_some_and(|name| name == "canvases")
+                && dir
+                    .parent()
+                    .is_some_and(|vault| vault.join("vault.json").exists())
+        })
+        .and_then(|canvases_dir| parent.strip_prefix(canvases_dir).ok())
+        .map(to_folder_path)
+        .unwrap_or_default()
+}
+
+/// Relative path as a '/'-separated folder path
+pub fn to_folder_path(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Hidden entries (.mosaic, .git, ...) are never folders
+pub fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -14,6 +14,11 @@ pub fn now_timestamp() -> i64 {
     Utc::now().timestamp_millis()
 }
 
+/// Get current time as a compact, filename-safe stamp (e.g. "20240131T154501123Z")
+pub fn now_file_stamp() -> String {
+    Utc::now().format("%Y%m%dT%H%M%S%3fZ").to_string()
+}
+
 /// Parse ISO 8601 string to timestamp
 pub fn parse_iso(iso: &str) -> Option<i64> {
     chrono::DateTime::parse_from_rfc3339(iso)
@@ -21,6 +26,27 @@ pub fn parse_iso(iso: &str) -> Option<i64> {
         .map(|dt| dt.timestamp_millis())
 }
 
+/// Convert a timestamp to ISO 8601 (RFC 3339)
+///
+/// Accepts ISO 8601 strings (returned unchanged) and the Unix epoch strings
+/// written by v1 vaults (milliseconds, or seconds for 10 digits or fewer).
+pub fn to_iso(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+
+    if chrono::DateTime::parse_from_rfc3339(raw).is_ok() {
+        return Some(raw.to_string());
+    }
+
+    if raw.is_empty() || !raw.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let value: i64 = raw.parse().ok()?;
+    let millis = if raw.len() <= 10 { value * 1000 } else { value };
+
+    chrono::DateTime::<Utc>::from_timestamp_millis(millis).map(|dt| dt.to_rfc3339())
+}
+
 /// Format relative time (e.g., "2 hours ago")
 pub fn relative_time(iso: &str) -> String {
     let now = Utc::now();
@@ -67,3 +93,25 @@ pub fn relative_time(iso: &str) -> String {
     let years = months / 12;
     format!("{} year{} ago", years, if years == 1 { "" } else { "s" })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_iso() {
+        assert_eq!(
+            to_iso("1700000000000").as_deref(),
+            Some("2023-11-14T22:13:20+00:00")
+        );
+        assert_eq!(
+            to_iso("1700000000").as_deref(),
+            Some("2023-11-14T22:13:20+00:00")
+        );
+        assert_eq!(
+            to_iso("2024-01-31T10:00:00+00:00").as_deref(),
+            Some("2024-01-31T10:00:00+00:00")
+        );
+        assert_eq!(to_iso("yesterday"), None);
+    }
+}
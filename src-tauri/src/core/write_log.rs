@@ -0,0 +1,64 @@
+// MosaicFlow Write Log
+//
+// Remembers paths the app itself wrote or removed recently, so the vault
+// watcher can tell our own saves apart from external edits
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long a write counts as our own (must outlast the watcher debounce)
+pub const SELF_WRITE_WINDOW: Duration = Duration::from_secs(2);
+
+fn recent() -> &'static Mutex<HashMap<PathBuf, Instant>> {
+    static RECENT: OnceLock<Mutex<HashMap<PathBuf, Instant>>> = OnceLock::new();
+    RECENT.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Resolve symlinks via the parent so removed files normalize too
+fn normalize(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .map(|p| p.join(name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Record that the app just wrote, created or removed a path
+pub fn record(path: &Path) {
+    if let Ok(mut map) = recent().lock() {
+        let now = Instant::now();
+        map.retain(|_, at| now.duration_since(*at) < SELF_WRITE_WINDOW);
+        map.insert(normalize(path), now);
+    }
+}
+
+/// Whether the app touched exactly this path recently
+///
+/// Directories match only themselves, so a file someone else drops into a
+/// folder we just created still counts as external.
+pub fn is_recent(path: &Path) -> bool {
+    let path = normalize(path);
+    let now = Instant::now();
+
+    recent().lock().is_ok_and(|map| {
+        map.get(&path)
+            .is_some_and(|at| now.duration_since(*at) < SELF_WRITE_WINDOW)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_recent_matches_exact_path() {
+        let dir = std::env::temp_dir().join(format!("write-log-{}", std::process::id()));
+        record(&dir);
+        assert!(is_recent(&dir));
+        assert!(!is_recent(&dir.join("external.json")));
+    }
+}
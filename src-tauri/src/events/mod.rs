@@ -3,6 +3,7 @@
 // Provides real-time updates to the frontend via Tauri events
 // This is the backbone for reactive UI updates
 
+use crate::core::MosaicError;
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter};
 
@@ -13,6 +14,8 @@ pub mod event_names {
     pub const VAULT_OPENED: &str = "vault:opened";
     pub const VAULT_UPDATED: &str = "vault:updated";
     pub const VAULT_CLOSED: &str = "vault:closed";
+    pub const VAULT_LOCKED: &str = "vault:locked";
+    pub const VAULT_UNLOCKED: &str = "vault:unlocked";
 
     // Canvas events
     pub const CANVAS_CREATED: &str = "canvas:created";
@@ -21,6 +24,11 @@ pub mod event_names {
     pub const CANVAS_CLOSED: &str = "canvas:closed";
     pub const CANVAS_DELETED: &str = "canvas:deleted";
 
+    // Folder events
+    pub const FOLDER_CREATED: &str = "folder:created";
+    pub const FOLDER_UPDATED: &str = "folder:updated";
+    pub const FOLDER_DELETED: &str = "folder:deleted";
+
     // Workspace events
     pub const WORKSPACE_LOADED: &str = "workspace:loaded";
     pub const WORKSPACE_SAVED: &str = "workspace:saved";
@@ -36,6 +44,9 @@ pub mod event_names {
     pub const STATE_CHANGED: &str = "state:changed";
     pub const HISTORY_CHANGED: &str = "history:changed";
 
+    // Audit events
+    pub const AUDIT_FAILED: &str = "audit:failed";
+
     // File system events
     pub const FILE_CREATED: &str = "fs:created";
     pub const FILE_MODIFIED: &str = "fs:modified";
@@ -59,6 +70,15 @@ pub struct CanvasEvent {
     pub vault_id: String,
 }
 
+/// Event payload for canvas folder changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderEvent {
+    pub vault_path: String,
+    pub folder_path: String,
+    /// Previous path for renames and moves
+    pub previous_path: Option<String>,
+}
+
 /// Event payload for workspace changes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkspaceEvent {
@@ -80,6 +100,26 @@ pub enum WorkspaceChangeType {
     EdgesUpdated,
     EdgesDeleted,
     BatchUpdate,
+    ExternalChange,
+}
+
+/// Event payload for file system changes made outside the app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEvent {
+    pub path: String,
+    pub vault_path: String,
+    pub canvas_id: Option<String>,
+    pub canvas_path: Option<String>,
+    pub node_id: Option<String>,
+}
+
+/// Kind of file system change
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    Created,
+    Modified,
+    Deleted,
 }
 
 /// Event payload for state changes
@@ -145,6 +185,28 @@ impl<'a> EventEmitter<'a> {
         );
     }
 
+    pub fn vault_locked(&self, vault_id: &str, path: &str, name: &str) {
+        let _ = self.emit(
+            event_names::VAULT_LOCKED,
+            VaultEvent {
+                vault_id: vault_id.to_string(),
+                vault_path: path.to_string(),
+                vault_name: name.to_string(),
+            },
+        );
+    }
+
+    pub fn vault_unlocked(&self, vault_id: &str, path: &str, name: &str) {
+        let _ = self.emit(
+            event_names::VAULT_UNLOCKED,
+            VaultEvent {
+                vault_id: vault_id.to_string(),
+                vault_path: path.to_string(),
+                vault_name: name.to_string(),
+            },
+        );
+    }
+
     // Canvas events
     pub fn canvas_created(&self, canvas_id: &str, path: &str, name: &str, vault_id: &str) {
         let _ = self.emit(
@@ -194,6 +256,40 @@ impl<'a> EventEmitter<'a> {
         );
     }
 
+    // Folder events
+    pub fn folder_created(&self, vault_path: &str, folder_path: &str) {
+        let _ = self.emit(
+            event_names::FOLDER_CREATED,
+            FolderEvent {
+                vault_path: vault_path.to_string(),
+                folder_path: folder_path.to_string(),
+                previous_path: None,
+            },
+        );
+    }
+
+    pub fn folder_updated(&self, vault_path: &str, folder_path: &str, previous_path: &str) {
+        let _ = self.emit(
+            event_names::FOLDER_UPDATED,
+            FolderEvent {
+                vault_path: vault_path.to_string(),
+                folder_path: folder_path.to_string(),
+                previous_path: Some(previous_path.to_string()),
+            },
+        );
+    }
+
+    pub fn folder_deleted(&self, vault_path: &str, folder_path: &str) {
+        let _ = self.emit(
+            event_names::FOLDER_DELETED,
+            FolderEvent {
+                vault_path: vault_path.to_string(),
+                folder_path: folder_path.to_string(),
+                previous_path: None,
+            },
+        );
+    }
+
     // Workspace events
     pub fn workspace_loaded(&self, canvas_path: &str) {
         let _ = self.emit(
@@ -253,6 +349,33 @@ impl<'a> EventEmitter<'a> {
         );
     }
 
+    pub fn workspace_changed_externally(
+        &self,
+        canvas_path: &str,
+        node_ids: Vec<String>,
+        edge_ids: Vec<String>,
+    ) {
+        let _ = self.emit(
+            event_names::WORKSPACE_CHANGED,
+            WorkspaceEvent {
+                canvas_path: canvas_path.to_string(),
+                change_type: WorkspaceChangeType::ExternalChange,
+                node_ids: Some(node_ids),
+                edge_ids: Some(edge_ids),
+            },
+        );
+    }
+
+    // File system events
+    pub fn file_changed(&self, kind: FileChangeKind, event: FileEvent) {
+        let name = match kind {
+            FileChangeKind::Created => event_names::FILE_CREATED,
+            FileChangeKind::Modified => event_names::FILE_MODIFIED,
+            FileChangeKind::Deleted => event_names::FILE_DELETED,
+        };
+        let _ = self.emit(name, event);
+    }
+
     // State events
     pub fn state_changed(&self, vault_id: Option<String>, canvas_id: Option<String>) {
         let _ = self.emit(
@@ -273,4 +396,9 @@ impl<'a> EventEmitter<'a> {
             },
         );
     }
+
+    // Audit events
+    pub fn audit_failed(&self, error: &MosaicError) {
+        let _ = self.emit(event_names::AUDIT_FAILED, error.clone());
+    }
 }
@@ -16,6 +16,7 @@ pub mod services;
 
 // Re-export commands for Tauri registration
 use commands::*;
+use services::WatcherState;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -26,6 +27,14 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_persisted_scope::init())
         .plugin(tauri_plugin_dialog::init())
+        // Managed state
+        .manage(WatcherState::default())
+        // Services that run without an AppHandle (vault secret keys) find
+        // the app data directory through core::paths
+        .setup(|app| {
+            core::paths::init_data_dir(app.handle())?;
+            Ok(())
+        })
         // Command handlers
         .invoke_handler(tauri::generate_handler![
             // Vault commands
@@ -35,26 +44,113 @@ pub fn run() {
             update_vault_description,
             is_valid_vault,
             get_vault_info,
+            watch_vault,
+            unwatch_vault,
+            // Encryption commands
+            get_vault_lock_status,
+            unlock_vault,
+            lock_vault,
+            set_vault_encryption,
+            // Audit commands
+            verify_audit_log,
+            query_audit_log,
+            // Attachment commands
+            import_attachment,
+            list_attachments,
+            verify_attachments,
+            // Image commands
+            import_image,
+            regenerate_image_variants,
+            index_image_hashes,
+            find_similar_images,
+            // Metadata commands
+            extract_metadata,
+            materialize_metadata,
+            // Indicator commands
+            extract_indicators,
+            extract_node_indicators,
+            create_indicator_nodes,
+            normalize_indicator,
+            defang_indicator,
+            refang_text,
+            // Hash commands
+            compute_attachment_hashes,
+            create_hash_nodes,
+            identify_hash,
+            import_hash_set,
+            list_hash_sets,
+            delete_hash_set,
+            check_hashes,
+            apply_hash_sets,
+            // Breach commands
+            get_breach_dataset,
+            set_breach_dataset,
+            clear_breach_dataset,
+            check_breached_passwords,
+            // GeoIP commands
+            get_geoip_databases,
+            set_geoip_databases,
+            lookup_ip,
+            enrich_ip_nodes,
+            // Transform commands
+            list_transforms,
+            get_transform_config,
+            set_transform_config,
+            run_transform,
+            // Scan commands
+            import_nmap_scan,
+            import_nmap_scan_to_new_canvas,
+            // Asset commands
+            import_asset,
+            resolve_asset,
+            list_assets,
+            gc_assets,
+            // Secret commands
+            reveal_secret,
+            get_secret_fields,
+            set_secret_fields,
             // Canvas commands
             create_canvas,
             open_canvas,
             list_canvases,
             rename_canvas,
             delete_canvas,
+            duplicate_canvas,
+            copy_canvas_to_vault,
+            move_canvas,
             update_canvas_tags,
+            update_canvas_classification,
             update_canvas_description,
             load_canvas_state,
             save_canvas_state,
+            // Folder commands
+            get_canvas_folder_tree,
+            create_canvas_folder,
+            rename_canvas_folder,
+            move_canvas_folder,
+            delete_canvas_folder,
+            list_canvases_in_folder,
+            move_canvas_to_folder,
+            // Template commands
+            save_canvas_as_template,
+            list_templates,
+            get_template,
+            delete_template,
             // Workspace commands
             load_workspace,
             save_workspace,
+            get_workspace_conflict,
             update_nodes,
             update_edges,
             add_node,
             remove_node,
+            classify_nodes,
             add_edge,
             remove_edge,
             batch_update_workspace,
+            read_canvas_file,
+            write_canvas_file,
+            write_node_files,
             // State commands
             load_app_state,
             save_app_state,
@@ -66,6 +162,18 @@ pub fn run() {
             save_png,
             svg_to_png,
             svg_to_png_headless,
+            export_canvas_json,
+            prepare_canvas_export,
+            // Evidence package commands
+            get_signing_identity,
+            export_evidence_package,
+            verify_package,
+            // Redaction commands
+            list_redaction_profiles,
+            get_redaction_profile,
+            save_redaction_profile,
+            delete_redaction_profile,
+            preview_redaction,
             // History commands
             load_history,
             track_vault_open,
@@ -76,6 +184,10 @@ pub fn run() {
             get_recent_canvases,
             find_vault_by_id,
             find_canvas_by_id,
+            // Migration commands
+            migrate_vault,
+            migrate_canvas,
+            migrate_vault_all,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,40 @@
+// Asset Models
+//
+// Data structures for the vault's content-addressed asset store in assets/
+
+use serde::{Deserialize, Serialize};
+
+/// A blob in the asset store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetInfo {
+    /// SHA-256 of the content, which is also its key in the store
+    pub hash: String,
+    /// Absolute path of the blob in the current vault, for `convertFileSrc`
+    /// (blobs in encrypted vaults are stored encrypted and must be read
+    /// through the backend)
+    pub path: String,
+    /// Stable `asset://<sha256>` reference to keep in node data
+    pub uri: String,
+    /// Size on disk in bytes
+    pub size: u64,
+    pub mime_type: String,
+}
+
+/// An asset and how many nodes refer to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetUsage {
+    #[serde(flatten)]
+    pub asset: AssetInfo,
+    /// Nodes in canvases and templates whose data mentions the asset
+    pub references: usize,
+}
+
+/// Result of removing unreferenced assets
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssetGcReport {
+    pub removed: Vec<AssetInfo>,
+    /// Bytes freed by the removed blobs
+    pub freed_bytes: u64,
+    /// Assets still referenced
+    pub kept: usize,
+}
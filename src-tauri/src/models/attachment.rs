@@ -0,0 +1,75 @@
+// Attachment Models
+//
+// Data structures for evidence files imported into a canvas and their
+// chain-of-custody manifest in .mosaic/attachments.json
+
+use serde::{Deserialize, Serialize};
+
+/// Custody record of one imported file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentRecord {
+    /// Unique identifier (UUID v4)
+    pub id: String,
+    /// Node the file belongs to
+    pub node_id: String,
+    /// Stored file, relative to the canvas root (e.g. "attachments/1a2b3c4d-report.pdf")
+    pub path: String,
+    /// Where the file was imported from
+    pub original_path: String,
+    /// Size in bytes
+    pub size: u64,
+    pub mime_type: String,
+    pub sha256: String,
+    pub sha1: String,
+    pub md5: String,
+    /// When the file was imported (ISO 8601)
+    pub imported_at: String,
+    /// OS user who imported the file
+    pub imported_by: String,
+}
+
+/// Every attachment imported into a canvas, oldest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttachmentManifest {
+    #[serde(default)]
+    pub attachments: Vec<AttachmentRecord>,
+}
+
+/// State of an attachment compared to its manifest record and the hash
+/// the audit log recorded at import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentStatus {
+    Intact,
+    Modified,
+    Missing,
+    /// The manifest's hash differs from the one in the audit log
+    ManifestAltered,
+    /// The audit log has no import entry for the attachment
+    Unaudited,
+}
+
+/// Result of re-hashing one attachment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentCheck {
+    pub id: String,
+    pub node_id: String,
+    pub path: String,
+    pub status: AttachmentStatus,
+    /// SHA-256 of the file as it is now
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// SHA-256 recorded in the audit log at import
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audited_sha256: Option<String>,
+}
+
+/// Result of verifying every attachment of a canvas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentVerification {
+    /// True when every attachment is intact and the audit log verifies
+    pub valid: bool,
+    /// Whether the vault's audit log chain verified
+    pub audit_valid: bool,
+    pub attachments: Vec<AttachmentCheck>,
+}
@@ -0,0 +1,158 @@
+// Audit Models
+//
+// Data structures for the per-vault, hash-chained audit log
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// `prev_hash` of the first entry
+pub const AUDIT_GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Operation recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOperation {
+    // Vault
+    VaultCreate,
+    VaultRename,
+    VaultUpdate,
+    VaultEncryption,
+    VaultMigrate,
+    // Canvas
+    CanvasCreate,
+    CanvasRename,
+    CanvasDelete,
+    CanvasDuplicate,
+    CanvasCopy,
+    CanvasMove,
+    CanvasUpdate,
+    CanvasMigrate,
+    // Folder
+    FolderCreate,
+    FolderRename,
+    FolderMove,
+    FolderDelete,
+    // Workspace
+    WorkspaceSave,
+    NodesUpdate,
+    EdgesUpdate,
+    NodeAdd,
+    NodeRemove,
+    NodesClassify,
+    EdgeAdd,
+    EdgeRemove,
+    WorkspaceBatch,
+    // Attachments and assets
+    AttachmentImport,
+    AssetImport,
+    AssetGc,
+    ImageVariantsRegenerate,
+    MetadataMaterialize,
+    // Analysis
+    IndicatorsMaterialize,
+    HashNodesCreate,
+    HashSetImport,
+    HashSetDelete,
+    HashSetsApply,
+    PasswordsCheck,
+    GeoIpEnrich,
+    TransformRun,
+    ScanImport,
+    // Templates, secrets and exports
+    TemplateSave,
+    TemplateDelete,
+    SecretReveal,
+    SecretFieldsUpdate,
+    BreachDatasetUpdate,
+    GeoIpDatabasesUpdate,
+    TransformsUpdate,
+    RedactionProfileSave,
+    RedactionProfileDelete,
+    Export,
+}
+
+/// One line of .mosaicflow/audit.log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the chain, starting at 1
+    pub seq: u64,
+    /// When the operation happened (ISO 8601)
+    pub timestamp: String,
+    /// OS user running the app
+    pub actor: String,
+    pub operation: AuditOperation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canvas_id: Option<String>,
+    /// Canvas path relative to the vault root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canvas_path: Option<String>,
+    /// Operation-specific details (node/edge IDs, names, destinations)
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+    /// Hash of the previous entry
+    pub prev_hash: String,
+    /// HMAC-SHA256 of this entry with `hash` left empty, under the vault's
+    /// audit key
+    #[serde(default)]
+    pub hash: String,
+}
+
+impl AuditEntry {
+    /// Keyed hash over every field but `hash` itself
+    pub fn compute_hash(&self, key: &[u8]) -> String {
+        crate::core::crypto::hmac_sha256(key, &self.unhashed_bytes())
+    }
+
+    /// Whether `hash` matches the entry, compared in constant time
+    pub fn verify_hash(&self, key: &[u8]) -> bool {
+        crate::core::crypto::verify_hmac_sha256(key, &self.unhashed_bytes(), &self.hash)
+    }
+
+    fn unhashed_bytes(&self) -> Vec<u8> {
+        let unhashed = AuditEntry {
+            hash: String::new(),
+            ..self.clone()
+        };
+        serde_json::to_vec(&unhashed).unwrap_or_default()
+    }
+}
+
+/// Newest entry, stored next to the log so truncation is detectable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditHead {
+    pub seq: u64,
+    pub hash: String,
+}
+
+/// Result of checking a vault's audit chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditVerification {
+    pub valid: bool,
+    /// Entries checked before the first problem (or in total)
+    pub entries: u64,
+    /// Line number of the first broken entry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broken_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Filter for querying the audit log (all fields optional)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditQuery {
+    /// Canvas ID or path (absolute or relative to the vault)
+    #[serde(default)]
+    pub canvas: Option<String>,
+    #[serde(default)]
+    pub operations: Vec<AuditOperation>,
+    /// Inclusive lower bound (ISO 8601)
+    #[serde(default)]
+    pub since: Option<String>,
+    /// Inclusive upper bound (ISO 8601)
+    #[serde(default)]
+    pub until: Option<String>,
+    /// Return only the newest `limit` matches
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
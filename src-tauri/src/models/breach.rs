@@ -0,0 +1,48 @@
+// Breach Models
+//
+// Data structures for checking credential passwords against a local
+// Pwned Passwords-style SHA-1 dataset
+
+use super::WorkspaceRevision;
+use serde::{Deserialize, Serialize};
+
+/// Layout of a breached-password dataset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BreachDatasetFormat {
+    /// Directory of range files named by the first 5 hex digits of the
+    /// SHA-1 ("21BD1.txt"), each holding `SUFFIX:COUNT` lines
+    Range,
+    /// One file of `SHA1:COUNT` lines sorted by hash
+    Combined,
+}
+
+/// Breached-password dataset configured for a vault, stored per vault in
+/// the app data directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreachDataset {
+    /// Directory or file on this machine; never copied into the vault
+    pub path: String,
+    pub format: BreachDatasetFormat,
+    pub configured_at: String,
+}
+
+/// A credential whose password appears in the dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordBreach {
+    pub node_id: String,
+    /// Times the password was seen in breaches
+    pub count: u64,
+}
+
+/// Result of checking a canvas's credential passwords
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreachCheckReport {
+    /// Credential nodes with a password
+    pub checked: usize,
+    pub breached: Vec<PasswordBreach>,
+    pub updated_node_ids: Vec<String>,
+    /// Workspace revision after the update, if anything changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: Option<WorkspaceRevision>,
+}
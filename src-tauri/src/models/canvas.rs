@@ -2,6 +2,7 @@
 //
 // Data structures for canvas management
 
+use super::Tlp;
 use serde::{Deserialize, Serialize};
 
 /// Canvas metadata stored in .mosaic/meta.json
@@ -19,6 +20,9 @@ pub struct CanvasMeta {
     /// Tags for organization
     #[serde(default)]
     pub tags: Vec<String>,
+    /// TLP marking of the canvas (and of nodes without their own)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classification: Option<Tlp>,
     /// When the canvas was created (ISO 8601)
     pub created_at: String,
     /// When the canvas was last modified (ISO 8601)
@@ -41,6 +45,7 @@ impl CanvasMeta {
             name,
             description: String::new(),
             tags: vec![],
+            classification: None,
             created_at: now.clone(),
             updated_at: now,
             version: default_version(),
@@ -57,6 +62,11 @@ impl CanvasMeta {
         self
     }
 
+    pub fn with_classification(mut self, classification: Option<Tlp>) -> Self {
+        self.classification = classification;
+        self
+    }
+
     pub fn touch(&mut self) {
         self.updated_at = crate::core::now_iso();
     }
@@ -84,22 +94,34 @@ pub struct CanvasInfo {
     pub name: String,
     pub description: String,
     pub path: String,
+    /// Folder within the vault's canvases directory ("" at top level)
+    #[serde(default)]
+    pub folder: String,
     pub created_at: String,
     pub updated_at: String,
     pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classification: Option<Tlp>,
+    /// Files are behind the current schema; see `migrate_canvas`
+    #[serde(default)]
+    pub needs_migration: bool,
 }
 
 impl CanvasInfo {
+    /// Info for a canvas at `path`; the folder is left for the caller to fill in
     pub fn from_meta(meta: &CanvasMeta, path: String) -> Self {
         Self {
             id: meta.id.clone(),
             vault_id: meta.vault_id.clone(),
             name: meta.name.clone(),
             description: meta.description.clone(),
+            folder: String::new(),
             path,
             created_at: meta.created_at.clone(),
             updated_at: meta.updated_at.clone(),
             tags: meta.tags.clone(),
+            classification: meta.classification,
+            needs_migration: false,
         }
     }
 }
@@ -125,7 +147,7 @@ impl From<&CanvasInfo> for CanvasRef {
 }
 
 /// Canvas UI state stored in .mosaic/state.json
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CanvasUIState {
     /// Viewport position and zoom
     #[serde(default)]
@@ -142,12 +164,28 @@ pub struct CanvasUIState {
     /// Last modified timestamp
     #[serde(default)]
     pub updated_at: String,
+    /// Schema version for migrations
+    #[serde(default = "default_version")]
+    pub version: String,
 }
 
 fn default_canvas_mode() -> String {
     "select".to_string()
 }
 
+impl Default for CanvasUIState {
+    fn default() -> Self {
+        Self {
+            viewport: ViewportState::default(),
+            selected_nodes: vec![],
+            selected_edges: vec![],
+            canvas_mode: default_canvas_mode(),
+            updated_at: String::new(),
+            version: default_version(),
+        }
+    }
+}
+
 /// Viewport state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ViewportState {
@@ -0,0 +1,54 @@
+// Classification Models
+//
+// Traffic Light Protocol (TLP 2.0) markings on canvases and nodes
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// TLP sharing level, ordered from least to most restricted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tlp {
+    Clear,
+    Green,
+    Amber,
+    #[serde(rename = "amber+strict")]
+    AmberStrict,
+    Red,
+}
+
+impl Tlp {
+    /// Official label colour (shown on a black background)
+    pub fn color(&self) -> &'static str {
+        match self {
+            Tlp::Clear => "#FFFFFF",
+            Tlp::Green => "#33FF00",
+            Tlp::Amber | Tlp::AmberStrict => "#FFC000",
+            Tlp::Red => "#FF2B2B",
+        }
+    }
+}
+
+impl fmt::Display for Tlp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Tlp::Clear => "TLP:CLEAR",
+            Tlp::Green => "TLP:GREEN",
+            Tlp::Amber => "TLP:AMBER",
+            Tlp::AmberStrict => "TLP:AMBER+STRICT",
+            Tlp::Red => "TLP:RED",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// What an export does with content above its sharing level
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClassificationPolicy {
+    /// Fail the whole export
+    #[default]
+    Refuse,
+    /// Leave out nodes above the level
+    Filter,
+}
@@ -0,0 +1,78 @@
+// Encryption Models
+//
+// Data structures for passphrase-encrypted vaults
+
+use serde::{Deserialize, Serialize};
+
+/// Encryption header stored (in plain JSON) in .mosaicflow/encryption.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultEncryption {
+    /// Key derivation settings
+    pub kdf: KdfParams,
+    /// AEAD cipher used for file contents
+    #[serde(default = "default_cipher")]
+    pub cipher: String,
+    /// Known plaintext encrypted with the vault key (base64), used to check passphrases
+    pub verifier: String,
+    /// When encryption was enabled (ISO 8601)
+    pub created_at: String,
+    /// Set while files are being encrypted or decrypted in place; plain
+    /// files in the vault are only read while it is
+    #[serde(default)]
+    pub converting: bool,
+    /// HMAC of the verifier under the vault key, written with `converting`
+    /// so the flag cannot be set without the key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub converting_mac: Option<String>,
+    /// Schema version for migrations
+    #[serde(default = "default_version")]
+    pub version: String,
+}
+
+fn default_cipher() -> String {
+    "xchacha20poly1305".to_string()
+}
+
+fn default_version() -> String {
+    "1.0.0".to_string()
+}
+
+/// Argon2 key derivation parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Always "argon2id"
+    pub algorithm: String,
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of passes
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+    /// Random salt (base64)
+    pub salt: String,
+}
+
+/// Lock state of a vault returned to frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultLockStatus {
+    pub vault_path: String,
+    /// Whether the vault is encrypted at all
+    pub encrypted: bool,
+    /// Whether the vault key is currently held in memory
+    pub unlocked: bool,
+    /// Inactivity timeout before the vault locks itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_lock_minutes: Option<u32>,
+}
+
+/// Outcome of encrypting or decrypting a vault in place
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultConversionReport {
+    pub vault_path: String,
+    /// Whether the vault is encrypted after the conversion
+    pub encrypted: bool,
+    /// Number of files rewritten
+    pub files_converted: usize,
+    /// Number of files already in the target form
+    pub files_skipped: usize,
+}
@@ -0,0 +1,50 @@
+// Export Models
+//
+// Data structures for canvas data exports
+
+use super::{CanvasMeta, ClassificationPolicy, Tlp, WorkspaceData};
+use serde::{Deserialize, Serialize};
+
+/// Options controlling what an export contains
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportOptions {
+    /// Write sensitive fields in cleartext instead of sealed
+    #[serde(default)]
+    pub reveal_secrets: bool,
+    /// Redaction profile applied before writing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redaction_profile: Option<String>,
+    /// Highest TLP level that may leave the vault
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_classification: Option<Tlp>,
+    /// What to do with content above `max_classification`
+    #[serde(default)]
+    pub classification_policy: ClassificationPolicy,
+}
+
+/// Self-contained canvas export written by export_canvas_json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanvasExport {
+    /// Format marker
+    pub format: String,
+    /// When the export was produced (ISO 8601)
+    pub exported_at: String,
+    /// Canvas metadata; `classification` is the highest marking of the
+    /// exported content
+    pub canvas: CanvasMeta,
+    /// Nodes, edges and settings
+    pub workspace: WorkspaceData,
+}
+
+impl CanvasExport {
+    pub const FORMAT: &'static str = "mosaicflow-canvas";
+
+    pub fn new(canvas: CanvasMeta, workspace: WorkspaceData) -> Self {
+        Self {
+            format: Self::FORMAT.to_string(),
+            exported_at: crate::core::now_iso(),
+            canvas,
+            workspace,
+        }
+    }
+}
@@ -0,0 +1,19 @@
+// Folder Models
+//
+// Data structures for the canvas folder hierarchy inside a vault
+
+use serde::{Deserialize, Serialize};
+
+/// Folder in a vault's canvases directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanvasFolder {
+    /// Folder name ("" for the canvases root)
+    pub name: String,
+    /// '/'-separated path relative to the canvases directory ("" for the root)
+    pub path: String,
+    /// Number of canvases directly inside this folder
+    pub canvas_count: usize,
+    /// Subfolders, sorted by name
+    #[serde(default)]
+    pub folders: Vec<CanvasFolder>,
+}
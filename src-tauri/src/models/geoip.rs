@@ -0,0 +1,82 @@
+// GeoIP Models
+//
+// Data structures for offline IP geolocation and ASN lookups in
+// MaxMind-format (.mmdb) databases
+
+use super::{WorkspaceEdge, WorkspaceNode, WorkspaceRevision};
+use serde::{Deserialize, Serialize};
+
+/// Databases configured for a vault, stored per vault in the app data
+/// directory
+///
+/// Paths point to files on this machine; databases are never copied into
+/// the vault.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GeoIpConfig {
+    /// City (or Country) database, e.g. GeoLite2-City.mmdb
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub city_db: Option<String>,
+    /// ASN database, e.g. GeoLite2-ASN.mmdb
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asn_db: Option<String>,
+}
+
+/// What the databases know about an address; stored on nodes as `geoip`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeoIpInfo {
+    pub ip: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// ISO 3166-1 alpha-2
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    /// Radius in km around the coordinates the address is likely in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accuracy_radius: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn: Option<u32>,
+    /// Organization announcing the address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+}
+
+impl GeoIpInfo {
+    /// Whether neither database had a record for the address
+    pub fn is_empty(&self) -> bool {
+        self.country_code.is_none()
+            && self.city.is_none()
+            && self.latitude.is_none()
+            && self.asn.is_none()
+            && self.organization.is_none()
+    }
+}
+
+/// Lookup result for one node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeGeoIp {
+    pub node_id: String,
+    pub info: GeoIpInfo,
+}
+
+/// Nodes annotated, and map and organization nodes added, by enrichment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoIpEnrichment {
+    /// Nodes whose address was found in a database
+    pub results: Vec<NodeGeoIp>,
+    /// Nodes whose `geoip` annotation changed
+    pub updated_node_ids: Vec<String>,
+    pub nodes: Vec<WorkspaceNode>,
+    pub edges: Vec<WorkspaceEdge>,
+    /// Workspace revision after the update, if anything changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: Option<WorkspaceRevision>,
+}
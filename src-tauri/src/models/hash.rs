@@ -0,0 +1,137 @@
+// Hash Models
+//
+// Data structures for file hashes, hash identification and local hash sets
+// (known-good lists and block lists)
+
+use super::{WorkspaceEdge, WorkspaceNode, WorkspaceRevision};
+use serde::{Deserialize, Serialize};
+
+/// Algorithms `hash` nodes are created for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 4] = [Self::Md5, Self::Sha1, Self::Sha256, Self::Sha512];
+
+    /// Name as stored in a hash node's `algorithm`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Md5 => "md5",
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Sha1 => "SHA-1",
+            Self::Sha256 => "SHA-256",
+            Self::Sha512 => "SHA-512",
+        }
+    }
+}
+
+/// Hex digests of a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileHashes {
+    pub md5: String,
+    pub sha1: String,
+    pub sha256: String,
+    pub sha512: String,
+}
+
+impl FileHashes {
+    pub fn get(&self, algorithm: HashAlgorithm) -> &str {
+        match algorithm {
+            HashAlgorithm::Md5 => &self.md5,
+            HashAlgorithm::Sha1 => &self.sha1,
+            HashAlgorithm::Sha256 => &self.sha256,
+            HashAlgorithm::Sha512 => &self.sha512,
+        }
+    }
+}
+
+/// Likely algorithm of a pasted hash value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashIdentification {
+    /// The value as a hash node would store it
+    pub value: String,
+    /// Hash node `algorithm`: "md5", "sha1", "sha256", "sha512" or "other"
+    pub algorithm: String,
+    /// Algorithms producing values of this form, most likely first
+    pub candidates: Vec<String>,
+}
+
+/// What being listed in a hash set says about a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashSetKind {
+    /// Known software, e.g. an NSRL-style list
+    KnownGood,
+    Suspicious,
+    /// Block list
+    Malicious,
+}
+
+impl HashSetKind {
+    /// Hash node `threatLevel` for a listed hash
+    pub fn threat_level(&self) -> &'static str {
+        match self {
+            Self::KnownGood => "safe",
+            Self::Suspicious => "suspicious",
+            Self::Malicious => "malicious",
+        }
+    }
+}
+
+/// A hash set imported into a vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashSetInfo {
+    pub id: String,
+    pub name: String,
+    pub kind: HashSetKind,
+    /// File the set was imported from
+    pub source: String,
+    pub imported_at: String,
+    /// Number of distinct hashes
+    pub count: usize,
+}
+
+/// A hash found in a hash set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashSetMatch {
+    pub hash: String,
+    pub set_id: String,
+    pub set_name: String,
+    pub kind: HashSetKind,
+}
+
+/// Hash nodes created or updated for an attachment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashNodes {
+    pub hashes: FileHashes,
+    pub nodes: Vec<WorkspaceNode>,
+    /// Existing hash nodes whose algorithm, file name or threat level changed
+    pub updated_node_ids: Vec<String>,
+    pub edges: Vec<WorkspaceEdge>,
+    pub revision: WorkspaceRevision,
+}
+
+/// Result of checking a canvas's hash nodes against the vault's hash sets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashSetReport {
+    /// Hash nodes looked up
+    pub checked: usize,
+    pub updated_node_ids: Vec<String>,
+    /// Workspace revision after the update, if anything changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: Option<WorkspaceRevision>,
+}
@@ -95,6 +95,14 @@ impl AppHistory {
         self.canvases.retain(|c| c.id != canvas_id);
     }
 
+    /// Point a canvas entry at its new vault and location after a move
+    pub fn relocate_canvas(&mut self, canvas_id: &str, vault_id: String, path: String) {
+        if let Some(entry) = self.canvases.iter_mut().find(|c| c.id == canvas_id) {
+            entry.vault_id = vault_id;
+            entry.path = path;
+        }
+    }
+
     /// Get recent vaults
     pub fn recent_vaults(&self, limit: usize) -> Vec<&VaultHistoryEntry> {
         self.vaults.iter().take(limit).collect()
@@ -108,7 +116,7 @@ impl AppHistory {
     ) -> Vec<&CanvasHistoryEntry> {
         self.canvases
             .iter()
-            .filter(|c| vault_id.map_or(true, |vid| c.vault_id == vid))
+            .filter(|c| vault_id.is_none_or(|vid| c.vault_id == vid))
             .take(limit)
             .collect()
     }
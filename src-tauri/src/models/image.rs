@@ -0,0 +1,126 @@
+// Image Models
+//
+// Data structures for images imported into a canvas's images/ directory,
+// their downscaled variants and the vault's perceptual hash index
+
+use super::{AttachmentRecord, WorkspaceRevision};
+use serde::{Deserialize, Serialize};
+
+/// An original image and the variants generated next to it
+///
+/// Paths are relative to the canvas root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageVariants {
+    pub path: String,
+    pub thumbnail_path: String,
+    pub preview_path: String,
+    /// Dimensions of the original after EXIF orientation
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Result of importing an image into a node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageImport {
+    /// Chain-of-custody record of the original
+    pub attachment: AttachmentRecord,
+    pub variants: ImageVariants,
+    /// Workspace revision after the node's data was updated
+    pub revision: WorkspaceRevision,
+}
+
+/// An image whose variants could not be generated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageVariantFailure {
+    /// Absolute path of the original
+    pub path: String,
+    pub error: String,
+}
+
+/// Result of regenerating the variants of every image in a vault
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImageVariantReport {
+    /// Images whose variants were written
+    pub processed: usize,
+    /// Image nodes whose data was updated
+    pub updated_nodes: usize,
+    pub failed: Vec<ImageVariantFailure>,
+}
+
+/// Perceptual hash algorithms
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PerceptualHashKind {
+    /// Average hash: pixels brighter than the mean
+    Ahash,
+    /// Difference hash: brightness gradients between neighbouring pixels
+    Dhash,
+    /// DCT hash: low frequencies above their median; the most robust
+    #[default]
+    Phash,
+}
+
+/// 64-bit perceptual hashes of an image, as 16 hex digits each
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PerceptualHashes {
+    pub ahash: String,
+    pub dhash: String,
+    pub phash: String,
+}
+
+impl PerceptualHashes {
+    pub fn get(&self, kind: PerceptualHashKind) -> &str {
+        match kind {
+            PerceptualHashKind::Ahash => &self.ahash,
+            PerceptualHashKind::Dhash => &self.dhash,
+            PerceptualHashKind::Phash => &self.phash,
+        }
+    }
+}
+
+/// An image referenced from a node, with its hashes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedImage {
+    pub canvas_id: String,
+    pub canvas_name: String,
+    pub canvas_path: String,
+    pub node_id: String,
+    pub node_type: String,
+    /// Node data field holding the reference (e.g. "avatar")
+    pub field: String,
+    /// The reference as stored in the node
+    pub source: String,
+    /// SHA-256 of the image file, so unchanged images are not hashed again
+    pub sha256: String,
+    #[serde(flatten)]
+    pub hashes: PerceptualHashes,
+}
+
+/// An image reference, or a whole canvas, that could not be read or decoded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageHashFailure {
+    pub canvas_id: String,
+    /// None when the canvas itself could not be loaded
+    pub node_id: Option<String>,
+    /// The reference as stored in the node, or the canvas path
+    pub source: String,
+    pub error: String,
+}
+
+/// Perceptual hashes of every image referenced in a vault's canvases
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImageHashIndex {
+    pub updated_at: String,
+    pub images: Vec<IndexedImage>,
+    #[serde(default)]
+    pub failed: Vec<ImageHashFailure>,
+}
+
+/// Two images in different canvases whose hashes are close
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarImageMatch {
+    pub image: IndexedImage,
+    pub similar: IndexedImage,
+    /// Hamming distance between the hashes (0 = identical, 64 = opposite)
+    pub distance: u32,
+}
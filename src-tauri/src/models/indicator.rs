@@ -0,0 +1,61 @@
+// Indicator Models
+//
+// Data structures for indicators of compromise found in free text
+
+use super::{WorkspaceEdge, WorkspaceNode, WorkspaceRevision};
+use serde::{Deserialize, Serialize};
+
+/// What an indicator is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndicatorKind {
+    Ipv4,
+    Ipv6,
+    Domain,
+    Url,
+    Email,
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+    Cve,
+    Bitcoin,
+    Phone,
+}
+
+impl IndicatorKind {
+    /// Hash algorithm name as used by `hash` nodes, for hash kinds
+    pub fn hash_algorithm(&self) -> Option<&'static str> {
+        match self {
+            Self::Md5 => Some("md5"),
+            Self::Sha1 => Some("sha1"),
+            Self::Sha256 => Some("sha256"),
+            Self::Sha512 => Some("sha512"),
+            _ => None,
+        }
+    }
+}
+
+/// One indicator found in a text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Indicator {
+    pub kind: IndicatorKind,
+    /// Refanged value (e.g. "http://evil.com" for "hxxp://evil[.]com")
+    pub value: String,
+    /// The text as written
+    pub raw: String,
+    /// Byte offset of the first occurrence in the text
+    pub offset: usize,
+}
+
+/// Nodes and edges added to a canvas for the indicators in a node's text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorNodes {
+    pub indicators: Vec<Indicator>,
+    /// Nodes created for indicators not yet on the canvas
+    pub nodes: Vec<WorkspaceNode>,
+    /// Edges from the source node to new and existing indicator nodes
+    pub edges: Vec<WorkspaceEdge>,
+    /// Workspace revision after the nodes were added
+    pub revision: WorkspaceRevision,
+}
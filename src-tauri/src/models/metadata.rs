@@ -0,0 +1,60 @@
+// Metadata Models
+//
+// Data structures for metadata extracted from attachments (EXIF, XMP, PDF
+// document info and Office document properties)
+
+use super::{WorkspaceEdge, WorkspaceNode, WorkspaceRevision};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A position from EXIF GPS tags, in decimal degrees
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GpsPoint {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Meters above sea level
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<f64>,
+}
+
+/// Everything read from one file
+///
+/// Raw fields are grouped by where they came from; the summary fields pick
+/// out what is usually worth pivoting on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileMetadata {
+    pub mime_type: String,
+    /// EXIF tags by name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub exif: BTreeMap<String, String>,
+    /// XMP properties by qualified name (e.g. "dc:creator")
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub xmp: BTreeMap<String, String>,
+    /// PDF document info or Office docProps by name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub document: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gps: Option<GpsPoint>,
+    /// Camera make and model
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera: Option<String>,
+    /// Authors, creators and last editors
+    pub authors: Vec<String>,
+    /// Software that created or edited the file
+    pub software: Vec<String>,
+    /// Creation time as recorded in the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    /// Parts of the file that could not be read
+    pub warnings: Vec<String>,
+}
+
+/// Nodes and edges added to a canvas from an attachment's metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataFindings {
+    pub metadata: FileMetadata,
+    pub nodes: Vec<WorkspaceNode>,
+    pub edges: Vec<WorkspaceEdge>,
+    /// Workspace revision after the nodes were added
+    pub revision: WorkspaceRevision,
+}
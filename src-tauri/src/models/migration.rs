@@ -0,0 +1,116 @@
+// Migration Models
+//
+// Data structures describing schema migrations and their outcome
+
+use serde::{Deserialize, Serialize};
+
+/// Versioned file kinds handled by the migration registry
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaKind {
+    /// vault.json
+    Vault,
+    /// .mosaic/meta.json
+    CanvasMeta,
+    /// .mosaic/state.json
+    CanvasState,
+    /// workspace.json
+    Workspace,
+}
+
+/// How a top-level field changed during migration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldChangeKind {
+    Added,
+    Modified,
+    Removed,
+}
+
+/// A single top-level field change in a migrated document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub change: FieldChangeKind,
+}
+
+/// Migration outcome for one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMigrationReport {
+    pub kind: SchemaKind,
+    /// File the data was read from (differs from `path` for legacy layouts)
+    pub source_path: String,
+    /// File the migrated data is written to
+    pub path: String,
+    pub from_version: String,
+    pub to_version: String,
+    /// Descriptions of the steps applied, in order
+    pub steps: Vec<String>,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Migration outcome for a vault or canvas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationReport {
+    /// Vault or canvas directory
+    pub target_path: String,
+    /// True when nothing was written to disk
+    pub dry_run: bool,
+    /// Files with pending or applied steps
+    pub files: Vec<FileMigrationReport>,
+    /// Snapshot of the original files (None for dry runs or no-op migrations)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<String>,
+    /// When the report was produced (ISO 8601)
+    pub created_at: String,
+}
+
+impl MigrationReport {
+    pub fn new(target_path: String, dry_run: bool) -> Self {
+        Self {
+            target_path,
+            dry_run,
+            files: vec![],
+            backup_path: None,
+            created_at: crate::core::now_iso(),
+        }
+    }
+
+    /// Whether any file has pending steps
+    pub fn has_changes(&self) -> bool {
+        !self.files.is_empty()
+    }
+}
+
+/// Migration outcome for one canvas in a vault-wide run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanvasMigrationResult {
+    pub canvas_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<MigrationReport>,
+    /// Set when this canvas failed and was rolled back
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Migration outcome for a vault and all of its canvases
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultMigrationReport {
+    pub vault: MigrationReport,
+    pub canvases: Vec<CanvasMigrationResult>,
+}
+
+impl VaultMigrationReport {
+    /// Number of canvases with pending or applied steps
+    pub fn migrated_count(&self) -> usize {
+        self.canvases
+            .iter()
+            .filter(|c| c.report.as_ref().is_some_and(|r| r.has_changes()))
+            .count()
+    }
+
+    /// Number of canvases that failed to migrate
+    pub fn failed_count(&self) -> usize {
+        self.canvases.iter().filter(|c| c.error.is_some()).count()
+    }
+}
@@ -0,0 +1,84 @@
+// Evidence Package Models
+//
+// Data structures for signed evidence packages: zip archives of canvases,
+// their attachments and the vault's audit log, with a signed hash manifest
+
+use super::ExportOptions;
+use serde::{Deserialize, Serialize};
+
+/// One file inside a package
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageFile {
+    /// Path inside the archive
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// One canvas inside a package
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageCanvas {
+    pub id: String,
+    pub name: String,
+    /// Directory inside the archive (e.g. "canvases/<id>/")
+    pub path: String,
+}
+
+/// manifest.json of a package, signed into manifest.sig
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageManifest {
+    /// Format marker
+    pub format: String,
+    /// When the package was produced (ISO 8601)
+    pub created_at: String,
+    /// OS user who produced the package
+    pub created_by: String,
+    pub vault_id: String,
+    pub vault_name: String,
+    /// Options every canvas was exported with
+    pub options: ExportOptions,
+    pub canvases: Vec<PackageCanvas>,
+    /// Every other file in the archive with its hash
+    pub files: Vec<PackageFile>,
+    /// Signer's Ed25519 public key (base64)
+    pub public_key: String,
+}
+
+impl PackageManifest {
+    pub const FORMAT: &'static str = "mosaicflow-evidence-package";
+}
+
+/// The current user's signing key, safe to share with recipients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigningIdentity {
+    /// Ed25519 public key (base64)
+    pub public_key: String,
+    /// SHA-256 of the public key, for comparing out of band
+    pub fingerprint: String,
+}
+
+/// Result of checking a package before opening it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageVerification {
+    /// True when a trusted signer's signature is valid and every file
+    /// matches its hash
+    pub valid: bool,
+    pub signature_valid: bool,
+    /// Whether the signer is one of the keys the caller trusts
+    pub signer_trusted: bool,
+    /// Signer's key; compare with the fingerprint the sender shared
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signer: Option<SigningIdentity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<PackageManifest>,
+    /// Files whose content does not match the manifest
+    pub modified: Vec<String>,
+    /// Files listed in the manifest but absent from the archive
+    pub missing: Vec<String>,
+    /// Files in the archive the manifest does not list
+    pub unexpected: Vec<String>,
+    /// Why the package could not be checked at all, or was rejected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
@@ -0,0 +1,93 @@
+// Redaction Models
+//
+// Data structures for redaction profiles stored in .mosaicflow/redaction
+
+use serde::{Deserialize, Serialize};
+
+/// What happens to matched content
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionAction {
+    /// Replace with a placeholder
+    #[default]
+    Mask,
+    /// Remove the field (or the whole node for node-level rules)
+    Drop,
+}
+
+/// One redaction rule
+///
+/// Empty `node_types` matches every node and empty `fields` every field.
+/// A rule with neither `fields` nor `pattern` applies to whole nodes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RedactionRule {
+    #[serde(default)]
+    pub node_types: Vec<String>,
+    #[serde(default)]
+    pub fields: Vec<String>,
+    /// Regex matched inside string values; only matches are masked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub action: RedactionAction,
+}
+
+/// Redaction profile stored in .mosaicflow/redaction/<id>.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionProfile {
+    /// Unique identifier (UUID v4)
+    pub id: String,
+    /// Display name
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub rules: Vec<RedactionRule>,
+    /// Applied to every export that names no profile (one per vault)
+    #[serde(default)]
+    pub is_default: bool,
+    /// When the profile was created (ISO 8601)
+    pub created_at: String,
+    /// When the profile was last modified (ISO 8601)
+    pub updated_at: String,
+}
+
+impl RedactionProfile {
+    pub fn new(id: String, name: String, rules: Vec<RedactionRule>) -> Self {
+        let now = crate::core::now_iso();
+        Self {
+            id,
+            name,
+            description: String::new(),
+            rules,
+            is_default: false,
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+}
+
+/// One redacted node field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionEntry {
+    pub node_id: String,
+    pub node_type: String,
+    pub field: String,
+    pub action: RedactionAction,
+    /// Pattern matches masked (0 when the whole value was redacted)
+    pub matches: usize,
+}
+
+/// What a profile redacts from a workspace
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RedactionReport {
+    pub profile_id: String,
+    pub profile_name: String,
+    pub entries: Vec<RedactionEntry>,
+    /// Nodes removed entirely
+    pub dropped_nodes: Vec<String>,
+    /// Edges removed along with their nodes
+    pub dropped_edges: Vec<String>,
+    /// Edge labels masked by patterns
+    pub masked_edge_labels: Vec<String>,
+}
@@ -0,0 +1,41 @@
+// Scan Models
+//
+// Data structures for importing network scans (Nmap XML) onto a canvas
+
+use super::{CanvasInfo, WorkspaceEdge, WorkspaceNode, WorkspaceRevision};
+use serde::{Deserialize, Serialize};
+
+/// A host found by a scan
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanHost {
+    /// Canonical IPv4 or IPv6 address
+    pub ip: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mac: Option<String>,
+    /// Vendor of the MAC address, as Nmap reports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<String>,
+    /// "online", "offline" or "unknown", as on router nodes
+    pub status: String,
+    /// Names the host resolved to (PTR or user-supplied)
+    pub hostnames: Vec<String>,
+    /// Open TCP and UDP ports, sorted
+    pub ports: Vec<u16>,
+}
+
+/// Nodes and edges added to, and updated on, a canvas by a scan import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanImport {
+    /// Canvas created for the import, if it went to a new one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canvas: Option<CanvasInfo>,
+    pub hosts: Vec<ScanHost>,
+    pub nodes: Vec<WorkspaceNode>,
+    pub edges: Vec<WorkspaceEdge>,
+    /// Nodes already on the canvas that changed: matched routers and
+    /// subnet groups that gained hosts
+    pub updated_node_ids: Vec<String>,
+    /// Workspace revision after the import, if anything changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: Option<WorkspaceRevision>,
+}
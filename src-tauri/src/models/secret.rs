@@ -0,0 +1,33 @@
+// Secret Models
+//
+// Configuration for field-level secrets in node data
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Sensitive node fields, stored in .mosaicflow/secrets.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretFieldConfig {
+    /// Node type -> data fields stored encrypted
+    #[serde(default = "default_fields")]
+    pub fields: BTreeMap<String, Vec<String>>,
+}
+
+fn default_fields() -> BTreeMap<String, Vec<String>> {
+    BTreeMap::from([("credential".to_string(), vec!["password".to_string()])])
+}
+
+impl Default for SecretFieldConfig {
+    fn default() -> Self {
+        Self {
+            fields: default_fields(),
+        }
+    }
+}
+
+impl SecretFieldConfig {
+    /// Sensitive fields of a node type
+    pub fn fields_for(&self, node_type: &str) -> &[String] {
+        self.fields.get(node_type).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
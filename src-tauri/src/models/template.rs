@@ -0,0 +1,86 @@
+// Template Models
+//
+// Data structures for canvas templates stored in .mosaicflow/templates
+
+use super::WorkspaceData;
+use serde::{Deserialize, Serialize};
+
+/// Canvas template stored in .mosaicflow/templates/<id>.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanvasTemplate {
+    /// Unique identifier (UUID v4)
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Description copied to canvases created from this template
+    #[serde(default)]
+    pub description: String,
+    /// Tags copied to canvases created from this template
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// `{{variable}}` placeholders used in node titles and data
+    #[serde(default)]
+    pub variables: Vec<String>,
+    /// Skeleton nodes, edges and settings
+    pub workspace: WorkspaceData,
+    /// When the template was created (ISO 8601)
+    pub created_at: String,
+    /// When the template was last modified (ISO 8601)
+    pub updated_at: String,
+    /// Schema version for migrations
+    #[serde(default = "default_version")]
+    pub version: String,
+}
+
+fn default_version() -> String {
+    "1.0.0".to_string()
+}
+
+impl CanvasTemplate {
+    pub fn new(id: String, name: String, workspace: WorkspaceData) -> Self {
+        let now = crate::core::now_iso();
+        Self {
+            id,
+            name,
+            description: String::new(),
+            tags: vec![],
+            variables: vec![],
+            workspace,
+            created_at: now.clone(),
+            updated_at: now,
+            version: default_version(),
+        }
+    }
+}
+
+/// Template summary returned to frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub variables: Vec<String>,
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub path: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl TemplateInfo {
+    pub fn from_template(template: &CanvasTemplate, path: String) -> Self {
+        Self {
+            id: template.id.clone(),
+            name: template.name.clone(),
+            description: template.description.clone(),
+            tags: template.tags.clone(),
+            variables: template.variables.clone(),
+            node_count: template.workspace.nodes.len(),
+            edge_count: template.workspace.edges.len(),
+            path,
+            created_at: template.created_at.clone(),
+            updated_at: template.updated_at.clone(),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+// Transform Models
+//
+// Data structures for enrichment transforms ("resolve domain", "look up
+// hash", ...) and the JSON contract of HTTP transform providers
+
+use super::{WorkspaceEdge, WorkspaceNode, WorkspaceRevision};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// What a transform accepts and produces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Node types the transform runs on
+    pub input_types: Vec<String>,
+    /// Node types it may add
+    pub output_node_types: Vec<String>,
+    /// Labels of the edges it may add
+    #[serde(default)]
+    pub edge_labels: Vec<String>,
+}
+
+/// A transform backed by an HTTP endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpTransformConfig {
+    #[serde(flatten)]
+    pub info: TransformInfo,
+    /// URL the input node is POSTed to
+    pub endpoint: String,
+    /// Extra request headers (e.g. an API key for an internal service);
+    /// kept in the app data directory, not the vault, and read back with
+    /// empty values
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// Whether the endpoint was saved on this machine; only approved
+    /// transforms are listed and run. Read back, never stored in the vault
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub approved: bool,
+}
+
+/// Transforms configured for a vault, stored in .mosaicflow/transforms.json
+/// without their headers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransformConfig {
+    #[serde(default)]
+    pub http: Vec<HttpTransformConfig>,
+}
+
+/// Node handed to a transform; sealed secrets are left out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformInput {
+    pub transform: String,
+    pub node: TransformInputNode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformInputNode {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub data: HashMap<String, Value>,
+}
+
+/// Node produced by a transform
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformOutputNode {
+    /// Name edges use to refer to this node; not kept as its ID
+    #[serde(default, rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    #[serde(default)]
+    pub data: HashMap<String, Value>,
+}
+
+/// Edge produced by a transform, between output node refs or "$input"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformOutputEdge {
+    pub source: String,
+    pub target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// What a transform returns; without `edges`, the input node is linked to
+/// every output node
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransformOutput {
+    #[serde(default)]
+    pub nodes: Vec<TransformOutputNode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edges: Option<Vec<TransformOutputEdge>>,
+}
+
+/// Nodes and edges added to a canvas by running a transform
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformResult {
+    pub transform_id: String,
+    pub nodes: Vec<WorkspaceNode>,
+    pub edges: Vec<WorkspaceEdge>,
+    /// Existing nodes the output matched, linked instead of duplicated
+    pub matched_node_ids: Vec<String>,
+    pub revision: WorkspaceRevision,
+}
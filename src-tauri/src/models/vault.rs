@@ -60,6 +60,9 @@ pub struct VaultInfo {
     pub created_at: String,
     pub updated_at: String,
     pub canvas_count: usize,
+    /// vault.json is behind the current schema; see `migrate_vault`
+    #[serde(default)]
+    pub needs_migration: bool,
 }
 
 impl VaultInfo {
@@ -72,6 +75,7 @@ impl VaultInfo {
             created_at: meta.created_at.clone(),
             updated_at: meta.updated_at.clone(),
             canvas_count,
+            needs_migration: false,
         }
     }
 }
@@ -2,12 +2,14 @@
 //
 // Data structures for canvas workspace (nodes, edges)
 
+use super::Tlp;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 
 /// Workspace data stored in workspace.json
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkspaceData {
     /// Schema version
     #[serde(default = "default_version")]
@@ -21,6 +23,16 @@ pub struct WorkspaceData {
     /// Workspace settings
     #[serde(default)]
     pub settings: WorkspaceSettings,
+    /// Incremented on every save; used to detect concurrent writers
+    #[serde(default)]
+    pub revision: u64,
+    /// SHA-256 of nodes, edges and settings as of `revision`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Frontend manifest metadata (name, timestamps, viewport, settings),
+    /// carried through unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
 }
 
 fn default_version() -> String {
@@ -48,6 +60,9 @@ pub struct WorkspaceNode {
     /// Parent node ID (for grouped nodes)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parent_id: Option<String>,
+    /// TLP marking; unmarked nodes inherit the canvas's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classification: Option<Tlp>,
     /// Type-specific data
     pub data: HashMap<String, Value>,
 }
@@ -56,6 +71,23 @@ fn default_z_index() -> i32 {
     1
 }
 
+impl WorkspaceNode {
+    /// New node with a fresh ID and default size
+    pub fn new(node_type: &str, position: Position, data: HashMap<String, Value>) -> Self {
+        Self {
+            id: crate::core::generate_uuid(),
+            node_type: node_type.to_string(),
+            position,
+            width: None,
+            height: None,
+            z_index: default_z_index(),
+            parent_id: None,
+            classification: None,
+            data,
+        }
+    }
+}
+
 /// Position on canvas
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Position {
@@ -96,6 +128,23 @@ fn default_edge_type() -> String {
     "default".to_string()
 }
 
+impl WorkspaceEdge {
+    /// New default edge with a fresh ID
+    pub fn new(source: &str, target: &str, label: Option<String>) -> Self {
+        Self {
+            id: crate::core::generate_uuid(),
+            source: source.to_string(),
+            target: target.to_string(),
+            source_handle: None,
+            target_handle: None,
+            edge_type: default_edge_type(),
+            label,
+            animated: false,
+            data: HashMap::new(),
+        }
+    }
+}
+
 /// Workspace settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkspaceSettings {
@@ -157,6 +206,20 @@ impl Default for WorkspaceSettings {
     }
 }
 
+impl Default for WorkspaceData {
+    fn default() -> Self {
+        Self {
+            version: default_version(),
+            nodes: vec![],
+            edges: vec![],
+            settings: WorkspaceSettings::default(),
+            revision: 0,
+            content_hash: None,
+            metadata: None,
+        }
+    }
+}
+
 impl WorkspaceData {
     /// Create an empty workspace
     pub fn new() -> Self {
@@ -180,6 +243,40 @@ impl WorkspaceData {
             .retain(|e| e.source != node_id && e.target != node_id);
     }
 
+    /// Remove several nodes with their edges and any group/parent references
+    /// to them, returning the IDs of the removed edges
+    pub fn remove_nodes(&mut self, node_ids: &HashSet<String>) -> Vec<String> {
+        if node_ids.is_empty() {
+            return vec![];
+        }
+
+        self.nodes.retain(|n| !node_ids.contains(&n.id));
+
+        let mut removed_edges = vec![];
+        self.edges.retain(|e| {
+            let keep = !node_ids.contains(&e.source) && !node_ids.contains(&e.target);
+            if !keep {
+                removed_edges.push(e.id.clone());
+            }
+            keep
+        });
+
+        for node in &mut self.nodes {
+            if node
+                .parent_id
+                .as_ref()
+                .is_some_and(|p| node_ids.contains(p))
+            {
+                node.parent_id = None;
+            }
+            if let Some(Value::Array(children)) = node.data.get_mut("childNodeIds") {
+                children.retain(|c| c.as_str().is_none_or(|id| !node_ids.contains(id)));
+            }
+        }
+
+        removed_edges
+    }
+
     /// Remove an edge
     pub fn remove_edge(&mut self, edge_id: &str) {
         self.edges.retain(|e| e.id != edge_id);
@@ -194,4 +291,84 @@ impl WorkspaceData {
     pub fn find_node_mut(&mut self, node_id: &str) -> Option<&mut WorkspaceNode> {
         self.nodes.iter_mut().find(|n| n.id == node_id)
     }
+
+    /// Give every node and edge a fresh ID, rewriting references to them
+    ///
+    /// Returns the old -> new node ID mapping.
+    pub fn regenerate_ids(&mut self) -> HashMap<String, String> {
+        let node_ids: HashMap<String, String> = self
+            .nodes
+            .iter()
+            .map(|n| (n.id.clone(), crate::core::generate_uuid()))
+            .collect();
+        let remap = |id: &str| node_ids.get(id).cloned().unwrap_or_else(|| id.to_string());
+
+        for node in &mut self.nodes {
+            node.id = remap(&node.id);
+            node.parent_id = node.parent_id.as_deref().map(remap);
+
+            // Group nodes list their members by ID
+            if let Some(Value::Array(children)) = node.data.get_mut("childNodeIds") {
+                for child in children.iter_mut() {
+                    if let Value::String(id) = child {
+                        *id = remap(id);
+                    }
+                }
+            }
+        }
+
+        for edge in &mut self.edges {
+            edge.id = crate::core::generate_uuid();
+            edge.source = remap(&edge.source);
+            edge.target = remap(&edge.target);
+        }
+
+        node_ids
+    }
+
+    /// Hash of the workspace content, independent of revision, key order and
+    /// the order of nodes and edges
+    pub fn compute_hash(&self) -> String {
+        // Node files come back in ID order, so hash them that way
+        let mut nodes: Vec<&WorkspaceNode> = self.nodes.iter().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut edges: Vec<&WorkspaceEdge> = self.edges.iter().collect();
+        edges.sort_by(|a, b| a.id.cmp(&b.id));
+
+        // Going through Value sorts object keys, so HashMap order doesn't matter
+        let content = serde_json::json!({
+            "nodes": nodes,
+            "edges": edges,
+            "settings": self.settings,
+        });
+        let bytes = serde_json::to_vec(&content).unwrap_or_default();
+        format!("{:x}", Sha256::digest(&bytes))
+    }
+
+    /// Whether the content no longer matches the stored hash (edited outside the app)
+    pub fn is_modified_externally(&self) -> bool {
+        self.content_hash
+            .as_ref()
+            .is_some_and(|hash| *hash != self.compute_hash())
+    }
+}
+
+/// Revision stamp returned after a successful save
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceRevision {
+    pub revision: u64,
+    pub content_hash: String,
+}
+
+/// Both sides of a failed save, for the UI to resolve
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceConflict {
+    pub canvas_path: String,
+    /// Revision the client based its edits on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_revision: Option<u64>,
+    /// The client's unsaved workspace
+    pub local: WorkspaceData,
+    /// What is currently on disk
+    pub remote: WorkspaceData,
 }
@@ -0,0 +1,338 @@
+// Asset Service
+//
+// Vault-level content-addressed store for images and other blobs. Each blob
+// lives once in assets/<aa>/<sha256>.<ext>, however many nodes use it, and
+// is kept while any node in the vault's canvases or templates mentions its
+// hash (in `imagePath`, `screenshotPath` or any other data field). Nodes
+// refer to blobs by `asset://<sha256>`, which is resolved against whichever
+// vault is open, so the reference survives moving or copying the vault. The
+// webview cannot load them directly: the frontend resolves them with
+// `resolve_asset` and passes the blob path to `convertFileSrc`.
+
+use crate::core::{
+    self,
+    paths::{CanvasPaths, VaultPaths},
+    MosaicError, MosaicResult,
+};
+use crate::models::{AssetGcReport, AssetInfo, AssetUsage};
+use crate::services::{NodeFileService, SecretService, TemplateService};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Blobs imported this recently survive collection, so an import whose node
+/// has not been saved yet is not lost
+const GC_GRACE: Duration = Duration::from_secs(10 * 60);
+
+/// Scheme of stable asset references
+const URI_PREFIX: &str = "asset://";
+
+pub struct AssetService;
+
+impl AssetService {
+    /// Store a file's content, reusing the existing blob if already present
+    pub fn import_file(vault_path: &Path, file_path: &Path) -> MosaicResult<AssetInfo> {
+        if !file_path.is_file() {
+            return Err(MosaicError::not_found(&file_path.to_string_lossy()));
+        }
+
+        let content = core::fs::read_bytes(file_path)?;
+        Self::store(vault_path, &content, file_path)
+    }
+
+    /// Store raw content (e.g. a pasted screenshot) named `file_name`
+    pub fn import_data(
+        vault_path: &Path,
+        content: &[u8],
+        file_name: &str,
+    ) -> MosaicResult<AssetInfo> {
+        Self::store(vault_path, content, Path::new(file_name))
+    }
+
+    /// Whether `uri` is a stable `asset://<sha256>` reference
+    pub fn is_uri(uri: &str) -> bool {
+        Self::uri_hash(uri).is_some()
+    }
+
+    /// The blob a stable `asset://<sha256>` URI refers to in this vault
+    pub fn resolve(vault_path: &Path, uri: &str) -> MosaicResult<AssetInfo> {
+        let hash = Self::uri_hash(uri)
+            .ok_or_else(|| MosaicError::invalid_format(format!("Not an asset URI: {}", uri)))?;
+
+        let shard = VaultPaths::from_root(vault_path).assets.join(&hash[..2]);
+        core::fs::list_dir(&shard)?
+            .into_iter()
+            .find(|p| p.file_stem().is_some_and(|stem| *stem == *hash))
+            .map(|path| Self::info(&path))
+            .ok_or_else(|| MosaicError::not_found(uri))
+    }
+
+    /// Every asset in the store with its reference count
+    pub fn list(vault_path: &Path) -> MosaicResult<Vec<AssetUsage>> {
+        let texts = Self::node_texts(vault_path)?;
+
+        Ok(Self::blobs(vault_path)?
+            .iter()
+            .map(|path| {
+                let asset = Self::info(path);
+                let references = texts.iter().filter(|t| t.contains(&asset.hash)).count();
+                AssetUsage { asset, references }
+            })
+            .collect())
+    }
+
+    /// Remove blobs no node refers to
+    ///
+    /// Fails without removing anything if a canvas or node file cannot be
+    /// read or a sealed value cannot be opened, since its references would
+    /// be missed.
+    pub fn gc(vault_path: &Path) -> MosaicResult<AssetGcReport> {
+        let mut report = AssetGcReport::default();
+
+        for usage in Self::list(vault_path)? {
+            let path = PathBuf::from(&usage.asset.path);
+            let recent = std::fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_none_or(|age| age < GC_GRACE);
+
+            if usage.references > 0 || recent {
+                report.kept += 1;
+                continue;
+            }
+
+            core::fs::remove_file(&path)?;
+            report.freed_bytes += usage.asset.size;
+            report.removed.push(usage.asset);
+
+            if let Some(shard) = path.parent() {
+                if core::fs::list_dir(shard)?.is_empty() {
+                    core::remove_dir_all(shard)?;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Write content under its hash unless a blob with that hash exists
+    fn store(vault_path: &Path, content: &[u8], name: &Path) -> MosaicResult<AssetInfo> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+        if !vault_paths.is_valid() {
+            return Err(MosaicError::vault_not_found(&vault_path.to_string_lossy()));
+        }
+
+        let hash = format!("{:x}", Sha256::digest(content));
+        let shard = vault_paths.assets.join(&hash[..2]);
+
+        let existing = core::fs::list_dir(&shard)?
+            .into_iter()
+            .find(|p| p.file_stem().is_some_and(|stem| *stem == *hash));
+        if let Some(path) = existing {
+            core::fs::touch(&path)?;
+            return Ok(Self::info(&path));
+        }
+
+        let path = match name.extension() {
+            Some(ext) => shard.join(format!(
+                "{}.{}",
+                hash,
+                core::sanitize_name(&ext.to_string_lossy()).to_lowercase()
+            )),
+            None => shard.join(&hash),
+        };
+        core::fs::write_bytes(&path, content)?;
+
+        Ok(Self::info(&path))
+    }
+
+    /// Blob files in the store
+    fn blobs(vault_path: &Path) -> MosaicResult<Vec<PathBuf>> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+
+        Ok(core::fs::list_files_recursive(&vault_paths.assets)?
+            .into_iter()
+            .filter(|p| {
+                p.file_stem()
+                    .map(|stem| stem.to_string_lossy())
+                    .is_some_and(|stem| {
+                        stem.len() == 64 && stem.chars().all(|c| c.is_ascii_hexdigit())
+                    })
+            })
+            .collect())
+    }
+
+    fn info(path: &Path) -> AssetInfo {
+        let hash = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        AssetInfo {
+            uri: Self::uri(&hash),
+            hash,
+            path: path.to_string_lossy().to_string(),
+            size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            mime_type: mime_guess::from_path(path)
+                .first_or_octet_stream()
+                .essence_str()
+                .to_string(),
+        }
+    }
+
+    fn uri_hash(uri: &str) -> Option<String> {
+        uri.strip_prefix(URI_PREFIX)
+            .map(|hash| hash.trim_end_matches('/').to_ascii_lowercase())
+            .filter(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+    }
+
+    /// Stable reference to a blob, resolved with `resolve`
+    fn uri(hash: &str) -> String {
+        format!("{}{}", URI_PREFIX, hash)
+    }
+
+    /// All string content of each node in the vault's canvases and
+    /// templates, with sealed values revealed
+    ///
+    /// Canvases are scanned file by file: every folder under nodes/ counts,
+    /// whether or not the manifest lists it yet.
+    fn node_texts(vault_path: &Path) -> MosaicResult<Vec<String>> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+
+        // (node ID, content) of every node; sealed values are bound to the ID
+        let mut nodes: Vec<(String, Value)> = Vec::new();
+        for canvas_dir in core::paths::list_canvas_dirs(&vault_paths.canvases)? {
+            let canvas_paths = CanvasPaths::from_root(&canvas_dir);
+            if canvas_paths.workspace_json.exists() {
+                let doc: Value = core::read_json(&canvas_paths.workspace_json)?;
+                if !NodeFileService::is_manifest(&doc) {
+                    if let Some(Value::Array(legacy)) = doc.get("nodes") {
+                        nodes.extend(legacy.iter().map(|node| {
+                            let id = node.get("id").and_then(Value::as_str).unwrap_or_default();
+                            (id.to_string(), node.clone())
+                        }));
+                    }
+                }
+            }
+
+            for node_dir in core::list_subdirs(&canvas_paths.nodes)? {
+                let data_dir = node_dir.join("data");
+                let mut files = Vec::new();
+                let props_path = data_dir.join("properties.json");
+                if props_path.exists() {
+                    files.push(core::read_json::<Value>(&props_path)?);
+                }
+                let content_path = data_dir.join("content");
+                if content_path.exists() {
+                    files.push(Value::String(core::read_string(&content_path)?));
+                }
+                let id = node_dir.file_name().unwrap_or_default().to_string_lossy();
+                nodes.push((id.to_string(), Value::Array(files)));
+            }
+        }
+        for template in TemplateService::list(vault_path)? {
+            let workspace = TemplateService::get(vault_path, &template.id)?.workspace;
+            for node in workspace.nodes {
+                nodes.push((node.id, serde_json::to_value(node.data)?));
+            }
+        }
+        SecretService::reveal_values(vault_path, &mut nodes)?;
+
+        Ok(nodes
+            .iter()
+            .map(|(_, node)| {
+                let mut text = String::new();
+                Self::collect_strings(node, &mut text);
+                text
+            })
+            .collect())
+    }
+
+    fn collect_strings(value: &Value, out: &mut String) {
+        match value {
+            Value::String(s) => {
+                out.push_str(s);
+                out.push('\n');
+            }
+            Value::Array(items) => items.iter().for_each(|v| Self::collect_strings(v, out)),
+            Value::Object(map) => map.values().for_each(|v| Self::collect_strings(v, out)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Position, WorkspaceNode};
+    use crate::services::testing::TempVault;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    /// Make a blob old enough to be collected
+    fn age(asset: &AssetInfo) {
+        let old = SystemTime::now() - GC_GRACE * 2;
+        std::fs::File::options()
+            .write(true)
+            .open(&asset.path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_import_dedups_and_resolves() {
+        let vault = TempVault::new();
+        let root = &vault.root;
+        let first = AssetService::import_data(root, b"screenshot", "a.PNG").unwrap();
+        let second = AssetService::import_data(root, b"screenshot", "b.jpg").unwrap();
+
+        assert_eq!(first.path, second.path);
+        assert!(first.path.ends_with(".png"));
+        assert_eq!(first.uri, format!("asset://{}", first.hash));
+
+        // The returned URI leads back to the imported bytes
+        let resolved = AssetService::resolve(root, &first.uri).unwrap();
+        assert_eq!(resolved.path, first.path);
+        assert_eq!(
+            core::fs::read_bytes(Path::new(&resolved.path)).unwrap(),
+            b"screenshot"
+        );
+        assert!(AssetService::resolve(root, "asset://localhost/a.png").is_err());
+        assert!(AssetService::resolve(root, &format!("asset://{}", "0".repeat(64))).is_err());
+    }
+
+    #[test]
+    fn test_gc_sees_sealed_references_in_node_files() {
+        let vault = TempVault::new();
+        let (root, canvas) = (&vault.root, vault.create_canvas("Case"));
+        let kept = AssetService::import_data(root, b"kept", "kept.png").unwrap();
+        let unused = AssetService::import_data(root, b"unused", "unused.png").unwrap();
+        age(&kept);
+        age(&unused);
+
+        // The reference only exists sealed, in a node folder the manifest
+        // does not list
+        let data = HashMap::from([("password".to_string(), json!(kept.uri))]);
+        let node = WorkspaceNode::new("credential", Position::default(), data);
+        let properties = json!({ "position": node.position, "data": node.data }).to_string();
+        NodeFileService::write_node_files(&canvas, &node.id, "credential", None, Some(&properties))
+            .unwrap();
+        let written = core::read_string(
+            &CanvasPaths::from_root(&canvas)
+                .nodes
+                .join(&node.id)
+                .join("data/properties.json"),
+        )
+        .unwrap();
+        assert!(!written.contains(&kept.hash));
+
+        let report = AssetService::gc(root).unwrap();
+        assert_eq!(report.kept, 1);
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.removed[0].hash, unused.hash);
+        assert!(Path::new(&kept.path).exists());
+        assert!(!Path::new(&unused.path).exists());
+    }
+}
@@ -0,0 +1,307 @@
+// Attachment Service
+//
+// Imports evidence files into a canvas and keeps their chain-of-custody
+// manifest, so later changes to a stored file can be detected
+
+use crate::core::{self, paths::CanvasPaths, MosaicError, MosaicResult};
+use crate::models::{
+    AttachmentCheck, AttachmentManifest, AttachmentRecord, AttachmentStatus,
+    AttachmentVerification, AuditOperation, AuditQuery,
+};
+use crate::services::{AuditService, CanvasService, SecretService, WorkspaceService};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+/// Serializes manifest updates so concurrent imports are not lost
+fn manifest_lock() -> &'static Mutex<()> {
+    static MANIFEST: OnceLock<Mutex<()>> = OnceLock::new();
+    MANIFEST.get_or_init(|| Mutex::new(()))
+}
+
+pub struct AttachmentService;
+
+impl AttachmentService {
+    /// Copy a file into a canvas and record it against a node
+    ///
+    /// Images are stored in `images/`, everything else in `attachments/`.
+    pub fn import(
+        canvas_path: &Path,
+        file_path: &Path,
+        node_id: &str,
+    ) -> MosaicResult<AttachmentRecord> {
+        CanvasService::open(canvas_path)?;
+        let canvas_paths = CanvasPaths::from_root(canvas_path);
+
+        let workspace = WorkspaceService::load(canvas_path)?;
+        if !workspace.nodes.iter().any(|n| n.id == node_id) {
+            return Err(MosaicError::not_found(&format!("Node {}", node_id)));
+        }
+        if !file_path.is_file() {
+            return Err(MosaicError::not_found(&file_path.to_string_lossy()));
+        }
+
+        let content = core::fs::read_bytes(file_path)?;
+        let mime_type = mime_guess::from_path(file_path)
+            .first_or_octet_stream()
+            .essence_str()
+            .to_string();
+
+        let dir = if mime_type.starts_with("image/") {
+            &canvas_paths.images
+        } else {
+            &canvas_paths.attachments
+        };
+        let target = dir.join(Self::stored_name(file_path));
+        core::fs::write_bytes(&target, &content)?;
+
+        let record = AttachmentRecord {
+            id: core::generate_uuid(),
+            node_id: node_id.to_string(),
+            path: target
+                .strip_prefix(canvas_path)
+                .unwrap_or(&target)
+                .to_string_lossy()
+                .replace('\\', "/"),
+            original_path: file_path.to_string_lossy().to_string(),
+            size: content.len() as u64,
+            mime_type,
+            sha256: format!("{:x}", Sha256::digest(&content)),
+            sha1: format!("{:x}", Sha1::digest(&content)),
+            md5: format!("{:x}", Md5::digest(&content)),
+            imported_at: core::now_iso(),
+            imported_by: AuditService::actor(),
+        };
+
+        let _guard = manifest_lock()
+            .lock()
+            .map_err(|_| MosaicError::io_error("Attachment manifest lock poisoned"))?;
+        let mut manifest = Self::manifest(canvas_path)?;
+        manifest.attachments.push(record.clone());
+        core::write_json(&canvas_paths.attachments_json, &manifest)?;
+
+        Ok(record)
+    }
+
+    /// Undo an import whose follow-up failed: remove the stored file and
+    /// its manifest record
+    pub fn discard(canvas_path: &Path, attachment_id: &str) -> MosaicResult<()> {
+        let canvas_paths = CanvasPaths::from_root(canvas_path);
+
+        let _guard = manifest_lock()
+            .lock()
+            .map_err(|_| MosaicError::io_error("Attachment manifest lock poisoned"))?;
+        let mut manifest = Self::manifest(canvas_path)?;
+        let Some(index) = manifest
+            .attachments
+            .iter()
+            .position(|record| record.id == attachment_id)
+        else {
+            return Ok(());
+        };
+        let record = manifest.attachments.remove(index);
+        core::write_json(&canvas_paths.attachments_json, &manifest)?;
+
+        let stored = canvas_path.join(&record.path);
+        if stored.is_file() {
+            core::fs::remove_file(&stored)?;
+        }
+        Ok(())
+    }
+
+    /// Chain-of-custody manifest of a canvas (empty if nothing was imported)
+    pub fn manifest(canvas_path: &Path) -> MosaicResult<AttachmentManifest> {
+        let canvas_paths = CanvasPaths::from_root(canvas_path);
+
+        if !canvas_paths.attachments_json.exists() {
+            return Ok(AttachmentManifest::default());
+        }
+
+        core::read_json(&canvas_paths.attachments_json)
+    }
+
+    /// Re-hash every attachment and flag those that changed or disappeared
+    ///
+    /// Hashes are checked against the ones the audit log recorded at
+    /// import, so rewriting the manifest along with a file is caught too.
+    pub fn verify(canvas_path: &Path) -> MosaicResult<AttachmentVerification> {
+        CanvasService::open(canvas_path)?;
+        let vault_path = SecretService::vault_of(canvas_path)?;
+        let audit_valid = AuditService::verify(&vault_path)?.valid;
+        let audited = Self::audited_hashes(&vault_path)?;
+
+        let mut checks = Vec::new();
+        for record in Self::manifest(canvas_path)?.attachments {
+            let audited_sha256 = audited.get(&record.id).cloned();
+            let stored = canvas_path.join(&record.path);
+            let sha256 = if stored.is_file() {
+                Some(format!(
+                    "{:x}",
+                    Sha256::digest(core::fs::read_bytes(&stored)?)
+                ))
+            } else {
+                None
+            };
+
+            let status = match (&sha256, &audited_sha256) {
+                (_, None) => AttachmentStatus::Unaudited,
+                (_, Some(audited)) if *audited != record.sha256 => {
+                    AttachmentStatus::ManifestAltered
+                }
+                (None, _) => AttachmentStatus::Missing,
+                (Some(sha256), Some(audited)) if sha256 != audited => AttachmentStatus::Modified,
+                _ => AttachmentStatus::Intact,
+            };
+
+            checks.push(AttachmentCheck {
+                id: record.id,
+                node_id: record.node_id,
+                path: record.path,
+                status,
+                sha256,
+                audited_sha256,
+            });
+        }
+
+        Ok(AttachmentVerification {
+            valid: audit_valid && checks.iter().all(|c| c.status == AttachmentStatus::Intact),
+            audit_valid,
+            attachments: checks,
+        })
+    }
+
+    /// SHA-256 of every attachment as recorded by its import audit entry
+    fn audited_hashes(vault_path: &Path) -> MosaicResult<HashMap<String, String>> {
+        let query = AuditQuery {
+            operations: vec![AuditOperation::AttachmentImport],
+            ..Default::default()
+        };
+
+        Ok(AuditService::query(vault_path, &query)?
+            .into_iter()
+            .filter_map(|entry| {
+                let id = entry.details.get("attachment_id")?.as_str()?;
+                let sha256 = entry.details.get("sha256")?.as_str()?;
+                Some((id.to_string(), sha256.to_string()))
+            })
+            .collect())
+    }
+
+    /// Unique file name keeping the original's name and extension
+    fn stored_name(file_path: &Path) -> String {
+        let stem = file_path
+            .file_stem()
+            .map(|s| core::sanitize_name(&s.to_string_lossy()))
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "attachment".to_string());
+
+        match file_path.extension() {
+            Some(ext) => format!(
+                "{}-{}.{}",
+                core::generate_short_id(),
+                stem,
+                core::sanitize_name(&ext.to_string_lossy())
+            ),
+            None => format!("{}-{}", core::generate_short_id(), stem),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Position, WorkspaceNode};
+    use crate::services::testing::TempVault;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn temp_canvas() -> (TempVault, PathBuf, String) {
+        let vault = TempVault::new();
+        let canvas_path = vault.create_canvas("Case");
+
+        let node = WorkspaceNode::new("file", Position::default(), Default::default());
+        let node_id = node.id.clone();
+        WorkspaceService::add_node(&canvas_path, node, None).unwrap();
+        (vault, canvas_path, node_id)
+    }
+
+    /// Import a file and audit it the way the import command does
+    fn import(root: &Path, canvas_path: &Path, node_id: &str, content: &str) -> AttachmentRecord {
+        let source = root.join(format!("{}.txt", core::generate_short_id()));
+        std::fs::write(&source, content).unwrap();
+        let record = AttachmentService::import(canvas_path, &source, node_id).unwrap();
+        AuditService::log(
+            canvas_path,
+            AuditOperation::AttachmentImport,
+            json!({ "attachment_id": record.id, "sha256": record.sha256 }),
+        )
+        .unwrap();
+        record
+    }
+
+    fn status(canvas_path: &Path, id: &str) -> AttachmentStatus {
+        AttachmentService::verify(canvas_path)
+            .unwrap()
+            .attachments
+            .into_iter()
+            .find(|c| c.id == id)
+            .unwrap()
+            .status
+    }
+
+    #[test]
+    fn test_verify_against_audit_log() {
+        let (vault, canvas_path, node_id) = temp_canvas();
+        let root = vault.outside();
+        let intact = import(root, &canvas_path, &node_id, "intact");
+        let modified = import(root, &canvas_path, &node_id, "modified");
+        let rewritten = import(root, &canvas_path, &node_id, "rewritten");
+        let missing = import(root, &canvas_path, &node_id, "missing");
+
+        let source = root.join("unaudited.txt");
+        std::fs::write(&source, "unaudited").unwrap();
+        let unaudited = AttachmentService::import(&canvas_path, &source, &node_id).unwrap();
+
+        std::fs::write(canvas_path.join(&modified.path), "changed").unwrap();
+        std::fs::remove_file(canvas_path.join(&missing.path)).unwrap();
+
+        // Rewriting the file and its manifest hash together is still caught
+        std::fs::write(canvas_path.join(&rewritten.path), "forged").unwrap();
+        let canvas_paths = CanvasPaths::from_root(&canvas_path);
+        let mut manifest = AttachmentService::manifest(&canvas_path).unwrap();
+        for record in &mut manifest.attachments {
+            if record.id == rewritten.id {
+                record.sha256 = format!("{:x}", Sha256::digest(b"forged"));
+            }
+        }
+        core::write_json(&canvas_paths.attachments_json, &manifest).unwrap();
+
+        let verification = AttachmentService::verify(&canvas_path).unwrap();
+        assert!(verification.audit_valid);
+        assert!(!verification.valid);
+        assert_eq!(status(&canvas_path, &intact.id), AttachmentStatus::Intact);
+        assert_eq!(
+            status(&canvas_path, &modified.id),
+            AttachmentStatus::Modified
+        );
+        assert_eq!(
+            status(&canvas_path, &rewritten.id),
+            AttachmentStatus::ManifestAltered
+        );
+        assert_eq!(status(&canvas_path, &missing.id), AttachmentStatus::Missing);
+        assert_eq!(
+            status(&canvas_path, &unaudited.id),
+            AttachmentStatus::Unaudited
+        );
+    }
+
+    #[test]
+    fn test_stored_name_keeps_extension() {
+        let name = AttachmentService::stored_name(Path::new("/tmp/report (final).pdf"));
+        assert!(name.ends_with("-report _final_.pdf"));
+        assert!(!AttachmentService::stored_name(Path::new("/tmp/README")).contains('.'));
+    }
+}
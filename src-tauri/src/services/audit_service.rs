@@ -0,0 +1,419 @@
+// Audit Service
+//
+// Append-only, hash-chained log of every operation touching vault data.
+// Each entry carries the hash of its predecessor, so editing, removing or
+// reordering lines breaks the chain. Hashes are keyed with a per-vault key
+// kept in the app data directory, so the chain cannot be rebuilt from the
+// vault alone.
+
+use crate::core::{self, paths::CanvasPaths, paths::VaultPaths, MosaicError, MosaicResult};
+use crate::events::EventEmitter;
+use crate::models::{
+    AuditEntry, AuditHead, AuditOperation, AuditQuery, AuditVerification, CanvasInfo, CanvasMeta,
+    AUDIT_GENESIS_HASH,
+};
+use crate::services::SecretService;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::AppHandle;
+
+/// Directory of per-vault audit keys inside the app data directory
+const AUDIT_KEY_DIR: &str = "audit";
+
+/// Serializes appends so concurrent commands cannot fork the chain
+fn append_lock() -> &'static Mutex<()> {
+    static APPEND: OnceLock<Mutex<()>> = OnceLock::new();
+    APPEND.get_or_init(|| Mutex::new(()))
+}
+
+pub struct AuditService;
+
+impl AuditService {
+    /// Record an operation on anything inside a vault
+    ///
+    /// A failure does not undo the operation that already happened, but is
+    /// returned so the caller reports it.
+    pub fn log(path: &Path, operation: AuditOperation, details: Value) -> MosaicResult<()> {
+        Self::record(path, None, operation, details).map(|_| ())
+    }
+
+    /// Report an entry that could not be recorded for a committed operation
+    ///
+    /// The operation stands, so its command still succeeds; the failure is
+    /// sent to the frontend as an `audit:failed` event instead.
+    pub fn report_failure(app_handle: &AppHandle, error: MosaicError) {
+        EventEmitter::new(app_handle).audit_failed(&error);
+    }
+
+    /// Record an operation on a canvas, which may no longer exist on disk
+    pub fn log_canvas(
+        canvas: &CanvasInfo,
+        operation: AuditOperation,
+        details: Value,
+    ) -> MosaicResult<()> {
+        Self::record(
+            Path::new(&canvas.path),
+            Some(&canvas.id),
+            operation,
+            details,
+        )
+        .map(|_| ())
+    }
+
+    /// Append an entry to the log of the vault containing `path`
+    ///
+    /// Paths outside any vault are not audited.
+    pub fn record(
+        path: &Path,
+        canvas_id: Option<&str>,
+        operation: AuditOperation,
+        details: Value,
+    ) -> MosaicResult<Option<AuditEntry>> {
+        let Some(vault_path) = core::paths::find_vault_root(path) else {
+            return Ok(None);
+        };
+
+        let canvas_dir = match canvas_id {
+            Some(_) => Some(path.to_path_buf()),
+            None => Self::canvas_dir(path, &vault_path),
+        };
+        let canvas_id = canvas_id.map(str::to_string).or_else(|| {
+            let canvas_paths = CanvasPaths::from_root(canvas_dir.as_ref()?);
+            core::read_json::<CanvasMeta>(&canvas_paths.meta_json)
+                .ok()
+                .map(|meta| meta.id)
+        });
+
+        let entry = AuditEntry {
+            seq: 0,
+            timestamp: core::now_iso(),
+            actor: Self::actor(),
+            operation,
+            canvas_id,
+            canvas_path: canvas_dir.map(|dir| Self::relative(&vault_path, &dir)),
+            details,
+            prev_hash: String::new(),
+            hash: String::new(),
+        };
+
+        Self::append(&vault_path, entry).map(Some)
+    }
+
+    /// Walk the chain and report the first entry that does not fit
+    ///
+    /// Entries can only be checked on a machine holding the vault's audit key.
+    pub fn verify(vault_path: &Path) -> MosaicResult<AuditVerification> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+        let key = SecretService::app_key(vault_path, AUDIT_KEY_DIR, false);
+
+        let mut previous = AuditHead {
+            seq: 0,
+            hash: AUDIT_GENESIS_HASH.to_string(),
+        };
+        let broken = |line: u64, entries: u64, error: String| AuditVerification {
+            valid: false,
+            entries,
+            broken_at: Some(line),
+            error: Some(error),
+        };
+
+        let content = if vault_paths.audit_log.exists() {
+            core::read_string(&vault_paths.audit_log)?
+        } else {
+            String::new()
+        };
+
+        for (index, line) in content.lines().filter(|l| !l.trim().is_empty()).enumerate() {
+            let line_no = index as u64 + 1;
+            let entry: AuditEntry = match serde_json::from_str(line) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    return Ok(broken(
+                        line_no,
+                        previous.seq,
+                        format!("Unreadable entry: {}", e),
+                    ))
+                }
+            };
+
+            if entry.seq != previous.seq + 1 {
+                return Ok(broken(
+                    line_no,
+                    previous.seq,
+                    format!("Expected entry {}, found {}", previous.seq + 1, entry.seq),
+                ));
+            }
+            if entry.prev_hash != previous.hash {
+                return Ok(broken(
+                    line_no,
+                    previous.seq,
+                    "Entry does not chain to its predecessor".to_string(),
+                ));
+            }
+            let key = match &key {
+                Ok(key) => key,
+                Err(e) => {
+                    return Ok(broken(
+                        line_no,
+                        previous.seq,
+                        format!("Audit key unavailable: {}", e),
+                    ))
+                }
+            };
+            if !entry.verify_hash(key.as_ref()) {
+                return Ok(broken(
+                    line_no,
+                    previous.seq,
+                    "Entry was modified".to_string(),
+                ));
+            }
+
+            previous = AuditHead {
+                seq: entry.seq,
+                hash: entry.hash,
+            };
+        }
+
+        // Removing entries from the end leaves a valid chain; the head catches it
+        if vault_paths.audit_head.exists() || previous.seq > 0 {
+            let Some(head) = Self::read_head(&vault_paths)? else {
+                return Ok(broken(
+                    previous.seq + 1,
+                    previous.seq,
+                    "Audit head is missing".to_string(),
+                ));
+            };
+            if head.seq != previous.seq || head.hash != previous.hash {
+                return Ok(broken(
+                    previous.seq + 1,
+                    previous.seq,
+                    format!(
+                        "Log ends at entry {} but head records entry {}",
+                        previous.seq, head.seq
+                    ),
+                ));
+            }
+        }
+
+        Ok(AuditVerification {
+            valid: true,
+            entries: previous.seq,
+            broken_at: None,
+            error: None,
+        })
+    }
+
+    /// Entries matching a query, oldest first
+    pub fn query(vault_path: &Path, query: &AuditQuery) -> MosaicResult<Vec<AuditEntry>> {
+        let canvas = query.canvas.as_deref().map(|canvas| {
+            let path = Path::new(canvas);
+            if path.is_absolute() {
+                Self::relative(vault_path, path)
+            } else {
+                canvas.replace('\\', "/")
+            }
+        });
+        let since = query.since.as_deref().and_then(core::time::parse_iso);
+        let until = query.until.as_deref().and_then(core::time::parse_iso);
+
+        let mut entries: Vec<AuditEntry> = Self::entries(vault_path)?
+            .into_iter()
+            .filter(|e| {
+                canvas.as_ref().is_none_or(|c| {
+                    e.canvas_id.as_ref() == Some(c) || e.canvas_path.as_ref() == Some(c)
+                })
+            })
+            .filter(|e| query.operations.is_empty() || query.operations.contains(&e.operation))
+            .filter(|e| {
+                let time = core::time::parse_iso(&e.timestamp);
+                since.is_none_or(|s| time.is_some_and(|t| t >= s))
+                    && until.is_none_or(|u| time.is_some_and(|t| t <= u))
+            })
+            .collect();
+
+        if let Some(limit) = query.limit {
+            entries.drain(..entries.len().saturating_sub(limit));
+        }
+
+        Ok(entries)
+    }
+
+    /// Chain an entry onto the log and move the head
+    fn append(vault_path: &Path, mut entry: AuditEntry) -> MosaicResult<AuditEntry> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+        let _guard = append_lock()
+            .lock()
+            .map_err(|_| MosaicError::io_error("Audit log lock poisoned"))?;
+
+        let head = Self::head(&vault_paths)?;
+        // Only a new log gets a new key; one that lost its key must not be
+        // continued under another
+        let key = SecretService::app_key(vault_path, AUDIT_KEY_DIR, head.seq == 0)?;
+        entry.seq = head.seq + 1;
+        entry.prev_hash = head.hash;
+        entry.hash = entry.compute_hash(key.as_ref());
+
+        // The line is appended, and only the small head file is replaced
+        // whole, so a crash leaves either the old or the new head
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        core::fs::append_string(&vault_paths.audit_log, &line)?;
+        core::fs::write_bytes_atomic(
+            &vault_paths.audit_head,
+            &serde_json::to_vec_pretty(&AuditHead {
+                seq: entry.seq,
+                hash: entry.hash.clone(),
+            })?,
+        )?;
+
+        Ok(entry)
+    }
+
+    /// Newest entry as recorded by the head file
+    ///
+    /// A log without its head is refused rather than continued from its
+    /// last line, which would hide a truncation.
+    fn head(vault_paths: &VaultPaths) -> MosaicResult<AuditHead> {
+        if let Some(head) = Self::read_head(vault_paths)? {
+            return Ok(head);
+        }
+        if !Self::entries(&vault_paths.root)?.is_empty() {
+            return Err(MosaicError::invalid_format(
+                "Audit head is missing; the log cannot be extended",
+            ));
+        }
+
+        Ok(AuditHead {
+            seq: 0,
+            hash: AUDIT_GENESIS_HASH.to_string(),
+        })
+    }
+
+    fn read_head(vault_paths: &VaultPaths) -> MosaicResult<Option<AuditHead>> {
+        if !vault_paths.audit_head.exists() {
+            return Ok(None);
+        }
+        core::read_json(&vault_paths.audit_head).map(Some)
+    }
+
+    /// Every readable entry in the log
+    fn entries(vault_path: &Path) -> MosaicResult<Vec<AuditEntry>> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+
+        if !vault_paths.audit_log.exists() {
+            return Ok(vec![]);
+        }
+
+        Ok(core::read_string(&vault_paths.audit_log)?
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Canvas directory containing `path`, if any
+    fn canvas_dir(path: &Path, vault_path: &Path) -> Option<PathBuf> {
+        path.ancestors()
+            .take_while(|dir| *dir != vault_path)
+            .find(|dir| CanvasPaths::from_root(dir).is_valid_v2())
+            .map(Path::to_path_buf)
+    }
+
+    fn relative(vault_path: &Path, path: &Path) -> String {
+        path.strip_prefix(vault_path)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    /// OS user running the app
+    pub fn actor() -> String {
+        std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "unknown".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::testing::TempVault;
+    use serde_json::json;
+
+    /// A vault with three audit entries
+    fn temp_vault() -> TempVault {
+        let vault = TempVault::new();
+        for i in 0..3 {
+            AuditService::log(
+                &vault.root,
+                AuditOperation::VaultUpdate,
+                json!({ "step": i }),
+            )
+            .unwrap();
+        }
+        vault
+    }
+
+    fn lines(vault_paths: &VaultPaths) -> Vec<String> {
+        core::read_string(&vault_paths.audit_log)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn write_lines(vault_paths: &VaultPaths, lines: &[String]) {
+        core::write_string(&vault_paths.audit_log, &format!("{}\n", lines.join("\n"))).unwrap();
+    }
+
+    #[test]
+    fn test_verify_valid_chain() {
+        let vault = temp_vault();
+        let root = &vault.root;
+        let verification = AuditService::verify(root).unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.entries, 3);
+    }
+
+    #[test]
+    fn test_verify_detects_edits_and_rehashing() {
+        let vault = temp_vault();
+        let root = &vault.root;
+        let vault_paths = &vault.paths;
+        let original = lines(vault_paths);
+
+        let mut edited = original.clone();
+        edited[1] = edited[1].replace(r#""step":1"#, r#""step":9"#);
+        write_lines(vault_paths, &edited);
+        let verification = AuditService::verify(root).unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.broken_at, Some(2));
+
+        // Recomputing the hash without the audit key does not help
+        let mut entry: AuditEntry = serde_json::from_str(&edited[1]).unwrap();
+        entry.hash = entry.compute_hash(b"guessed key");
+        edited[1] = serde_json::to_string(&entry).unwrap();
+        write_lines(vault_paths, &edited);
+        assert_eq!(AuditService::verify(root).unwrap().broken_at, Some(2));
+    }
+
+    #[test]
+    fn test_verify_detects_truncation_and_missing_head() {
+        let vault = temp_vault();
+        let root = &vault.root;
+        let vault_paths = &vault.paths;
+        let original = lines(vault_paths);
+
+        write_lines(vault_paths, &original[..2]);
+        let verification = AuditService::verify(root).unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.broken_at, Some(3));
+
+        // Without its head the log is neither valid nor extended
+        write_lines(vault_paths, &original);
+        std::fs::remove_file(&vault_paths.audit_head).unwrap();
+        assert!(!AuditService::verify(root).unwrap().valid);
+        assert!(AuditService::log(root, AuditOperation::VaultUpdate, Value::Null).is_err());
+        assert_eq!(lines(vault_paths).len(), 3);
+    }
+}
@@ -0,0 +1,347 @@
+// Breach Service
+//
+// Checks credential passwords against a local Pwned Passwords-style SHA-1
+// dataset. Passwords are hashed in memory and looked up on disk; neither
+// the plaintext nor its hash leaves the process.
+
+use crate::core::{self, MosaicError, MosaicResult};
+use crate::models::{BreachCheckReport, BreachDataset, BreachDatasetFormat, PasswordBreach};
+use crate::services::{ConfigService, SecretService, WorkspaceService};
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Hex digits of the SHA-1 that name a range file
+const RANGE_PREFIX_LEN: usize = 5;
+
+/// App data folder of the per-vault dataset settings
+const DATASET_DIR: &str = "breach-datasets";
+
+pub struct BreachService;
+
+impl BreachService {
+    /// Use a dataset directory (range files) or file (sorted `SHA1:COUNT`
+    /// lines) for a vault's password checks
+    pub fn set_dataset(vault_path: &Path, dataset_path: &Path) -> MosaicResult<BreachDataset> {
+        let format = if dataset_path.is_dir() {
+            let has_range_file = core::fs::list_dir(dataset_path)?.iter().any(|p| {
+                p.extension().is_some_and(|ext| ext == "txt")
+                    && p.file_stem().is_some_and(|stem| {
+                        stem.len() == RANGE_PREFIX_LEN
+                            && stem
+                                .to_string_lossy()
+                                .chars()
+                                .all(|c| c.is_ascii_hexdigit())
+                    })
+            });
+            if !has_range_file {
+                return Err(MosaicError::invalid_format(format!(
+                    "No range files (e.g. 21BD1.txt) in {}",
+                    dataset_path.display()
+                )));
+            }
+            BreachDatasetFormat::Range
+        } else if dataset_path.is_file() {
+            let mut first = String::new();
+            BufReader::new(File::open(dataset_path)?).read_line(&mut first)?;
+            if parse_line(&first).is_none_or(|(hash, _)| hash.len() != 40) {
+                return Err(MosaicError::invalid_format(format!(
+                    "Expected SHA1:COUNT lines in {}",
+                    dataset_path.display()
+                )));
+            }
+            BreachDatasetFormat::Combined
+        } else {
+            return Err(MosaicError::not_found(&dataset_path.to_string_lossy()));
+        };
+
+        let dataset = BreachDataset {
+            path: dataset_path.to_string_lossy().to_string(),
+            format,
+            configured_at: core::now_iso(),
+        };
+        core::write_json(&Self::dataset_file(vault_path)?, &dataset)?;
+
+        Ok(dataset)
+    }
+
+    /// Dataset configured for a vault on this machine, if any
+    pub fn dataset(vault_path: &Path) -> MosaicResult<Option<BreachDataset>> {
+        let dataset_file = Self::dataset_file(vault_path)?;
+
+        if !dataset_file.exists() {
+            return Ok(None);
+        }
+
+        core::read_json(&dataset_file).map(Some)
+    }
+
+    /// Stop using a dataset; the dataset itself is left in place
+    pub fn clear_dataset(vault_path: &Path) -> MosaicResult<()> {
+        let dataset_file = Self::dataset_file(vault_path)?;
+
+        if dataset_file.exists() {
+            core::fs::remove_file(&dataset_file)?;
+        }
+        Ok(())
+    }
+
+    /// The dataset is on this machine, so its settings are kept in the app
+    /// data directory rather than in the vault
+    fn dataset_file(vault_path: &Path) -> MosaicResult<PathBuf> {
+        ConfigService::vault_file(vault_path, DATASET_DIR)
+    }
+
+    /// Check the passwords of a canvas's credential nodes (or the given
+    /// ones) and record the result on each node
+    ///
+    /// A breached password sets `breached`, `compromised` and
+    /// `breachCount`. A password not in the dataset only gets a
+    /// `breachCount` of 0: the account may still have leaked elsewhere, so
+    /// the flags are left as the analyst set them.
+    pub fn check(
+        canvas_path: &Path,
+        node_ids: Option<&[String]>,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<BreachCheckReport> {
+        let vault_path = SecretService::vault_of(canvas_path)?;
+        let dataset = Self::dataset(&vault_path)?.ok_or_else(|| {
+            MosaicError::not_found(&format!(
+                "Breach dataset for vault {}",
+                vault_path.display()
+            ))
+        })?;
+
+        // Sealed passwords are opened in this copy only, which is never saved
+        let mut workspace = WorkspaceService::load(canvas_path)?;
+        SecretService::reveal_workspace(&vault_path, &mut workspace)?;
+
+        let mut checked = 0;
+        let mut breached = Vec::new();
+        let mut updates: HashMap<String, HashMap<String, Value>> = HashMap::new();
+        for node in workspace.nodes.iter().filter(|n| {
+            n.node_type == "credential" && node_ids.is_none_or(|ids| ids.contains(&n.id))
+        }) {
+            let Some(password) = node
+                .data
+                .get("password")
+                .and_then(Value::as_str)
+                .filter(|p| !p.is_empty())
+            else {
+                continue;
+            };
+            checked += 1;
+
+            let hash = format!("{:X}", Sha1::digest(password.as_bytes()));
+            let count = Self::count(&dataset, &hash)?;
+
+            let mut fields = HashMap::from([("breachCount".to_string(), json!(count))]);
+            if count > 0 {
+                fields.insert("breached".to_string(), json!(true));
+                fields.insert("compromised".to_string(), json!(true));
+                breached.push(PasswordBreach {
+                    node_id: node.id.clone(),
+                    count,
+                });
+            }
+            fields.retain(|key, value| node.data.get(key) != Some(value));
+            if !fields.is_empty() {
+                updates.insert(node.id.clone(), fields);
+            }
+        }
+
+        let mut updated_node_ids: Vec<String> = updates.keys().cloned().collect();
+        updated_node_ids.sort();
+        let revision = if updates.is_empty() {
+            None
+        } else {
+            Some(WorkspaceService::update_nodes_data(
+                canvas_path,
+                updates,
+                expected_revision,
+            )?)
+        };
+
+        Ok(BreachCheckReport {
+            checked,
+            breached,
+            updated_node_ids,
+            revision,
+        })
+    }
+
+    /// Times an uppercase hex SHA-1 appears in the dataset (0 if absent)
+    fn count(dataset: &BreachDataset, hash: &str) -> MosaicResult<u64> {
+        match dataset.format {
+            BreachDatasetFormat::Range => {
+                let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LEN);
+                let range_path = Path::new(&dataset.path).join(format!("{}.txt", prefix));
+                // Prefixes without a file count as not found, so partial
+                // datasets work
+                if !range_path.exists() {
+                    return Ok(0);
+                }
+
+                for line in BufReader::new(File::open(&range_path)?).lines() {
+                    if let Some((line_suffix, count)) = parse_line(&line?) {
+                        if line_suffix.eq_ignore_ascii_case(suffix) {
+                            return Ok(count);
+                        }
+                    }
+                }
+                Ok(0)
+            }
+            BreachDatasetFormat::Combined => search_sorted(Path::new(&dataset.path), hash),
+        }
+    }
+}
+
+/// `HASH:COUNT`, tolerating a missing count
+fn parse_line(line: &str) -> Option<(&str, u64)> {
+    let line = line.trim();
+    let (hash, count) = line.split_once(':').unwrap_or((line, "1"));
+    if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((hash, count.trim().parse().unwrap_or(1)))
+}
+
+/// Count of `hash` in a file of `HASH:COUNT` lines sorted by hash
+fn search_sorted(path: &Path, hash: &str) -> MosaicResult<u64> {
+    Ok(core::fs::search_sorted(path, hash)?
+        .as_deref()
+        .and_then(parse_line)
+        .map_or(0, |(_, count)| count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::CanvasPaths;
+    use crate::models::{Position, WorkspaceNode};
+    use crate::services::testing::{self, TempVault};
+    use crate::services::NodeFileService;
+
+    /// SHA-1 of "password"
+    const PASSWORD_SHA1: &str = "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8";
+
+    #[test]
+    fn test_search_sorted() {
+        let temp = testing::temp_dir();
+        let dir = temp.path();
+        let lines = [
+            "0000000A2DC2CA7A32F9B3F7E4B94F13B97F0B5C:3",
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:9545824",
+            "7C4A8D09CA3762AF61E59520943DC26494F8941B:42",
+            "FFFFFFF8A0382AA9C8D9536EFBA77F261815334D:1",
+        ];
+
+        for (name, content) in [
+            ("lf.txt", lines.join("\n")),
+            ("trailing.txt", format!("{}\n", lines.join("\n"))),
+            ("crlf.txt", format!("{}\r\n", lines.join("\r\n"))),
+        ] {
+            let path = dir.join(name);
+            std::fs::write(&path, content).unwrap();
+
+            // First and last lines, one in between and hashes sorting
+            // before, between and after every line
+            assert_eq!(
+                search_sorted(&path, &lines[0][..40]).unwrap(),
+                3,
+                "{}",
+                name
+            );
+            assert_eq!(
+                search_sorted(&path, PASSWORD_SHA1).unwrap(),
+                9545824,
+                "{}",
+                name
+            );
+            assert_eq!(
+                search_sorted(&path, &lines[3][..40]).unwrap(),
+                1,
+                "{}",
+                name
+            );
+            for missing in ["0".repeat(40), "6".repeat(40), "F".repeat(40)] {
+                assert_eq!(search_sorted(&path, &missing).unwrap(), 0, "{}", name);
+            }
+            // Lowercase files sort the same way
+            let lower = dir.join(format!("lower-{}", name));
+            std::fs::write(
+                &lower,
+                std::fs::read_to_string(&path).unwrap().to_lowercase(),
+            )
+            .unwrap();
+            assert_eq!(
+                search_sorted(&lower, PASSWORD_SHA1).unwrap(),
+                9545824,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_range_dataset_against_node_files() {
+        let vault = TempVault::new();
+        let vault_path = &vault.root;
+        let canvas = vault.default_canvas();
+
+        let dataset = vault.outside().join("range");
+        std::fs::create_dir_all(&dataset).unwrap();
+        std::fs::write(
+            dataset.join("5BAA6.txt"),
+            "003D68EB55068C33ACE09247EE4C639306B:3\r\n1E4C9B93F3F0682250B6CF8331B7EE68FD8:9545824\r\n",
+        )
+        .unwrap();
+        let configured = BreachService::set_dataset(vault_path, &dataset).unwrap();
+        assert_eq!(configured.format, BreachDatasetFormat::Range);
+        // The machine-local path stays out of the vault
+        assert!(!core::fs::list_files_recursive(vault_path)
+            .unwrap()
+            .iter()
+            .any(|p| core::read_string(p).is_ok_and(|s| s.contains(&configured.path))));
+
+        // Credentials saved the way the frontend does, the password sealed
+        // in properties.json
+        let mut ids = Vec::new();
+        for password in ["password", "correct horse battery staple"] {
+            let node = WorkspaceNode::new("credential", Position::default(), HashMap::new());
+            WorkspaceService::add_node(&canvas, node.clone(), None).unwrap();
+            let props = json!({
+                "position": { "x": 0, "y": 0 },
+                "data": { "password": password }
+            });
+            NodeFileService::write_node_files(
+                &canvas,
+                &node.id,
+                "credential",
+                Some("alice"),
+                Some(&props.to_string()),
+            )
+            .unwrap();
+            ids.push(node.id);
+        }
+        let props_path = CanvasPaths::from_root(&canvas)
+            .nodes
+            .join(&ids[0])
+            .join("data")
+            .join("properties.json");
+        let stored: Value = core::read_json(&props_path).unwrap();
+        assert_ne!(stored["data"]["password"], "password");
+
+        let report = BreachService::check(&canvas, None, None).unwrap();
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.breached.len(), 1);
+        assert_eq!(report.breached[0].node_id, ids[0]);
+        assert_eq!(report.breached[0].count, 9545824);
+
+        BreachService::clear_dataset(vault_path).unwrap();
+        assert!(BreachService::dataset(vault_path).unwrap().is_none());
+    }
+}
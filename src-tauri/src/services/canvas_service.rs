@@ -3,9 +3,12 @@
 // Handles all canvas-related operations
 
 use crate::core::{self, paths::CanvasPaths, MosaicError, MosaicResult};
-use crate::models::{CanvasInfo, CanvasMeta, CanvasUIState, WorkspaceData};
-use crate::services::MigrationService;
-use std::path::Path;
+use crate::models::{CanvasInfo, CanvasMeta, CanvasTemplate, CanvasUIState, Tlp, WorkspaceData};
+use crate::services::{
+    MigrationService, NodeFileService, SecretService, TemplateService, WorkspaceService,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 pub struct CanvasService;
 
@@ -17,24 +20,8 @@ impl CanvasService {
         name: &str,
         description: Option<&str>,
     ) -> MosaicResult<CanvasInfo> {
-        // Generate folder name
-        let folder_name = core::sanitize_name(name);
-        let canvas_path = canvases_dir.join(&folder_name);
-
-        // Handle name collision
-        let final_path = if canvas_path.exists() {
-            let mut counter = 1;
-            loop {
-                let new_name = format!("{}_{}", folder_name, counter);
-                let new_path = canvases_dir.join(&new_name);
-                if !new_path.exists() {
-                    break new_path;
-                }
-                counter += 1;
-            }
-        } else {
-            canvas_path
-        };
+        // Generate folder name (handling collisions)
+        let final_path = Self::unique_path(canvases_dir, name);
 
         let canvas_paths = CanvasPaths::from_root(&final_path);
 
@@ -56,39 +43,72 @@ impl CanvasService {
         core::write_json(&canvas_paths.state_json, &state)?;
 
         // Create empty workspace
-        let workspace = WorkspaceData::new();
-        core::write_json(&canvas_paths.workspace_json, &workspace)?;
+        NodeFileService::write(&canvas_paths, &WorkspaceData::new())?;
 
-        Ok(CanvasInfo::from_meta(
-            &meta,
-            final_path.to_string_lossy().to_string(),
-        ))
+        Ok(Self::info(&meta, &final_path))
+    }
+
+    /// Create a new canvas in a vault from a template
+    ///
+    /// `canvas_name` and `date` are always available as variables unless overridden.
+    pub fn create_from_template(
+        canvases_dir: &Path,
+        vault_id: &str,
+        name: &str,
+        description: Option<&str>,
+        template: &CanvasTemplate,
+        variables: &HashMap<String, String>,
+    ) -> MosaicResult<CanvasInfo> {
+        let mut variables = variables.clone();
+        variables
+            .entry("canvas_name".to_string())
+            .or_insert_with(|| name.to_string());
+        variables
+            .entry("date".to_string())
+            .or_insert_with(|| chrono::Utc::now().format("%Y-%m-%d").to_string());
+
+        let description = description.unwrap_or(&template.description);
+        let canvas = Self::create(canvases_dir, vault_id, name, Some(description))?;
+        let path = Path::new(&canvas.path);
+
+        let (mut workspace, node_ids) = TemplateService::instantiate(template, &variables);
+        let result = core::paths::find_vault_root(path)
+            .map_or(Ok(()), |vault_path| {
+                SecretService::rebind_nodes(&vault_path, &mut workspace, &node_ids)
+            })
+            .and_then(|_| WorkspaceService::save(path, &workspace))
+            .and_then(|_| Self::update_tags(path, template.tags.clone()));
+
+        if result.is_err() {
+            let _ = core::remove_dir_all(path);
+        }
+        result
     }
 
     /// Open a canvas (with auto-migration from v1)
     pub fn open(path: &Path) -> MosaicResult<CanvasInfo> {
-        let canvas_paths = CanvasPaths::from_root(&path.to_path_buf());
+        let canvas_paths = CanvasPaths::from_root(path);
+
+        // Outdated canvases are reported, not rewritten; migrate_canvas
+        // upgrades them when asked to
+        if MigrationService::canvas_needs_migration(path) {
+            let meta = MigrationService::preview_canvas_meta(path)?;
+            let mut info = Self::info(&meta, path);
+            info.needs_migration = true;
+            return Ok(info);
+        }
 
-        // Check v2 format first
         if canvas_paths.is_valid_v2() {
             let meta: CanvasMeta = core::read_json(&canvas_paths.meta_json)?;
-            return Ok(CanvasInfo::from_meta(
-                &meta,
-                path.to_string_lossy().to_string(),
-            ));
-        }
-
-        // Try v1 format and migrate
-        if canvas_paths.is_valid_v1() {
-            return MigrationService::migrate_canvas(path);
+            return Ok(Self::info(&meta, path));
         }
 
         Err(MosaicError::canvas_not_found(&path.to_string_lossy()))
     }
 
-    /// List all canvases in a directory
+    /// List all canvases in a directory, including those in folders
     pub fn list(canvases_dir: &Path) -> MosaicResult<Vec<CanvasInfo>> {
-        let subdirs = core::list_subdirs(canvases_dir)?;
+        let subdirs = core::paths::list_canvas_dirs(canvases_dir)?;
 
         let mut canvases = Vec::new();
         for dir in subdirs {
@@ -105,10 +125,12 @@ impl CanvasService {
 
     /// Rename a canvas
     pub fn rename(path: &Path, new_name: &str) -> MosaicResult<CanvasInfo> {
-        let canvas_paths = CanvasPaths::from_root(&path.to_path_buf());
+        let canvas_paths = CanvasPaths::from_root(path);
 
-        // Ensure v2 format (auto-migrate if needed)
-        let _info = Self::open(path)?;
+        // Renaming rewrites meta.json, so bring the canvas up to date first
+        if MigrationService::canvas_needs_migration(path) {
+            MigrationService::migrate_canvas(path)?;
+        }
 
         // Read and update metadata
         let mut meta: CanvasMeta = core::read_json(&canvas_paths.meta_json)?;
@@ -132,10 +154,7 @@ impl CanvasService {
             path.to_path_buf()
         };
 
-        Ok(CanvasInfo::from_meta(
-            &meta,
-            final_path.to_string_lossy().to_string(),
-        ))
+        Ok(Self::info(&meta, &final_path))
     }
 
     /// Delete a canvas
@@ -148,9 +167,29 @@ impl CanvasService {
         Ok(canvas_id)
     }
 
+    /// Set or clear the canvas's TLP classification
+    pub fn update_classification(
+        path: &Path,
+        classification: Option<Tlp>,
+    ) -> MosaicResult<CanvasInfo> {
+        let canvas_paths = CanvasPaths::from_root(path);
+
+        if !canvas_paths.is_valid_v2() {
+            return Err(MosaicError::canvas_not_found(&path.to_string_lossy()));
+        }
+
+        let mut meta: CanvasMeta = core::read_json(&canvas_paths.meta_json)?;
+        meta.classification = classification;
+        meta.touch();
+
+        core::write_json(&canvas_paths.meta_json, &meta)?;
+
+        Ok(Self::info(&meta, path))
+    }
+
     /// Update canvas tags
     pub fn update_tags(path: &Path, tags: Vec<String>) -> MosaicResult<CanvasInfo> {
-        let canvas_paths = CanvasPaths::from_root(&path.to_path_buf());
+        let canvas_paths = CanvasPaths::from_root(path);
 
         if !canvas_paths.is_valid_v2() {
             return Err(MosaicError::canvas_not_found(&path.to_string_lossy()));
@@ -162,15 +201,12 @@ impl CanvasService {
 
         core::write_json(&canvas_paths.meta_json, &meta)?;
 
-        Ok(CanvasInfo::from_meta(
-            &meta,
-            path.to_string_lossy().to_string(),
-        ))
+        Ok(Self::info(&meta, path))
     }
 
     /// Update canvas description
     pub fn update_description(path: &Path, description: &str) -> MosaicResult<CanvasInfo> {
-        let canvas_paths = CanvasPaths::from_root(&path.to_path_buf());
+        let canvas_paths = CanvasPaths::from_root(path);
 
         if !canvas_paths.is_valid_v2() {
             return Err(MosaicError::canvas_not_found(&path.to_string_lossy()));
@@ -182,15 +218,12 @@ impl CanvasService {
 
         core::write_json(&canvas_paths.meta_json, &meta)?;
 
-        Ok(CanvasInfo::from_meta(
-            &meta,
-            path.to_string_lossy().to_string(),
-        ))
+        Ok(Self::info(&meta, path))
     }
 
     /// Load canvas UI state
     pub fn load_state(path: &Path) -> MosaicResult<CanvasUIState> {
-        let canvas_paths = CanvasPaths::from_root(&path.to_path_buf());
+        let canvas_paths = CanvasPaths::from_root(path);
 
         if canvas_paths.state_json.exists() {
             core::read_json(&canvas_paths.state_json)
@@ -201,7 +234,7 @@ impl CanvasService {
 
     /// Save canvas UI state
     pub fn save_state(path: &Path, state: &CanvasUIState) -> MosaicResult<()> {
-        let canvas_paths = CanvasPaths::from_root(&path.to_path_buf());
+        let canvas_paths = CanvasPaths::from_root(path);
         core::ensure_dir(&canvas_paths.mosaic)?;
 
         let mut state = state.clone();
@@ -210,9 +243,223 @@ impl CanvasService {
         core::write_json(&canvas_paths.state_json, &state)
     }
 
+    /// Duplicate a canvas alongside the original under a new ID
+    pub fn duplicate(
+        path: &Path,
+        new_name: Option<&str>,
+        regenerate_ids: bool,
+    ) -> MosaicResult<CanvasInfo> {
+        let source = Self::open(path)?;
+        let parent = path
+            .parent()
+            .ok_or_else(|| MosaicError::io_error("Cannot get parent"))?;
+        let name = new_name
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{} (copy)", source.name));
+
+        Self::copy_into(path, parent, &source.vault_id, &name, regenerate_ids)
+    }
+
+    /// Copy a canvas into another vault's canvases directory under a new ID
+    pub fn copy_to_vault(
+        path: &Path,
+        canvases_dir: &Path,
+        vault_id: &str,
+        new_name: Option<&str>,
+        regenerate_ids: bool,
+    ) -> MosaicResult<CanvasInfo> {
+        let source = Self::open(path)?;
+        let name = new_name.unwrap_or(&source.name);
+
+        Self::copy_into(path, canvases_dir, vault_id, name, regenerate_ids)
+    }
+
+    /// Move a canvas into another vault, keeping its ID
+    pub fn move_to_vault(
+        path: &Path,
+        canvases_dir: &Path,
+        vault_id: &str,
+    ) -> MosaicResult<CanvasInfo> {
+        let source_vault = core::paths::find_vault_root(path);
+        let target = Self::relocate(path, canvases_dir, vault_id)?;
+        if let Some(source_vault) = source_vault {
+            SecretService::transfer(&target, &source_vault)?;
+        }
+
+        let canvas_paths = CanvasPaths::from_root(&target);
+        let mut meta: CanvasMeta = core::read_json(&canvas_paths.meta_json)?;
+        meta.vault_id = vault_id.to_string();
+        meta.touch();
+        core::write_json(&canvas_paths.meta_json, &meta)?;
+
+        Ok(Self::info(&meta, &target))
+    }
+
+    /// Move a canvas into a folder of the same vault
+    pub fn move_to_folder(path: &Path, folder_dir: &Path) -> MosaicResult<CanvasInfo> {
+        let source = Self::open(path)?;
+        let target = Self::relocate(path, folder_dir, &source.vault_id)?;
+        Self::open(&target)
+    }
+
+    /// Canvas info with the folder it sits in
+    fn info(meta: &CanvasMeta, path: &Path) -> CanvasInfo {
+        CanvasInfo {
+            folder: core::paths::canvas_folder(path),
+            ..CanvasInfo::from_meta(meta, path.to_string_lossy().to_string())
+        }
+    }
+
+    /// Move a canvas directory into `dir` (no-op if already there)
+    ///
+    /// If the original cannot be removed after a copy, the copy becomes a
+    /// canvas of its own in `vault_id` so two canvases never share an ID.
+    fn relocate(path: &Path, dir: &Path, vault_id: &str) -> MosaicResult<PathBuf> {
+        let source = Self::open(path)?;
+        let in_place = match (path.parent().map(Path::canonicalize), dir.canonicalize()) {
+            (Some(Ok(parent)), Ok(dir)) => parent == dir,
+            _ => false,
+        };
+        if in_place {
+            return Ok(path.to_path_buf());
+        }
+
+        let target = Self::unique_path(dir, &source.name);
+        core::ensure_dir(dir)?;
+
+        // rename() fails across filesystems and cannot re-encrypt between
+        // vaults; fall back to copy + delete
+        let same_encryption =
+            core::crypto::encrypted_root(path) == core::crypto::encrypted_root(dir);
+        if !same_encryption || core::rename(path, &target).is_err() {
+            if let Err(e) = core::copy_dir_all(path, &target, &[]) {
+                let _ = core::remove_dir_all(&target);
+                return Err(e);
+            }
+            if let Err(e) = core::remove_dir_all(path) {
+                let kept = Self::adopt_copy(path, &target, vault_id, &source.name, false)?;
+                return Err(MosaicError::io_error(format!(
+                    "{}; the copy was kept as a new canvas at {}",
+                    e, kept.path
+                )));
+            }
+        }
+
+        Ok(target)
+    }
+
+    /// Copy a canvas tree into `canvases_dir` and give the copy its own identity
+    fn copy_into(
+        path: &Path,
+        canvases_dir: &Path,
+        vault_id: &str,
+        name: &str,
+        regenerate_ids: bool,
+    ) -> MosaicResult<CanvasInfo> {
+        let source_paths = CanvasPaths::from_root(path);
+        let target = Self::unique_path(canvases_dir, name);
+
+        // Migration backups belong to the original
+        let result = core::copy_dir_all(path, &target, &[source_paths.backups])
+            .and_then(|_| Self::adopt_copy(path, &target, vault_id, name, regenerate_ids));
+
+        if result.is_err() {
+            let _ = core::remove_dir_all(&target);
+        }
+        result
+    }
+
+    /// Give a copied canvas tree a new identity and re-seal its secrets for
+    /// the vault it landed in
+    fn adopt_copy(
+        path: &Path,
+        target: &Path,
+        vault_id: &str,
+        name: &str,
+        regenerate_ids: bool,
+    ) -> MosaicResult<CanvasInfo> {
+        // Re-seal first, so regenerated IDs are re-sealed under the new key
+        if let Some(source_vault) = core::paths::find_vault_root(path) {
+            SecretService::transfer(target, &source_vault)?;
+        }
+        Self::rebind_copy(target, vault_id, name, regenerate_ids)
+    }
+
+    /// Rewrite a freshly copied canvas's metadata, state and (optionally) IDs
+    fn rebind_copy(
+        path: &Path,
+        vault_id: &str,
+        name: &str,
+        regenerate_ids: bool,
+    ) -> MosaicResult<CanvasInfo> {
+        let canvas_paths = CanvasPaths::from_root(path);
+
+        let source: CanvasMeta = core::read_json(&canvas_paths.meta_json)?;
+        let meta = CanvasMeta::new(
+            core::generate_uuid(),
+            vault_id.to_string(),
+            name.to_string(),
+        )
+        .with_description(source.description)
+        .with_tags(source.tags)
+        .with_classification(source.classification);
+        core::write_json(&canvas_paths.meta_json, &meta)?;
+
+        // Selection refers to the original's session
+        let mut state = Self::load_state(path)?;
+        state.selected_nodes.clear();
+        state.selected_edges.clear();
+        Self::save_state(path, &state)?;
+
+        if regenerate_ids {
+            let mut workspace = WorkspaceService::load(path)?;
+            let node_ids = workspace.regenerate_ids();
+            if let Some(vault_path) = core::paths::find_vault_root(path) {
+                SecretService::rebind_nodes(&vault_path, &mut workspace, &node_ids)?;
+            }
+            Self::rename_node_files(&canvas_paths.nodes, &node_ids)?;
+            WorkspaceService::save(path, &workspace)?;
+        }
+
+        Ok(Self::info(&meta, path))
+    }
+
+    /// Rename per-node files (nodes/<node_id>.<ext>) after IDs were regenerated
+    fn rename_node_files(nodes_dir: &Path, node_ids: &HashMap<String, String>) -> MosaicResult<()> {
+        for file in core::fs::list_dir(nodes_dir)? {
+            let stem = file.file_stem().map(|s| s.to_string_lossy().to_string());
+            if let Some(new_id) = stem.and_then(|s| node_ids.get(&s)) {
+                let mut renamed = nodes_dir.join(new_id);
+                if let Some(ext) = file.extension() {
+                    renamed.set_extension(ext);
+                }
+                core::rename(&file, &renamed)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Non-existing folder for `name` inside `dir`, suffixed with _1, _2, ... on collision
+    fn unique_path(dir: &Path, name: &str) -> PathBuf {
+        let folder_name = core::sanitize_name(name);
+        let path = dir.join(&folder_name);
+        if !path.exists() {
+            return path;
+        }
+
+        let mut counter = 1;
+        loop {
+            let new_path = dir.join(format!("{}_{}", folder_name, counter));
+            if !new_path.exists() {
+                return new_path;
+            }
+            counter += 1;
+        }
+    }
+
     /// Get canvas ID from meta.json
     fn get_canvas_id(path: &Path) -> Option<String> {
-        let canvas_paths = CanvasPaths::from_root(&path.to_path_buf());
+        let canvas_paths = CanvasPaths::from_root(path);
 
         if canvas_paths.is_valid_v2() {
             core::read_json::<CanvasMeta>(&canvas_paths.meta_json)
@@ -223,3 +470,80 @@ impl CanvasService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::WorkspaceNode;
+    use crate::services::testing::TempVault;
+    use serde_json::Value;
+
+    #[test]
+    fn test_duplicate_gets_new_id() {
+        let vault = TempVault::new();
+        let original =
+            CanvasService::create(&vault.paths.canvases, &vault.id, "Case", None).unwrap();
+
+        let copy = CanvasService::duplicate(Path::new(&original.path), None, true).unwrap();
+        assert_ne!(copy.id, original.id);
+        assert_eq!(copy.name, "Case (copy)");
+        assert!(Path::new(&original.path).exists());
+    }
+
+    #[test]
+    fn test_move_to_folder() {
+        let vault = TempVault::new();
+        let vault_paths = &vault.paths;
+        let original =
+            CanvasService::create(&vault_paths.canvases, &vault.id, "Case", None).unwrap();
+        let path = PathBuf::from(&original.path);
+
+        // The same directory spelled differently is a no-op
+        let same_dir = vault_paths.canvases.join("..").join("canvases");
+        let unmoved = CanvasService::move_to_folder(&path, &same_dir).unwrap();
+        assert_eq!(unmoved.path, original.path);
+
+        let moved =
+            CanvasService::move_to_folder(&path, &vault_paths.canvases.join("Cases")).unwrap();
+        assert_eq!(moved.id, original.id);
+        assert!(!path.exists());
+        assert!(Path::new(&moved.path).starts_with(vault_paths.canvases.join("Cases")));
+    }
+
+    #[test]
+    fn test_move_to_vault_keeps_id() {
+        let (source, target) = (TempVault::new(), TempVault::new());
+        let original =
+            CanvasService::create(&source.paths.canvases, &source.id, "Case", None).unwrap();
+
+        let moved = CanvasService::move_to_vault(
+            Path::new(&original.path),
+            &target.paths.canvases,
+            &target.id,
+        )
+        .unwrap();
+        assert_eq!(moved.id, original.id);
+        assert_eq!(moved.vault_id, target.id);
+        assert!(!Path::new(&original.path).exists());
+    }
+
+    #[test]
+    fn test_copy_with_new_ids_keeps_secrets_readable() {
+        let (source, target) = (TempVault::new(), TempVault::new());
+        let canvas = source.create_canvas("Case");
+        let data = HashMap::from([("password".to_string(), Value::from("hunter2"))]);
+        let mut workspace = WorkspaceData::new();
+        workspace.add_node(WorkspaceNode::new("credential", Default::default(), data));
+        WorkspaceService::save(&canvas, &workspace).unwrap();
+
+        let copy =
+            CanvasService::copy_to_vault(&canvas, &target.paths.canvases, &target.id, None, true)
+                .unwrap();
+        let copied = WorkspaceService::load(Path::new(&copy.path)).unwrap();
+        assert_ne!(copied.nodes[0].id, workspace.nodes[0].id);
+
+        let password =
+            SecretService::reveal(Path::new(&copy.path), &copied.nodes[0].id, "password");
+        assert_eq!(password.unwrap().as_deref(), Some("hunter2"));
+    }
+}
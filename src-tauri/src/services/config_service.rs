@@ -2,8 +2,9 @@
 //
 // Handles app-level configuration persistence
 
-use crate::core::{self, paths::get_config_path, MosaicResult};
-use crate::models::AppConfig;
+use crate::core::{self, paths::get_config_path, MosaicResult, VaultPaths};
+use crate::models::{AppConfig, VaultMeta};
+use std::path::{Path, PathBuf};
 use tauri::AppHandle;
 
 pub struct ConfigService;
@@ -25,4 +26,16 @@ impl ConfigService {
         let path = get_config_path(app_handle)?;
         core::write_json(&path, config)
     }
+
+    /// Per-vault settings file in the app data directory
+    /// (`<data>/<dir>/<vault id>.json`), for settings that belong to this
+    /// machine - such as paths of local datasets - and must not travel
+    /// with a shared vault
+    pub fn vault_file(vault_path: &Path, dir: &str) -> MosaicResult<PathBuf> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+        let meta: VaultMeta = core::read_json(&vault_paths.vault_json)?;
+        Ok(core::paths::data_dir()?
+            .join(dir)
+            .join(format!("{}.json", meta.id)))
+    }
 }
@@ -0,0 +1,400 @@
+// Encryption Service
+//
+// Passphrase-encrypted vaults: enabling/disabling encryption in place,
+// unlocking, locking and auto-locking after inactivity
+
+use crate::core::{self, crypto, paths::VaultPaths, MosaicError, MosaicResult};
+use crate::events::EventEmitter;
+use crate::models::{KdfParams, VaultConversionReport, VaultEncryption, VaultLockStatus};
+use crate::services::VaultService;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// Known plaintext sealed into the header to check passphrases
+const VERIFIER: &[u8] = b"mosaicflow-vault";
+const VERIFIER_AAD: &[u8] = b".mosaicflow/encryption.json";
+
+/// Inactivity timeout used when none is given
+pub const DEFAULT_AUTO_LOCK_MINUTES: u32 = 15;
+
+/// How often the auto-lock thread checks for idle vaults
+const AUTO_LOCK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Directories never rewritten by conversion
+const SKIPPED_DIRS: &[&str] = &[".git"];
+
+pub struct EncryptionService;
+
+impl EncryptionService {
+    /// Whether a vault is encrypted and whether its key is held in memory
+    pub fn status(vault_path: &Path) -> VaultLockStatus {
+        let vault_paths = VaultPaths::from_root(vault_path);
+
+        VaultLockStatus {
+            vault_path: vault_path.to_string_lossy().to_string(),
+            encrypted: vault_paths.is_encrypted(),
+            unlocked: crypto::is_unlocked(vault_path),
+            auto_lock_minutes: crypto::auto_lock_timeout(vault_path)
+                .map(|d| (d.as_secs() / 60) as u32),
+        }
+    }
+
+    /// Derive the vault key from a passphrase and keep it in memory
+    ///
+    /// `auto_lock_minutes` of 0 disables auto-lock.
+    pub fn unlock(
+        vault_path: &Path,
+        passphrase: &str,
+        auto_lock_minutes: Option<u32>,
+    ) -> MosaicResult<VaultLockStatus> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+
+        if !vault_paths.is_encrypted() {
+            return Err(MosaicError::invalid_format("Vault is not encrypted"));
+        }
+
+        let header: VaultEncryption = core::read_json(&vault_paths.encryption_json)?;
+        let key = Self::verify_passphrase(&header, passphrase)?;
+        crypto::unlock(vault_path, key, Self::auto_lock(auto_lock_minutes));
+
+        Ok(Self::status(vault_path))
+    }
+
+    /// Forget the vault key
+    pub fn lock(vault_path: &Path) -> VaultLockStatus {
+        crypto::lock(vault_path);
+        Self::status(vault_path)
+    }
+
+    /// Encrypt an existing vault in place and leave it unlocked
+    ///
+    /// The header is written first and plain files stay readable, so an
+    /// interrupted conversion can be resumed by calling this again with the
+    /// same passphrase.
+    pub fn enable(
+        vault_path: &Path,
+        passphrase: &str,
+        auto_lock_minutes: Option<u32>,
+    ) -> MosaicResult<VaultConversionReport> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+
+        if !vault_paths.is_valid() {
+            return Err(MosaicError::vault_not_found(&vault_path.to_string_lossy()));
+        }
+        if passphrase.is_empty() {
+            return Err(MosaicError::invalid_format("Passphrase must not be empty"));
+        }
+
+        let (mut header, key) = if vault_paths.is_encrypted() {
+            let header: VaultEncryption = core::read_json(&vault_paths.encryption_json)?;
+            let key = Self::verify_passphrase(&header, passphrase)?;
+            (header, key)
+        } else {
+            Self::new_header(passphrase)?
+        };
+        Self::set_converting(&vault_paths, &mut header, &key, true)?;
+
+        crypto::unlock(vault_path, key.clone(), Self::auto_lock(auto_lock_minutes));
+
+        let (converted, skipped) = Self::convert(vault_path, |file, data| {
+            if crypto::is_encrypted(data) {
+                Ok(None)
+            } else {
+                crypto::encrypt(&key, &crypto::file_aad(vault_path, file), data).map(Some)
+            }
+        })?;
+
+        // From here on plain files in the vault are rejected
+        Self::set_converting(&vault_paths, &mut header, &key, false)?;
+
+        Ok(VaultConversionReport {
+            vault_path: vault_path.to_string_lossy().to_string(),
+            encrypted: true,
+            files_converted: converted,
+            files_skipped: skipped,
+        })
+    }
+
+    /// Derive a key for a new passphrase and build its encryption header
+    fn new_header(passphrase: &str) -> MosaicResult<(VaultEncryption, crypto::VaultKey)> {
+        let salt = crypto::random_salt();
+        let key = crypto::derive_key(
+            passphrase,
+            &salt,
+            crypto::DEFAULT_MEMORY_KIB,
+            crypto::DEFAULT_ITERATIONS,
+            crypto::DEFAULT_PARALLELISM,
+        )?;
+
+        let header = VaultEncryption {
+            kdf: KdfParams {
+                algorithm: "argon2id".to_string(),
+                memory_kib: crypto::DEFAULT_MEMORY_KIB,
+                iterations: crypto::DEFAULT_ITERATIONS,
+                parallelism: crypto::DEFAULT_PARALLELISM,
+                salt: BASE64.encode(&salt),
+            },
+            cipher: "xchacha20poly1305".to_string(),
+            verifier: BASE64.encode(crypto::encrypt(&key, VERIFIER_AAD, VERIFIER)?),
+            created_at: core::now_iso(),
+            converting: false,
+            converting_mac: None,
+            version: "1.0.0".to_string(),
+        };
+
+        Ok((header, key))
+    }
+
+    /// Record in the header whether a conversion is in progress, with a MAC
+    /// under the vault key so the flag cannot be forged
+    fn set_converting(
+        vault_paths: &VaultPaths,
+        header: &mut VaultEncryption,
+        key: &crypto::VaultKey,
+        converting: bool,
+    ) -> MosaicResult<()> {
+        header.converting = converting;
+        header.converting_mac = converting.then(|| crypto::conversion_mac(key, &header.verifier));
+        core::write_json(&vault_paths.encryption_json, header)
+    }
+
+    /// Decrypt an encrypted vault in place and remove its header
+    pub fn disable(vault_path: &Path, passphrase: &str) -> MosaicResult<VaultConversionReport> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+
+        if !vault_paths.is_encrypted() {
+            return Err(MosaicError::invalid_format("Vault is not encrypted"));
+        }
+
+        let mut header: VaultEncryption = core::read_json(&vault_paths.encryption_json)?;
+        let key = Self::verify_passphrase(&header, passphrase)?;
+        Self::set_converting(&vault_paths, &mut header, &key, true)?;
+
+        let (converted, skipped) = Self::convert(vault_path, |file, data| {
+            if crypto::is_encrypted(data) {
+                crypto::decrypt(&key, &crypto::file_aad(vault_path, file), data)
+                    .map_err(|_| MosaicError::decryption_failed(&file.to_string_lossy()))
+                    .map(Some)
+            } else {
+                Ok(None)
+            }
+        })?;
+
+        // Only drop the header once every file is plain again
+        core::fs::remove_file(&vault_paths.encryption_json)?;
+        crypto::lock(vault_path);
+
+        Ok(VaultConversionReport {
+            vault_path: vault_path.to_string_lossy().to_string(),
+            encrypted: false,
+            files_converted: converted,
+            files_skipped: skipped,
+        })
+    }
+
+    /// Start the background thread that locks idle vaults (once per process)
+    pub fn start_auto_lock(app_handle: &AppHandle) {
+        static STARTED: AtomicBool = AtomicBool::new(false);
+        if STARTED.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let handle = app_handle.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(AUTO_LOCK_INTERVAL);
+
+            for root in crypto::lock_expired() {
+                Self::emit_locked(&handle, &root);
+            }
+        });
+    }
+
+    /// Announce that a vault was locked
+    pub fn emit_locked(app_handle: &AppHandle, vault_path: &Path) {
+        if let Ok(Some(vault)) = VaultService::get_info(vault_path) {
+            let emitter = EventEmitter::new(app_handle);
+            emitter.vault_locked(&vault.id, &vault.path, &vault.name);
+        }
+    }
+
+    fn auto_lock(minutes: Option<u32>) -> Option<Duration> {
+        match minutes.unwrap_or(DEFAULT_AUTO_LOCK_MINUTES) {
+            0 => None,
+            m => Some(Duration::from_secs(u64::from(m) * 60)),
+        }
+    }
+
+    fn verify_passphrase(
+        header: &VaultEncryption,
+        passphrase: &str,
+    ) -> MosaicResult<crypto::VaultKey> {
+        if header.kdf.algorithm != "argon2id" || header.cipher != "xchacha20poly1305" {
+            return Err(MosaicError::invalid_format(format!(
+                "Unsupported vault encryption: {} / {}",
+                header.kdf.algorithm, header.cipher
+            )));
+        }
+        crypto::check_kdf_params(header.kdf.memory_kib, header.kdf.iterations)?;
+
+        let salt = BASE64
+            .decode(&header.kdf.salt)
+            .map_err(|e| MosaicError::invalid_format(format!("Invalid salt: {}", e)))?;
+        let verifier = BASE64
+            .decode(&header.verifier)
+            .map_err(|e| MosaicError::invalid_format(format!("Invalid verifier: {}", e)))?;
+
+        let key = crypto::derive_key(
+            passphrase,
+            &salt,
+            header.kdf.memory_kib,
+            header.kdf.iterations,
+            header.kdf.parallelism,
+        )?;
+
+        match crypto::decrypt(&key, VERIFIER_AAD, &verifier) {
+            Ok(plain) if plain == VERIFIER => Ok(key),
+            _ => Err(MosaicError::invalid_passphrase()),
+        }
+    }
+
+    /// Rewrite every vault file through `transform` (None = leave as is)
+    ///
+    /// Each file is replaced through a temp file and rename, so an
+    /// interruption never leaves a half-written file. Returns (converted,
+    /// skipped) counts.
+    fn convert(
+        vault_path: &Path,
+        transform: impl Fn(&Path, &[u8]) -> MosaicResult<Option<Vec<u8>>>,
+    ) -> MosaicResult<(usize, usize)> {
+        let mut converted = 0;
+        let mut skipped = 0;
+
+        for file in Self::vault_files(vault_path)? {
+            let data = core::fs::read_raw(&file)?;
+            match transform(&file, &data)? {
+                Some(rewritten) => {
+                    core::fs::write_raw_atomic(&file, &rewritten)?;
+                    converted += 1;
+                }
+                None => skipped += 1,
+            }
+        }
+
+        Ok((converted, skipped))
+    }
+
+    /// Files subject to encryption (everything except vault.json and the header)
+    fn vault_files(vault_path: &Path) -> MosaicResult<Vec<PathBuf>> {
+        let files = core::fs::list_files_recursive(vault_path)?
+            .into_iter()
+            .filter(|file| !crypto::stays_plaintext(vault_path, file))
+            .filter(|file| {
+                !file
+                    .strip_prefix(vault_path)
+                    .unwrap_or(file)
+                    .components()
+                    .any(|c| SKIPPED_DIRS.iter().any(|d| c.as_os_str() == *d))
+            })
+            .collect();
+
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::ErrorCode;
+    use crate::core::paths::CanvasPaths;
+    use crate::services::testing::TempVault;
+    use crate::services::{CanvasService, WorkspaceService};
+
+    #[test]
+    fn test_enable_resume_and_path_binding() {
+        let temp = TempVault::new();
+        let (vault, canvas) = (&temp.root, temp.default_canvas());
+
+        let first = EncryptionService::enable(vault, "pw", Some(0)).unwrap();
+        assert!(first.files_converted > 0);
+
+        // Rerunning resumes with the same passphrase only
+        let again = EncryptionService::enable(vault, "pw", Some(0)).unwrap();
+        assert_eq!(again.files_converted, 0);
+        assert_eq!(
+            EncryptionService::enable(vault, "other", Some(0))
+                .unwrap_err()
+                .code,
+            ErrorCode::InvalidPassphrase
+        );
+
+        // A sealed file moved onto another path does not decrypt
+        let paths = CanvasPaths::from_root(&canvas);
+        std::fs::copy(&paths.meta_json, &paths.state_json).unwrap();
+        assert_eq!(
+            core::read_string(&paths.state_json).unwrap_err().code,
+            ErrorCode::DecryptionFailed
+        );
+        CanvasService::save_state(&canvas, &Default::default()).unwrap();
+
+        // Renames re-seal files for their new path
+        let renamed = CanvasService::rename(&canvas, "Renamed").unwrap();
+        assert!(WorkspaceService::load(Path::new(&renamed.path)).is_ok());
+
+        EncryptionService::disable(vault, "pw").unwrap();
+        assert!(!VaultPaths::from_root(vault).is_encrypted());
+    }
+
+    #[test]
+    fn test_plain_files_only_read_while_converting() {
+        let temp = TempVault::new();
+        let (vault, canvas) = (&temp.root, temp.default_canvas());
+        EncryptionService::enable(vault, "pw", Some(0)).unwrap();
+
+        // A file planted without the marker is rejected once encryption is complete
+        let paths = CanvasPaths::from_root(&canvas);
+        std::fs::write(&paths.state_json, "{}").unwrap();
+        assert_eq!(
+            core::read_string(&paths.state_json).unwrap_err().code,
+            ErrorCode::DecryptionFailed
+        );
+
+        // Setting the flag without the key does not let it through
+        let vault_paths = VaultPaths::from_root(vault);
+        let mut header: VaultEncryption = core::read_json(&vault_paths.encryption_json).unwrap();
+        assert!(!header.converting);
+        header.converting = true;
+        header.converting_mac = Some("00".repeat(32));
+        core::write_json(&vault_paths.encryption_json, &header).unwrap();
+        assert!(core::read_string(&paths.state_json).is_err());
+
+        // ...but it is read as-is while a real conversion is in progress
+        let key = crypto::key_for(vault).unwrap();
+        EncryptionService::set_converting(&vault_paths, &mut header, &key, true).unwrap();
+        assert_eq!(core::read_string(&paths.state_json).unwrap(), "{}");
+
+        // Resuming the conversion seals it and marks the vault complete again
+        EncryptionService::enable(vault, "pw", Some(0)).unwrap();
+        assert_eq!(core::read_string(&paths.state_json).unwrap(), "{}");
+        assert!(crypto::is_encrypted(
+            &std::fs::read(&paths.state_json).unwrap()
+        ));
+        let header: VaultEncryption = core::read_json(&vault_paths.encryption_json).unwrap();
+        assert!(!header.converting);
+    }
+
+    #[test]
+    fn test_duplicate_reseals_files_for_their_new_path() {
+        let temp = TempVault::new();
+        let (vault, canvas) = (&temp.root, temp.default_canvas());
+        EncryptionService::enable(vault, "pw", Some(0)).unwrap();
+
+        let copy = CanvasService::duplicate(&canvas, None, true).unwrap();
+        let copy = Path::new(&copy.path);
+        assert!(CanvasService::open(copy).is_ok());
+        assert!(CanvasService::load_state(copy).is_ok());
+        assert!(WorkspaceService::load(copy).is_ok());
+    }
+}
@@ -0,0 +1,353 @@
+// Export Service
+//
+// Builds self-contained canvas data exports. Every export path goes
+// through here so secrets, classification limits and redaction profiles
+// are applied uniformly.
+
+use crate::core::{self, paths::CanvasPaths, MosaicError, MosaicResult};
+use crate::models::{
+    CanvasExport, CanvasMeta, ClassificationPolicy, ExportOptions, RedactionProfile,
+    RedactionReport, Tlp, WorkspaceData,
+};
+use crate::services::{CanvasService, RedactionService, SecretService, WorkspaceService};
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// viewBox, width and height of the root `<svg>` element, used to place
+/// the banner
+fn svg_attrs() -> &'static [(&'static str, Regex); 3] {
+    static SVG_ATTRS: OnceLock<[(&str, Regex); 3]> = OnceLock::new();
+    SVG_ATTRS.get_or_init(|| {
+        ["viewBox", "width", "height"].map(|name| {
+            (
+                name,
+                Regex::new(&format!(r#"<svg[^>]*?\s{}="([^"]+)""#, name)).unwrap(),
+            )
+        })
+    })
+}
+
+fn svg_attr(name: &str) -> &'static Regex {
+    &svg_attrs()
+        .iter()
+        .find(|(attr, _)| *attr == name)
+        .expect("known SVG attribute")
+        .1
+}
+
+pub struct ExportService;
+
+impl ExportService {
+    /// Canvas metadata and workspace as an export document
+    ///
+    /// Sensitive fields stay sealed unless `options.reveal_secrets` is set.
+    /// Content above `options.max_classification` fails the export or is
+    /// left out, then the redaction profile is applied: the one named in
+    /// `options`, else the vault's default profile, if any.
+    pub fn prepare(
+        canvas_path: &Path,
+        options: &ExportOptions,
+    ) -> MosaicResult<(CanvasExport, Option<RedactionReport>)> {
+        CanvasService::open(canvas_path)?;
+        let canvas_paths = CanvasPaths::from_root(canvas_path);
+
+        let mut meta: CanvasMeta = core::read_json(&canvas_paths.meta_json)?;
+        let mut workspace = WorkspaceService::load(canvas_path)?;
+        Self::enforce_classification(&mut meta, &mut workspace, options)?;
+
+        if options.reveal_secrets {
+            SecretService::reveal_workspace(
+                &SecretService::vault_of(canvas_path)?,
+                &mut workspace,
+            )?;
+        }
+
+        let report = match Self::redaction_profile(canvas_path, options)? {
+            Some(profile) => Some(RedactionService::apply(&profile, &mut workspace)?),
+            None => None,
+        };
+
+        Ok((CanvasExport::new(meta, workspace), report))
+    }
+
+    /// Canvas export with secrets, classification and redaction applied
+    pub fn build(canvas_path: &Path, options: &ExportOptions) -> MosaicResult<CanvasExport> {
+        Self::prepare(canvas_path, options).map(|(export, _)| export)
+    }
+
+    /// Write a canvas export to `file_path` as JSON
+    pub fn export_json(
+        canvas_path: &Path,
+        file_path: &Path,
+        options: &ExportOptions,
+    ) -> MosaicResult<CanvasExport> {
+        let export = Self::build(canvas_path, options)?;
+        core::write_json(file_path, &export)?;
+        Ok(export)
+    }
+
+    /// Apply export options to SVG rendered by the frontend
+    ///
+    /// The SVG itself cannot be filtered, so the frontend renders images
+    /// from `build`'s output, which is already redacted; pattern rules are
+    /// applied to its text once more. The `Filter` policy is refused here
+    /// for the same reason. With `stamp`, the highest classification is
+    /// drawn as a banner on top.
+    pub fn prepare_svg(
+        canvas_path: &Path,
+        svg: String,
+        options: &ExportOptions,
+        stamp: bool,
+    ) -> MosaicResult<String> {
+        if options.max_classification.is_some()
+            && options.classification_policy == ClassificationPolicy::Filter
+        {
+            return Err(MosaicError::invalid_format(
+                "Rendered images cannot be filtered by classification; render them from the filtered canvas export",
+            ));
+        }
+
+        let export = Self::build(canvas_path, options)?;
+
+        let svg = match Self::redaction_profile(canvas_path, options)? {
+            Some(profile) => RedactionService::apply_to_svg(&profile, &svg)?,
+            None => svg,
+        };
+
+        match export.canvas.classification {
+            Some(level) if stamp => Ok(Self::stamp_banner(&svg, level)),
+            _ => Ok(svg),
+        }
+    }
+
+    /// Profile an export applies: the one `options` names, else the vault's
+    /// default
+    pub fn redaction_profile(
+        canvas_path: &Path,
+        options: &ExportOptions,
+    ) -> MosaicResult<Option<RedactionProfile>> {
+        let Some(vault_path) = core::paths::find_vault_root(canvas_path) else {
+            return match &options.redaction_profile {
+                Some(_) => Err(MosaicError::vault_not_found(&canvas_path.to_string_lossy())),
+                None => Ok(None),
+            };
+        };
+
+        match &options.redaction_profile {
+            Some(profile_id) => RedactionService::get(&vault_path, profile_id).map(Some),
+            None => RedactionService::default_profile(&vault_path),
+        }
+    }
+
+    /// Refuse or filter content above the sharing level, and mark the
+    /// exported canvas with the highest level that remains
+    ///
+    /// Unmarked nodes carry the canvas's classification.
+    fn enforce_classification(
+        meta: &mut CanvasMeta,
+        workspace: &mut WorkspaceData,
+        options: &ExportOptions,
+    ) -> MosaicResult<()> {
+        let Some(limit) = options.max_classification else {
+            meta.classification = Self::highest(meta.classification, workspace);
+            return Ok(());
+        };
+
+        match options.classification_policy {
+            ClassificationPolicy::Refuse => {
+                let highest = Self::highest(meta.classification, workspace);
+                if let Some(level) = highest.filter(|level| *level > limit) {
+                    return Err(MosaicError::classification_exceeded(
+                        &level.to_string(),
+                        &limit.to_string(),
+                    ));
+                }
+                meta.classification = highest;
+            }
+            ClassificationPolicy::Filter => {
+                let above: HashSet<String> = workspace
+                    .nodes
+                    .iter()
+                    .filter(|n| n.classification.or(meta.classification) > Some(limit))
+                    .map(|n| n.id.clone())
+                    .collect();
+                workspace.remove_nodes(&above);
+
+                let canvas_level = meta.classification.filter(|level| *level <= limit);
+                meta.classification = Self::highest(canvas_level, workspace);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Highest marking among a canvas and its nodes
+    fn highest(canvas: Option<Tlp>, workspace: &WorkspaceData) -> Option<Tlp> {
+        workspace
+            .nodes
+            .iter()
+            .filter_map(|n| n.classification)
+            .chain(canvas)
+            .max()
+    }
+
+    /// Draw a TLP banner across the top of an SVG
+    fn stamp_banner(svg: &str, level: Tlp) -> String {
+        let (x, y, width, height) = Self::svg_bounds(svg);
+        let banner = (height * 0.04).max(24.0);
+
+        let group = format!(
+            concat!(
+                r#"<g id="tlp-banner">"#,
+                r##"<rect x="{x}" y="{y}" width="{w}" height="{h}" fill="#000000"/>"##,
+                r#"<text x="{tx}" y="{ty}" fill="{color}" font-family="sans-serif" "#,
+                r#"font-size="{size}" font-weight="bold" text-anchor="middle">{label}</text>"#,
+                "</g>"
+            ),
+            x = x,
+            y = y,
+            w = width,
+            h = banner,
+            tx = x + width / 2.0,
+            ty = y + banner * 0.72,
+            color = level.color(),
+            size = banner * 0.6,
+            label = level,
+        );
+
+        match svg.rfind("</svg>") {
+            Some(end) => format!("{}{}{}", &svg[..end], group, &svg[end..]),
+            None => svg.to_string(),
+        }
+    }
+
+    /// Drawing area of an SVG: its viewBox, else its width and height
+    fn svg_bounds(svg: &str) -> (f64, f64, f64, f64) {
+        if let Some(captures) = svg_attr("viewBox").captures(svg) {
+            let values: Vec<f64> = captures[1]
+                .split([' ', ','])
+                .filter_map(|v| v.parse().ok())
+                .collect();
+            if let [x, y, width, height] = values[..] {
+                return (x, y, width, height);
+            }
+        }
+
+        let dimension = |name: &str, default: f64| {
+            svg_attr(name)
+                .captures(svg)
+                .and_then(|c| c[1].trim_end_matches("px").parse().ok())
+                .unwrap_or(default)
+        };
+        (
+            0.0,
+            0.0,
+            dimension("width", 1920.0),
+            dimension("height", 1080.0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Position, WorkspaceNode};
+    use std::collections::HashMap;
+
+    fn node(id: &str, classification: Option<Tlp>) -> WorkspaceNode {
+        let mut node = WorkspaceNode::new("note", Position::default(), HashMap::new());
+        node.id = id.to_string();
+        node.classification = classification;
+        node
+    }
+
+    fn workspace() -> WorkspaceData {
+        let mut workspace = WorkspaceData::new();
+        workspace.add_node(node("green", Some(Tlp::Green)));
+        workspace.add_node(node("red", Some(Tlp::Red)));
+        workspace.add_node(node("unmarked", None));
+        workspace
+    }
+
+    fn meta(classification: Option<Tlp>) -> CanvasMeta {
+        CanvasMeta {
+            classification,
+            ..CanvasMeta::new("c".to_string(), "v".to_string(), "Case".to_string())
+        }
+    }
+
+    fn options(limit: Tlp, policy: ClassificationPolicy) -> ExportOptions {
+        ExportOptions {
+            max_classification: Some(limit),
+            classification_policy: policy,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_refuse_above_limit() {
+        let mut meta = meta(Some(Tlp::Green));
+        let mut data = workspace();
+        let result = ExportService::enforce_classification(
+            &mut meta,
+            &mut data,
+            &options(Tlp::Amber, ClassificationPolicy::Refuse),
+        );
+        assert!(result.is_err());
+
+        let mut meta = self::meta(Some(Tlp::Green));
+        ExportService::enforce_classification(
+            &mut meta,
+            &mut data,
+            &options(Tlp::Red, ClassificationPolicy::Refuse),
+        )
+        .unwrap();
+        assert_eq!(meta.classification, Some(Tlp::Red));
+    }
+
+    #[test]
+    fn test_filter_drops_nodes_above_limit() {
+        // Unmarked nodes carry the canvas's level
+        let mut meta = meta(Some(Tlp::Red));
+        let mut data = workspace();
+        ExportService::enforce_classification(
+            &mut meta,
+            &mut data,
+            &options(Tlp::Amber, ClassificationPolicy::Filter),
+        )
+        .unwrap();
+
+        let ids: Vec<&str> = data.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["green"]);
+        assert_eq!(meta.classification, Some(Tlp::Green));
+    }
+
+    #[test]
+    fn test_images_refuse_filter_policy() {
+        let result = ExportService::prepare_svg(
+            Path::new("canvas"),
+            "<svg></svg>".to_string(),
+            &options(Tlp::Amber, ClassificationPolicy::Filter),
+            true,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_stamp_banner() {
+        let svg = r#"<svg width="400" height="300" viewBox="10 20 800 600"><g/></svg>"#;
+        assert_eq!(ExportService::svg_bounds(svg), (10.0, 20.0, 800.0, 600.0));
+        assert_eq!(
+            ExportService::svg_bounds(r#"<svg width="400px" height="300"></svg>"#),
+            (0.0, 0.0, 400.0, 300.0)
+        );
+
+        let stamped = ExportService::stamp_banner(svg, Tlp::Amber);
+        assert!(stamped.ends_with("</g></svg>"));
+        assert!(stamped.contains(r#"<g id="tlp-banner">"#));
+        assert!(stamped.contains(">TLP:AMBER</text>"));
+        assert!(stamped.contains(r##"fill="#FFC000""##));
+    }
+}
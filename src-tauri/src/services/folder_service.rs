@@ -0,0 +1,315 @@
+// Folder Service
+//
+// Handles the canvas folder hierarchy (real subdirectories of canvases/)
+
+use crate::core::{self, paths::VaultPaths, CanvasPaths, MosaicError, MosaicResult};
+use crate::models::{CanvasFolder, CanvasInfo, CanvasMeta};
+use crate::services::CanvasService;
+use std::path::{Component, Path, PathBuf};
+
+pub struct FolderService;
+
+impl FolderService {
+    /// Folder tree of a vault, rooted at the canvases directory
+    pub fn tree(vault_path: &Path) -> MosaicResult<CanvasFolder> {
+        let canvases_dir = Self::canvases_dir(vault_path);
+        Self::build(&canvases_dir, &canvases_dir)
+    }
+
+    /// Create a folder inside `parent` ("" for the top level)
+    pub fn create(vault_path: &Path, parent: &str, name: &str) -> MosaicResult<CanvasFolder> {
+        let parent_dir = Self::resolve_existing(vault_path, parent)?;
+
+        let folder_name = Self::folder_name(name)?;
+
+        let dir = parent_dir.join(&folder_name);
+        if dir.exists() {
+            return Err(MosaicError::already_exists(&format!(
+                "Folder {}",
+                folder_name
+            )));
+        }
+
+        core::ensure_dir(&dir)?;
+        Self::build(&Self::canvases_dir(vault_path), &dir)
+    }
+
+    /// Rename a folder in place
+    pub fn rename(vault_path: &Path, folder: &str, new_name: &str) -> MosaicResult<CanvasFolder> {
+        let dir = Self::resolve_folder(vault_path, folder)?;
+        let parent = dir
+            .parent()
+            .ok_or_else(|| MosaicError::io_error("Cannot get parent"))?;
+
+        let folder_name = Self::folder_name(new_name)?;
+
+        let target = parent.join(&folder_name);
+        if target == dir {
+            return Self::build(&Self::canvases_dir(vault_path), &dir);
+        }
+        if target.exists() {
+            return Err(MosaicError::already_exists(&format!(
+                "Folder {}",
+                folder_name
+            )));
+        }
+
+        core::rename(&dir, &target)?;
+        Self::build(&Self::canvases_dir(vault_path), &target)
+    }
+
+    /// Move a folder (and everything in it) under `new_parent` ("" for the top level)
+    pub fn move_folder(
+        vault_path: &Path,
+        folder: &str,
+        new_parent: &str,
+    ) -> MosaicResult<CanvasFolder> {
+        let dir = Self::resolve_folder(vault_path, folder)?;
+        let parent_dir = Self::resolve_existing(vault_path, new_parent)?;
+
+        if parent_dir.starts_with(&dir) {
+            return Err(MosaicError::invalid_format(
+                "Cannot move a folder into itself",
+            ));
+        }
+
+        let name = dir
+            .file_name()
+            .ok_or_else(|| MosaicError::folder_not_found(folder))?;
+        let target = parent_dir.join(name);
+        if target == dir {
+            return Self::build(&Self::canvases_dir(vault_path), &dir);
+        }
+        if target.exists() {
+            return Err(MosaicError::already_exists(&format!(
+                "Folder {}",
+                name.to_string_lossy()
+            )));
+        }
+
+        core::rename(&dir, &target)?;
+        Self::build(&Self::canvases_dir(vault_path), &target)
+    }
+
+    /// Delete a folder
+    ///
+    /// Non-empty folders are only deleted with `recursive`; returns the IDs of
+    /// canvases that were deleted with it.
+    pub fn delete(vault_path: &Path, folder: &str, recursive: bool) -> MosaicResult<Vec<String>> {
+        let dir = Self::resolve_folder(vault_path, folder)?;
+
+        let canvas_ids: Vec<String> = core::paths::list_canvas_dirs(&dir)?
+            .into_iter()
+            .filter_map(|canvas| {
+                core::read_json::<CanvasMeta>(&CanvasPaths::from_root(&canvas).meta_json)
+                    .ok()
+                    .map(|m| m.id)
+            })
+            .collect();
+
+        if !recursive && !core::fs::list_dir(&dir)?.is_empty() {
+            return Err(MosaicError::folder_not_empty(folder));
+        }
+
+        core::remove_dir_all(&dir)?;
+        Ok(canvas_ids)
+    }
+
+    /// Canvases in a folder ("" for the top level), optionally including subfolders
+    pub fn list_canvases(
+        vault_path: &Path,
+        folder: &str,
+        recursive: bool,
+    ) -> MosaicResult<Vec<CanvasInfo>> {
+        let dir = Self::resolve_existing(vault_path, folder)?;
+
+        if recursive {
+            return CanvasService::list(&dir);
+        }
+
+        let mut canvases: Vec<CanvasInfo> = core::list_subdirs(&dir)?
+            .into_iter()
+            .filter(|d| CanvasPaths::from_root(d).is_canvas())
+            .filter_map(|d| CanvasService::open(&d).ok())
+            .collect();
+
+        // Sort by updated_at descending
+        canvases.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+
+        Ok(canvases)
+    }
+
+    /// Move a canvas into a folder ("" for the top level)
+    pub fn move_canvas(
+        vault_path: &Path,
+        canvas_path: &Path,
+        folder: &str,
+    ) -> MosaicResult<CanvasInfo> {
+        let dir = Self::resolve_existing(vault_path, folder)?;
+
+        let canvases_dir = Self::canvases_dir(vault_path).canonicalize()?;
+        let in_vault = canvas_path
+            .canonicalize()
+            .is_ok_and(|p| p.starts_with(&canvases_dir) && p != canvases_dir);
+        if !in_vault || !CanvasPaths::from_root(canvas_path).is_canvas() {
+            return Err(MosaicError::canvas_not_found(
+                &canvas_path.to_string_lossy(),
+            ));
+        }
+
+        CanvasService::move_to_folder(canvas_path, &dir)
+    }
+
+    /// Absolute directory of a folder ("" for the top level), creating nothing
+    pub fn folder_dir(vault_path: &Path, folder: &str) -> MosaicResult<PathBuf> {
+        let mut dir = Self::canvases_dir(vault_path);
+
+        for component in Path::new(folder).components() {
+            match component {
+                Component::Normal(name) if !name.to_string_lossy().starts_with('.') => {
+                    dir.push(name)
+                }
+                Component::CurDir => {}
+                _ => {
+                    return Err(MosaicError::invalid_format(format!(
+                        "Invalid folder path: {}",
+                        folder
+                    )))
+                }
+            }
+        }
+
+        Ok(dir)
+    }
+
+    /// Sanitized folder name, rejecting empty and hidden names
+    fn folder_name(name: &str) -> MosaicResult<String> {
+        let folder_name = core::sanitize_name(name);
+        if folder_name.is_empty() || folder_name.starts_with('.') {
+            return Err(MosaicError::invalid_format(format!(
+                "Invalid folder name: {}",
+                name
+            )));
+        }
+        Ok(folder_name)
+    }
+
+    fn canvases_dir(vault_path: &Path) -> PathBuf {
+        VaultPaths::from_root(vault_path).canvases
+    }
+
+    /// Existing folder (or the canvases root) that is not a canvas
+    fn resolve_existing(vault_path: &Path, folder: &str) -> MosaicResult<PathBuf> {
+        let dir = Self::folder_dir(vault_path, folder)?;
+
+        if folder.trim_matches('/').is_empty() {
+            core::ensure_dir(&dir)?;
+            return Ok(dir);
+        }
+        if !dir.is_dir() || CanvasPaths::from_root(&dir).has_canvas_files() {
+            return Err(MosaicError::folder_not_found(folder));
+        }
+
+        Ok(dir)
+    }
+
+    /// Existing folder other than the canvases root
+    fn resolve_folder(vault_path: &Path, folder: &str) -> MosaicResult<PathBuf> {
+        if folder.trim_matches('/').is_empty() {
+            return Err(MosaicError::invalid_format(
+                "The canvases root cannot be changed",
+            ));
+        }
+        Self::resolve_existing(vault_path, folder)
+    }
+
+    fn build(canvases_dir: &Path, dir: &Path) -> MosaicResult<CanvasFolder> {
+        let mut canvas_count = 0;
+        let mut folders = Vec::new();
+
+        for sub in core::list_subdirs(dir)? {
+            if core::paths::is_hidden(&sub) {
+                continue;
+            }
+            let canvas_paths = CanvasPaths::from_root(&sub);
+            if canvas_paths.is_canvas() {
+                canvas_count += 1;
+            } else if !canvas_paths.has_canvas_files() {
+                folders.push(Self::build(canvases_dir, &sub)?);
+            }
+        }
+
+        folders.sort_by_key(|f| f.name.to_lowercase());
+
+        let relative = dir.strip_prefix(canvases_dir).unwrap_or(Path::new(""));
+        Ok(CanvasFolder {
+            name: relative
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: core::paths::to_folder_path(relative),
+            canvas_count,
+            folders,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::testing::TempVault;
+
+    #[test]
+    fn test_broken_canvas_is_not_a_folder() {
+        let vault = TempVault::new();
+        let root = &vault.root;
+        let broken = FolderService::canvases_dir(root).join("Broken");
+        std::fs::create_dir_all(broken.join(".mosaic")).unwrap();
+        std::fs::write(broken.join("workspace.json"), "{").unwrap();
+
+        let tree = FolderService::tree(root).unwrap();
+        assert!(tree.folders.iter().all(|f| f.name != "Broken"));
+        assert!(FolderService::create(root, "Broken", "Inner").is_err());
+    }
+
+    #[test]
+    fn test_folder_names() {
+        let vault = TempVault::new();
+        let root = &vault.root;
+        assert!(FolderService::create(root, "", "  ").is_err());
+        assert_eq!(
+            FolderService::create(root, "", ".hidden").unwrap().name,
+            "_hidden"
+        );
+
+        let folder = FolderService::create(root, "", "Cases").unwrap();
+        assert_eq!(folder.path, "Cases");
+        assert!(FolderService::rename(root, "Cases", "").is_err());
+        assert_eq!(
+            FolderService::rename(root, "Cases", "Closed").unwrap().path,
+            "Closed"
+        );
+        assert!(FolderService::folder_dir(root, "../outside").is_err());
+    }
+
+    #[test]
+    fn test_move_canvas_checks_vault() {
+        let (vault, other) = (TempVault::new(), TempVault::new());
+        let root = &vault.root;
+        FolderService::create(root, "", "Cases").unwrap();
+        let outside = CanvasService::create(
+            &FolderService::canvases_dir(&other.root),
+            &other.id,
+            "Elsewhere",
+            None,
+        )
+        .unwrap();
+        assert!(FolderService::move_canvas(root, Path::new(&outside.path), "Cases").is_err());
+
+        let inside =
+            CanvasService::create(&FolderService::canvases_dir(root), &vault.id, "Case", None)
+                .unwrap();
+        let moved = FolderService::move_canvas(root, Path::new(&inside.path), "Cases").unwrap();
+        assert_eq!(moved.folder, "Cases");
+    }
+}
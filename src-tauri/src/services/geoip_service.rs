@@ -0,0 +1,532 @@
+// GeoIP Service
+//
+// Offline IP geolocation and ASN lookups in user-provided MaxMind-format
+// (.mmdb) databases, and enrichment of router and domain nodes with the
+// results. Nothing is sent over the network.
+
+use crate::core::{self, normalize, MosaicError, MosaicResult};
+use crate::models::{
+    GeoIpConfig, GeoIpEnrichment, GeoIpInfo, NodeGeoIp, Position, WorkspaceEdge, WorkspaceNode,
+};
+use crate::services::{ConfigService, NormalizeService, WorkspaceService};
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Node data key of the lookup result
+pub const GEOIP_FIELD: &str = "geoip";
+
+/// Fields holding a node's address, in order of preference
+const IP_FIELDS: &[(&str, &str)] = &[
+    ("router", "ipAddress"),
+    ("router", "ip"),
+    ("domain", "ip"),
+    ("domain", "domain"),
+];
+
+/// Gap between an enriched node and the map and organization nodes
+const NODE_OFFSET_X: f64 = 100.0;
+const NODE_SPACING_Y: f64 = 160.0;
+
+/// App data folder of the per-vault database settings
+const CONFIG_DIR: &str = "geoip";
+
+type Database = Arc<Reader<Vec<u8>>>;
+
+/// Open databases by path, with the modification time they were read at
+type DatabaseCache = HashMap<PathBuf, (Option<SystemTime>, Database)>;
+
+fn databases() -> &'static Mutex<DatabaseCache> {
+    static DATABASES: OnceLock<Mutex<DatabaseCache>> = OnceLock::new();
+    DATABASES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub struct GeoIpService;
+
+impl GeoIpService {
+    /// Databases configured for a vault on this machine
+    pub fn config(vault_path: &Path) -> MosaicResult<GeoIpConfig> {
+        let config_file = Self::config_file(vault_path)?;
+
+        if !config_file.exists() {
+            return Ok(GeoIpConfig::default());
+        }
+
+        core::read_json(&config_file)
+    }
+
+    /// Set the City and ASN databases of a vault (`None` removes one)
+    ///
+    /// Each file is opened first, so a missing or wrong database is
+    /// reported now rather than at lookup time.
+    pub fn set_databases(
+        vault_path: &Path,
+        city_db: Option<&Path>,
+        asn_db: Option<&Path>,
+    ) -> MosaicResult<GeoIpConfig> {
+        let roles: [(Option<&Path>, &[&str]); 2] =
+            [(city_db, &["city", "country"]), (asn_db, &["asn"])];
+        for (path, expected) in roles {
+            let Some(path) = path else {
+                continue;
+            };
+            let database_type = Self::open(path)?.metadata.database_type.to_lowercase();
+            if !expected.iter().any(|kind| database_type.contains(kind)) {
+                return Err(MosaicError::invalid_format(format!(
+                    "{} is a {} database, expected {}",
+                    path.display(),
+                    database_type,
+                    expected[0]
+                )));
+            }
+        }
+
+        let config = GeoIpConfig {
+            city_db: city_db.map(|p| p.to_string_lossy().to_string()),
+            asn_db: asn_db.map(|p| p.to_string_lossy().to_string()),
+        };
+        let previous = Self::config(vault_path)?;
+        core::write_json(&Self::config_file(vault_path)?, &config)?;
+
+        // Databases no longer configured are dropped from memory
+        let mut databases = databases().lock().unwrap_or_else(|e| e.into_inner());
+        for path in [previous.city_db, previous.asn_db].into_iter().flatten() {
+            if config.city_db.as_ref() != Some(&path) && config.asn_db.as_ref() != Some(&path) {
+                databases.remove(Path::new(&path));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Look an address up in a vault's databases
+    pub fn lookup(vault_path: &Path, ip: &str) -> MosaicResult<GeoIpInfo> {
+        let address: IpAddr = normalize::ip(ip)
+            .and_then(|ip| ip.parse().ok())
+            .ok_or_else(|| MosaicError::invalid_format(format!("Invalid IP address: {}", ip)))?;
+
+        Self::lookup_with(&Self::configured(vault_path)?, address)
+    }
+
+    /// Add a `geoip` annotation (country, city, coordinates, ASN and
+    /// organization) to router and domain nodes with an address, or to the
+    /// given nodes only
+    ///
+    /// With `create_nodes`, a `map` node for the coordinates and an
+    /// `organization` node for the network owner are linked from each
+    /// node; organizations already on the canvas are reused. Everything is
+    /// saved in one write, or none if nothing changed.
+    pub fn enrich(
+        canvas_path: &Path,
+        node_ids: Option<&[String]>,
+        create_nodes: bool,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<GeoIpEnrichment> {
+        let vault_path = core::paths::find_vault_root(canvas_path)
+            .ok_or_else(|| MosaicError::vault_not_found(&canvas_path.to_string_lossy()))?;
+        let config = Self::configured(&vault_path)?;
+
+        let mut results = Vec::new();
+        let mut updates: HashMap<String, HashMap<String, Value>> = HashMap::new();
+        let mut nodes: Vec<WorkspaceNode> = Vec::new();
+        let mut edges = Vec::new();
+
+        let revision = WorkspaceService::update(canvas_path, expected_revision, |workspace| {
+            for source in workspace
+                .nodes
+                .iter()
+                .filter(|n| node_ids.is_none_or(|ids| ids.contains(&n.id)))
+            {
+                let Some(address) = Self::node_ip(source) else {
+                    continue;
+                };
+                let info = Self::lookup_with(&config, address)?;
+                if info.is_empty() {
+                    continue;
+                }
+
+                let value = serde_json::to_value(&info)?;
+                if source.data.get(GEOIP_FIELD) != Some(&value) {
+                    updates.insert(
+                        source.id.clone(),
+                        HashMap::from([(GEOIP_FIELD.to_string(), value)]),
+                    );
+                }
+
+                if create_nodes {
+                    let linked: Vec<&WorkspaceNode> = workspace
+                        .edges
+                        .iter()
+                        .filter(|e| e.source == source.id)
+                        .filter_map(|e| workspace.nodes.iter().find(|n| n.id == e.target))
+                        .collect();
+                    let x = source.position.x + source.width.unwrap_or(300.0) + NODE_OFFSET_X;
+                    let mut added = 0;
+                    let mut next_position = || {
+                        let position = Position {
+                            x,
+                            y: source.position.y + added as f64 * NODE_SPACING_Y,
+                        };
+                        added += 1;
+                        position
+                    };
+
+                    if let (Some(latitude), Some(longitude)) = (info.latitude, info.longitude) {
+                        let on_map = linked.iter().any(|n| {
+                            n.node_type == "map"
+                                && n.data.get("latitude") == Some(&json!(latitude))
+                                && n.data.get("longitude") == Some(&json!(longitude))
+                        });
+                        if !on_map {
+                            let place = Self::place(&info);
+                            let data = HashMap::from([
+                                (
+                                    "title".to_string(),
+                                    json!(place.clone().unwrap_or_else(|| info.ip.clone())),
+                                ),
+                                ("latitude".to_string(), json!(latitude)),
+                                ("longitude".to_string(), json!(longitude)),
+                                ("zoom".to_string(), json!(10)),
+                                ("label".to_string(), json!(place.unwrap_or_default())),
+                            ]);
+                            let node = WorkspaceNode::new("map", next_position(), data);
+                            edges.push(WorkspaceEdge::new(
+                                &source.id,
+                                &node.id,
+                                Some("located in".to_string()),
+                            ));
+                            nodes.push(node);
+                        }
+                    }
+
+                    if let Some(organization) = &info.organization {
+                        let existing = workspace
+                            .nodes
+                            .iter()
+                            .chain(nodes.iter())
+                            .find(|n| Self::is_organization(n, organization))
+                            .map(|n| n.id.clone());
+                        let target = match existing {
+                            Some(id) => id,
+                            None => {
+                                let mut data = HashMap::from([
+                                    ("title".to_string(), json!(organization)),
+                                    ("name".to_string(), json!(organization)),
+                                ]);
+                                if let Some(asn) = info.asn {
+                                    data.insert(
+                                        "description".to_string(),
+                                        json!(format!("AS{}", asn)),
+                                    );
+                                }
+                                let node =
+                                    WorkspaceNode::new("organization", next_position(), data);
+                                let id = node.id.clone();
+                                nodes.push(node);
+                                id
+                            }
+                        };
+                        if !linked.iter().any(|n| n.id == target) {
+                            edges.push(WorkspaceEdge::new(
+                                &source.id,
+                                &target,
+                                Some("network owner".to_string()),
+                            ));
+                        }
+                    }
+                }
+
+                results.push(NodeGeoIp {
+                    node_id: source.id.clone(),
+                    info,
+                });
+            }
+
+            for node in &mut workspace.nodes {
+                if let Some(fields) = updates.get(&node.id) {
+                    node.data
+                        .extend(fields.iter().map(|(k, v)| (k.clone(), v.clone())));
+                }
+            }
+            for node in &nodes {
+                workspace.add_node(node.clone());
+            }
+            for edge in &edges {
+                workspace.add_edge(edge.clone());
+            }
+            Ok(!(nodes.is_empty() && edges.is_empty() && updates.is_empty()))
+        })?;
+        let changed = !(nodes.is_empty() && edges.is_empty() && updates.is_empty());
+        let mut updated_node_ids: Vec<String> = updates.keys().cloned().collect();
+        updated_node_ids.sort();
+
+        Ok(GeoIpEnrichment {
+            results,
+            updated_node_ids,
+            nodes,
+            edges,
+            revision: changed.then_some(revision),
+        })
+    }
+
+    /// The databases are on this machine, so their paths are kept in the
+    /// app data directory rather than in the vault
+    fn config_file(vault_path: &Path) -> MosaicResult<PathBuf> {
+        ConfigService::vault_file(vault_path, CONFIG_DIR)
+    }
+
+    /// Configuration of a vault with at least one database
+    fn configured(vault_path: &Path) -> MosaicResult<GeoIpConfig> {
+        let config = Self::config(vault_path)?;
+        if config.city_db.is_none() && config.asn_db.is_none() {
+            return Err(MosaicError::not_found(&format!(
+                "GeoIP database for vault {}",
+                vault_path.display()
+            )));
+        }
+        Ok(config)
+    }
+
+    fn lookup_with(config: &GeoIpConfig, address: IpAddr) -> MosaicResult<GeoIpInfo> {
+        let mut info = GeoIpInfo {
+            ip: address.to_string(),
+            ..Default::default()
+        };
+
+        if let Some(path) = &config.city_db {
+            let database = Self::open(Path::new(path))?;
+            if let Some(city) = found(database.lookup::<geoip2::City>(address))? {
+                if let Some(country) = city.country.or(city.registered_country) {
+                    info.country = country.names.as_ref().and_then(english_name);
+                    info.country_code = country.iso_code.map(str::to_string);
+                }
+                info.region = city
+                    .subdivisions
+                    .as_ref()
+                    .and_then(|s| s.first())
+                    .and_then(|s| s.names.as_ref())
+                    .and_then(english_name);
+                info.city = city
+                    .city
+                    .and_then(|c| c.names)
+                    .as_ref()
+                    .and_then(english_name);
+                if let Some(location) = city.location {
+                    info.latitude = location.latitude;
+                    info.longitude = location.longitude;
+                    info.accuracy_radius = location.accuracy_radius;
+                }
+            }
+        }
+
+        if let Some(path) = &config.asn_db {
+            let database = Self::open(Path::new(path))?;
+            if let Some(asn) = found(database.lookup::<geoip2::Asn>(address))? {
+                info.asn = asn.autonomous_system_number;
+                info.organization = asn.autonomous_system_organization.map(str::to_string);
+            }
+        }
+
+        Ok(info)
+    }
+
+    /// Open a database, reusing the copy in memory unless the file changed
+    ///
+    /// Copies of databases whose file is gone are dropped on the way.
+    fn open(path: &Path) -> MosaicResult<Database> {
+        let mut databases = databases().lock().unwrap_or_else(|e| e.into_inner());
+        databases.retain(|cached, _| cached.is_file());
+        let modified = std::fs::metadata(path)?.modified().ok();
+
+        if let Some((read_at, database)) = databases.get(path) {
+            if *read_at == modified {
+                return Ok(database.clone());
+            }
+        }
+
+        let database = Arc::new(
+            Reader::open_readfile(path)
+                .map_err(|e| MosaicError::invalid_format(format!("{}: {}", path.display(), e)))?,
+        );
+        databases.insert(path.to_path_buf(), (modified, database.clone()));
+        Ok(database)
+    }
+
+    /// Address of a router or domain node
+    fn node_ip(node: &WorkspaceNode) -> Option<IpAddr> {
+        let from_fields = IP_FIELDS
+            .iter()
+            .filter(|(node_type, _)| *node_type == node.node_type)
+            .find_map(|(_, field)| {
+                // Routers may hold a network; its base address is used
+                let value = NormalizeService::value(node, field)?;
+                normalize::ip(value.split('/').next()?)
+            });
+        let from_list = || {
+            (node.node_type == "domain")
+                .then(|| node.data.get("ipAddresses")?.as_array()?.first()?.as_str())
+                .flatten()
+                .and_then(normalize::ip)
+        };
+
+        from_fields.or_else(from_list)?.parse().ok()
+    }
+
+    /// Whether a node is the `organization` node of a network owner, by
+    /// name ignoring case and surrounding space
+    fn is_organization(node: &WorkspaceNode, organization: &str) -> bool {
+        node.node_type == "organization"
+            && node
+                .data
+                .get("name")
+                .and_then(Value::as_str)
+                .is_some_and(|name| name.trim().eq_ignore_ascii_case(organization.trim()))
+    }
+
+    /// "City, Region, Country" from whatever parts are known
+    fn place(info: &GeoIpInfo) -> Option<String> {
+        let parts: Vec<&str> = [&info.city, &info.region, &info.country]
+            .into_iter()
+            .filter_map(|part| part.as_deref())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+/// A record, or `None` if the database has none for the address
+fn found<T>(result: Result<T, MaxMindDBError>) -> MosaicResult<Option<T>> {
+    match result {
+        Ok(record) => Ok(Some(record)),
+        Err(MaxMindDBError::AddressNotFoundError(_)) => Ok(None),
+        Err(e) => Err(MosaicError::invalid_format(e.to_string())),
+    }
+}
+
+/// English name from a localized names map, or any name if there is none
+fn english_name(names: &BTreeMap<&str, &str>) -> Option<String> {
+    names
+        .get("en")
+        .or_else(|| names.values().next())
+        .map(|name| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::CanvasPaths;
+    use crate::services::testing::TempCanvas;
+    use crate::services::NodeFileService;
+
+    fn node(node_type: &str, data: Value) -> WorkspaceNode {
+        let data = serde_json::from_value(data).unwrap();
+        WorkspaceNode::new(node_type, Position::default(), data)
+    }
+
+    #[test]
+    fn test_node_ip() {
+        let ip = |node: WorkspaceNode| GeoIpService::node_ip(&node).map(|ip| ip.to_string());
+
+        assert_eq!(
+            ip(node(
+                "router",
+                json!({ "ipAddress": "10.0.0.1", "ip": "10.0.0.2" })
+            ))
+            .as_deref(),
+            Some("10.0.0.1")
+        );
+        assert_eq!(
+            ip(node("router", json!({ "ip": "192.168.1.0/24" }))).as_deref(),
+            Some("192.168.1.0")
+        );
+        assert_eq!(
+            ip(node(
+                "domain",
+                json!({ "domain": "example.com", "ip": "10[.]0[.]0[.]3" })
+            ))
+            .as_deref(),
+            Some("10.0.0.3")
+        );
+        assert_eq!(
+            ip(node("domain", json!({ "domain": "[2001:db8::1]" }))).as_deref(),
+            Some("2001:db8::1")
+        );
+        assert_eq!(
+            ip(node(
+                "domain",
+                json!({ "domain": "example.com", "ipAddresses": ["10.0.0.4", "10.0.0.5"] })
+            ))
+            .as_deref(),
+            Some("10.0.0.4")
+        );
+        // The normalized shadow wins over the value as typed
+        assert_eq!(
+            ip(node(
+                "router",
+                json!({ "ip": "junk", "normalized": { "ip": "10.0.0.6" } })
+            ))
+            .as_deref(),
+            Some("10.0.0.6")
+        );
+        assert_eq!(ip(node("domain", json!({ "domain": "example.com" }))), None);
+        assert_eq!(ip(node("person", json!({ "ip": "10.0.0.7" }))), None);
+    }
+
+    #[test]
+    fn test_place() {
+        let info = |city: Option<&str>, region: Option<&str>, country: Option<&str>| GeoIpInfo {
+            city: city.map(str::to_string),
+            region: region.map(str::to_string),
+            country: country.map(str::to_string),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            GeoIpService::place(&info(Some("Berlin"), Some("Land Berlin"), Some("Germany")))
+                .as_deref(),
+            Some("Berlin, Land Berlin, Germany")
+        );
+        assert_eq!(
+            GeoIpService::place(&info(None, None, Some("Germany"))).as_deref(),
+            Some("Germany")
+        );
+        assert_eq!(GeoIpService::place(&info(None, None, None)), None);
+    }
+
+    #[test]
+    fn test_organization_name_is_read_from_node_files() {
+        let temp = TempCanvas::new();
+        let canvas = &temp.path;
+        let organization = node("organization", json!({ "name": "Example Networks" }));
+        WorkspaceService::add_node(canvas, organization.clone(), None).unwrap();
+
+        // The name lives in the content file, as the frontend saves it
+        let data_dir = CanvasPaths::from_root(canvas)
+            .nodes
+            .join(&organization.id)
+            .join("data");
+        let props: Value = core::read_json(&data_dir.join("properties.json")).unwrap();
+        assert!(props["data"].get("name").is_none());
+        NodeFileService::write_node_files(
+            canvas,
+            &organization.id,
+            "organization",
+            Some(" EXAMPLE networks "),
+            None,
+        )
+        .unwrap();
+
+        let workspace = WorkspaceService::load(canvas).unwrap();
+        assert!(GeoIpService::is_organization(
+            &workspace.nodes[0],
+            "Example Networks"
+        ));
+        assert!(!GeoIpService::is_organization(
+            &workspace.nodes[0],
+            "Other Networks"
+        ));
+    }
+}
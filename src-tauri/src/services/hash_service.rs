@@ -0,0 +1,321 @@
+// Hash Service
+//
+// Computes digests of attachments and keeps `hash` nodes for them, and
+// guesses the algorithm behind a pasted hash value
+
+use crate::core::{self, normalize, MosaicError, MosaicResult};
+use crate::models::{
+    AttachmentRecord, FileHashes, HashAlgorithm, HashIdentification, HashNodes, Position,
+    WorkspaceEdge, WorkspaceNode,
+};
+use crate::services::{AttachmentService, HashSetService, NormalizeService, WorkspaceService};
+use md5::Md5;
+use serde_json::{json, Value};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Gap between the attachment's node and the hash nodes
+const NODE_OFFSET_X: f64 = 100.0;
+const NODE_SPACING_Y: f64 = 160.0;
+
+const EDGE_LABEL: &str = "hash";
+
+/// Password hash formats recognized by their `$id$` prefix
+const CRYPT_PREFIXES: &[(&str, &str)] = &[
+    ("$argon2id$", "Argon2id"),
+    ("$argon2i$", "Argon2i"),
+    ("$argon2d$", "Argon2d"),
+    ("$2a$", "bcrypt"),
+    ("$2b$", "bcrypt"),
+    ("$2y$", "bcrypt"),
+    ("$y$", "yescrypt"),
+    ("$1$", "MD5-crypt"),
+    ("$5$", "SHA-256-crypt"),
+    ("$6$", "SHA-512-crypt"),
+];
+
+pub struct HashService;
+
+impl HashService {
+    /// MD5, SHA-1, SHA-256 and SHA-512 of a file
+    pub fn compute(path: &Path) -> MosaicResult<FileHashes> {
+        if !path.is_file() {
+            return Err(MosaicError::not_found(&path.to_string_lossy()));
+        }
+
+        let content = core::fs::read_bytes(path)?;
+        Ok(FileHashes {
+            md5: format!("{:x}", Md5::digest(&content)),
+            sha1: format!("{:x}", Sha1::digest(&content)),
+            sha256: format!("{:x}", Sha256::digest(&content)),
+            sha512: format!("{:x}", Sha512::digest(&content)),
+        })
+    }
+
+    /// Digests of an attachment imported into a canvas, as stored now
+    pub fn compute_attachment(
+        canvas_path: &Path,
+        attachment_id: &str,
+    ) -> MosaicResult<(AttachmentRecord, FileHashes)> {
+        let record = AttachmentService::manifest(canvas_path)?
+            .attachments
+            .into_iter()
+            .find(|a| a.id == attachment_id)
+            .ok_or_else(|| MosaicError::not_found(&format!("Attachment {}", attachment_id)))?;
+        let hashes = Self::compute(&canvas_path.join(&record.path))?;
+
+        Ok((record, hashes))
+    }
+
+    /// Likely algorithm of a pasted hash value, judged by its form
+    pub fn identify(value: &str) -> HashIdentification {
+        let trimmed = value.trim();
+
+        if let Some((_, name)) = CRYPT_PREFIXES
+            .iter()
+            .find(|(prefix, _)| trimmed.starts_with(prefix))
+        {
+            return HashIdentification {
+                value: trimmed.to_string(),
+                algorithm: "other".to_string(),
+                candidates: vec![name.to_string()],
+            };
+        }
+
+        let Some(hex) = normalize::hash(trimmed) else {
+            return HashIdentification {
+                value: trimmed.to_string(),
+                algorithm: "other".to_string(),
+                candidates: vec![],
+            };
+        };
+        let (algorithm, candidates): (&str, &[&str]) = match hex.len() {
+            8 => ("other", &["CRC32", "Adler-32"]),
+            16 => ("other", &["MySQL 3.x", "CRC64"]),
+            32 => ("md5", &["MD5", "MD4", "NTLM", "LM"]),
+            40 => ("sha1", &["SHA-1", "RIPEMD-160", "MySQL 5.x"]),
+            56 => ("other", &["SHA-224", "SHA3-224"]),
+            64 => ("sha256", &["SHA-256", "SHA3-256", "BLAKE2s-256"]),
+            96 => ("other", &["SHA-384", "SHA3-384"]),
+            128 => (
+                "sha512",
+                &["SHA-512", "SHA3-512", "BLAKE2b-512", "Whirlpool"],
+            ),
+            _ => ("other", &[]),
+        };
+
+        HashIdentification {
+            value: hex,
+            algorithm: algorithm.to_string(),
+            candidates: candidates.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    /// Add a `hash` node per digest of an attachment, linked from the
+    /// attachment's node, and give them the threat level of the vault's
+    /// hash sets
+    ///
+    /// Hash nodes already on the canvas are linked and brought up to date
+    /// instead of duplicated, all in one write; nothing is written when
+    /// they are already in place. `algorithms` defaults to all four.
+    pub fn hash_nodes(
+        canvas_path: &Path,
+        attachment_id: &str,
+        algorithms: Option<&[HashAlgorithm]>,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<HashNodes> {
+        let (record, hashes) = Self::compute_attachment(canvas_path, attachment_id)?;
+
+        let algorithms = algorithms.unwrap_or(&HashAlgorithm::ALL);
+        let values: Vec<String> = algorithms
+            .iter()
+            .map(|a| hashes.get(*a).to_string())
+            .collect();
+        // Canvases outside a vault have no hash sets
+        let matches = match core::paths::find_vault_root(canvas_path) {
+            Some(vault_path) => HashSetService::lookup(&vault_path, &values)?,
+            None => vec![],
+        };
+        let file_name = Path::new(&record.original_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut updates: HashMap<String, HashMap<String, Value>> = HashMap::new();
+
+        let revision = WorkspaceService::update(canvas_path, expected_revision, |workspace| {
+            let source = workspace
+                .nodes
+                .iter()
+                .find(|n| n.id == record.node_id)
+                .ok_or_else(|| MosaicError::not_found(&format!("Node {}", record.node_id)))?;
+            let x = source.position.x + source.width.unwrap_or(300.0) + NODE_OFFSET_X;
+
+            for (index, algorithm) in algorithms.iter().enumerate() {
+                let hash = hashes.get(*algorithm);
+                let existing = workspace.nodes.iter().find(|n| {
+                    n.node_type == "hash"
+                        && NormalizeService::value(n, "hash")
+                            .is_some_and(|v| v.eq_ignore_ascii_case(hash))
+                });
+
+                let target = match existing {
+                    Some(node) => {
+                        let mut fields = HashSetService::threat_fields(&node.data, hash, &matches)
+                            .unwrap_or_default();
+                        if node.data.get("algorithm").and_then(Value::as_str)
+                            != Some(algorithm.as_str())
+                        {
+                            fields.insert("algorithm".to_string(), json!(algorithm.as_str()));
+                        }
+                        let has_file_name = node
+                            .data
+                            .get("filename")
+                            .and_then(Value::as_str)
+                            .is_some_and(|name| !name.is_empty());
+                        if !has_file_name {
+                            fields.insert("filename".to_string(), json!(file_name));
+                        }
+                        if !fields.is_empty() {
+                            updates.insert(node.id.clone(), fields);
+                        }
+                        node.id.clone()
+                    }
+                    None => {
+                        let mut data = HashMap::from([
+                            (
+                                "title".to_string(),
+                                json!(format!("{} ({})", file_name, algorithm.label())),
+                            ),
+                            ("hash".to_string(), json!(hash)),
+                            ("algorithm".to_string(), json!(algorithm.as_str())),
+                            ("filename".to_string(), json!(file_name)),
+                            ("threatLevel".to_string(), json!("unknown")),
+                        ]);
+                        if let Some(fields) = HashSetService::threat_fields(&data, hash, &matches) {
+                            data.extend(fields);
+                        }
+                        let position = Position {
+                            x,
+                            y: source.position.y + index as f64 * NODE_SPACING_Y,
+                        };
+                        let node = WorkspaceNode::new("hash", position, data);
+                        let id = node.id.clone();
+                        nodes.push(node);
+                        id
+                    }
+                };
+
+                let linked = workspace
+                    .edges
+                    .iter()
+                    .chain(&edges)
+                    .any(|e| e.source == source.id && e.target == target);
+                if !linked {
+                    edges.push(WorkspaceEdge::new(
+                        &source.id,
+                        &target,
+                        Some(EDGE_LABEL.to_string()),
+                    ));
+                }
+            }
+
+            for node in &mut workspace.nodes {
+                for (key, value) in updates.get(&node.id).into_iter().flatten() {
+                    if value.is_null() {
+                        node.data.remove(key);
+                    } else {
+                        node.data.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            for node in &nodes {
+                workspace.add_node(node.clone());
+            }
+            for edge in &edges {
+                workspace.add_edge(edge.clone());
+            }
+            Ok(!(nodes.is_empty() && edges.is_empty() && updates.is_empty()))
+        })?;
+        let mut updated_node_ids: Vec<String> = updates.keys().cloned().collect();
+        updated_node_ids.sort();
+
+        Ok(HashNodes {
+            hashes,
+            nodes,
+            updated_node_ids,
+            edges,
+            revision,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::testing::TempVault;
+
+    #[test]
+    fn test_identify() {
+        assert_eq!(
+            HashService::identify("$2b$12$abc").candidates,
+            vec!["bcrypt"]
+        );
+        let md5 = HashService::identify(" 0x0CC175B9C0F1B6A831C399E269772661 ");
+        assert_eq!(md5.value, "0cc175b9c0f1b6a831c399e269772661");
+        assert_eq!(md5.algorithm, "md5");
+        assert_eq!(HashService::identify(&"a".repeat(64)).algorithm, "sha256");
+        assert_eq!(HashService::identify("not a hash").algorithm, "other");
+    }
+
+    #[test]
+    fn test_hash_nodes_reuse_nodes_and_skip_empty_writes() {
+        let vault = TempVault::new();
+        let root = vault.outside();
+        let canvas = vault.default_canvas();
+        let file = root.join("a.txt");
+        std::fs::write(&file, "a").unwrap();
+
+        let source = WorkspaceNode::new("note", Position::default(), HashMap::new());
+        let source_id = source.id.clone();
+        // An MD5 node typed by hand, in upper case, before the import
+        let data = HashMap::from([(
+            "hash".to_string(),
+            json!("0CC175B9C0F1B6A831C399E269772661"),
+        )]);
+        let existing = WorkspaceNode::new("hash", Position::default(), data);
+        let existing_id = existing.id.clone();
+        WorkspaceService::add_node(&canvas, source, None).unwrap();
+        WorkspaceService::add_node(&canvas, existing, None).unwrap();
+        let attachment = AttachmentService::import(&canvas, &file, &source_id).unwrap();
+
+        let algorithms = [HashAlgorithm::Md5, HashAlgorithm::Sha1];
+        let before = WorkspaceService::load(&canvas).unwrap().revision;
+        let first =
+            HashService::hash_nodes(&canvas, &attachment.id, Some(&algorithms), None).unwrap();
+        assert_eq!(first.nodes.len(), 1);
+        assert_eq!(first.updated_node_ids, vec![existing_id.clone()]);
+        assert_eq!(first.edges.len(), 2);
+        // One write for new nodes, edges and updates together
+        assert_eq!(first.revision.revision, before + 1);
+
+        let second = HashService::hash_nodes(
+            &canvas,
+            &attachment.id,
+            Some(&algorithms),
+            Some(first.revision.revision),
+        )
+        .unwrap();
+        assert!(second.nodes.is_empty() && second.edges.is_empty());
+        assert!(second.updated_node_ids.is_empty());
+        assert_eq!(second.revision.revision, first.revision.revision);
+
+        let workspace = WorkspaceService::load(&canvas).unwrap();
+        assert_eq!(workspace.revision, first.revision.revision);
+        assert_eq!(workspace.nodes.len(), 3);
+    }
+}
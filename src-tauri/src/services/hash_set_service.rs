@@ -0,0 +1,444 @@
+// Hash Set Service
+//
+// Hash sets imported into a vault - known-good lists such as NSRL exports
+// and team block lists - and the threat level they give `hash` nodes
+
+use crate::core::{self, crypto, paths::VaultPaths, MosaicError, MosaicResult};
+use crate::models::{HashSetInfo, HashSetKind, HashSetMatch, HashSetReport};
+use crate::services::{NormalizeService, WorkspaceService};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Hash node data field naming the hash set its threat level came from;
+/// threat levels set by hand have none and are left alone
+pub const HASH_SET_FIELD: &str = "hashSet";
+
+/// Hashes sorted in memory at a time while importing a hash set
+const SORT_RUN_LEN: usize = 1_000_000;
+
+/// Hex runs that may be digests; filtered to MD5/SHA-1/SHA-256/SHA-512 lengths
+fn hex_token() -> &'static Regex {
+    static HEX_TOKEN: OnceLock<Regex> = OnceLock::new();
+    HEX_TOKEN.get_or_init(|| Regex::new(r"\b[0-9a-fA-F]{32,128}\b").unwrap())
+}
+
+pub struct HashSetService;
+
+impl HashSetService {
+    /// Import the hashes found in a text or CSV file as a hash set
+    ///
+    /// Any MD5, SHA-1, SHA-256 or SHA-512 digest in the file is taken, so
+    /// plain lists and NSRL-style CSV exports both work. The file is read
+    /// as a stream, so lists larger than memory import too.
+    pub fn import(
+        vault_path: &Path,
+        file_path: &Path,
+        name: Option<&str>,
+        kind: HashSetKind,
+    ) -> MosaicResult<HashSetInfo> {
+        if !file_path.is_file() {
+            return Err(MosaicError::not_found(&file_path.to_string_lossy()));
+        }
+
+        let id = core::generate_uuid();
+        let (info_path, hashes_path) = Self::set_paths(vault_path, &id)?;
+        let count = Self::write_sorted(file_path, &hashes_path, SORT_RUN_LEN)?;
+        if count == 0 {
+            core::fs::remove_file(&hashes_path)?;
+            return Err(MosaicError::invalid_format(format!(
+                "No MD5, SHA-1, SHA-256 or SHA-512 hashes in {}",
+                file_path.display()
+            )));
+        }
+
+        let name = name
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(str::to_string)
+            .or_else(|| {
+                file_path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+            })
+            .unwrap_or_default();
+        let info = HashSetInfo {
+            id,
+            name,
+            kind,
+            source: file_path.to_string_lossy().to_string(),
+            imported_at: core::now_iso(),
+            count,
+        };
+        core::write_json(&info_path, &info)?;
+
+        Ok(info)
+    }
+
+    /// List hash sets in a vault, sorted by name
+    pub fn list(vault_path: &Path) -> MosaicResult<Vec<HashSetInfo>> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+
+        let mut sets: Vec<HashSetInfo> = core::fs::list_dir(&vault_paths.hash_sets)?
+            .into_iter()
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|p| core::read_json(&p).ok())
+            .collect();
+
+        sets.sort_by_key(|s| s.name.to_lowercase());
+
+        Ok(sets)
+    }
+
+    /// Delete a hash set by ID
+    ///
+    /// Threat levels it set stay until hash sets are applied again.
+    pub fn delete(vault_path: &Path, set_id: &str) -> MosaicResult<HashSetInfo> {
+        let (info_path, hashes_path) = Self::set_paths(vault_path, set_id)?;
+
+        if !info_path.exists() {
+            return Err(MosaicError::not_found(&format!("Hash set {}", set_id)));
+        }
+
+        let info: HashSetInfo = core::read_json(&info_path)?;
+        if hashes_path.exists() {
+            core::fs::remove_file(&hashes_path)?;
+        }
+        core::fs::remove_file(&info_path)?;
+
+        Ok(info)
+    }
+
+    /// Hash sets listing each of the given hashes
+    pub fn lookup(vault_path: &Path, hashes: &[String]) -> MosaicResult<Vec<HashSetMatch>> {
+        let mut wanted: Vec<String> = hashes
+            .iter()
+            .filter_map(|h| core::normalize::hash(h))
+            .collect();
+        wanted.sort_unstable();
+        wanted.dedup();
+
+        let mut matches = Vec::new();
+        if wanted.is_empty() {
+            return Ok(matches);
+        }
+
+        for set in Self::list(vault_path)? {
+            let (_, hashes_path) = Self::set_paths(vault_path, &set.id)?;
+            // Sealed files cannot be searched in place, so hash sets of
+            // encrypted vaults are read whole
+            let sealed = match crypto::encrypted_root(&hashes_path) {
+                Some(_) => Some(core::read_string(&hashes_path)?),
+                None => None,
+            };
+            let sealed: Option<Vec<&str>> = sealed.as_deref().map(|s| s.lines().collect());
+
+            for hash in &wanted {
+                let listed = match &sealed {
+                    Some(lines) => lines.binary_search(&hash.as_str()).is_ok(),
+                    None => core::fs::search_sorted(&hashes_path, hash)?.is_some(),
+                };
+                if listed {
+                    matches.push(HashSetMatch {
+                        hash: hash.clone(),
+                        set_id: set.id.clone(),
+                        set_name: set.name.clone(),
+                        kind: set.kind,
+                    });
+                }
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Fields giving a hash node the threat level of the most severe set
+    /// listing its hash, or `None` if nothing changes
+    ///
+    /// Threat levels set by hand are kept. A level set earlier by a hash
+    /// set that no longer lists the hash goes back to "unknown".
+    pub fn threat_fields(
+        data: &HashMap<String, Value>,
+        hash: &str,
+        matches: &[HashSetMatch],
+    ) -> Option<HashMap<String, Value>> {
+        let current = data.get("threatLevel").and_then(Value::as_str);
+        let from_set = data.get(HASH_SET_FIELD).and_then(Value::as_str);
+        if from_set.is_none() && current.is_some_and(|level| level != "unknown") {
+            return None;
+        }
+
+        let listed = matches
+            .iter()
+            .filter(|m| m.hash.eq_ignore_ascii_case(hash))
+            .max_by_key(|m| m.kind);
+        let (level, set_name) = match listed {
+            Some(m) => (m.kind.threat_level(), json!(m.set_name)),
+            None if from_set.is_some() => ("unknown", Value::Null),
+            None => return None,
+        };
+        if current == Some(level) && from_set == set_name.as_str() {
+            return None;
+        }
+
+        Some(HashMap::from([
+            ("threatLevel".to_string(), json!(level)),
+            (HASH_SET_FIELD.to_string(), set_name),
+        ]))
+    }
+
+    /// Check every `hash` node of a canvas against its vault's hash sets
+    /// and update threat levels
+    pub fn apply(
+        canvas_path: &Path,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<HashSetReport> {
+        let vault_path = core::paths::find_vault_root(canvas_path)
+            .ok_or_else(|| MosaicError::vault_not_found(&canvas_path.to_string_lossy()))?;
+        let workspace = WorkspaceService::load(canvas_path)?;
+        let hash_nodes: Vec<(&str, &str, &HashMap<String, Value>)> = workspace
+            .nodes
+            .iter()
+            .filter(|n| n.node_type == "hash")
+            .filter_map(|n| Some((n.id.as_str(), NormalizeService::value(n, "hash")?, &n.data)))
+            .collect();
+
+        let hashes: Vec<String> = hash_nodes.iter().map(|(_, h, _)| h.to_string()).collect();
+        let matches = Self::lookup(&vault_path, &hashes)?;
+
+        let updates: HashMap<String, HashMap<String, Value>> = hash_nodes
+            .iter()
+            .filter_map(|(id, hash, data)| {
+                Some((id.to_string(), Self::threat_fields(data, hash, &matches)?))
+            })
+            .collect();
+
+        let mut updated_node_ids: Vec<String> = updates.keys().cloned().collect();
+        updated_node_ids.sort();
+        let revision = if updates.is_empty() {
+            None
+        } else {
+            Some(WorkspaceService::update_nodes_data(
+                canvas_path,
+                updates,
+                expected_revision,
+            )?)
+        };
+
+        Ok(HashSetReport {
+            checked: hash_nodes.len(),
+            updated_node_ids,
+            revision,
+        })
+    }
+
+    /// Write the digests found in `source` to `target`, sorted and without
+    /// duplicates, and return how many there are
+    ///
+    /// Runs of `run_len` digests are sorted in memory and merged from
+    /// temporary files, so memory use does not grow with the list. Files
+    /// in encrypted vaults are sealed whole; others are merged into a temp
+    /// file moved into place when complete.
+    fn write_sorted(source: &Path, target: &Path, run_len: usize) -> MosaicResult<usize> {
+        let run_dir =
+            std::env::temp_dir().join(format!("mosaicflow-hashset-{}", core::generate_uuid()));
+        let temp = target.with_extension("txt.tmp");
+
+        let result = Self::sort_runs(source, &run_dir, run_len).and_then(|runs| {
+            if crypto::encrypted_root(target).is_some() {
+                let mut content = Vec::new();
+                let count = Self::merge_runs(&runs, &mut content)?;
+                core::fs::write_bytes(target, &content)?;
+                return Ok(count);
+            }
+
+            core::ensure_dir(target.parent().unwrap_or(target))?;
+            let mut writer = BufWriter::new(File::create(&temp)?);
+            let count = Self::merge_runs(&runs, &mut writer)?;
+            writer.flush()?;
+            drop(writer);
+            core::rename(&temp, target)?;
+            Ok(count)
+        });
+
+        let _ = std::fs::remove_dir_all(&run_dir);
+        if result.is_err() && temp.exists() {
+            let _ = std::fs::remove_file(&temp);
+        }
+        result
+    }
+
+    /// Split the digests of `source` into sorted run files in `run_dir`
+    fn sort_runs(source: &Path, run_dir: &Path, run_len: usize) -> MosaicResult<Vec<PathBuf>> {
+        core::ensure_dir(run_dir)?;
+        let mut runs = Vec::new();
+        let mut run: Vec<String> = Vec::with_capacity(run_len.min(SORT_RUN_LEN));
+
+        let mut write_run = |run: &mut Vec<String>| -> MosaicResult<()> {
+            run.sort_unstable();
+            run.dedup();
+            let path = run_dir.join(format!("{}.txt", runs.len()));
+            let mut writer = BufWriter::new(File::create(&path)?);
+            for hash in run.drain(..) {
+                writeln!(writer, "{}", hash)?;
+            }
+            writer.flush()?;
+            runs.push(path);
+            Ok(())
+        };
+
+        for line in BufReader::new(File::open(source)?).split(b'\n') {
+            let line = line?;
+            for m in hex_token().find_iter(&String::from_utf8_lossy(&line)) {
+                if matches!(m.len(), 32 | 40 | 64 | 128) {
+                    run.push(m.as_str().to_ascii_lowercase());
+                }
+            }
+            if run.len() >= run_len {
+                write_run(&mut run)?;
+            }
+        }
+        if !run.is_empty() {
+            write_run(&mut run)?;
+        }
+
+        Ok(runs)
+    }
+
+    /// Merge sorted run files into `out`, dropping duplicates
+    fn merge_runs(runs: &[PathBuf], out: &mut impl Write) -> MosaicResult<usize> {
+        let mut readers = runs
+            .iter()
+            .map(|path| Ok(BufReader::new(File::open(path)?).lines()))
+            .collect::<MosaicResult<Vec<_>>>()?;
+        let mut heads = BinaryHeap::new();
+        for (index, reader) in readers.iter_mut().enumerate() {
+            if let Some(line) = reader.next() {
+                heads.push(Reverse((line?, index)));
+            }
+        }
+
+        let mut count = 0;
+        let mut last: Option<String> = None;
+        while let Some(Reverse((hash, index))) = heads.pop() {
+            if let Some(line) = readers[index].next() {
+                heads.push(Reverse((line?, index)));
+            }
+            if last.as_deref() != Some(hash.as_str()) {
+                writeln!(out, "{}", hash)?;
+                count += 1;
+                last = Some(hash);
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Metadata and hash list files of a set
+    fn set_paths(vault_path: &Path, set_id: &str) -> MosaicResult<(PathBuf, PathBuf)> {
+        if set_id.is_empty() || set_id.contains(['/', '\\']) || set_id.starts_with('.') {
+            return Err(MosaicError::invalid_format(format!(
+                "Invalid hash set ID: {}",
+                set_id
+            )));
+        }
+
+        let vault_paths = VaultPaths::from_root(vault_path);
+        Ok((
+            vault_paths.hash_sets.join(format!("{}.json", set_id)),
+            vault_paths.hash_sets.join(format!("{}.txt", set_id)),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::CanvasPaths;
+    use crate::models::{Position, WorkspaceNode};
+    use crate::services::testing::{self, TempVault};
+
+    const MD5_A: &str = "0cc175b9c0f1b6a831c399e269772661";
+    const MD5_B: &str = "92eb5ffee6ae2fec3ad71c777531578f";
+    const SHA1_C: &str = "84a516841ba77a5b4648de2cd0dfcb30ea46dbb4";
+
+    #[test]
+    fn test_import_merges_sorted_runs() {
+        let temp = testing::temp_dir();
+        let root = temp.path();
+        let source = root.join("list.csv");
+        std::fs::write(
+            &source,
+            format!(
+                "\"SHA-1\",\"MD5\",\"FileName\"\n\"{}\",\"{}\",\"a.exe\"\n{}\ntoo short: abc123\n{}\n{}\n",
+                SHA1_C.to_uppercase(),
+                MD5_B,
+                MD5_A,
+                MD5_B,
+                MD5_A.to_uppercase()
+            ),
+        )
+        .unwrap();
+
+        // Runs of two force a merge of several files
+        let target = root.join("set.txt");
+        let count = HashSetService::write_sorted(&source, &target, 2).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(
+            std::fs::read_to_string(&target).unwrap(),
+            format!("{}\n{}\n{}\n", MD5_A, SHA1_C, MD5_B)
+        );
+        for hash in [MD5_A, MD5_B, SHA1_C] {
+            assert!(core::fs::search_sorted(&target, hash).unwrap().is_some());
+        }
+        assert!(core::fs::search_sorted(&target, &"0".repeat(32))
+            .unwrap()
+            .is_none());
+        assert!(!root.join("set.txt.tmp").exists());
+    }
+
+    #[test]
+    fn test_apply_reads_hashes_from_node_files() {
+        let vault = TempVault::new();
+        let (root, vault_path) = (vault.outside(), &vault.root);
+        let canvas = vault.default_canvas();
+        let list = root.join("block.txt");
+        std::fs::write(&list, MD5_A).unwrap();
+        HashSetService::import(vault_path, &list, None, HashSetKind::Malicious).unwrap();
+
+        let data = HashMap::from([("hash".to_string(), json!(MD5_A.to_uppercase()))]);
+        let node = WorkspaceNode::new("hash", Position::default(), data);
+        let node_id = node.id.clone();
+        WorkspaceService::add_node(&canvas, node, None).unwrap();
+
+        // Saved the way the frontend does: the hash only in the content
+        // file and no normalized shadow
+        let props_path = CanvasPaths::from_root(&canvas)
+            .nodes
+            .join(&node_id)
+            .join("data")
+            .join("properties.json");
+        let mut props: Value = core::read_json(&props_path).unwrap();
+        assert!(props["data"].get("hash").is_none());
+        props["data"].as_object_mut().unwrap().remove("normalized");
+        core::write_json(&props_path, &props).unwrap();
+
+        let report = HashSetService::apply(&canvas, None).unwrap();
+        assert_eq!(report.updated_node_ids, vec![node_id.clone()]);
+        let node = WorkspaceService::load(&canvas)
+            .unwrap()
+            .nodes
+            .into_iter()
+            .find(|n| n.id == node_id)
+            .unwrap();
+        assert_eq!(node.data["threatLevel"], "malicious");
+
+        let again = HashSetService::apply(&canvas, None).unwrap();
+        assert!(again.updated_node_ids.is_empty() && again.revision.is_none());
+    }
+}
@@ -72,6 +72,18 @@ impl HistoryService {
         Self::save(app_handle, &history)
     }
 
+    /// Update a canvas entry after it moved to another vault or folder
+    pub fn relocate_canvas(
+        app_handle: &AppHandle,
+        canvas_id: &str,
+        vault_id: String,
+        path: String,
+    ) -> MosaicResult<()> {
+        let mut history = Self::load(app_handle)?;
+        history.relocate_canvas(canvas_id, vault_id, path);
+        Self::save(app_handle, &history)
+    }
+
     /// Get recent vaults
     pub fn get_recent_vaults(
         app_handle: &AppHandle,
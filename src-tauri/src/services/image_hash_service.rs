@@ -0,0 +1,434 @@
+// Image Hash Service
+//
+// Perceptual hashes of the images nodes show (image and snapshot nodes,
+// person avatars), kept in a vault index so a picture can be recognised in
+// other canvases even after it was resized or recompressed
+
+use crate::core::{self, paths::VaultPaths, MosaicError, MosaicResult};
+use crate::models::{
+    ImageHashFailure, ImageHashIndex, IndexedImage, PerceptualHashKind, PerceptualHashes,
+    SimilarImageMatch,
+};
+use crate::services::{AssetService, CanvasService, ImageService, WorkspaceService};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+
+/// Node data fields that hold images, by node type
+const IMAGE_FIELDS: [(&str, &[&str]); 3] = [
+    ("image", &["imagePath", "imageUrl"]),
+    ("snapshot", &["screenshotPath", "imageUrl"]),
+    ("person", &["avatar"]),
+];
+/// Default for `find_similar`: about 15% of the bits may differ
+pub const DEFAULT_MAX_DISTANCE: u32 = 10;
+/// Side of the downscaled image the pHash DCT runs on
+const DCT_SIZE: usize = 32;
+
+pub struct ImageHashService;
+
+impl ImageHashService {
+    /// Hash every image referenced from a node and save the index
+    ///
+    /// Images whose content is unchanged since the last run keep their
+    /// hashes. Canvases and references that cannot be read or decoded are
+    /// reported in `failed`; remote URLs and files outside the vault are
+    /// not read.
+    pub fn index(vault_path: &Path) -> MosaicResult<ImageHashIndex> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+        if !vault_paths.is_valid() {
+            return Err(MosaicError::vault_not_found(&vault_path.to_string_lossy()));
+        }
+
+        let mut known: HashMap<String, PerceptualHashes> = Self::load(vault_path)?
+            .images
+            .into_iter()
+            .map(|image| (image.sha256, image.hashes))
+            .collect();
+        let mut index = ImageHashIndex {
+            updated_at: core::now_iso(),
+            ..Default::default()
+        };
+        let vault_root = vault_path.canonicalize()?;
+
+        for canvas in CanvasService::list(&vault_paths.canvases)? {
+            let canvas_dir = PathBuf::from(&canvas.path);
+            let workspace = match WorkspaceService::load(&canvas_dir) {
+                Ok(workspace) => workspace,
+                Err(e) => {
+                    index.failed.push(ImageHashFailure {
+                        canvas_id: canvas.id.clone(),
+                        node_id: None,
+                        source: canvas.path.clone(),
+                        error: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            for node in &workspace.nodes {
+                let Some((_, fields)) = IMAGE_FIELDS.iter().find(|(t, _)| *t == node.node_type)
+                else {
+                    continue;
+                };
+
+                // imagePath and imageUrl often point at the same file
+                let mut seen = Vec::new();
+                for field in fields.iter() {
+                    let Some(source) = node
+                        .data
+                        .get(*field)
+                        .and_then(Value::as_str)
+                        .filter(|s| !s.is_empty())
+                    else {
+                        continue;
+                    };
+
+                    match Self::hash_reference(&vault_root, &canvas_dir, source, &mut known) {
+                        Ok(Some((sha256, hashes))) if !seen.contains(&sha256) => {
+                            seen.push(sha256.clone());
+                            index.images.push(IndexedImage {
+                                canvas_id: canvas.id.clone(),
+                                canvas_name: canvas.name.clone(),
+                                canvas_path: canvas.path.clone(),
+                                node_id: node.id.clone(),
+                                node_type: node.node_type.clone(),
+                                field: field.to_string(),
+                                source: source.to_string(),
+                                sha256,
+                                hashes,
+                            });
+                        }
+                        Ok(_) => {}
+                        Err(e) => index.failed.push(ImageHashFailure {
+                            canvas_id: canvas.id.clone(),
+                            node_id: Some(node.id.clone()),
+                            source: source.to_string(),
+                            error: e.to_string(),
+                        }),
+                    }
+                }
+            }
+        }
+
+        core::write_json(&vault_paths.image_index, &index)?;
+        Ok(index)
+    }
+
+    /// The saved index; empty if the vault was never indexed
+    pub fn load(vault_path: &Path) -> MosaicResult<ImageHashIndex> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+        if !vault_paths.image_index.exists() {
+            return Ok(ImageHashIndex::default());
+        }
+        core::read_json(&vault_paths.image_index)
+    }
+
+    /// Pairs of images in different canvases within `max_distance` bits
+    ///
+    /// Searches the saved index, which is built first if the vault was
+    /// never indexed or `refresh` is set. `canvas_path` and `node_id`
+    /// narrow the search to matches for the images of that canvas or node.
+    pub fn find_similar(
+        vault_path: &Path,
+        canvas_path: Option<&Path>,
+        node_id: Option<&str>,
+        max_distance: u32,
+        kind: PerceptualHashKind,
+        refresh: bool,
+    ) -> MosaicResult<Vec<SimilarImageMatch>> {
+        let index_path = VaultPaths::from_root(vault_path).image_index;
+        let index = if refresh || !index_path.exists() {
+            Self::index(vault_path)?
+        } else {
+            Self::load(vault_path)?
+        };
+        let filtered = canvas_path.is_some() || node_id.is_some();
+        let is_query = |image: &IndexedImage| {
+            canvas_path.is_none_or(|path| Path::new(&image.canvas_path) == path)
+                && node_id.is_none_or(|id| image.node_id == id)
+        };
+
+        let mut matches = Vec::new();
+        for (i, image) in index.images.iter().enumerate() {
+            if !is_query(image) {
+                continue;
+            }
+            for (j, other) in index.images.iter().enumerate() {
+                // Without a filter every pair would otherwise show up twice
+                if other.canvas_id == image.canvas_id || (!filtered && j <= i) {
+                    continue;
+                }
+                let Some(distance) = hamming(image.hashes.get(kind), other.hashes.get(kind)) else {
+                    continue;
+                };
+                if distance <= max_distance {
+                    matches.push(SimilarImageMatch {
+                        image: image.clone(),
+                        similar: other.clone(),
+                        distance,
+                    });
+                }
+            }
+        }
+
+        matches.sort_by(|a, b| {
+            a.distance
+                .cmp(&b.distance)
+                .then_with(|| a.image.canvas_name.cmp(&b.image.canvas_name))
+                .then_with(|| a.similar.canvas_name.cmp(&b.similar.canvas_name))
+        });
+        Ok(matches)
+    }
+
+    /// SHA-256 and hashes of a referenced image; `None` for remote URLs
+    fn hash_reference(
+        vault_root: &Path,
+        canvas_dir: &Path,
+        source: &str,
+        known: &mut HashMap<String, PerceptualHashes>,
+    ) -> MosaicResult<Option<(String, PerceptualHashes)>> {
+        let Some(content) = Self::read_reference(vault_root, canvas_dir, source)? else {
+            return Ok(None);
+        };
+
+        let sha256 = format!("{:x}", Sha256::digest(&content));
+        let hashes = match known.get(&sha256) {
+            Some(hashes) => hashes.clone(),
+            None => {
+                let hashes = Self::hash(&ImageService::decode(&content)?);
+                known.insert(sha256.clone(), hashes.clone());
+                hashes
+            }
+        };
+
+        Ok(Some((sha256, hashes)))
+    }
+
+    /// Content of a canvas-relative or absolute path, an asset URI or a
+    /// base64 data URI
+    ///
+    /// Paths must lead into `vault_root` (canonical), so a crafted node
+    /// cannot make the index read arbitrary files.
+    fn read_reference(
+        vault_root: &Path,
+        canvas_dir: &Path,
+        source: &str,
+    ) -> MosaicResult<Option<Vec<u8>>> {
+        if let Some(data) = source.strip_prefix("data:") {
+            let Some((_, encoded)) = data.split_once(";base64,") else {
+                return Ok(None);
+            };
+            return BASE64
+                .decode(encoded.trim())
+                .map(Some)
+                .map_err(|e| MosaicError::invalid_format(format!("Invalid data URI: {}", e)));
+        }
+
+        let path = if AssetService::is_uri(source) {
+            PathBuf::from(AssetService::resolve(vault_root, source)?.path)
+        } else if let Some(encoded) = source
+            .strip_prefix("asset://localhost/")
+            .or_else(|| source.strip_prefix("http://asset.localhost/"))
+        {
+            let decoded = urlencoding::decode(encoded)
+                .map_err(|e| MosaicError::invalid_format(format!("Invalid asset URI: {}", e)))?;
+            PathBuf::from(decoded.into_owned())
+        } else if let Some(path) = source.strip_prefix("file://") {
+            PathBuf::from(path)
+        } else if source.contains("://") {
+            return Ok(None);
+        } else {
+            canvas_dir.join(source)
+        };
+
+        if !path.is_file() {
+            return Err(MosaicError::not_found(&path.to_string_lossy()));
+        }
+        let path = path.canonicalize()?;
+        if !path.starts_with(vault_root) {
+            return Err(MosaicError::invalid_format(format!(
+                "Image outside the vault: {}",
+                path.to_string_lossy()
+            )));
+        }
+        core::fs::read_bytes(&path).map(Some)
+    }
+
+    fn hash(image: &DynamicImage) -> PerceptualHashes {
+        let gray = image.grayscale();
+        let pixels = |width: u32, height: u32| -> Vec<f64> {
+            gray.resize_exact(width, height, FilterType::Triangle)
+                .to_luma8()
+                .into_raw()
+                .into_iter()
+                .map(f64::from)
+                .collect()
+        };
+
+        let small = pixels(8, 8);
+        let mean = small.iter().sum::<f64>() / small.len() as f64;
+        let ahash = bits(small.iter().map(|&p| p > mean));
+
+        let wide = pixels(9, 8);
+        let dhash = bits(
+            wide.chunks(9)
+                .flat_map(|row| row.windows(2).map(|pair| pair[1] > pair[0])),
+        );
+
+        // The DC term is left out of the median, as it only reflects brightness
+        let low = dct_low_frequencies(&pixels(DCT_SIZE as u32, DCT_SIZE as u32));
+        let mut ac = low[1..].to_vec();
+        ac.sort_by(f64::total_cmp);
+        let median = ac[ac.len() / 2];
+        let phash = bits(low.iter().map(|&c| c > median));
+
+        PerceptualHashes {
+            ahash: format!("{:016x}", ahash),
+            dhash: format!("{:016x}", dhash),
+            phash: format!("{:016x}", phash),
+        }
+    }
+}
+
+/// The top-left 8x8 coefficients of the 2D DCT-II of a square image
+fn dct_low_frequencies(pixels: &[f64]) -> Vec<f64> {
+    const LOW: usize = 8;
+    let n = DCT_SIZE;
+    let basis = |k: usize, x: usize| ((2 * x + 1) as f64 * k as f64 * PI / (2 * n) as f64).cos();
+
+    // Rows first, then columns of the row results
+    let mut rows = vec![0.0; n * LOW];
+    for y in 0..n {
+        for k in 0..LOW {
+            rows[y * LOW + k] = (0..n).map(|x| pixels[y * n + x] * basis(k, x)).sum();
+        }
+    }
+
+    let mut low = vec![0.0; LOW * LOW];
+    for k in 0..LOW {
+        for x in 0..LOW {
+            low[k * LOW + x] = (0..n).map(|y| rows[y * LOW + x] * basis(k, y)).sum();
+        }
+    }
+    low
+}
+
+fn bits(values: impl Iterator<Item = bool>) -> u64 {
+    values.fold(0, |hash, bit| (hash << 1) | u64::from(bit))
+}
+
+/// Number of differing bits between two hex-encoded hashes
+fn hamming(a: &str, b: &str) -> Option<u32> {
+    let a = u64::from_str_radix(a, 16).ok()?;
+    let b = u64::from_str_radix(b, 16).ok()?;
+    Some((a ^ b).count_ones())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Position, WorkspaceNode};
+    use crate::services::testing::TempVault;
+    use image::{GrayImage, Luma};
+    use serde_json::json;
+
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, y| {
+            Luma([((x * 255 / width + y * 64 / height) % 256) as u8])
+        }))
+    }
+
+    #[test]
+    fn test_hamming() {
+        assert_eq!(hamming("0000000000000000", "0000000000000000"), Some(0));
+        assert_eq!(hamming("ffffffffffffffff", "0000000000000000"), Some(64));
+        assert_eq!(hamming("00000000000000f0", "0000000000000001"), Some(5));
+        assert_eq!(hamming("not hex", "0000000000000000"), None);
+    }
+
+    #[test]
+    fn test_bits_are_msb_first() {
+        assert_eq!(bits([true, false, true].into_iter()), 0b101);
+        assert_eq!(bits(std::iter::repeat_n(true, 64)), u64::MAX);
+    }
+
+    #[test]
+    fn test_dct_of_flat_image_is_dc_only() {
+        let low = dct_low_frequencies(&vec![10.0; DCT_SIZE * DCT_SIZE]);
+        assert_eq!(low.len(), 64);
+        assert!((low[0] - 10.0 * (DCT_SIZE * DCT_SIZE) as f64).abs() < 1e-6);
+        assert!(low[1..].iter().all(|c| c.abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_hash_survives_resizing() {
+        let original = ImageHashService::hash(&gradient(640, 480));
+        let resized = ImageHashService::hash(&gradient(640, 480).thumbnail(160, 120));
+        let flipped = ImageHashService::hash(&gradient(640, 480).fliph());
+
+        for kind in [
+            PerceptualHashKind::Ahash,
+            PerceptualHashKind::Dhash,
+            PerceptualHashKind::Phash,
+        ] {
+            assert_eq!(original.get(kind).len(), 16);
+            let near = hamming(original.get(kind), resized.get(kind)).unwrap();
+            let far = hamming(original.get(kind), flipped.get(kind)).unwrap();
+            assert!(near <= DEFAULT_MAX_DISTANCE, "{:?}: {}", kind, near);
+            assert!(far > DEFAULT_MAX_DISTANCE, "{:?}: {}", kind, far);
+        }
+    }
+
+    #[test]
+    fn test_index_skips_files_outside_the_vault() {
+        let vault = TempVault::new();
+        let (root, vault_path) = (vault.outside(), &vault.root);
+        let canvas_dir = vault.default_canvas();
+        gradient(64, 48).save(root.join("outside.png")).unwrap();
+        gradient(64, 48)
+            .save(canvas_dir.join("inside.png"))
+            .unwrap();
+
+        for (id, source) in [
+            ("inside", "inside.png".to_string()),
+            ("relative", "../../../outside.png".to_string()),
+            (
+                "absolute",
+                root.join("outside.png").to_string_lossy().to_string(),
+            ),
+        ] {
+            let mut node = WorkspaceNode::new(
+                "image",
+                Position::default(),
+                HashMap::from([("imagePath".to_string(), json!(source))]),
+            );
+            node.id = id.to_string();
+            WorkspaceService::add_node(&canvas_dir, node, None).unwrap();
+        }
+
+        // A canvas that cannot be loaded is reported, not fatal
+        let canvases = VaultPaths::from_root(vault_path).canvases;
+        let broken = CanvasService::create(&canvases, "vault", "Broken", None).unwrap();
+        std::fs::write(Path::new(&broken.path).join("workspace.json"), "{").unwrap();
+
+        let index = ImageHashService::index(vault_path).unwrap();
+        assert_eq!(index.images.len(), 1);
+        assert_eq!(index.images[0].node_id, "inside");
+        let mut failed: Vec<_> = index.failed.iter().map(|f| f.node_id.clone()).collect();
+        failed.sort();
+        assert_eq!(
+            failed,
+            vec![
+                None,
+                Some("absolute".to_string()),
+                Some("relative".to_string())
+            ]
+        );
+    }
+}
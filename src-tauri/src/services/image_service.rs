@@ -0,0 +1,339 @@
+// Image Service
+//
+// Decodes images in a canvas's images/ directory and writes a thumbnail and
+// a medium preview next to each original, so canvases can show downscaled
+// copies instead of loading full-size evidence photos
+
+use crate::core::{self, paths::CanvasPaths, paths::VaultPaths, MosaicError, MosaicResult};
+use crate::models::{
+    ImageImport, ImageVariantFailure, ImageVariantReport, ImageVariants, WorkspaceData,
+};
+use crate::services::{AttachmentService, WorkspaceService};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageError, ImageFormat, ImageReader};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// Longest side of thumbnails, in pixels
+const THUMBNAIL_SIZE: u32 = 256;
+/// Longest side of previews, in pixels
+const PREVIEW_SIZE: u32 = 1280;
+/// Variants are named `<original file name><suffix>.<jpg|png>`
+const THUMBNAIL_SUFFIX: &str = ".thumb";
+const PREVIEW_SUFFIX: &str = ".preview";
+const JPEG_QUALITY: u8 = 85;
+
+pub struct ImageService;
+
+impl ImageService {
+    /// Import an image as an attachment of a node and generate its variants
+    ///
+    /// The node's data gets `imagePath`, `thumbnailPath`, `previewPath`,
+    /// `imageWidth` and `imageHeight`. If the variants or the node update
+    /// fail, the stored image, its manifest record and any variants written
+    /// so far are removed again.
+    pub fn import(
+        canvas_path: &Path,
+        file_path: &Path,
+        node_id: &str,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<ImageImport> {
+        if !Self::is_decodable(file_path) {
+            return Err(MosaicError::invalid_format(format!(
+                "Unsupported image format: {}",
+                file_path.to_string_lossy()
+            )));
+        }
+
+        let attachment = AttachmentService::import(canvas_path, file_path, node_id)?;
+        let result = Self::generate(canvas_path, &attachment.path).and_then(|variants| {
+            let revision = WorkspaceService::update_node_data(
+                canvas_path,
+                node_id,
+                Self::node_fields(&variants),
+                expected_revision,
+            )?;
+            Ok((variants, revision))
+        });
+
+        match result {
+            Ok((variants, revision)) => Ok(ImageImport {
+                attachment,
+                variants,
+                revision,
+            }),
+            Err(e) => {
+                let original = canvas_path.join(&attachment.path);
+                for suffix in [THUMBNAIL_SUFFIX, PREVIEW_SUFFIX] {
+                    for extension in ["jpg", "png"] {
+                        let variant = Self::variant_path(&original, suffix, extension);
+                        if variant.exists() {
+                            core::fs::remove_file(&variant)?;
+                        }
+                    }
+                }
+                AttachmentService::discard(canvas_path, &attachment.id)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Write the thumbnail and preview of an image, `relative` to the canvas
+    pub fn generate(canvas_path: &Path, relative: &str) -> MosaicResult<ImageVariants> {
+        let original = canvas_path.join(relative);
+        let image = Self::decode(&core::fs::read_bytes(&original)?)?;
+
+        // JPEG has no alpha channel
+        let (extension, keep_alpha) = if image.color().has_alpha() {
+            ("png", true)
+        } else {
+            ("jpg", false)
+        };
+        let thumbnail = Self::variant_path(&original, THUMBNAIL_SUFFIX, extension);
+        let preview = Self::variant_path(&original, PREVIEW_SUFFIX, extension);
+        Self::write_variant(
+            &image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE),
+            &thumbnail,
+            keep_alpha,
+        )?;
+        Self::write_variant(&Self::downscale(&image, PREVIEW_SIZE), &preview, keep_alpha)?;
+
+        let relative_to_canvas = |path: &Path| {
+            path.strip_prefix(canvas_path)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/")
+        };
+        Ok(ImageVariants {
+            path: relative_to_canvas(&original),
+            thumbnail_path: relative_to_canvas(&thumbnail),
+            preview_path: relative_to_canvas(&preview),
+            width: image.width(),
+            height: image.height(),
+        })
+    }
+
+    /// Regenerate variants of every image in every canvas of a vault
+    ///
+    /// Nodes whose `imagePath` points at a regenerated image get fresh
+    /// variant paths and dimensions; a canvas saved meanwhile fails with a
+    /// conflict rather than being overwritten. Images that fail to decode
+    /// are reported and skipped.
+    pub fn regenerate(vault_path: &Path) -> MosaicResult<ImageVariantReport> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+        if !vault_paths.is_valid() {
+            return Err(MosaicError::vault_not_found(&vault_path.to_string_lossy()));
+        }
+
+        let mut report = ImageVariantReport::default();
+        for canvas_dir in core::paths::list_canvas_dirs(&vault_paths.canvases)? {
+            let canvas_paths = CanvasPaths::from_root(&canvas_dir);
+
+            let mut generated = Vec::new();
+            for path in core::fs::list_dir(&canvas_paths.images)? {
+                if !path.is_file() || !Self::is_decodable(&path) || Self::is_variant(&path) {
+                    continue;
+                }
+                let relative = path
+                    .strip_prefix(&canvas_dir)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+
+                match Self::generate(&canvas_dir, &relative) {
+                    Ok(variants) => generated.push(variants),
+                    Err(e) => report.failed.push(ImageVariantFailure {
+                        path: path.to_string_lossy().to_string(),
+                        error: e.to_string(),
+                    }),
+                }
+            }
+            report.processed += generated.len();
+
+            if generated.is_empty() {
+                continue;
+            }
+            let mut workspace = WorkspaceService::load(&canvas_dir)?;
+            let updated = Self::update_nodes(&mut workspace, &generated);
+            if updated > 0 {
+                WorkspaceService::save_checked(&canvas_dir, &workspace, Some(workspace.revision))?;
+                report.updated_nodes += updated;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Point image nodes at their regenerated variants; returns how many changed
+    fn update_nodes(workspace: &mut WorkspaceData, generated: &[ImageVariants]) -> usize {
+        let mut updated = 0;
+
+        for node in &mut workspace.nodes {
+            let image_path = node.data.get("imagePath").and_then(Value::as_str);
+            let Some(variants) = generated
+                .iter()
+                .find(|v| Some(v.path.as_str()) == image_path)
+            else {
+                continue;
+            };
+
+            let fields = Self::node_fields(variants);
+            if fields
+                .iter()
+                .any(|(key, value)| node.data.get(key) != Some(value))
+            {
+                node.data.extend(fields);
+                updated += 1;
+            }
+        }
+
+        updated
+    }
+
+    fn node_fields(variants: &ImageVariants) -> HashMap<String, Value> {
+        HashMap::from([
+            ("imagePath".to_string(), json!(variants.path)),
+            ("thumbnailPath".to_string(), json!(variants.thumbnail_path)),
+            ("previewPath".to_string(), json!(variants.preview_path)),
+            ("imageWidth".to_string(), json!(variants.width)),
+            ("imageHeight".to_string(), json!(variants.height)),
+        ])
+    }
+
+    /// Variant of `original`; the whole file name is kept so `a.png` and
+    /// `a.jpg` get distinct variants
+    fn variant_path(original: &Path, suffix: &str, extension: &str) -> PathBuf {
+        let name = original
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        original.with_file_name(format!("{}{}.{}", name, suffix, extension))
+    }
+
+    /// Decode an image, rotated and flipped upright per its EXIF orientation
+    pub(crate) fn decode(content: &[u8]) -> MosaicResult<DynamicImage> {
+        let invalid =
+            |e: ImageError| MosaicError::invalid_format(format!("Cannot decode image: {}", e));
+
+        let mut decoder = ImageReader::new(Cursor::new(content))
+            .with_guessed_format()?
+            .into_decoder()
+            .map_err(invalid)?;
+        let orientation = decoder.orientation().map_err(invalid)?;
+        let mut image = DynamicImage::from_decoder(decoder).map_err(invalid)?;
+        image.apply_orientation(orientation);
+
+        Ok(image)
+    }
+
+    /// Shrink to fit `size` on the longest side; smaller images are kept as-is
+    fn downscale(image: &DynamicImage, size: u32) -> DynamicImage {
+        if image.width() <= size && image.height() <= size {
+            return image.clone();
+        }
+        image.resize(size, size, FilterType::Lanczos3)
+    }
+
+    fn write_variant(image: &DynamicImage, path: &Path, keep_alpha: bool) -> MosaicResult<()> {
+        let mut bytes = Vec::new();
+        let encoded = if keep_alpha {
+            image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        } else {
+            JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY).encode_image(&image.to_rgb8())
+        };
+        encoded.map_err(|e| MosaicError::io_error(format!("Cannot encode image: {}", e)))?;
+
+        core::fs::write_bytes(path, &bytes)
+    }
+
+    fn is_decodable(path: &Path) -> bool {
+        ImageFormat::from_path(path).is_ok_and(|format| format.reading_enabled())
+    }
+
+    fn is_variant(path: &Path) -> bool {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .is_some_and(|stem| stem.ends_with(THUMBNAIL_SUFFIX) || stem.ends_with(PREVIEW_SUFFIX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Position, WorkspaceNode};
+    use crate::services::testing::TempVault;
+    use image::{Rgb, RgbImage};
+    use std::path::PathBuf;
+
+    fn temp_canvas() -> (TempVault, PathBuf, String) {
+        let vault = TempVault::new();
+        let canvas_path = vault.create_canvas("Case");
+
+        let node = WorkspaceNode::new("image", Position::default(), Default::default());
+        let node_id = node.id.clone();
+        WorkspaceService::add_node(&canvas_path, node, None).unwrap();
+        (vault, canvas_path, node_id)
+    }
+
+    fn write_image(path: &Path, format: ImageFormat) {
+        RgbImage::from_pixel(400, 300, Rgb([200, 40, 40]))
+            .save_with_format(path, format)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_variant_names_keep_the_extension() {
+        let (vault, canvas_path, _) = temp_canvas();
+        let images = CanvasPaths::from_root(&canvas_path).images;
+        std::fs::create_dir_all(&images).unwrap();
+        write_image(&images.join("a.png"), ImageFormat::Png);
+        write_image(&images.join("a.jpg"), ImageFormat::Jpeg);
+
+        let png = ImageService::generate(&canvas_path, "images/a.png").unwrap();
+        let jpg = ImageService::generate(&canvas_path, "images/a.jpg").unwrap();
+        assert_eq!(png.thumbnail_path, "images/a.png.thumb.jpg");
+        assert_eq!(jpg.thumbnail_path, "images/a.jpg.thumb.jpg");
+        assert_eq!((png.width, png.height), (400, 300));
+        assert!(ImageService::is_variant(Path::new(&png.preview_path)));
+
+        // Variants are skipped, so regenerating finds just the two originals
+        let report = ImageService::regenerate(&vault.root).unwrap();
+        assert_eq!(report.processed, 2);
+        assert!(report.failed.is_empty());
+    }
+
+    #[test]
+    fn test_failed_import_is_cleaned_up() {
+        let (vault, canvas_path, node_id) = temp_canvas();
+        let source = vault.outside().join("photo.png");
+        write_image(&source, ImageFormat::Png);
+
+        // A stale revision makes the node update fail after the variants
+        // were written
+        let stale = WorkspaceService::load(&canvas_path).unwrap().revision + 5;
+        assert!(ImageService::import(&canvas_path, &source, &node_id, Some(stale)).is_err());
+
+        let images = CanvasPaths::from_root(&canvas_path).images;
+        assert!(core::fs::list_dir(&images).unwrap().is_empty());
+        assert!(AttachmentService::manifest(&canvas_path)
+            .unwrap()
+            .attachments
+            .is_empty());
+
+        let import = ImageService::import(&canvas_path, &source, &node_id, None).unwrap();
+        let node = WorkspaceService::load(&canvas_path)
+            .unwrap()
+            .nodes
+            .into_iter()
+            .find(|n| n.id == node_id)
+            .unwrap();
+        assert_eq!(
+            node.data["thumbnailPath"],
+            json!(import.variants.thumbnail_path)
+        );
+        assert_eq!(node.data["imageWidth"], json!(400));
+    }
+}
@@ -0,0 +1,481 @@
+// Indicator Service
+//
+// Finds indicators of compromise (addresses, domains, URLs, hashes, CVEs,
+// wallets, ...) in threat reports pasted into notes, including defanged
+// forms such as `hxxp://evil[.]com`, and adds them to the canvas as OSINT
+// nodes linked to the note they came from
+
+use crate::core::{normalize, MosaicError, MosaicResult};
+use crate::models::{
+    Indicator, IndicatorKind, IndicatorNodes, Position, WorkspaceEdge, WorkspaceNode,
+};
+use crate::services::{NormalizeService, WorkspaceService};
+use regex::Regex;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Tried in order; text matched by one pattern is not matched again, so a
+/// URL's host is not also reported as a domain
+const PATTERNS: [(IndicatorKind, &str); 9] = [
+    (
+        IndicatorKind::Url,
+        r#"(?i)\b(?:https?|ftp)://[^\s<>"'`()\[\]{}|\\^]+"#,
+    ),
+    (
+        IndicatorKind::Email,
+        r"(?i)\b[a-z0-9][a-z0-9._%+-]*@(?:[a-z0-9](?:[a-z0-9-]*[a-z0-9])?\.)+[a-z]{2,24}\b",
+    ),
+    (IndicatorKind::Cve, r"(?i)\bCVE-\d{4}-\d{4,7}\b"),
+    (
+        IndicatorKind::Ipv6,
+        r"(?i)(?:[0-9a-f]{0,4}:){2,7}[0-9a-f]{0,4}",
+    ),
+    (IndicatorKind::Ipv4, r"\b(?:\d{1,3}\.){3}\d{1,3}\b"),
+    // Typed by length
+    (IndicatorKind::Sha256, r"\b[0-9a-fA-F]{32,128}\b"),
+    (
+        IndicatorKind::Bitcoin,
+        r"\b(?:[13][1-9A-HJ-NP-Za-km-z]{25,34}|bc1[02-9ac-hj-np-z]{11,71})\b",
+    ),
+    (IndicatorKind::Phone, r"\+\d[\d ().-]{6,20}\d"),
+    (
+        IndicatorKind::Domain,
+        r"(?i)\b(?:[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\.)+(?:[a-z]{2,24}|xn--[a-z0-9-]{1,59})\b",
+    ),
+];
+
+/// Endings that make `name.ext` a file name rather than a domain, unless
+/// it was written defanged
+const FILE_EXTENSIONS: &[&str] = &[
+    "bat", "bin", "cfg", "csv", "dat", "dll", "doc", "docx", "exe", "gif", "htm", "html", "ini",
+    "jar", "jpeg", "jpg", "js", "json", "lnk", "log", "md", "pdf", "php", "png", "ps1", "py",
+    "rar", "rs", "sh", "so", "sys", "tmp", "ts", "txt", "vbs", "xls", "xlsx", "xml", "yaml", "yml",
+    "zip",
+];
+
+/// Gap between the source node and the first column of indicator nodes
+const NODE_OFFSET_X: f64 = 100.0;
+const NODE_SPACING_X: f64 = 320.0;
+const NODE_SPACING_Y: f64 = 220.0;
+const NODE_COLUMNS: usize = 4;
+const EDGE_LABEL: &str = "mentions";
+
+fn patterns() -> &'static [(IndicatorKind, Regex)] {
+    static PATTERNS_RE: OnceLock<Vec<(IndicatorKind, Regex)>> = OnceLock::new();
+    PATTERNS_RE.get_or_init(|| {
+        PATTERNS
+            .iter()
+            .map(|(kind, pattern)| (*kind, Regex::new(pattern).unwrap()))
+            .collect()
+    })
+}
+
+pub struct IndicatorService;
+
+impl IndicatorService {
+    /// Indicators in a text, in order of first occurrence, each listed once
+    pub fn extract(text: &str) -> Vec<Indicator> {
+        let (refanged, offsets) = normalize::refang_mapped(text);
+        let mut covered: Vec<(usize, usize)> = Vec::new();
+        let mut seen = HashSet::new();
+        let mut indicators = Vec::new();
+
+        for (kind, pattern) in patterns() {
+            for m in pattern.find_iter(&refanged) {
+                // Sentence punctuation after a URL is not part of it
+                let matched = match kind {
+                    IndicatorKind::Url => {
+                        m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?'])
+                    }
+                    _ => m.as_str(),
+                };
+                let (start, end) = (m.start(), m.start() + matched.len());
+                if covered.iter().any(|&(s, e)| start < e && s < end) {
+                    continue;
+                }
+                let raw = &text[offsets[start]..offsets[end]];
+                let Some((kind, value)) = classify(*kind, &refanged, start, matched, raw) else {
+                    continue;
+                };
+
+                covered.push((start, end));
+                if seen.insert((kind, value.clone())) {
+                    indicators.push(Indicator {
+                        kind,
+                        value,
+                        raw: raw.to_string(),
+                        offset: offsets[start],
+                    });
+                }
+            }
+        }
+
+        indicators.sort_by_key(|i| i.offset);
+        indicators
+    }
+
+    /// Indicators in the text of a note, code or other text node
+    pub fn extract_from_node(canvas_path: &Path, node_id: &str) -> MosaicResult<Vec<Indicator>> {
+        let workspace = WorkspaceService::load(canvas_path)?;
+        let node = workspace
+            .nodes
+            .iter()
+            .find(|n| n.id == node_id)
+            .ok_or_else(|| MosaicError::not_found(&format!("Node {}", node_id)))?;
+
+        Ok(Self::extract(Self::node_text(node)?))
+    }
+
+    /// Add a node per indicator in a node's text, linked from that node
+    ///
+    /// Indicators already on the canvas are linked instead of duplicated,
+    /// and the canvas is left untouched when there is nothing new. `kinds`
+    /// limits which kinds of indicator are added.
+    pub fn materialize(
+        canvas_path: &Path,
+        node_id: &str,
+        kinds: Option<&[IndicatorKind]>,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<IndicatorNodes> {
+        let workspace = WorkspaceService::load(canvas_path)?;
+        let source = workspace
+            .nodes
+            .iter()
+            .find(|n| n.id == node_id)
+            .ok_or_else(|| MosaicError::not_found(&format!("Node {}", node_id)))?;
+        let indicators: Vec<Indicator> = Self::extract(Self::node_text(source)?)
+            .into_iter()
+            .filter(|i| kinds.is_none_or(|kinds| kinds.contains(&i.kind)))
+            .collect();
+
+        let mut linked: HashSet<String> = workspace
+            .edges
+            .iter()
+            .filter(|e| e.source == source.id)
+            .map(|e| e.target.clone())
+            .collect();
+        // Compared by normalized form where the node has one
+        let find_existing = |node_type: &str, key: &str, value: &str| {
+            workspace.nodes.iter().find(|n| {
+                n.node_type == node_type
+                    && NormalizeService::value(n, key)
+                        .is_some_and(|v| v.eq_ignore_ascii_case(value))
+            })
+        };
+
+        let origin_x = source.position.x + source.width.unwrap_or(300.0) + NODE_OFFSET_X;
+        let mut nodes: Vec<WorkspaceNode> = Vec::new();
+        let mut edges = Vec::new();
+        for indicator in &indicators {
+            let (node_type, key, data) = Self::node_data(indicator);
+            let value = data[key].as_str().unwrap_or_default().to_string();
+
+            let target = match find_existing(node_type, key, &value) {
+                Some(existing) if linked.contains(&existing.id) => continue,
+                Some(existing) => existing.id.clone(),
+                // Two indicators can map to the same node (e.g. URL and CVE link)
+                None if nodes.iter().any(|n| {
+                    n.node_type == node_type && n.data.get(key) == Some(&json!(value))
+                }) =>
+                {
+                    continue
+                }
+                None => {
+                    let index = nodes.len();
+                    let position = Position {
+                        x: origin_x + (index % NODE_COLUMNS) as f64 * NODE_SPACING_X,
+                        y: source.position.y + (index / NODE_COLUMNS) as f64 * NODE_SPACING_Y,
+                    };
+                    let node = WorkspaceNode::new(node_type, position, data);
+                    let id = node.id.clone();
+                    nodes.push(node);
+                    id
+                }
+            };
+            edges.push(WorkspaceEdge::new(
+                &source.id,
+                &target,
+                Some(EDGE_LABEL.to_string()),
+            ));
+            linked.insert(target);
+        }
+
+        let revision = if edges.is_empty() {
+            WorkspaceService::revision(canvas_path, expected_revision)?
+        } else {
+            WorkspaceService::batch_update(
+                canvas_path,
+                nodes.clone(),
+                vec![],
+                edges.clone(),
+                vec![],
+                expected_revision,
+            )?
+        };
+
+        Ok(IndicatorNodes {
+            indicators,
+            nodes,
+            edges,
+            revision,
+        })
+    }
+
+    fn node_text(node: &WorkspaceNode) -> MosaicResult<&str> {
+        let key = match node.node_type.as_str() {
+            "note" | "simpleText" | "socialPost" => "content",
+            "code" => "code",
+            other => {
+                return Err(MosaicError::invalid_format(format!(
+                    "{} nodes have no text to scan",
+                    other
+                )))
+            }
+        };
+        Ok(node
+            .data
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default())
+    }
+
+    /// Node type, the data field identifying it, and the node's data
+    fn node_data(indicator: &Indicator) -> (&'static str, &'static str, HashMap<String, Value>) {
+        let value = indicator.value.as_str();
+        let mut data = HashMap::from([("title".to_string(), json!(value))]);
+        let mut set = |key: &str, value: Value| {
+            data.insert(key.to_string(), value);
+        };
+
+        let (node_type, key) = match indicator.kind {
+            IndicatorKind::Ipv4 | IndicatorKind::Ipv6 => {
+                set("domain", json!(value));
+                set("ip", json!(value));
+                ("domain", "domain")
+            }
+            IndicatorKind::Domain => {
+                set("domain", json!(value));
+                ("domain", "domain")
+            }
+            IndicatorKind::Url => {
+                set("url", json!(value));
+                set("description", json!(""));
+                ("link", "url")
+            }
+            IndicatorKind::Cve => {
+                set(
+                    "url",
+                    json!(format!("https://nvd.nist.gov/vuln/detail/{}", value)),
+                );
+                set("description", json!(value));
+                ("link", "url")
+            }
+            IndicatorKind::Md5
+            | IndicatorKind::Sha1
+            | IndicatorKind::Sha256
+            | IndicatorKind::Sha512 => {
+                set("hash", json!(value));
+                set("algorithm", json!(indicator.kind.hash_algorithm()));
+                set("threatLevel", json!("unknown"));
+                ("hash", "hash")
+            }
+            IndicatorKind::Email => {
+                set("email", json!(value));
+                set("username", json!(""));
+                set("platform", json!(""));
+                set("breached", json!(false));
+                ("credential", "email")
+            }
+            IndicatorKind::Bitcoin | IndicatorKind::Phone => {
+                let platform = if indicator.kind == IndicatorKind::Bitcoin {
+                    "Bitcoin"
+                } else {
+                    "Phone"
+                };
+                set("username", json!(value));
+                set("email", json!(""));
+                set("platform", json!(platform));
+                set("breached", json!(false));
+                ("credential", "username")
+            }
+        };
+
+        (node_type, key, data)
+    }
+}
+
+/// Validate a match and normalize its value; `None` if it is not an indicator
+fn classify(
+    kind: IndicatorKind,
+    text: &str,
+    start: usize,
+    value: &str,
+    raw: &str,
+) -> Option<(IndicatorKind, String)> {
+    let was_defanged = raw != value;
+
+    match kind {
+        IndicatorKind::Ipv6 => {
+            // The pattern has no word boundaries, as addresses may start with "::"
+            let end = start + value.len();
+            let bounded = !text[..start].ends_with(|c: char| c.is_alphanumeric() || c == ':')
+                && !text[end..].starts_with(|c: char| c.is_alphanumeric());
+            let groups = value.split(':').filter(|g| !g.is_empty()).count();
+            if !bounded || groups < 2 || value.len() < 6 {
+                return None;
+            }
+            normalize::ip(value).map(|ip| (kind, ip))
+        }
+        IndicatorKind::Sha256 => {
+            let kind = match value.len() {
+                32 => IndicatorKind::Md5,
+                40 => IndicatorKind::Sha1,
+                64 => IndicatorKind::Sha256,
+                128 => IndicatorKind::Sha512,
+                _ => return None,
+            };
+            normalize::hash(value).map(|hash| (kind, hash))
+        }
+        IndicatorKind::Bitcoin if !value.starts_with("bc1") && !is_base58check(value) => None,
+        IndicatorKind::Domain => {
+            let domain = normalize::domain(value)?;
+            let tld = domain.rsplit('.').next()?;
+            (was_defanged || !FILE_EXTENSIONS.contains(&tld)).then_some((kind, domain))
+        }
+        _ => NormalizeService::normalize(kind, value).map(|value| (kind, value)),
+    }
+}
+
+/// Whether a legacy Bitcoin address decodes with a valid checksum
+fn is_base58check(address: &str) -> bool {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    // Big-endian base-256 digits of the number, 25 bytes for an address
+    let mut bytes = [0u8; 25];
+    for c in address.bytes() {
+        let Some(mut carry) = ALPHABET.iter().position(|&a| a == c) else {
+            return false;
+        };
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as usize * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        if carry != 0 {
+            return false;
+        }
+    }
+
+    let (payload, checksum) = bytes.split_at(21);
+    Sha256::digest(Sha256::digest(payload))[..4] == *checksum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::testing::TempCanvas;
+
+    fn found(text: &str) -> Vec<(IndicatorKind, String)> {
+        IndicatorService::extract(text)
+            .into_iter()
+            .map(|i| (i.kind, i.value))
+            .collect()
+    }
+
+    #[test]
+    fn test_refanged_offsets_point_into_the_original() {
+        let text = "C2 at hxxps://evil[.]example/a and 10[.]0[.]0[.]1.";
+        let indicators = IndicatorService::extract(text);
+
+        assert_eq!(indicators.len(), 2);
+        assert_eq!(indicators[0].value, "https://evil.example/a");
+        assert_eq!(indicators[0].raw, "hxxps://evil[.]example/a");
+        assert_eq!(indicators[0].offset, text.find("hxxps").unwrap());
+        assert_eq!(indicators[1].kind, IndicatorKind::Ipv4);
+        assert_eq!(indicators[1].raw, "10[.]0[.]0[.]1");
+        assert_eq!(indicators[1].offset, text.find("10[").unwrap());
+    }
+
+    #[test]
+    fn test_overlapping_matches_are_reported_once() {
+        // The URL's host and the email's domain are not domains of their own
+        assert_eq!(
+            found("see https://example.com/x, mail bob@corp.example, visit other.example"),
+            vec![
+                (IndicatorKind::Url, "https://example.com/x".to_string()),
+                (IndicatorKind::Email, "bob@corp.example".to_string()),
+                (IndicatorKind::Domain, "other.example".to_string()),
+            ]
+        );
+        // File names are not domains unless written defanged
+        assert_eq!(found("dropped payload.exe"), vec![]);
+        assert_eq!(
+            found("dropped payload[.]exe"),
+            vec![(IndicatorKind::Domain, "payload.exe".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_hashes_are_typed_by_length() {
+        let md5 = "D41D8CD98F00B204E9800998ECF8427E";
+        let sha1 = "da39a3ee5e6b4b0d3255bfef95601890afd80709";
+        let sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let sha512 = "a".repeat(128);
+        let text = format!("{} {} {} {} {}", md5, sha1, sha256, sha512, "b".repeat(50));
+
+        assert_eq!(
+            found(&text),
+            vec![
+                (IndicatorKind::Md5, md5.to_lowercase()),
+                (IndicatorKind::Sha1, sha1.to_string()),
+                (IndicatorKind::Sha256, sha256.to_string()),
+                (IndicatorKind::Sha512, sha512),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_materialize_twice_writes_once() {
+        let temp = TempCanvas::new();
+        let canvas = &temp.path;
+        let data = HashMap::from([("content".to_string(), json!("evil[.]example 10.0.0.1"))]);
+        let note = WorkspaceNode::new("note", Position::default(), data);
+        let note_id = note.id.clone();
+        WorkspaceService::add_node(canvas, note, None).unwrap();
+
+        let first = IndicatorService::materialize(canvas, &note_id, None, None).unwrap();
+        assert_eq!((first.nodes.len(), first.edges.len()), (2, 2));
+
+        let second =
+            IndicatorService::materialize(canvas, &note_id, None, Some(first.revision.revision))
+                .unwrap();
+        assert!(second.nodes.is_empty() && second.edges.is_empty());
+        assert_eq!(second.revision.revision, first.revision.revision);
+        assert_eq!(
+            WorkspaceService::load(canvas).unwrap().revision,
+            first.revision.revision
+        );
+    }
+
+    #[test]
+    fn test_base58check() {
+        assert!(is_base58check("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"));
+        assert!(is_base58check("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy"));
+        // One character changed breaks the checksum
+        assert!(!is_base58check("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb"));
+        // 0, O, I and l are not in the alphabet
+        assert!(!is_base58check("1A1zP1eP5QGefi2DMPTfTL5SLmv7Divf0a"));
+        assert_eq!(
+            found(
+                "send to 1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa or 1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb"
+            ),
+            vec![(
+                IndicatorKind::Bitcoin,
+                "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,661 @@
+// Metadata Service
+//
+// Reads metadata from attachments: EXIF and XMP from images, document info
+// from PDFs and docProps from Office files. Findings such as GPS positions
+// and authors can be added to the canvas as nodes linked to the node the
+// attachment belongs to.
+
+use crate::core::{self, MosaicError, MosaicResult};
+use crate::models::{
+    AttachmentRecord, FileMetadata, GpsPoint, MetadataFindings, Position, WorkspaceEdge,
+    WorkspaceNode,
+};
+use crate::services::{AttachmentService, WorkspaceService};
+use exif::{Exif, In, Tag};
+use lopdf::Object;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Cursor, Read};
+use std::path::Path;
+use zip::ZipArchive;
+
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+/// Office Open XML parts holding document properties
+const OFFICE_PARTS: [&str; 2] = ["docProps/core.xml", "docProps/app.xml"];
+/// Largest file metadata is read from
+const MAX_FILE_SIZE: u64 = 256 * 1024 * 1024;
+/// Largest PDF handed to the parser
+const MAX_PDF_SIZE: usize = 64 * 1024 * 1024;
+/// Largest docProps part or XMP packet parsed
+const MAX_XML_SIZE: usize = 1024 * 1024;
+/// Horizontal gap between an attachment's node and the nodes made from it
+const FINDING_OFFSET_X: f64 = 100.0;
+const FINDING_SPACING_Y: f64 = 260.0;
+
+pub struct MetadataService;
+
+impl MetadataService {
+    /// Metadata of any file
+    pub fn extract(path: &Path) -> MosaicResult<FileMetadata> {
+        let size = std::fs::metadata(path)?.len();
+        if size > MAX_FILE_SIZE {
+            return Err(MosaicError::invalid_format(format!(
+                "File too large to read metadata from ({} bytes)",
+                size
+            )));
+        }
+        let content = core::fs::read_bytes(path)?;
+        let mime_type = mime_guess::from_path(path)
+            .first_or_octet_stream()
+            .essence_str()
+            .to_string();
+
+        let mut metadata = FileMetadata {
+            mime_type,
+            ..Default::default()
+        };
+        if metadata.mime_type.starts_with("image/") {
+            Self::read_exif(&content, &mut metadata);
+        }
+        if content.starts_with(b"%PDF") {
+            Self::read_pdf(&content, &mut metadata);
+        }
+        if content.starts_with(b"PK\x03\x04") {
+            Self::read_office(&content, &mut metadata);
+        }
+        Self::read_xmp(&content, &mut metadata);
+
+        dedup(&mut metadata.authors);
+        dedup(&mut metadata.software);
+        Ok(metadata)
+    }
+
+    /// Metadata of an attachment imported into a canvas
+    pub fn extract_attachment(
+        canvas_path: &Path,
+        attachment_id: &str,
+    ) -> MosaicResult<(AttachmentRecord, FileMetadata)> {
+        let record = AttachmentService::manifest(canvas_path)?
+            .attachments
+            .into_iter()
+            .find(|a| a.id == attachment_id)
+            .ok_or_else(|| MosaicError::not_found(&format!("Attachment {}", attachment_id)))?;
+        let metadata = Self::extract(&canvas_path.join(&record.path))?;
+
+        Ok((record, metadata))
+    }
+
+    /// Add a `map` node for the GPS position and a `person` node per author,
+    /// linked from the attachment's node
+    ///
+    /// Findings already linked from that node are not added again; when
+    /// nothing is new, the canvas is left untouched.
+    pub fn materialize(
+        canvas_path: &Path,
+        attachment_id: &str,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<MetadataFindings> {
+        let (record, metadata) = Self::extract_attachment(canvas_path, attachment_id)?;
+        let workspace = WorkspaceService::load(canvas_path)?;
+        let source = workspace
+            .nodes
+            .iter()
+            .find(|n| n.id == record.node_id)
+            .ok_or_else(|| MosaicError::not_found(&format!("Node {}", record.node_id)))?;
+
+        let linked: Vec<&WorkspaceNode> = workspace
+            .edges
+            .iter()
+            .filter(|e| e.source == source.id)
+            .filter_map(|e| workspace.nodes.iter().find(|n| n.id == e.target))
+            .collect();
+        let is_linked = |node_type: &str, key: &str, value: &Value| {
+            linked
+                .iter()
+                .any(|n| n.node_type == node_type && n.data.get(key) == Some(value))
+        };
+
+        let file_name = Path::new(&record.original_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut findings: Vec<(&str, HashMap<String, Value>, &str)> = Vec::new();
+
+        if let Some(gps) = metadata.gps {
+            if !(is_linked("map", "latitude", &json!(gps.latitude))
+                && is_linked("map", "longitude", &json!(gps.longitude)))
+            {
+                let mut data = HashMap::from([
+                    ("title".to_string(), json!("Location")),
+                    ("latitude".to_string(), json!(gps.latitude)),
+                    ("longitude".to_string(), json!(gps.longitude)),
+                    ("zoom".to_string(), json!(15)),
+                    ("label".to_string(), json!(file_name)),
+                ]);
+                if let Some(altitude) = gps.altitude {
+                    data.insert("altitude".to_string(), json!(altitude));
+                }
+                findings.push(("map", data, "GPS location"));
+            }
+        }
+        for author in &metadata.authors {
+            if is_linked("person", "name", &json!(author)) {
+                continue;
+            }
+            let data = HashMap::from([
+                ("title".to_string(), json!(author)),
+                ("name".to_string(), json!(author)),
+                ("email".to_string(), json!("")),
+                ("phone".to_string(), json!("")),
+                ("aliases".to_string(), json!([])),
+            ]);
+            findings.push(("person", data, "author"));
+        }
+
+        let x = source.position.x + source.width.unwrap_or(300.0) + FINDING_OFFSET_X;
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        for (index, (node_type, data, label)) in findings.into_iter().enumerate() {
+            let position = Position {
+                x,
+                y: source.position.y + index as f64 * FINDING_SPACING_Y,
+            };
+            let node = WorkspaceNode::new(node_type, position, data);
+            edges.push(WorkspaceEdge::new(
+                &source.id,
+                &node.id,
+                Some(label.to_string()),
+            ));
+            nodes.push(node);
+        }
+
+        let revision = if nodes.is_empty() {
+            WorkspaceService::revision(canvas_path, expected_revision)?
+        } else {
+            WorkspaceService::batch_update(
+                canvas_path,
+                nodes.clone(),
+                vec![],
+                edges.clone(),
+                vec![],
+                expected_revision,
+            )?
+        };
+
+        Ok(MetadataFindings {
+            metadata,
+            nodes,
+            edges,
+            revision,
+        })
+    }
+
+    /// EXIF tags, GPS position, camera, artist and software
+    fn read_exif(content: &[u8], metadata: &mut FileMetadata) {
+        let exif = match exif::Reader::new().read_from_container(&mut Cursor::new(content)) {
+            Ok(exif) => exif,
+            // Most images simply carry no EXIF
+            Err(exif::Error::NotFound(_)) | Err(exif::Error::NotSupported(_)) => return,
+            Err(e) => {
+                metadata.warnings.push(format!("EXIF: {}", e));
+                return;
+            }
+        };
+
+        for field in exif.fields().filter(|f| f.ifd_num == In::PRIMARY) {
+            let value = match &field.value {
+                exif::Value::Ascii(_) => Self::exif_text(&exif, field.tag),
+                // Maker notes and other binary blobs
+                exif::Value::Undefined(bytes, _) if bytes.len() > 64 => None,
+                _ => Some(field.display_value().with_unit(&exif).to_string()),
+            };
+            if let Some(value) = value {
+                metadata.exif.insert(field.tag.to_string(), value);
+            }
+        }
+
+        metadata.gps = Self::exif_gps(&exif);
+        let camera: Vec<String> = [Tag::Make, Tag::Model]
+            .into_iter()
+            .filter_map(|tag| Self::exif_text(&exif, tag))
+            .collect();
+        if !camera.is_empty() {
+            metadata.camera = Some(camera.join(" "));
+        }
+        metadata.authors.extend(
+            Self::exif_text(&exif, Tag::Artist)
+                .map(|a| split_names(&a))
+                .unwrap_or_default(),
+        );
+        metadata
+            .software
+            .extend(Self::exif_text(&exif, Tag::Software));
+        metadata.created = metadata.created.take().or_else(|| {
+            Self::exif_text(&exif, Tag::DateTimeOriginal)
+                .or_else(|| Self::exif_text(&exif, Tag::DateTime))
+        });
+    }
+
+    fn exif_text(exif: &Exif, tag: Tag) -> Option<String> {
+        match &exif.get_field(tag, In::PRIMARY)?.value {
+            exif::Value::Ascii(parts) => parts
+                .first()
+                .map(|part| String::from_utf8_lossy(part).trim().to_string())
+                .filter(|text| !text.is_empty()),
+            _ => None,
+        }
+    }
+
+    /// Decimal position from degrees/minutes/seconds and N/S, E/W references
+    fn exif_gps(exif: &Exif) -> Option<GpsPoint> {
+        let coordinate = |tag: Tag, reference: Tag, negative: u8| {
+            let exif::Value::Rational(parts) = &exif.get_field(tag, In::PRIMARY)?.value else {
+                return None;
+            };
+            let [degrees, minutes, seconds] = parts.get(..3)? else {
+                return None;
+            };
+            let value = degrees.to_f64() + minutes.to_f64() / 60.0 + seconds.to_f64() / 3600.0;
+            let negated = Self::exif_text(exif, reference)
+                .is_some_and(|r| r.as_bytes().first() == Some(&negative));
+            Some(if negated { -value } else { value })
+        };
+
+        let latitude = coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, b'S')?;
+        let longitude = coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, b'W')?;
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return None;
+        }
+
+        let altitude = match exif
+            .get_field(Tag::GPSAltitude, In::PRIMARY)
+            .map(|f| &f.value)
+        {
+            Some(exif::Value::Rational(parts)) if !parts.is_empty() => {
+                let below_sea_level = exif
+                    .get_field(Tag::GPSAltitudeRef, In::PRIMARY)
+                    .and_then(|f| f.value.get_uint(0))
+                    == Some(1);
+                let meters = parts[0].to_f64();
+                Some(if below_sea_level { -meters } else { meters }).filter(|m| m.is_finite())
+            }
+            _ => None,
+        };
+
+        Some(GpsPoint {
+            latitude,
+            longitude,
+            altitude,
+        })
+    }
+
+    /// The Info dictionary of a PDF
+    fn read_pdf(content: &[u8], metadata: &mut FileMetadata) {
+        if content.len() > MAX_PDF_SIZE {
+            metadata
+                .warnings
+                .push("PDF: too large to parse for document info".to_string());
+            return;
+        }
+        // lopdf panics on some malformed files
+        let document = match std::panic::catch_unwind(|| lopdf::Document::load_mem(content)) {
+            Ok(Ok(document)) => document,
+            Ok(Err(e)) => {
+                metadata.warnings.push(format!("PDF: {}", e));
+                return;
+            }
+            Err(_) => {
+                metadata
+                    .warnings
+                    .push("PDF: malformed document".to_string());
+                return;
+            }
+        };
+        if document.is_encrypted() {
+            metadata
+                .warnings
+                .push("PDF is encrypted; document info may be unreadable".to_string());
+        }
+
+        let info = match document.trailer.get(b"Info") {
+            Ok(Object::Reference(id)) => document.get_dictionary(*id).ok(),
+            Ok(Object::Dictionary(info)) => Some(info),
+            _ => None,
+        };
+        for (key, value) in info.into_iter().flat_map(|info| info.iter()) {
+            let value = match value {
+                Object::String(bytes, _) => pdf_text(bytes),
+                Object::Name(name) => String::from_utf8_lossy(name).to_string(),
+                Object::Integer(number) => number.to_string(),
+                Object::Real(number) => number.to_string(),
+                Object::Boolean(flag) => flag.to_string(),
+                _ => continue,
+            };
+            if !value.trim().is_empty() {
+                metadata
+                    .document
+                    .insert(String::from_utf8_lossy(key).to_string(), value);
+            }
+        }
+
+        let field = |name: &str| metadata.document.get(name).cloned();
+        let authors = field("Author").map(|a| split_names(&a)).unwrap_or_default();
+        let software: Vec<String> = ["Creator", "Producer"]
+            .into_iter()
+            .filter_map(field)
+            .collect();
+        let created = field("CreationDate");
+
+        metadata.authors.extend(authors);
+        metadata.software.extend(software);
+        metadata.created = metadata.created.take().or(created);
+    }
+
+    /// docProps/core.xml and docProps/app.xml of DOCX, XLSX and PPTX files
+    fn read_office(content: &[u8], metadata: &mut FileMetadata) {
+        let Ok(mut archive) = ZipArchive::new(Cursor::new(content)) else {
+            return;
+        };
+
+        for part in OFFICE_PARTS {
+            let Ok(file) = archive.by_name(part) else {
+                continue;
+            };
+            if file.size() > MAX_XML_SIZE as u64 {
+                metadata.warnings.push(format!("{}: too large", part));
+                continue;
+            }
+            let mut xml = String::new();
+            if let Err(e) = file.take(MAX_XML_SIZE as u64).read_to_string(&mut xml) {
+                metadata.warnings.push(format!("{}: {}", part, e));
+                continue;
+            }
+            let document = match roxmltree::Document::parse(&xml) {
+                Ok(document) => document,
+                Err(e) => {
+                    metadata.warnings.push(format!("{}: {}", part, e));
+                    continue;
+                }
+            };
+
+            for node in document
+                .root_element()
+                .children()
+                .filter(|n| n.is_element())
+            {
+                if let Some(text) = node.text().map(str::trim).filter(|t| !t.is_empty()) {
+                    metadata
+                        .document
+                        .insert(node.tag_name().name().to_string(), text.to_string());
+                }
+            }
+        }
+
+        let field = |name: &str| metadata.document.get(name).cloned();
+        let authors: Vec<String> = ["creator", "lastModifiedBy"]
+            .into_iter()
+            .filter_map(field)
+            .flat_map(|a| split_names(&a))
+            .collect();
+        let software = field("Application").map(|app| match field("AppVersion") {
+            Some(version) => format!("{} {}", app, version),
+            None => app,
+        });
+        let created = field("created");
+
+        metadata.authors.extend(authors);
+        metadata.software.extend(software);
+        metadata.created = metadata.created.take().or(created);
+    }
+
+    /// Properties of an embedded XMP packet, in any kind of file
+    fn read_xmp(content: &[u8], metadata: &mut FileMetadata) {
+        const OPEN: &[u8] = b"<x:xmpmeta";
+        const CLOSE: &[u8] = b"</x:xmpmeta>";
+
+        let Some(start) = find(content, OPEN) else {
+            return;
+        };
+        let window = &content[start..content.len().min(start + MAX_XML_SIZE)];
+        let Some(length) = find(window, CLOSE) else {
+            return;
+        };
+        let packet = String::from_utf8_lossy(&content[start..start + length + CLOSE.len()]);
+        let document = match roxmltree::Document::parse(&packet) {
+            Ok(document) => document,
+            Err(e) => {
+                metadata.warnings.push(format!("XMP: {}", e));
+                return;
+            }
+        };
+
+        let mut properties: BTreeMap<String, String> = BTreeMap::new();
+        let mut add = |key: String, value: &str| {
+            properties
+                .entry(key)
+                .and_modify(|existing| {
+                    existing.push_str("; ");
+                    existing.push_str(value);
+                })
+                .or_insert_with(|| value.to_string());
+        };
+
+        for node in document.descendants().filter(|n| n.is_element()) {
+            // Simple properties may be written as attributes of rdf:Description
+            if node.tag_name().namespace() == Some(RDF_NS)
+                && node.tag_name().name() == "Description"
+            {
+                for attribute in node.attributes() {
+                    if let Some(namespace) = attribute.namespace().filter(|ns| *ns != RDF_NS) {
+                        add(
+                            qualified(&node, namespace, attribute.name()),
+                            attribute.value(),
+                        );
+                    }
+                }
+                continue;
+            }
+            if node.children().any(|c| c.is_element()) {
+                continue;
+            }
+            let Some(text) = node.text().map(str::trim).filter(|t| !t.is_empty()) else {
+                continue;
+            };
+            // Items of rdf:Seq/Bag/Alt belong to the property around them
+            let Some(property) = node
+                .ancestors()
+                .find(|a| a.is_element() && a.tag_name().namespace() != Some(RDF_NS))
+            else {
+                continue;
+            };
+            add(
+                qualified(
+                    &property,
+                    property.tag_name().namespace().unwrap_or_default(),
+                    property.tag_name().name(),
+                ),
+                text,
+            );
+        }
+
+        let field = |name: &str| properties.get(name).cloned();
+        let authors = field("dc:creator")
+            .map(|a| split_names(&a))
+            .unwrap_or_default();
+        let software: Vec<String> = ["xmp:CreatorTool", "pdf:Producer"]
+            .into_iter()
+            .filter_map(field)
+            .collect();
+        let created = field("xmp:CreateDate");
+
+        metadata.authors.extend(authors);
+        metadata.software.extend(software);
+        metadata.created = metadata.created.take().or(created);
+        metadata.xmp = properties;
+    }
+}
+
+/// `prefix:name` of an XML name, as declared in the document
+fn qualified(node: &roxmltree::Node, namespace: &str, name: &str) -> String {
+    match node.lookup_prefix(namespace) {
+        Some(prefix) => format!("{}:{}", prefix, name),
+        None => name.to_string(),
+    }
+}
+
+/// PDF text string: UTF-16BE with a byte order mark, else PDFDocEncoding
+/// (read as Latin-1, which it matches for printable characters)
+fn pdf_text(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&[0xFE, 0xFF]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// Names in a field that may list several, separated by semicolons
+fn split_names(names: &str) -> Vec<String> {
+    names
+        .split(';')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Remove repeated entries, keeping the first of each
+fn dedup(values: &mut Vec<String>) {
+    let mut seen = std::collections::HashSet::new();
+    values.retain(|value| seen.insert(value.to_lowercase()));
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exif::experimental::Writer;
+    use exif::{Field, Rational};
+
+    fn rationals(values: &[(u32, u32)]) -> exif::Value {
+        exif::Value::Rational(
+            values
+                .iter()
+                .map(|&(num, denom)| Rational { num, denom })
+                .collect(),
+        )
+    }
+
+    fn ascii(text: &str) -> exif::Value {
+        exif::Value::Ascii(vec![text.as_bytes().to_vec()])
+    }
+
+    fn exif_of(fields: &[Field]) -> Exif {
+        let mut writer = Writer::new();
+        for field in fields {
+            writer.push_field(field);
+        }
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        exif::Reader::new().read_raw(tiff.into_inner()).unwrap()
+    }
+
+    fn gps_field(tag: Tag, value: exif::Value) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value,
+        }
+    }
+
+    #[test]
+    fn test_exif_gps() {
+        let exif = exif_of(&[
+            gps_field(Tag::GPSLatitudeRef, ascii("S")),
+            gps_field(Tag::GPSLatitude, rationals(&[(33, 1), (51, 1), (36, 1)])),
+            gps_field(Tag::GPSLongitudeRef, ascii("E")),
+            gps_field(
+                Tag::GPSLongitude,
+                rationals(&[(151, 1), (12, 1), (3600, 100)]),
+            ),
+            gps_field(Tag::GPSAltitudeRef, exif::Value::Byte(vec![1])),
+            gps_field(Tag::GPSAltitude, rationals(&[(25, 2)])),
+        ]);
+        let gps = MetadataService::exif_gps(&exif).unwrap();
+        assert!((gps.latitude + 33.86).abs() < 1e-9);
+        assert!((gps.longitude - 151.21).abs() < 1e-9);
+        assert_eq!(gps.altitude, Some(-12.5));
+
+        // Out of range positions are dropped
+        let exif = exif_of(&[
+            gps_field(Tag::GPSLatitude, rationals(&[(91, 1), (0, 1), (0, 1)])),
+            gps_field(Tag::GPSLongitude, rationals(&[(10, 1), (0, 1), (0, 1)])),
+        ]);
+        assert!(MetadataService::exif_gps(&exif).is_none());
+    }
+
+    #[test]
+    fn test_pdf_text() {
+        assert_eq!(pdf_text(b"Jane Doe"), "Jane Doe");
+        assert_eq!(pdf_text(&[0x4A, 0xF6, 0x72, 0x67]), "J\u{f6}rg");
+        assert_eq!(
+            pdf_text(&[0xFE, 0xFF, 0x00, 0x4A, 0x00, 0xF6, 0x30, 0x42]),
+            "J\u{f6}\u{3042}"
+        );
+    }
+
+    #[test]
+    fn test_split_names() {
+        assert_eq!(
+            split_names(" Jane Doe; John Roe ;;"),
+            vec!["Jane Doe", "John Roe"]
+        );
+        assert!(split_names(" ; ").is_empty());
+    }
+
+    #[test]
+    fn test_read_xmp() {
+        let content = br#"binary<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+              <rdf:Description xmlns:dc="http://purl.org/dc/elements/1.1/"
+                  xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+                  xmp:CreatorTool="Editor 2" xmp:CreateDate="2024-01-02">
+                <dc:creator><rdf:Seq><rdf:li>Jane Doe</rdf:li><rdf:li>John Roe</rdf:li></rdf:Seq></dc:creator>
+              </rdf:Description>
+            </rdf:RDF>
+          </x:xmpmeta>trailer"#;
+        let mut metadata = FileMetadata::default();
+        MetadataService::read_xmp(content, &mut metadata);
+
+        assert_eq!(metadata.xmp["dc:creator"], "Jane Doe; John Roe");
+        assert_eq!(metadata.authors, vec!["Jane Doe", "John Roe"]);
+        assert_eq!(metadata.software, vec!["Editor 2"]);
+        assert_eq!(metadata.created.as_deref(), Some("2024-01-02"));
+        assert!(metadata.warnings.is_empty());
+
+        // A packet that does not close within the size limit is ignored
+        let mut unclosed = b"<x:xmpmeta>".to_vec();
+        unclosed.resize(MAX_XML_SIZE + 1, b' ');
+        unclosed.extend_from_slice(b"</x:xmpmeta>");
+        let mut metadata = FileMetadata::default();
+        MetadataService::read_xmp(&unclosed, &mut metadata);
+        assert!(metadata.xmp.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_pdf_is_a_warning() {
+        let mut metadata = FileMetadata::default();
+        MetadataService::read_pdf(b"%PDF-1.4\n1 0 obj <<", &mut metadata);
+        assert_eq!(metadata.warnings.len(), 1);
+        assert!(metadata.document.is_empty());
+    }
+}
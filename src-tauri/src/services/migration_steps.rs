@@ -0,0 +1,460 @@
+// Migration Steps
+//
+// Registry of schema migration steps for versioned files.
+// Each step rewrites the raw JSON of one file kind from one version to the next;
+// MigrationService chains them in order and handles backup and rollback.
+
+use crate::core::{self, MosaicError, MosaicResult};
+use crate::models::SchemaKind;
+use serde_json::{json, Map, Value};
+
+/// Version assumed for files written before schema versioning
+pub const LEGACY_VERSION: &str = "1.0.0";
+
+/// Information a step may need that is not contained in the file itself
+pub struct StepContext {
+    /// Timestamp used for all fields touched in this run (ISO 8601)
+    pub now: String,
+    /// Owning vault ID (canvas files only)
+    pub vault_id: Option<String>,
+    /// Display name to fall back to (vault or canvas folder name)
+    pub fallback_name: String,
+    /// Original v1 workspace.json, whose viewport and metadata block also
+    /// seed state.json and meta.json
+    pub legacy_workspace: Option<Value>,
+}
+
+/// A single version-to-version transformation
+pub struct MigrationStep {
+    pub kind: SchemaKind,
+    pub from: &'static str,
+    pub to: &'static str,
+    pub description: &'static str,
+    pub apply: fn(&mut Value, &StepContext) -> MosaicResult<()>,
+}
+
+/// All known steps, grouped by kind and ordered by version
+pub const STEPS: &[MigrationStep] = &[
+    MigrationStep {
+        kind: SchemaKind::Vault,
+        from: "1.0.0",
+        to: "2.0.0",
+        description: "Add vault id and description, convert epoch timestamps",
+        apply: vault_v1_to_v2,
+    },
+    MigrationStep {
+        kind: SchemaKind::CanvasMeta,
+        from: "1.0.0",
+        to: "2.0.0",
+        description: "Convert canvas.json and workspace metadata into .mosaic/meta.json",
+        apply: canvas_meta_v1_to_v2,
+    },
+    MigrationStep {
+        kind: SchemaKind::CanvasState,
+        from: "1.0.0",
+        to: "2.0.0",
+        description: "Move workspace viewport into state.json and fill in defaults",
+        apply: canvas_state_v1_to_v2,
+    },
+    MigrationStep {
+        kind: SchemaKind::Workspace,
+        from: "1.0.0",
+        to: "2.0.0",
+        description: "Rename camelCase workspace fields and split nodes into node files",
+        apply: workspace_v1_to_v2,
+    },
+];
+
+/// Latest schema version known for a file kind
+pub fn latest_version(kind: SchemaKind) -> &'static str {
+    STEPS
+        .iter()
+        .filter(|s| s.kind == kind)
+        .map(|s| s.to)
+        .max_by_key(|v| parse_version(v))
+        .unwrap_or(LEGACY_VERSION)
+}
+
+/// Read the schema version of a raw document (missing or empty means legacy)
+///
+/// The frontend keeps the workspace version in `metadata.version`.
+pub fn document_version(doc: &Value) -> String {
+    doc.pointer("/metadata/version")
+        .or_else(|| doc.get("version"))
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .unwrap_or(LEGACY_VERSION)
+        .to_string()
+}
+
+/// Steps needed to bring a document of `kind` from `version` to the latest version
+///
+/// Documents already at or beyond the latest version need no steps.
+pub fn pending_steps(kind: SchemaKind, version: &str) -> MosaicResult<Vec<&'static MigrationStep>> {
+    let latest = parse_version(latest_version(kind));
+    let mut current = version.to_string();
+    let mut steps = Vec::new();
+
+    while parse_version(&current) < latest {
+        let step = STEPS
+            .iter()
+            .find(|s| s.kind == kind && s.from == current)
+            .ok_or_else(|| {
+                MosaicError::migration_failed(format!(
+                    "No migration path for {:?} from version {}",
+                    kind, current
+                ))
+            })?;
+        steps.push(step);
+        current = step.to.to_string();
+    }
+
+    Ok(steps)
+}
+
+/// Whether a document of `kind` at `version` is behind the latest schema
+pub fn is_outdated(kind: SchemaKind, version: &str) -> bool {
+    parse_version(version) < parse_version(latest_version(kind))
+}
+
+/// Parse "major.minor.patch" into a comparable tuple (missing parts are 0)
+pub fn parse_version(version: &str) -> (u32, u32, u32) {
+    let mut parts = version
+        .trim()
+        .split('.')
+        .map(|p| p.parse::<u32>().unwrap_or(0));
+    (
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+    )
+}
+
+/// Borrow a document as a JSON object
+fn as_object(doc: &mut Value) -> MosaicResult<&mut Map<String, Value>> {
+    doc.as_object_mut()
+        .ok_or_else(|| MosaicError::invalid_format("Expected a JSON object"))
+}
+
+/// Insert a value only when the key is missing or null
+fn set_default(obj: &mut Map<String, Value>, key: &str, value: Value) {
+    if obj.get(key).is_none_or(Value::is_null) {
+        obj.insert(key.to_string(), value);
+    }
+}
+
+/// Move a value to a new key, keeping an existing value at the new key
+fn rename_key(obj: &mut Map<String, Value>, from: &str, to: &str) {
+    if let Some(value) = obj.remove(from) {
+        obj.entry(to.to_string()).or_insert(value);
+    }
+}
+
+/// Read a timestamp field as ISO 8601, converting v1 epoch values
+fn timestamp(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) => core::time::to_iso(s),
+        Value::Number(n) => core::time::to_iso(&n.to_string()),
+        _ => None,
+    }
+}
+
+/// The metadata block of the v1 workspace.json, if any
+fn legacy_metadata(ctx: &StepContext) -> Option<&Map<String, Value>> {
+    ctx.legacy_workspace.as_ref()?.get("metadata")?.as_object()
+}
+
+/// The v1 viewport: `metadata.viewport` as the frontend saves it, or a
+/// top-level `viewport` in older files
+fn legacy_viewport(ctx: &StepContext) -> Option<Value> {
+    let workspace = ctx.legacy_workspace.as_ref()?;
+    [
+        workspace.pointer("/metadata/viewport"),
+        workspace.get("viewport"),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|v| ["x", "y", "zoom"].iter().all(|k| v[*k].is_number()))
+    .cloned()
+}
+
+// ============================================================================
+// 1.0.0 -> 2.0.0
+// ============================================================================
+
+fn vault_v1_to_v2(doc: &mut Value, ctx: &StepContext) -> MosaicResult<()> {
+    let obj = as_object(doc)?;
+
+    set_default(obj, "id", json!(core::generate_uuid()));
+    set_default(obj, "name", json!(ctx.fallback_name));
+    set_default(obj, "description", json!(""));
+
+    let created_at = timestamp(obj.get("created_at")).unwrap_or_else(|| ctx.now.clone());
+    let updated_at = timestamp(obj.get("updated_at")).unwrap_or_else(|| ctx.now.clone());
+    obj.insert("created_at".to_string(), json!(created_at));
+    obj.insert("updated_at".to_string(), json!(updated_at));
+    obj.insert("version".to_string(), json!("2.0.0"));
+
+    Ok(())
+}
+
+fn canvas_meta_v1_to_v2(doc: &mut Value, ctx: &StepContext) -> MosaicResult<()> {
+    let obj = as_object(doc)?;
+
+    set_default(obj, "id", json!(core::generate_uuid()));
+    if let Some(vault_id) = &ctx.vault_id {
+        obj.insert("vault_id".to_string(), json!(vault_id));
+    }
+    set_default(obj, "vault_id", json!(core::generate_uuid()));
+
+    // canvas.json wins; the workspace metadata block fills the gaps
+    let metadata = legacy_metadata(ctx);
+    let from_metadata = |key: &str| metadata.and_then(|m| m.get(key)).cloned();
+
+    if let Some(name) = from_metadata("name").filter(Value::is_string) {
+        set_default(obj, "name", name);
+    }
+    set_default(obj, "name", json!(ctx.fallback_name));
+    if let Some(description) = from_metadata("description").filter(Value::is_string) {
+        set_default(obj, "description", description);
+    }
+    set_default(obj, "description", json!(""));
+    if let Some(tags) = from_metadata("tags").filter(Value::is_array) {
+        set_default(obj, "tags", tags);
+    }
+    set_default(obj, "tags", json!([]));
+
+    let created_at = timestamp(obj.get("created_at"))
+        .or_else(|| timestamp(from_metadata("createdAt").as_ref()))
+        .unwrap_or_else(|| ctx.now.clone());
+    let updated_at = timestamp(obj.get("updated_at"))
+        .or_else(|| timestamp(from_metadata("updatedAt").as_ref()))
+        .unwrap_or_else(|| ctx.now.clone());
+    obj.insert("created_at".to_string(), json!(created_at));
+    obj.insert("updated_at".to_string(), json!(updated_at));
+    obj.insert("version".to_string(), json!("2.0.0"));
+
+    Ok(())
+}
+
+fn canvas_state_v1_to_v2(doc: &mut Value, ctx: &StepContext) -> MosaicResult<()> {
+    let obj = as_object(doc)?;
+
+    // The v1 viewport lived in workspace.json
+    if let Some(viewport) = legacy_viewport(ctx) {
+        set_default(obj, "viewport", viewport);
+    }
+    set_default(obj, "viewport", json!({ "x": 0.0, "y": 0.0, "zoom": 1.0 }));
+    set_default(obj, "selected_nodes", json!([]));
+    set_default(obj, "selected_edges", json!([]));
+    set_default(obj, "canvas_mode", json!("select"));
+    set_default(obj, "updated_at", json!(ctx.now));
+    obj.insert("version".to_string(), json!("2.0.0"));
+
+    Ok(())
+}
+
+fn workspace_v1_to_v2(doc: &mut Value, _ctx: &StepContext) -> MosaicResult<()> {
+    let obj = as_object(doc)?;
+
+    // The frontend keeps nodes and edges as maps keyed by ID
+    for key in ["nodes", "edges"] {
+        if let Some(Value::Object(map)) = obj.get_mut(key) {
+            let values = std::mem::take(map).into_iter().map(|(_, v)| v).collect();
+            obj.insert(key.to_string(), Value::Array(values));
+        }
+    }
+
+    // The frontend reads name, timestamps, viewport and settings from the
+    // metadata block, so it moves forward whole
+    let viewport = obj.remove("viewport");
+    if let Some(metadata) = obj.get_mut("metadata").and_then(Value::as_object_mut) {
+        if let Some(viewport) = viewport {
+            set_default(metadata, "viewport", viewport);
+        }
+        metadata.insert("version".to_string(), json!("2.0.0"));
+    }
+    if let Some(settings) = obj.get("metadata").and_then(|m| m.get("settings")).cloned() {
+        set_default(obj, "settings", settings);
+    }
+
+    set_default(obj, "nodes", json!([]));
+    set_default(obj, "edges", json!([]));
+    set_default(obj, "settings", json!({}));
+
+    if let Some(settings) = obj.get_mut("settings").and_then(Value::as_object_mut) {
+        rename_key(settings, "gridSize", "grid_size");
+        rename_key(settings, "snapToGrid", "snap_to_grid");
+        rename_key(settings, "showMinimap", "show_minimap");
+        rename_key(settings, "autoSave", "auto_save");
+        rename_key(settings, "autoSaveInterval", "auto_save_interval");
+        rename_key(settings, "defaultNodeColor", "default_node_color");
+        rename_key(settings, "defaultEdgeColor", "default_edge_color");
+    }
+
+    if let Some(nodes) = obj.get_mut("nodes").and_then(Value::as_array_mut) {
+        // Group parents must come before their children
+        nodes.sort_by_key(|n| !n["parentId"].is_null() || !n["parent_id"].is_null());
+        for node in nodes.iter_mut().filter_map(Value::as_object_mut) {
+            rename_key(node, "zIndex", "z_index");
+            rename_key(node, "parentId", "parent_id");
+            set_default(node, "data", json!({}));
+        }
+    }
+
+    if let Some(edges) = obj.get_mut("edges").and_then(Value::as_array_mut) {
+        for edge in edges.iter_mut().filter_map(Value::as_object_mut) {
+            rename_key(edge, "sourceHandle", "source_handle");
+            rename_key(edge, "targetHandle", "target_handle");
+            rename_key(edge, "type", "edge_type");
+        }
+    }
+
+    obj.insert("version".to_string(), json!("2.0.0"));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("2.0.0"), (2, 0, 0));
+        assert_eq!(parse_version("1.2"), (1, 2, 0));
+        assert!(parse_version("1.10.0") > parse_version("1.9.0"));
+    }
+
+    #[test]
+    fn test_pending_steps() {
+        assert_eq!(
+            pending_steps(SchemaKind::Workspace, "1.0.0").unwrap().len(),
+            1
+        );
+        assert!(pending_steps(SchemaKind::Workspace, "2.0.0")
+            .unwrap()
+            .is_empty());
+        assert!(pending_steps(SchemaKind::Workspace, "1.5.0").is_err());
+    }
+
+    #[test]
+    fn test_workspace_v1_to_v2() {
+        let ctx = StepContext {
+            now: "2024-01-01T00:00:00+00:00".to_string(),
+            vault_id: None,
+            fallback_name: "Test".to_string(),
+            legacy_workspace: None,
+        };
+        let mut doc = json!({
+            "nodes": [{ "id": "n1", "zIndex": 3 }],
+            "edges": [{ "id": "e1", "type": "smoothstep" }],
+            "settings": { "gridSize": 10 }
+        });
+
+        workspace_v1_to_v2(&mut doc, &ctx).unwrap();
+
+        assert_eq!(doc["version"], "2.0.0");
+        assert_eq!(doc["settings"]["grid_size"], 10);
+        assert_eq!(doc["nodes"][0]["z_index"], 3);
+        assert_eq!(doc["edges"][0]["edge_type"], "smoothstep");
+    }
+    #[test]
+    fn test_document_version_of_frontend_workspace() {
+        let manifest: Value = serde_json::from_str(include_str!(
+            "../../tests/fixtures/frontend_workspace_v2.json"
+        ))
+        .unwrap();
+        let legacy: Value = serde_json::from_str(include_str!(
+            "../../tests/fixtures/frontend_workspace_v1.json"
+        ))
+        .unwrap();
+
+        assert_eq!(document_version(&manifest), "2.0.0");
+        assert!(!is_outdated(
+            SchemaKind::Workspace,
+            &document_version(&manifest)
+        ));
+        assert_eq!(document_version(&legacy), "1.0.0");
+    }
+
+    #[test]
+    fn test_workspace_v1_to_v2_frontend_fixture() {
+        let ctx = StepContext {
+            now: "2024-01-01T00:00:00+00:00".to_string(),
+            vault_id: None,
+            fallback_name: "Test".to_string(),
+            legacy_workspace: None,
+        };
+        let mut doc: Value = serde_json::from_str(include_str!(
+            "../../tests/fixtures/frontend_workspace_v1.json"
+        ))
+        .unwrap();
+
+        workspace_v1_to_v2(&mut doc, &ctx).unwrap();
+        let data: crate::models::WorkspaceData = serde_json::from_value(doc).unwrap();
+
+        assert_eq!(data.nodes.len(), 2);
+        let note = data.nodes.iter().find(|n| n.node_type == "note").unwrap();
+        assert_eq!(note.width, Some(280.0));
+        assert_eq!(note.data["title"], "Lure");
+        assert_eq!(data.edges[0].source_handle.as_deref(), Some("right-source"));
+        assert_eq!(data.edges[0].label.as_deref(), Some("mentions"));
+    }
+
+    #[test]
+    fn test_v1_metadata_survives() {
+        let legacy: Value = serde_json::from_str(include_str!(
+            "../../tests/fixtures/frontend_workspace_v1.json"
+        ))
+        .unwrap();
+        let ctx = StepContext {
+            now: "2024-01-01T00:00:00+00:00".to_string(),
+            vault_id: Some("vault-1".to_string()),
+            fallback_name: "Folder".to_string(),
+            legacy_workspace: Some(legacy.clone()),
+        };
+
+        let mut workspace = legacy.clone();
+        workspace_v1_to_v2(&mut workspace, &ctx).unwrap();
+        let metadata = &workspace["metadata"];
+        assert_eq!(metadata["version"], "2.0.0");
+        assert_eq!(metadata["name"], legacy["metadata"]["name"]);
+        assert_eq!(metadata["createdAt"], legacy["metadata"]["createdAt"]);
+        assert_eq!(metadata["viewport"], legacy["metadata"]["viewport"]);
+        assert_eq!(metadata["settings"], legacy["metadata"]["settings"]);
+        assert_eq!(metadata["tags"], legacy["metadata"]["tags"]);
+        assert_eq!(workspace["settings"]["grid_size"], 20);
+        assert_eq!(workspace["settings"]["snap_to_grid"], true);
+
+        let mut state = json!({});
+        canvas_state_v1_to_v2(&mut state, &ctx).unwrap();
+        assert_eq!(state["viewport"], legacy["metadata"]["viewport"]);
+
+        let mut meta = json!({ "id": "abc" });
+        canvas_meta_v1_to_v2(&mut meta, &ctx).unwrap();
+        assert_eq!(meta["name"], "Phishing campaign");
+        assert_eq!(meta["description"], "Lures sent to finance");
+        assert_eq!(meta["tags"], json!(["phishing", "finance"]));
+    }
+
+    #[test]
+    fn test_canvas_meta_v1_to_v2_keeps_timestamps() {
+        let ctx = StepContext {
+            now: "2024-01-01T00:00:00+00:00".to_string(),
+            vault_id: Some("vault-1".to_string()),
+            fallback_name: "Folder".to_string(),
+            legacy_workspace: Some(json!({
+                "metadata": { "description": "Phishing case", "createdAt": "1600000000000" }
+            })),
+        };
+        let mut doc = json!({ "id": "abc", "name": "Case", "updated_at": "1700000000000" });
+
+        canvas_meta_v1_to_v2(&mut doc, &ctx).unwrap();
+
+        assert_eq!(doc["vault_id"], "vault-1");
+        assert_eq!(doc["description"], "Phishing case");
+        assert_eq!(doc["created_at"], "2020-09-13T12:26:40+00:00");
+        assert_eq!(doc["updated_at"], "2023-11-14T22:13:20+00:00");
+    }
+}
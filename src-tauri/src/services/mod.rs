@@ -3,19 +3,68 @@
 // Business logic layer - clean separation from commands
 // All heavy computation and I/O operations happen here
 
+pub mod asset_service;
+pub mod attachment_service;
+pub mod audit_service;
+pub mod breach_service;
 pub mod canvas_service;
 pub mod config_service;
+pub mod encryption_service;
+pub mod export_service;
+pub mod folder_service;
+pub mod geoip_service;
+pub mod hash_service;
+pub mod hash_set_service;
 pub mod history_service;
+pub mod image_hash_service;
+pub mod image_service;
+pub mod indicator_service;
+pub mod metadata_service;
 pub mod migration_service;
+pub mod migration_steps;
+pub mod node_file_service;
+pub mod normalize_service;
+pub mod package_service;
+pub mod redaction_service;
+pub mod scan_service;
+pub mod secret_service;
 pub mod state_service;
+pub mod template_service;
+#[cfg(test)]
+pub(crate) mod testing;
+pub mod transform_service;
 pub mod vault_service;
+pub mod watcher_service;
 pub mod workspace_service;
 
 // Re-export services
+pub use asset_service::AssetService;
+pub use attachment_service::AttachmentService;
+pub use audit_service::AuditService;
+pub use breach_service::BreachService;
 pub use canvas_service::CanvasService;
 pub use config_service::ConfigService;
+pub use encryption_service::EncryptionService;
+pub use export_service::ExportService;
+pub use folder_service::FolderService;
+pub use geoip_service::GeoIpService;
+pub use hash_service::HashService;
+pub use hash_set_service::HashSetService;
 pub use history_service::HistoryService;
+pub use image_hash_service::ImageHashService;
+pub use image_service::ImageService;
+pub use indicator_service::IndicatorService;
+pub use metadata_service::MetadataService;
 pub use migration_service::MigrationService;
+pub use node_file_service::NodeFileService;
+pub use normalize_service::NormalizeService;
+pub use package_service::PackageService;
+pub use redaction_service::RedactionService;
+pub use scan_service::ScanService;
+pub use secret_service::SecretService;
 pub use state_service::StateService;
+pub use template_service::TemplateService;
+pub use transform_service::{TransformRegistry, TransformService};
 pub use vault_service::VaultService;
+pub use watcher_service::{WatcherService, WatcherState};
 pub use workspace_service::WorkspaceService;
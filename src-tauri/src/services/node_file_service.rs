@@ -0,0 +1,739 @@
+// Node File Service
+//
+// Reads and writes workspaces in the layout the frontend saves in real time
+// (src/lib/services/nodeFileService.ts and edgeFileService.ts):
+//
+//   workspace.json                    metadata plus { id, type } per node and { id } per edge
+//   nodes/<id>/data/content           the node's main text (note body, domain, hash, ...)
+//   nodes/<id>/data/properties.json   position, size, parent and the rest of the data
+//   edges/<id>/joined.json            source, target, handles, label, type and data
+//
+// The manifest has no room for the backend's revision counter, so that and
+// the content hash live in .mosaic/revision.json.
+
+use crate::core::{self, paths::CanvasPaths, MosaicError, MosaicResult};
+use crate::models::{CanvasMeta, Position, Tlp, WorkspaceData, WorkspaceEdge, WorkspaceNode};
+use crate::services::{NormalizeService, SecretService, WorkspaceService};
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/// Manifest version written by the frontend's real-time format
+pub const MANIFEST_VERSION: &str = "2.0.0";
+
+pub struct NodeFileService;
+
+/// workspace.json in the frontend's format
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    metadata: Option<Value>,
+    #[serde(default)]
+    nodes: Entries,
+    #[serde(default)]
+    edges: Entries,
+}
+
+/// A JSON object kept in file order; the frontend renders nodes in manifest
+/// order and needs group parents before their children
+#[derive(Default)]
+struct Entries(Vec<(String, Value)>);
+
+impl Serialize for Entries {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(k, v)| (k, v)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Entries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = Entries;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Entries, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+/// Contents of .mosaic/revision.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RevisionStamp {
+    revision: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_hash: Option<String>,
+}
+
+impl NodeFileService {
+    /// Whether a workspace.json is a manifest whose nodes live in node files
+    pub fn is_manifest(doc: &Value) -> bool {
+        let version = doc
+            .pointer("/metadata/version")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        version.starts_with("2.") && doc.get("nodes").is_none_or(Value::is_object)
+    }
+
+    /// Assemble a workspace from a manifest and the node and edge files
+    ///
+    /// Like the frontend, nodes without a properties file are skipped and
+    /// every folder under edges/ counts, listed in the manifest or not.
+    pub fn read(canvas_paths: &CanvasPaths) -> MosaicResult<WorkspaceData> {
+        let manifest: Manifest = core::read_json(&canvas_paths.workspace_json)?;
+        let mut data = WorkspaceData::new();
+        data.metadata = manifest.metadata;
+
+        for (id, entry) in &manifest.nodes.0 {
+            let node_type = entry.get("type").and_then(Value::as_str).unwrap_or("note");
+            if let Some(node) = Self::read_node(canvas_paths, id, node_type)? {
+                data.nodes.push(node);
+            }
+        }
+
+        let mut edge_dirs = core::list_subdirs(&canvas_paths.edges)?;
+        edge_dirs.sort();
+        for dir in edge_dirs {
+            if let Some(edge) = Self::read_edge(&dir)? {
+                data.edges.push(edge);
+            }
+        }
+
+        let stamp: RevisionStamp = if canvas_paths.revision_json.exists() {
+            core::read_json(&canvas_paths.revision_json)?
+        } else {
+            RevisionStamp::default()
+        };
+        data.revision = stamp.revision;
+        data.content_hash = stamp.content_hash;
+
+        Ok(data)
+    }
+
+    /// Load one node from nodes/<id>/data (None if it has no properties file)
+    pub fn read_node(
+        canvas_paths: &CanvasPaths,
+        node_id: &str,
+        node_type: &str,
+    ) -> MosaicResult<Option<WorkspaceNode>> {
+        let data_dir = Self::data_dir(canvas_paths, node_id)?;
+        let props_path = data_dir.join("properties.json");
+        if !props_path.exists() {
+            return Ok(None);
+        }
+
+        let props: Map<String, Value> = core::read_json(&props_path)?;
+        let content_path = data_dir.join("content");
+        let content = if content_path.exists() {
+            core::read_string(&content_path)?
+        } else {
+            String::new()
+        };
+
+        Ok(Some(Self::node_from_files(
+            node_id, node_type, &props, &content,
+        )))
+    }
+
+    /// Node from the parsed properties.json and content of its folder
+    fn node_from_files(
+        node_id: &str,
+        node_type: &str,
+        props: &Map<String, Value>,
+        content: &str,
+    ) -> WorkspaceNode {
+        let mut data: HashMap<String, Value> = props
+            .get("data")
+            .and_then(Value::as_object)
+            .map(|d| d.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default();
+        apply_content(node_type, &mut data, content);
+
+        let position = props
+            .get("position")
+            .and_then(|p| serde_json::from_value::<Position>(p.clone()).ok())
+            .unwrap_or_default();
+        let mut node = WorkspaceNode::new(node_type, position, data);
+        node.id = node_id.to_string();
+        node.width = props.get("width").and_then(Value::as_f64);
+        node.height = props.get("height").and_then(Value::as_f64);
+        node.z_index = props
+            .get("zIndex")
+            .and_then(Value::as_i64)
+            .map(|z| z as i32)
+            .unwrap_or(if node_type == "group" { -1 } else { 1 });
+        node.parent_id = props
+            .get("parentId")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        node.classification = props
+            .get("classification")
+            .and_then(|c| serde_json::from_value::<Tlp>(c.clone()).ok());
+
+        node
+    }
+
+    /// Write the manifest, every node and edge file and the revision stamp,
+    /// removing folders of nodes and edges no longer in the workspace
+    pub fn write(canvas_paths: &CanvasPaths, data: &WorkspaceData) -> MosaicResult<()> {
+        // Check every ID before anything is written
+        for id in data.nodes.iter().map(|n| &n.id) {
+            Self::entry_dir(&canvas_paths.nodes, id)?;
+        }
+        for id in data.edges.iter().map(|e| &e.id) {
+            Self::entry_dir(&canvas_paths.edges, id)?;
+        }
+
+        for node in &data.nodes {
+            Self::write_node(canvas_paths, node)?;
+        }
+        for edge in &data.edges {
+            Self::write_edge(canvas_paths, edge)?;
+        }
+
+        let node_ids: HashSet<&str> = data.nodes.iter().map(|n| n.id.as_str()).collect();
+        let edge_ids: HashSet<&str> = data.edges.iter().map(|e| e.id.as_str()).collect();
+        Self::remove_stale(&canvas_paths.nodes, &node_ids)?;
+        Self::remove_stale(&canvas_paths.edges, &edge_ids)?;
+
+        let manifest = Manifest {
+            metadata: Some(Self::metadata(canvas_paths, data.metadata.as_ref())),
+            nodes: Entries(
+                data.nodes
+                    .iter()
+                    .map(|n| (n.id.clone(), json!({ "id": n.id, "type": n.node_type })))
+                    .collect(),
+            ),
+            edges: Entries(
+                data.edges
+                    .iter()
+                    .map(|e| (e.id.clone(), json!({ "id": e.id })))
+                    .collect(),
+            ),
+        };
+        core::write_json(&canvas_paths.workspace_json, &manifest)?;
+
+        Self::write_revision(canvas_paths, data)
+    }
+
+    /// Read a file of the canvas for the frontend, decrypted if the vault is
+    /// encrypted; `None` if it does not exist
+    pub fn read_file(canvas_path: &Path, relative_path: &str) -> MosaicResult<Option<String>> {
+        let path = Self::canvas_file(canvas_path, relative_path)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+        core::read_string(&path).map(Some)
+    }
+
+    /// Write a file of the canvas for the frontend, encrypted if the vault is
+    ///
+    /// Node files go through `write_node_files`, which seals them.
+    pub fn write_file(canvas_path: &Path, relative_path: &str, content: &str) -> MosaicResult<()> {
+        let path = Self::canvas_file(canvas_path, relative_path)?;
+        if path.starts_with(canvas_path.join("nodes")) {
+            return Err(MosaicError::invalid_format(format!(
+                "Node files are written with write_node_files: {}",
+                relative_path
+            )));
+        }
+        WorkspaceService::with_lock(canvas_path, || core::write_string(&path, content))
+    }
+
+    /// Path of a file inside the canvas; absolute paths and `..` are rejected
+    fn canvas_file(canvas_path: &Path, relative_path: &str) -> MosaicResult<PathBuf> {
+        let relative = Path::new(relative_path);
+        let inside = relative.components().count() > 0
+            && relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)));
+        if !inside {
+            return Err(MosaicError::invalid_format(format!(
+                "Not a canvas file: {}",
+                relative_path
+            )));
+        }
+        Ok(canvas_path.join(relative))
+    }
+
+    /// Write only the revision stamp
+    pub fn write_revision(canvas_paths: &CanvasPaths, data: &WorkspaceData) -> MosaicResult<()> {
+        core::write_json(
+            &canvas_paths.revision_json,
+            &RevisionStamp {
+                revision: data.revision,
+                content_hash: data.content_hash.clone(),
+            },
+        )
+    }
+
+    /// Write nodes/<id>/data/content and properties.json
+    ///
+    /// Keys of an existing properties file the backend does not model
+    /// (extent, expandParent, ...) are kept.
+    pub fn write_node(canvas_paths: &CanvasPaths, node: &WorkspaceNode) -> MosaicResult<()> {
+        let data_dir = Self::data_dir(canvas_paths, &node.id)?;
+        let props_path = data_dir.join("properties.json");
+
+        let existing: Map<String, Value> = if props_path.exists() {
+            core::read_json(&props_path).unwrap_or_default()
+        } else {
+            Map::new()
+        };
+        let (content, props) = Self::node_files(node, existing);
+
+        core::write_string(&data_dir.join("content"), &content)?;
+        core::write_json(&props_path, &props)
+    }
+
+    /// Write a node's content and/or properties.json as the frontend sends
+    /// them, sealing sensitive fields and refreshing the normalized shadow
+    /// on the way
+    ///
+    /// The file not sent is read from disk to assemble the node and only
+    /// rewritten if sealing or normalizing changed it.
+    pub fn write_node_files(
+        canvas_path: &Path,
+        node_id: &str,
+        node_type: &str,
+        content: Option<&str>,
+        properties: Option<&str>,
+    ) -> MosaicResult<()> {
+        let canvas_paths = CanvasPaths::from_root(canvas_path);
+        let data_dir = Self::data_dir(&canvas_paths, node_id)?;
+        let props_path = data_dir.join("properties.json");
+        let content_path = data_dir.join("content");
+
+        WorkspaceService::with_lock(canvas_path, || {
+            let old_props: Map<String, Value> = match properties {
+                Some(props) => serde_json::from_str(props)?,
+                None if props_path.exists() => core::read_json(&props_path)?,
+                None => Map::new(),
+            };
+            let old_content = match content {
+                Some(content) => content.to_string(),
+                None if content_path.exists() => core::read_string(&content_path)?,
+                None => String::new(),
+            };
+
+            let mut node = Self::node_from_files(node_id, node_type, &old_props, &old_content);
+            SecretService::seal_node(canvas_path, &mut node)?;
+            NormalizeService::normalize_node(&mut node);
+            let (new_content, new_props) = Self::node_files(&node, old_props.clone());
+
+            if content.is_some() || new_content != old_content {
+                core::write_string(&content_path, &new_content)?;
+            }
+            // Without properties the node does not exist yet; the frontend
+            // sends them right after
+            if properties.is_some() || (props_path.exists() && new_props != old_props) {
+                core::write_json(&props_path, &new_props)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Content and properties.json of a node, merged into `props` so keys
+    /// the backend does not model survive
+    fn node_files(
+        node: &WorkspaceNode,
+        mut props: Map<String, Value>,
+    ) -> (String, Map<String, Value>) {
+        let mut data: Map<String, Value> = node
+            .data
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let content = extract_content(&node.node_type, &mut data);
+
+        props.insert("position".to_string(), json!(node.position));
+        props.insert("zIndex".to_string(), json!(node.z_index));
+        props.insert("data".to_string(), Value::Object(data));
+        set_optional(&mut props, "width", node.width.map(|w| json!(w)));
+        set_optional(&mut props, "height", node.height.map(|h| json!(h)));
+        set_optional(
+            &mut props,
+            "parentId",
+            node.parent_id.as_ref().map(|p| json!(p)),
+        );
+        set_optional(
+            &mut props,
+            "classification",
+            node.classification.map(|c| json!(c)),
+        );
+
+        (content, props)
+    }
+
+    /// Reshape a node's data the way a write and read back through its node
+    /// files would, so content hashes survive the round trip
+    pub fn canonicalize(node: &mut WorkspaceNode) {
+        let mut data: Map<String, Value> = node.data.drain().collect();
+        let content = extract_content(&node.node_type, &mut data);
+        node.data = data.into_iter().collect();
+        apply_content(&node.node_type, &mut node.data, &content);
+    }
+
+    fn read_edge(dir: &Path) -> MosaicResult<Option<WorkspaceEdge>> {
+        let joined = dir.join("joined.json");
+        let Some(id) = dir.file_name().and_then(|n| n.to_str()) else {
+            return Ok(None);
+        };
+        if !joined.exists() {
+            return Ok(None);
+        }
+
+        let doc: Value = core::read_json(&joined)?;
+        let text = |key: &str| doc.get(key).and_then(Value::as_str).map(str::to_string);
+        let (Some(source), Some(target)) = (text("source"), text("target")) else {
+            return Ok(None);
+        };
+
+        let mut edge = WorkspaceEdge::new(&source, &target, text("label"));
+        edge.id = id.to_string();
+        edge.source_handle = text("sourceHandle");
+        edge.target_handle = text("targetHandle");
+        if let Some(edge_type) = text("type") {
+            edge.edge_type = edge_type;
+        }
+        edge.animated = doc
+            .get("animated")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        if let Some(data) = doc.get("data").and_then(Value::as_object) {
+            edge.data = data.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        }
+
+        Ok(Some(edge))
+    }
+
+    /// Write edges/<id>/joined.json as a single line, like the frontend
+    fn write_edge(canvas_paths: &CanvasPaths, edge: &WorkspaceEdge) -> MosaicResult<()> {
+        let mut doc = Map::new();
+        doc.insert("source".to_string(), json!(edge.source));
+        doc.insert("target".to_string(), json!(edge.target));
+        set_optional(
+            &mut doc,
+            "sourceHandle",
+            edge.source_handle.as_ref().map(|h| json!(h)),
+        );
+        set_optional(
+            &mut doc,
+            "targetHandle",
+            edge.target_handle.as_ref().map(|h| json!(h)),
+        );
+        set_optional(&mut doc, "label", edge.label.as_ref().map(|l| json!(l)));
+        doc.insert("type".to_string(), json!(edge.edge_type));
+        doc.insert("animated".to_string(), json!(edge.animated));
+        doc.insert("data".to_string(), json!(edge.data));
+
+        let path = Self::entry_dir(&canvas_paths.edges, &edge.id)?.join("joined.json");
+        core::write_string(&path, &Value::Object(doc).to_string())
+    }
+
+    /// Remove node or edge folders whose ID is not in `keep`
+    fn remove_stale(dir: &Path, keep: &HashSet<&str>) -> MosaicResult<()> {
+        for sub in core::list_subdirs(dir)? {
+            let name = sub.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            if !keep.contains(name) {
+                core::remove_dir_all(&sub)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Manifest metadata: the workspace's own, or defaults from meta.json,
+    /// stamped with the current time and format version
+    fn metadata(canvas_paths: &CanvasPaths, metadata: Option<&Value>) -> Value {
+        let now = core::now_iso();
+        let mut metadata = metadata
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_else(|| {
+                let meta: Option<CanvasMeta> = core::read_json(&canvas_paths.meta_json).ok();
+                let mut defaults = Map::new();
+                defaults.insert(
+                    "name".to_string(),
+                    json!(meta.as_ref().map(|m| m.name.as_str()).unwrap_or("Untitled")),
+                );
+                defaults.insert(
+                    "description".to_string(),
+                    json!(meta.as_ref().map(|m| m.description.as_str()).unwrap_or("")),
+                );
+                defaults.insert("createdAt".to_string(), json!(now));
+                defaults.insert("viewport".to_string(), json!({ "x": 0, "y": 0, "zoom": 1 }));
+                defaults.insert("settings".to_string(), json!({}));
+                defaults
+            });
+
+        metadata.insert("updatedAt".to_string(), json!(now));
+        metadata.insert("version".to_string(), json!(MANIFEST_VERSION));
+        Value::Object(metadata)
+    }
+
+    fn data_dir(canvas_paths: &CanvasPaths, node_id: &str) -> MosaicResult<PathBuf> {
+        Ok(Self::entry_dir(&canvas_paths.nodes, node_id)?.join("data"))
+    }
+
+    /// Folder of a node or edge; the ID must be a single path segment
+    fn entry_dir(dir: &Path, id: &str) -> MosaicResult<PathBuf> {
+        let mut components = Path::new(id).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => Ok(dir.join(id)),
+            _ => Err(MosaicError::invalid_input(format!("Invalid ID: {}", id))),
+        }
+    }
+}
+
+/// Data fields a node type keeps in its content file instead of properties.json
+fn content_fields(node_type: &str) -> &'static [&'static str] {
+    match node_type {
+        "note" | "socialPost" => &["content"],
+        "code" => &["code"],
+        "image" => &["imageUrl", "imagePath"],
+        "link" | "iframe" => &["url"],
+        "timestamp" => &["datetime", "customTimestamp"],
+        "person" | "organization" | "router" => &["name"],
+        "domain" => &["domain"],
+        "hash" => &["hash"],
+        "credential" => &["username"],
+        "snapshot" => &["imageUrl", "sourceUrl"],
+        "group" | "annotation" => &["label"],
+        "map" => &["latitude", "longitude"],
+        "linkList" => &["links"],
+        "action" => &["action"],
+        _ => &[],
+    }
+}
+
+/// Remove a node's content fields from `data` and render them as its content file
+fn extract_content(node_type: &str, data: &mut Map<String, Value>) -> String {
+    let mut take = |key: &str| data.remove(key);
+    let text = |value: Option<Value>| {
+        value
+            .and_then(|v| v.as_str().map(str::to_string))
+            .filter(|s| !s.is_empty())
+    };
+
+    match node_type {
+        "image" => {
+            let (url, path) = (take("imageUrl"), take("imagePath"));
+            text(url).or(text(path)).unwrap_or_default()
+        }
+        "snapshot" => {
+            let (url, source) = (take("imageUrl"), take("sourceUrl"));
+            text(url).or(text(source)).unwrap_or_default()
+        }
+        "timestamp" => {
+            take("datetime");
+            text(take("customTimestamp")).unwrap_or_default()
+        }
+        "map" => {
+            let coordinate = |v: Option<Value>| v.and_then(|v| v.as_f64()).unwrap_or(0.0);
+            let (lat, lon) = (take("latitude"), take("longitude"));
+            format!("{},{}", coordinate(lat), coordinate(lon))
+        }
+        "linkList" => take("links")
+            .and_then(|v| v.as_array().cloned())
+            .unwrap_or_default()
+            .iter()
+            .map(|link| {
+                let field = |k: &str| link.get(k).and_then(Value::as_str).unwrap_or_default();
+                format!("{}|{}", field("title"), field("url"))
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => match content_fields(node_type).first() {
+            Some(key) => text(take(key)).unwrap_or_default(),
+            None => String::new(),
+        },
+    }
+}
+
+/// Put a content file's text back into the node's data fields
+fn apply_content(node_type: &str, data: &mut HashMap<String, Value>, content: &str) {
+    let mut set = |key: &str, value: Value| {
+        data.insert(key.to_string(), value);
+    };
+
+    match node_type {
+        "image" | "snapshot" => {
+            let fallback = if node_type == "image" {
+                "imagePath"
+            } else {
+                "sourceUrl"
+            };
+            if content.starts_with("asset://") || content.starts_with("http") {
+                set("imageUrl", json!(content));
+            } else if !content.is_empty() {
+                set(fallback, json!(content));
+            }
+        }
+        "timestamp" => {
+            if !content.is_empty() {
+                set("customTimestamp", json!(content));
+                set("datetime", json!(content));
+            }
+        }
+        "map" => {
+            let mut parts = content
+                .split(',')
+                .map(|p| p.trim().parse::<f64>().unwrap_or(0.0));
+            set("latitude", json!(parts.next().unwrap_or(0.0)));
+            set("longitude", json!(parts.next().unwrap_or(0.0)));
+        }
+        "linkList" => {
+            let links: Vec<Value> = content
+                .split('\n')
+                .filter(|line| !line.is_empty())
+                .map(|line| {
+                    let (title, url) = line.split_once('|').unwrap_or((line, ""));
+                    json!({ "title": title, "url": url })
+                })
+                .collect();
+            set("links", json!(links));
+        }
+        _ => {
+            if let Some(key) = content_fields(node_type).first() {
+                set(key, json!(content));
+            }
+        }
+    }
+}
+
+/// Insert `value` under `key`, or remove the key when there is none
+fn set_optional(obj: &mut Map<String, Value>, key: &str, value: Option<Value>) {
+    match value {
+        Some(value) => {
+            obj.insert(key.to_string(), value);
+        }
+        None => {
+            obj.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::testing::TempCanvas;
+
+    #[test]
+    fn test_content_round_trip() {
+        for (node_type, fields) in [
+            (
+                "note",
+                json!({ "content": "Seen on forum", "title": "Lead" }),
+            ),
+            (
+                "domain",
+                json!({ "domain": "example.com", "status": "active" }),
+            ),
+            ("map", json!({ "latitude": 52.5, "longitude": 13.4 })),
+            (
+                "linkList",
+                json!({ "links": [{ "title": "Repo", "url": "https://example.com" }] }),
+            ),
+        ] {
+            let original: Map<String, Value> = fields.as_object().unwrap().clone();
+            let mut props = original.clone();
+            let content = extract_content(node_type, &mut props);
+            assert!(content_fields(node_type)
+                .iter()
+                .all(|k| !props.contains_key(*k)));
+
+            let mut data: HashMap<String, Value> = props.into_iter().collect();
+            apply_content(node_type, &mut data, &content);
+            let restored: Map<String, Value> = data.into_iter().collect();
+            assert_eq!(restored, original, "{}", node_type);
+        }
+    }
+
+    #[test]
+    fn test_frontend_writes_are_normalized() {
+        let temp = TempCanvas::new();
+        let canvas = &temp.path;
+        let props = json!({
+            "position": { "x": 0, "y": 0 },
+            "data": { "ip": "10.1.2.3/8" }
+        });
+        let data_dir = NodeFileService::data_dir(&CanvasPaths::from_root(canvas), "d1").unwrap();
+        let shadow = || {
+            let written: Value = core::read_json(&data_dir.join("properties.json")).unwrap();
+            assert_eq!(written["data"]["ip"], "10.1.2.3/8");
+            written["data"]["normalized"].clone()
+        };
+
+        NodeFileService::write_node_files(canvas, "d1", "domain", None, Some(&props.to_string()))
+            .unwrap();
+        assert_eq!(shadow(), json!({ "ip": "10.0.0.0/8" }));
+
+        // A content-only save still refreshes the shadow in properties.json
+        NodeFileService::write_node_files(
+            canvas,
+            "d1",
+            "domain",
+            Some("Www.Example[.]co.uk."),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            shadow(),
+            json!({
+                "domain": "www.example.co.uk",
+                "registrableDomain": "example.co.uk",
+                "ip": "10.0.0.0/8"
+            })
+        );
+    }
+
+    #[test]
+    fn test_ids_stay_inside_the_canvas() {
+        let canvas_paths = CanvasPaths::from_root(&PathBuf::from("/vault/case"));
+        assert!(NodeFileService::data_dir(&canvas_paths, "n1").is_ok());
+        for id in ["", "..", "../../x", "a/b", "/etc"] {
+            let err = NodeFileService::data_dir(&canvas_paths, id).unwrap_err();
+            assert_eq!(err.code, core::error::ErrorCode::InvalidInput, "{:?}", id);
+        }
+
+        let err = NodeFileService::write_node_files(
+            Path::new("/vault/case"),
+            "../../x",
+            "note",
+            Some("text"),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, core::error::ErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn test_is_manifest() {
+        assert!(NodeFileService::is_manifest(&json!({
+            "metadata": { "version": "2.0.0" },
+            "nodes": { "n1": { "id": "n1", "type": "note" } }
+        })));
+        assert!(!NodeFileService::is_manifest(&json!({
+            "metadata": { "version": "1.0.0" },
+            "nodes": { "n1": { "id": "n1", "type": "note" } }
+        })));
+        assert!(!NodeFileService::is_manifest(
+            &json!({ "version": "2.0.0", "nodes": [] })
+        ));
+    }
+}
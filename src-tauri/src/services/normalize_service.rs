@@ -0,0 +1,119 @@
+// Normalize Service
+//
+// Keeps a `normalized` object in the data of entity nodes (domains, hashes,
+// routers, credentials, ...) holding the canonical form of each indicator
+// field, so search and dedup need not care how a value was typed
+
+use crate::core::normalize;
+use crate::models::{IndicatorKind, WorkspaceData, WorkspaceNode};
+use serde_json::{Map, Value};
+
+/// Node data key of the shadow object
+pub const NORMALIZED_FIELD: &str = "normalized";
+
+/// How a node data field is normalized
+#[derive(Clone, Copy)]
+enum FieldKind {
+    /// Domain or IP address
+    Host,
+    /// IP address or CIDR network
+    Ip,
+    Url,
+    Email,
+    Phone,
+    Hash,
+    Mac,
+}
+
+/// Indicator fields by node type
+const FIELDS: &[(&str, &str, FieldKind)] = &[
+    ("domain", "domain", FieldKind::Host),
+    ("domain", "ip", FieldKind::Ip),
+    ("hash", "hash", FieldKind::Hash),
+    ("router", "ipAddress", FieldKind::Ip),
+    ("router", "ip", FieldKind::Ip),
+    ("router", "macAddress", FieldKind::Mac),
+    ("router", "mac", FieldKind::Mac),
+    ("credential", "email", FieldKind::Email),
+    ("person", "email", FieldKind::Email),
+    ("person", "phone", FieldKind::Phone),
+    ("organization", "website", FieldKind::Url),
+    ("link", "url", FieldKind::Url),
+    ("snapshot", "url", FieldKind::Url),
+];
+
+pub struct NormalizeService;
+
+impl NormalizeService {
+    /// Recompute the shadow object of every entity node
+    ///
+    /// Runs after secrets are sealed, so sealed fields get no shadow.
+    pub fn normalize_workspace(data: &mut WorkspaceData) {
+        for node in &mut data.nodes {
+            Self::normalize_node(node);
+        }
+    }
+
+    pub fn normalize_node(node: &mut WorkspaceNode) {
+        let mut normalized = Map::new();
+
+        for (_, field, kind) in FIELDS.iter().filter(|(t, _, _)| *t == node.node_type) {
+            let Some(value) = node.data.get(*field).and_then(Value::as_str) else {
+                continue;
+            };
+            let canonical = match kind {
+                FieldKind::Host => normalize::ip(value).or_else(|| {
+                    let domain = normalize::domain(value)?;
+                    if let Some(registrable) = normalize::registrable_domain(&domain) {
+                        normalized.insert("registrableDomain".to_string(), registrable.into());
+                    }
+                    Some(domain)
+                }),
+                FieldKind::Ip if value.contains('/') => normalize::cidr(value),
+                FieldKind::Ip => normalize::ip(value),
+                FieldKind::Url => normalize::url(value),
+                FieldKind::Email => normalize::email(value),
+                FieldKind::Phone => normalize::phone(value),
+                FieldKind::Hash => normalize::hash(value),
+                FieldKind::Mac => normalize::mac(value),
+            };
+            if let Some(canonical) = canonical {
+                normalized.insert(field.to_string(), canonical.into());
+            }
+        }
+
+        if normalized.is_empty() {
+            node.data.remove(NORMALIZED_FIELD);
+        } else {
+            node.data
+                .insert(NORMALIZED_FIELD.to_string(), Value::Object(normalized));
+        }
+    }
+
+    /// Normalized form of a node data field, falling back to the value as
+    /// stored when the field has no shadow
+    pub fn value<'a>(node: &'a WorkspaceNode, key: &str) -> Option<&'a str> {
+        node.data
+            .get(NORMALIZED_FIELD)
+            .and_then(|shadow| shadow.get(key))
+            .or_else(|| node.data.get(key))
+            .and_then(Value::as_str)
+    }
+
+    /// Canonical form of a value of the given indicator kind
+    pub fn normalize(kind: IndicatorKind, value: &str) -> Option<String> {
+        match kind {
+            IndicatorKind::Ipv4 | IndicatorKind::Ipv6 => normalize::ip(value),
+            IndicatorKind::Domain => normalize::domain(value),
+            IndicatorKind::Url => normalize::url(value),
+            IndicatorKind::Email => normalize::email(value),
+            IndicatorKind::Phone => normalize::phone(value),
+            IndicatorKind::Md5
+            | IndicatorKind::Sha1
+            | IndicatorKind::Sha256
+            | IndicatorKind::Sha512 => normalize::hash(value),
+            IndicatorKind::Cve => Some(value.trim().to_uppercase()),
+            IndicatorKind::Bitcoin => Some(value.trim().to_string()),
+        }
+    }
+}
@@ -0,0 +1,506 @@
+// Package Service
+//
+// Builds signed evidence packages for handing a case to another party, and
+// checks received packages before they are opened. A package is a zip of
+// canvas exports, their attachments and the audit entries of each canvas,
+// with a hash manifest signed by the current user's Ed25519 key.
+
+use crate::core::{self, crypto, paths::VaultPaths, MosaicError, MosaicResult};
+use crate::models::{
+    AttachmentManifest, AuditQuery, CanvasExport, ExportOptions, PackageCanvas, PackageFile,
+    PackageManifest, PackageVerification, SigningIdentity, VaultMeta,
+};
+use crate::services::{
+    AttachmentService, AuditService, ExportService, RedactionService, SecretService,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+/// Signed hash manifest inside a package
+const MANIFEST: &str = "manifest.json";
+/// Base64 Ed25519 signature over manifest.json
+const SIGNATURE: &str = "manifest.sig";
+/// Signing key seed in the app data directory
+const SIGNING_KEY: &str = "signing.key";
+
+/// Most files a package may hold
+const MAX_ENTRIES: usize = 10_000;
+/// Most bytes a package may unpack to, in total
+const MAX_UNPACKED_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+pub struct PackageService;
+
+impl PackageService {
+    /// Public half of the current user's signing key, created on first use
+    pub fn identity(data_dir: &Path) -> MosaicResult<SigningIdentity> {
+        let key = Self::signing_key(data_dir)?;
+        Ok(Self::identity_of(&key.verifying_key()))
+    }
+
+    /// Bundle canvases of one vault into a signed package at `file_path`
+    ///
+    /// Every canvas goes through the regular export pipeline with `options`,
+    /// so secrets, classification limits and redaction apply as usual.
+    /// Attachments of nodes left out of an export are left out too.
+    pub fn export(
+        data_dir: &Path,
+        canvas_paths: &[PathBuf],
+        file_path: &Path,
+        options: &ExportOptions,
+    ) -> MosaicResult<PackageManifest> {
+        let Some(first) = canvas_paths.first() else {
+            return Err(MosaicError::invalid_format("No canvases selected"));
+        };
+        let vault_path = SecretService::vault_of(first)?;
+        for canvas_path in canvas_paths {
+            if SecretService::vault_of(canvas_path)? != vault_path {
+                return Err(MosaicError::invalid_format(
+                    "All canvases in a package must belong to the same vault",
+                ));
+            }
+        }
+        let vault_paths = VaultPaths::from_root(&vault_path);
+        let vault: VaultMeta = core::read_json(&vault_paths.vault_json)?;
+
+        let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+        let mut canvases = Vec::new();
+        for canvas_path in canvas_paths {
+            let export = ExportService::build(canvas_path, options)?;
+            let dir = format!("canvases/{}/", export.canvas.id);
+
+            let kept: HashSet<&String> = export.workspace.nodes.iter().map(|n| &n.id).collect();
+            let attachments: Vec<_> = AttachmentService::manifest(canvas_path)?
+                .attachments
+                .into_iter()
+                .filter(|record| kept.contains(&record.node_id))
+                .collect();
+            for record in &attachments {
+                let content = core::fs::read_bytes(&canvas_path.join(&record.path))?;
+                if format!("{:x}", Sha256::digest(&content)) != record.sha256 {
+                    return Err(MosaicError::invalid_format(format!(
+                        "Attachment changed since it was imported: {}",
+                        record.path
+                    )));
+                }
+                entries.push((format!("{}{}", dir, record.path), content));
+            }
+
+            entries.push((
+                format!("{}attachments.json", dir),
+                serde_json::to_vec_pretty(&AttachmentManifest { attachments })?,
+            ));
+            entries.push((
+                format!("{}audit.jsonl", dir),
+                Self::audit_entries(&vault_path, canvas_path, &export, options)?,
+            ));
+            entries.push((
+                format!("{}canvas.json", dir),
+                serde_json::to_vec_pretty(&export)?,
+            ));
+            canvases.push(PackageCanvas {
+                id: export.canvas.id.clone(),
+                name: export.canvas.name.clone(),
+                path: dir,
+            });
+        }
+
+        let key = Self::signing_key(data_dir)?;
+        let manifest = PackageManifest {
+            format: PackageManifest::FORMAT.to_string(),
+            created_at: core::now_iso(),
+            created_by: AuditService::actor(),
+            vault_id: vault.id,
+            vault_name: vault.name,
+            options: options.clone(),
+            canvases,
+            files: entries
+                .iter()
+                .map(|(path, content)| PackageFile {
+                    path: path.clone(),
+                    size: content.len() as u64,
+                    sha256: format!("{:x}", Sha256::digest(content)),
+                })
+                .collect(),
+            public_key: BASE64.encode(key.verifying_key().to_bytes()),
+        };
+        let manifest_bytes = serde_json::to_vec_pretty(&manifest)?;
+        let signature = BASE64.encode(key.sign(&manifest_bytes).to_bytes());
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let zip_options = SimpleFileOptions::default();
+        for (name, content) in [
+            (MANIFEST, manifest_bytes.as_slice()),
+            (SIGNATURE, signature.as_bytes()),
+        ]
+        .into_iter()
+        .chain(entries.iter().map(|(p, c)| (p.as_str(), c.as_slice())))
+        {
+            zip.start_file(name, zip_options)
+                .map_err(MosaicError::io_error)?;
+            zip.write_all(content)?;
+        }
+        let archive = zip.finish().map_err(MosaicError::io_error)?.into_inner();
+        core::fs::write_bytes(file_path, &archive)?;
+
+        Ok(manifest)
+    }
+
+    /// Audit entries of an exported canvas as JSON lines
+    ///
+    /// Entries about nodes the export left out are dropped, IDs of left-out
+    /// nodes and edges are removed from the rest, and the export's
+    /// redaction profile masks pattern matches in the details.
+    fn audit_entries(
+        vault_path: &Path,
+        canvas_path: &Path,
+        export: &CanvasExport,
+        options: &ExportOptions,
+    ) -> MosaicResult<Vec<u8>> {
+        let workspace = &export.workspace;
+        let nodes: HashSet<&str> = workspace.nodes.iter().map(|n| n.id.as_str()).collect();
+        let edges: HashSet<&str> = workspace.edges.iter().map(|e| e.id.as_str()).collect();
+        let profile = ExportService::redaction_profile(canvas_path, options)?;
+        let query = AuditQuery {
+            canvas: Some(export.canvas.id.clone()),
+            ..Default::default()
+        };
+
+        let mut lines = Vec::new();
+        for mut entry in AuditService::query(vault_path, &query)? {
+            if !Self::keep_exported_ids(&mut entry.details, &nodes, &edges) {
+                continue;
+            }
+            if let Some(profile) = &profile {
+                RedactionService::apply_to_value(profile, &mut entry.details)?;
+            }
+            serde_json::to_writer(&mut lines, &entry)?;
+            lines.push(b'\n');
+        }
+
+        Ok(lines)
+    }
+
+    /// Remove IDs of left-out nodes and edges from audit details
+    ///
+    /// Returns false when the entry was only about left-out nodes.
+    fn keep_exported_ids(
+        details: &mut Value,
+        nodes: &HashSet<&str>,
+        edges: &HashSet<&str>,
+    ) -> bool {
+        let Some(details) = details.as_object_mut() else {
+            return true;
+        };
+
+        let mut about_kept = true;
+        details.retain(|key, value| {
+            let kept = if key.ends_with("node_ids") || key.ends_with("node_id") {
+                nodes
+            } else if key.ends_with("edge_ids") {
+                edges
+            } else {
+                return true;
+            };
+
+            match value {
+                Value::String(id) => {
+                    let keep = kept.contains(id.as_str());
+                    about_kept &= keep;
+                    keep
+                }
+                Value::Array(ids) => {
+                    let before = ids.len();
+                    ids.retain(|id| id.as_str().is_some_and(|id| kept.contains(id)));
+                    if key == "node_ids" && before > 0 && ids.is_empty() {
+                        about_kept = false;
+                    }
+                    true
+                }
+                _ => true,
+            }
+        });
+
+        about_kept
+    }
+
+    /// Check a package's signature and every file's hash
+    ///
+    /// The signer must be one of `trusted_signers` (fingerprints or public
+    /// keys shared out of band), else the package is reported as coming
+    /// from an untrusted signer. Problems with the package itself are
+    /// reported in the result rather than as errors, so the caller can show
+    /// why it was rejected.
+    pub fn verify(
+        package_path: &Path,
+        trusted_signers: &[String],
+    ) -> MosaicResult<PackageVerification> {
+        let rejected = |error: String| PackageVerification {
+            error: Some(error),
+            ..Default::default()
+        };
+
+        let mut files = match Self::read_archive(&core::fs::read_bytes(package_path)?) {
+            Ok(files) => files,
+            Err(e) => return Ok(rejected(format!("Not a valid package: {}", e))),
+        };
+        let (Some(manifest_bytes), Some(signature)) =
+            (files.remove(MANIFEST), files.remove(SIGNATURE))
+        else {
+            return Ok(rejected("Package has no signed manifest".to_string()));
+        };
+
+        let manifest: PackageManifest = match serde_json::from_slice(&manifest_bytes) {
+            Ok(manifest) => manifest,
+            Err(e) => return Ok(rejected(format!("Unreadable manifest: {}", e))),
+        };
+        if manifest.format != PackageManifest::FORMAT {
+            return Ok(rejected(format!(
+                "Unsupported package format: {}",
+                manifest.format
+            )));
+        }
+        let Some(public_key) = Self::decode_public_key(&manifest.public_key) else {
+            return Ok(rejected("Invalid signer key".to_string()));
+        };
+
+        let signature_valid = BASE64
+            .decode(signature.trim_ascii())
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .is_some_and(|sig| public_key.verify_strict(&manifest_bytes, &sig).is_ok());
+
+        let mut modified = Vec::new();
+        let mut missing = Vec::new();
+        for file in &manifest.files {
+            match files.remove(&file.path) {
+                Some(content) => {
+                    if content.len() as u64 != file.size
+                        || format!("{:x}", Sha256::digest(&content)) != file.sha256
+                    {
+                        modified.push(file.path.clone());
+                    }
+                }
+                None => missing.push(file.path.clone()),
+            }
+        }
+        let unexpected: Vec<String> = files.into_keys().collect();
+
+        let signer = Self::identity_of(&public_key);
+        let signer_trusted = trusted_signers.iter().any(|trusted| {
+            let trusted = trusted.trim();
+            trusted.eq_ignore_ascii_case(&signer.fingerprint) || trusted == signer.public_key
+        });
+
+        Ok(PackageVerification {
+            valid: signature_valid
+                && signer_trusted
+                && modified.is_empty()
+                && missing.is_empty()
+                && unexpected.is_empty(),
+            signature_valid,
+            signer_trusted,
+            error: (!signer_trusted).then(|| format!("Untrusted signer: {}", signer.fingerprint)),
+            signer: Some(signer),
+            manifest: Some(manifest),
+            modified,
+            missing,
+            unexpected,
+        })
+    }
+
+    /// Every file in a zip archive, by path
+    ///
+    /// Archives with too many entries, too much content (declared or
+    /// actual) or the same name twice are refused.
+    fn read_archive(bytes: &[u8]) -> MosaicResult<BTreeMap<String, Vec<u8>>> {
+        let mut archive = ZipArchive::new(Cursor::new(bytes))
+            .map_err(|e| MosaicError::invalid_format(e.to_string()))?;
+        // The zip reader keeps one entry per name, so duplicates only show
+        // up as extra headers in the central directory
+        let headers = Self::central_headers(bytes, archive.central_directory_start());
+        if headers > MAX_ENTRIES {
+            return Err(MosaicError::invalid_format(format!(
+                "More than {} files",
+                MAX_ENTRIES
+            )));
+        }
+        if headers != archive.len() {
+            return Err(MosaicError::invalid_format(
+                "Duplicate file names in package",
+            ));
+        }
+
+        let mut files = BTreeMap::new();
+        let mut remaining = MAX_UNPACKED_SIZE;
+        for index in 0..archive.len() {
+            let file = archive
+                .by_index(index)
+                .map_err(|e| MosaicError::invalid_format(e.to_string()))?;
+            if file.is_dir() {
+                continue;
+            }
+            if file.size() > remaining {
+                return Err(MosaicError::invalid_format("Package content too large"));
+            }
+
+            let name = file.name().to_string();
+            let mut content = Vec::new();
+            file.take(remaining + 1).read_to_end(&mut content)?;
+            if content.len() as u64 > remaining {
+                return Err(MosaicError::invalid_format("Package content too large"));
+            }
+            remaining -= content.len() as u64;
+            files.insert(name, content);
+        }
+
+        Ok(files)
+    }
+
+    /// Number of central directory headers starting at `start`, counting
+    /// at most one past `MAX_ENTRIES`
+    fn central_headers(bytes: &[u8], start: u64) -> usize {
+        const SIGNATURE: &[u8] = &[0x50, 0x4b, 0x01, 0x02];
+        const FIXED_LEN: usize = 46;
+
+        let mut offset = start as usize;
+        let mut count = 0;
+        while count <= MAX_ENTRIES {
+            let Some(header) = bytes.get(offset..offset + FIXED_LEN) else {
+                break;
+            };
+            if &header[..4] != SIGNATURE {
+                break;
+            }
+            let len = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]) as usize;
+            offset += FIXED_LEN + len(28) + len(30) + len(32);
+            count += 1;
+        }
+        count
+    }
+
+    /// Load the user's signing key, generating it on first use
+    fn signing_key(data_dir: &Path) -> MosaicResult<SigningKey> {
+        let path = data_dir.join(SIGNING_KEY);
+
+        if path.exists() {
+            let encoded = core::read_string(&path)?;
+            let bytes = BASE64
+                .decode(encoded.trim())
+                .map_err(|e| MosaicError::invalid_format(format!("Invalid signing key: {}", e)))?;
+            let seed = crypto::key_from_bytes(&bytes)?;
+            return Ok(SigningKey::from_bytes(&seed));
+        }
+
+        let seed = crypto::random_key();
+        core::write_string(&path, &BASE64.encode(seed.as_ref()))?;
+        Ok(SigningKey::from_bytes(&seed))
+    }
+
+    fn decode_public_key(encoded: &str) -> Option<VerifyingKey> {
+        let bytes: [u8; 32] = BASE64.decode(encoded).ok()?.try_into().ok()?;
+        VerifyingKey::from_bytes(&bytes).ok()
+    }
+
+    fn identity_of(key: &VerifyingKey) -> SigningIdentity {
+        SigningIdentity {
+            public_key: BASE64.encode(key.to_bytes()),
+            fingerprint: format!("{:x}", Sha256::digest(key.to_bytes())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AuditOperation, Position, Tlp, WorkspaceNode};
+    use crate::services::testing::TempVault;
+    use crate::services::WorkspaceService;
+    use serde_json::json;
+
+    fn zip_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_verify_requires_trusted_signer() {
+        let vault = TempVault::new();
+        let root = vault.outside();
+        let canvas_path = vault.create_canvas("Canvas");
+        let mut hidden = WorkspaceNode::new("note", Position::default(), Default::default());
+        hidden.classification = Some(Tlp::Red);
+        let hidden_id = hidden.id.clone();
+        WorkspaceService::add_node(&canvas_path, hidden, None).unwrap();
+        AuditService::log(
+            &canvas_path,
+            AuditOperation::NodeAdd,
+            json!({ "node_ids": [hidden_id] }),
+        )
+        .unwrap();
+
+        let options = ExportOptions {
+            max_classification: Some(Tlp::Amber),
+            classification_policy: crate::models::ClassificationPolicy::Filter,
+            ..Default::default()
+        };
+        let data_dir = root.join("data");
+        let package = root.join("case.zip");
+        let manifest = PackageService::export(
+            &data_dir,
+            std::slice::from_ref(&canvas_path),
+            &package,
+            &options,
+        )
+        .unwrap();
+        let audit = manifest
+            .files
+            .iter()
+            .find(|f| f.path.ends_with("audit.jsonl"))
+            .unwrap();
+        assert_eq!(audit.size, 0);
+
+        let untrusted = PackageService::verify(&package, &[]).unwrap();
+        assert!(untrusted.signature_valid && !untrusted.signer_trusted && !untrusted.valid);
+        assert!(untrusted.error.unwrap().starts_with("Untrusted signer"));
+
+        let fingerprint = PackageService::identity(&data_dir).unwrap().fingerprint;
+        let trusted = PackageService::verify(&package, &[fingerprint]).unwrap();
+        assert!(trusted.valid && trusted.error.is_none());
+    }
+
+    #[test]
+    fn test_read_archive_limits() {
+        let files = PackageService::read_archive(&zip_of(&[("a", b"1"), ("b", b"2")])).unwrap();
+        assert_eq!(files.len(), 2);
+
+        // The zip writer refuses duplicate names, so rename "b" to "a" in
+        // the local and central headers
+        let mut duplicate = zip_of(&[("a", b"1"), ("b", b"2")]);
+        let names: Vec<usize> = duplicate
+            .windows(4)
+            .enumerate()
+            .filter(|(_, w)| *w == [0x50, 0x4b, 0x03, 0x04] || *w == [0x50, 0x4b, 0x01, 0x02])
+            .map(|(i, w)| i + if w[2] == 0x03 { 30 } else { 46 })
+            .collect();
+        for at in names {
+            duplicate[at] = b'a';
+        }
+        let error = PackageService::read_archive(&duplicate).unwrap_err();
+        assert!(error.to_string().contains("Duplicate"));
+
+        let many: Vec<String> = (0..=MAX_ENTRIES).map(|i| i.to_string()).collect();
+        let many: Vec<(&str, &[u8])> = many.iter().map(|n| (n.as_str(), &b""[..])).collect();
+        assert!(PackageService::read_archive(&zip_of(&many)).is_err());
+    }
+}
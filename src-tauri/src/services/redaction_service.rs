@@ -0,0 +1,463 @@
+// Redaction Service
+//
+// Stores redaction profiles and applies them to workspace data and
+// rendered SVG before anything leaves the vault
+
+use crate::core::{self, paths::VaultPaths, MosaicError, MosaicResult};
+use crate::models::{
+    RedactionAction, RedactionEntry, RedactionProfile, RedactionReport, RedactionRule,
+    WorkspaceData,
+};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Placeholder written over redacted content
+pub const MASK: &str = "[REDACTED]";
+
+/// Text content between SVG tags
+fn svg_text() -> &'static Regex {
+    static SVG_TEXT: OnceLock<Regex> = OnceLock::new();
+    SVG_TEXT.get_or_init(|| Regex::new(r">([^<]+)<").unwrap())
+}
+
+pub struct RedactionService;
+
+impl RedactionService {
+    /// List redaction profiles in a vault, sorted by name
+    pub fn list(vault_path: &Path) -> MosaicResult<Vec<RedactionProfile>> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+
+        let mut profiles: Vec<RedactionProfile> = core::fs::list_dir(&vault_paths.redaction)?
+            .into_iter()
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|p| core::read_json(&p).ok())
+            .collect();
+
+        profiles.sort_by_key(|p| p.name.to_lowercase());
+
+        Ok(profiles)
+    }
+
+    /// Load a profile by ID
+    pub fn get(vault_path: &Path, profile_id: &str) -> MosaicResult<RedactionProfile> {
+        let path = Self::profile_path(vault_path, profile_id)?;
+
+        if !path.exists() {
+            return Err(MosaicError::not_found(&format!(
+                "Redaction profile {}",
+                profile_id
+            )));
+        }
+
+        core::read_json(&path)
+    }
+
+    /// The profile applied to exports that name none, if the vault has one
+    pub fn default_profile(vault_path: &Path) -> MosaicResult<Option<RedactionProfile>> {
+        Ok(Self::list(vault_path)?.into_iter().find(|p| p.is_default))
+    }
+
+    /// Create or update a profile (an empty ID creates a new one)
+    ///
+    /// Making a profile the default unsets the previous one.
+    pub fn save(
+        vault_path: &Path,
+        mut profile: RedactionProfile,
+    ) -> MosaicResult<RedactionProfile> {
+        Self::compile(&profile)?;
+
+        if profile.id.is_empty() {
+            profile = RedactionProfile {
+                description: profile.description,
+                is_default: profile.is_default,
+                ..RedactionProfile::new(core::generate_uuid(), profile.name, profile.rules)
+            };
+        } else {
+            profile.updated_at = core::now_iso();
+        }
+
+        if profile.is_default {
+            for mut other in Self::list(vault_path)? {
+                if other.is_default && other.id != profile.id {
+                    other.is_default = false;
+                    core::write_json(&Self::profile_path(vault_path, &other.id)?, &other)?;
+                }
+            }
+        }
+
+        let path = Self::profile_path(vault_path, &profile.id)?;
+        core::write_json(&path, &profile)?;
+
+        Ok(profile)
+    }
+
+    /// Delete a profile by ID
+    pub fn delete(vault_path: &Path, profile_id: &str) -> MosaicResult<()> {
+        let path = Self::profile_path(vault_path, profile_id)?;
+
+        if !path.exists() {
+            return Err(MosaicError::not_found(&format!(
+                "Redaction profile {}",
+                profile_id
+            )));
+        }
+
+        core::fs::remove_file(&path)
+    }
+
+    /// Redact a workspace in place, reporting what was removed or masked
+    ///
+    /// Edges of dropped nodes go with them; pattern rules without node types
+    /// or fields also mask edge labels.
+    pub fn apply(
+        profile: &RedactionProfile,
+        data: &mut WorkspaceData,
+    ) -> MosaicResult<RedactionReport> {
+        let rules = Self::compile(profile)?;
+        let mut report = RedactionReport {
+            profile_id: profile.id.clone(),
+            profile_name: profile.name.clone(),
+            ..Default::default()
+        };
+        let mut dropped = HashSet::new();
+
+        for (rule, pattern) in &rules {
+            for node in &mut data.nodes {
+                let applies =
+                    rule.node_types.is_empty() || rule.node_types.contains(&node.node_type);
+                if !applies || dropped.contains(&node.id) {
+                    continue;
+                }
+
+                // Node-level rule
+                if rule.fields.is_empty() && pattern.is_none() {
+                    if rule.action == RedactionAction::Drop {
+                        dropped.insert(node.id.clone());
+                        report.dropped_nodes.push(node.id.clone());
+                        continue;
+                    }
+                    for (field, value) in node.data.iter_mut() {
+                        if Self::mask_strings(value) {
+                            report.entries.push(RedactionEntry {
+                                node_id: node.id.clone(),
+                                node_type: node.node_type.clone(),
+                                field: field.clone(),
+                                action: rule.action,
+                                matches: 0,
+                            });
+                        }
+                    }
+                    continue;
+                }
+
+                let mut fields: Vec<String> = if rule.fields.is_empty() {
+                    node.data.keys().cloned().collect()
+                } else {
+                    rule.fields.clone()
+                };
+                fields.sort();
+
+                for field in fields {
+                    let Some(value) = node.data.get_mut(&field) else {
+                        continue;
+                    };
+                    let matches = match pattern {
+                        Some(re) => {
+                            let matches = Self::count_matches(value, re);
+                            if matches == 0 {
+                                continue;
+                            }
+                            if rule.action == RedactionAction::Mask {
+                                Self::mask_matches(value, re);
+                            }
+                            matches
+                        }
+                        None => {
+                            if rule.action == RedactionAction::Mask {
+                                *value = Value::from(MASK);
+                            }
+                            0
+                        }
+                    };
+                    if rule.action == RedactionAction::Drop {
+                        node.data.remove(&field);
+                    }
+
+                    report.entries.push(RedactionEntry {
+                        node_id: node.id.clone(),
+                        node_type: node.node_type.clone(),
+                        field,
+                        action: rule.action,
+                        matches,
+                    });
+                }
+            }
+
+            if let Some(re) = pattern {
+                if rule.node_types.is_empty() && rule.fields.is_empty() {
+                    for edge in &mut data.edges {
+                        let Some(label) = &edge.label else {
+                            continue;
+                        };
+                        if !re.is_match(label) {
+                            continue;
+                        }
+                        edge.label = match rule.action {
+                            RedactionAction::Mask => Some(re.replace_all(label, MASK).into_owned()),
+                            RedactionAction::Drop => None,
+                        };
+                        report.masked_edge_labels.push(edge.id.clone());
+                    }
+                }
+            }
+        }
+
+        report.dropped_edges = data.remove_nodes(&dropped);
+
+        Ok(report)
+    }
+
+    /// Mask pattern matches in the text content of rendered SVG
+    ///
+    /// Only pattern rules apply: field and node-type rules need the
+    /// workspace, so images should be rendered from redacted data.
+    pub fn apply_to_svg(profile: &RedactionProfile, svg: &str) -> MosaicResult<String> {
+        let patterns: Vec<Regex> = Self::compile(profile)?
+            .into_iter()
+            .filter_map(|(_, pattern)| pattern)
+            .collect();
+        if patterns.is_empty() {
+            return Ok(svg.to_string());
+        }
+
+        Ok(svg_text()
+            .replace_all(svg, |captures: &regex::Captures| {
+                let text = patterns.iter().fold(captures[1].to_string(), |text, re| {
+                    re.replace_all(&text, MASK).into_owned()
+                });
+                format!(">{}<", text)
+            })
+            .into_owned())
+    }
+
+    /// Mask pattern matches in every string of a value
+    ///
+    /// Like `apply_to_svg`, only pattern rules apply.
+    pub fn apply_to_value(profile: &RedactionProfile, value: &mut Value) -> MosaicResult<()> {
+        for (_, pattern) in Self::compile(profile)? {
+            if let Some(re) = pattern {
+                Self::mask_matches(value, &re);
+            }
+        }
+        Ok(())
+    }
+
+    /// Rules paired with their compiled patterns
+    fn compile(profile: &RedactionProfile) -> MosaicResult<Vec<(&RedactionRule, Option<Regex>)>> {
+        profile
+            .rules
+            .iter()
+            .map(|rule| {
+                let pattern = rule
+                    .pattern
+                    .as_deref()
+                    .map(|p| {
+                        Regex::new(p).map_err(|e| {
+                            MosaicError::invalid_format(format!(
+                                "Invalid redaction pattern {}: {}",
+                                p, e
+                            ))
+                        })
+                    })
+                    .transpose()?;
+                Ok((rule, pattern))
+            })
+            .collect()
+    }
+
+    /// Replace every string inside a value with the mask
+    fn mask_strings(value: &mut Value) -> bool {
+        match value {
+            Value::String(s) => {
+                *s = MASK.to_string();
+                true
+            }
+            Value::Array(items) => items
+                .iter_mut()
+                .map(Self::mask_strings)
+                .fold(false, |acc, masked| acc | masked),
+            Value::Object(map) => map
+                .values_mut()
+                .map(Self::mask_strings)
+                .fold(false, |acc, masked| acc | masked),
+            _ => false,
+        }
+    }
+
+    fn count_matches(value: &Value, re: &Regex) -> usize {
+        match value {
+            Value::String(s) => re.find_iter(s).count(),
+            Value::Array(items) => items.iter().map(|v| Self::count_matches(v, re)).sum(),
+            Value::Object(map) => map.values().map(|v| Self::count_matches(v, re)).sum(),
+            _ => 0,
+        }
+    }
+
+    fn mask_matches(value: &mut Value, re: &Regex) {
+        match value {
+            Value::String(s) => *s = re.replace_all(s, MASK).into_owned(),
+            Value::Array(items) => items.iter_mut().for_each(|v| Self::mask_matches(v, re)),
+            Value::Object(map) => map.values_mut().for_each(|v| Self::mask_matches(v, re)),
+            _ => {}
+        }
+    }
+
+    /// Path of a profile file, rejecting IDs that would escape the redaction directory
+    fn profile_path(vault_path: &Path, profile_id: &str) -> MosaicResult<PathBuf> {
+        if profile_id.is_empty() || profile_id.contains(['/', '\\']) || profile_id.starts_with('.')
+        {
+            return Err(MosaicError::invalid_format(format!(
+                "Invalid redaction profile ID: {}",
+                profile_id
+            )));
+        }
+
+        let vault_paths = VaultPaths::from_root(vault_path);
+        Ok(vault_paths.redaction.join(format!("{}.json", profile_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Position, WorkspaceEdge, WorkspaceNode};
+    use std::collections::HashMap;
+
+    fn node(id: &str, node_type: &str, data: &[(&str, &str)]) -> WorkspaceNode {
+        WorkspaceNode {
+            id: id.to_string(),
+            node_type: node_type.to_string(),
+            position: Position::default(),
+            width: None,
+            height: None,
+            z_index: 1,
+            parent_id: None,
+            classification: None,
+            data: data
+                .iter()
+                .map(|(k, v)| (k.to_string(), Value::from(*v)))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn test_apply_masks_and_drops() {
+        let mut workspace = WorkspaceData::new();
+        workspace.add_node(node(
+            "n1",
+            "note",
+            &[("content", "mail bob@example.com now")],
+        ));
+        workspace.add_node(node(
+            "c1",
+            "credential",
+            &[("password", "hunter2"), ("username", "bob")],
+        ));
+        workspace.add_node(node("p1", "person", &[("name", "Bob")]));
+        workspace.add_edge(WorkspaceEdge {
+            label: Some("bob@example.com".to_string()),
+            ..serde_json::from_value(
+                serde_json::json!({"id": "e1", "source": "n1", "target": "p1"}),
+            )
+            .unwrap()
+        });
+
+        let profile = RedactionProfile::new(
+            "r".to_string(),
+            "External".to_string(),
+            vec![
+                RedactionRule {
+                    pattern: Some(r"[\w.]+@[\w.]+".to_string()),
+                    ..Default::default()
+                },
+                RedactionRule {
+                    node_types: vec!["credential".to_string()],
+                    fields: vec!["password".to_string()],
+                    action: RedactionAction::Drop,
+                    ..Default::default()
+                },
+                RedactionRule {
+                    node_types: vec!["person".to_string()],
+                    action: RedactionAction::Drop,
+                    ..Default::default()
+                },
+            ],
+        );
+        let report = RedactionService::apply(&profile, &mut workspace).unwrap();
+
+        assert_eq!(
+            workspace.nodes[0].data["content"],
+            Value::from("mail [REDACTED] now")
+        );
+        assert!(!workspace.nodes[1].data.contains_key("password"));
+        assert_eq!(workspace.nodes.len(), 2);
+        assert!(workspace.edges.is_empty());
+        assert_eq!(report.entries.len(), 2);
+        assert_eq!(report.dropped_nodes, vec!["p1"]);
+        assert_eq!(report.dropped_edges, vec!["e1"]);
+        assert_eq!(report.masked_edge_labels, vec!["e1"]);
+
+        let svg =
+            RedactionService::apply_to_svg(&profile, "<text x=\"1\">to a@b.io</text>").unwrap();
+        assert_eq!(svg, "<text x=\"1\">to [REDACTED]</text>");
+    }
+
+    #[test]
+    fn test_default_profile_applies_to_exports() {
+        use crate::models::ExportOptions;
+        use crate::services::testing::TempVault;
+        use crate::services::{ExportService, WorkspaceService};
+
+        let vault = TempVault::new();
+        let root = &vault.root;
+        let canvas_path = vault.create_canvas("Case");
+        let mut data = WorkspaceService::load(&canvas_path).unwrap();
+        data.add_node(node("n1", "note", &[("content", "call 555-0100")]));
+        WorkspaceService::save(&canvas_path, &data).unwrap();
+
+        let rules = vec![RedactionRule {
+            pattern: Some(r"\d{3}-\d{4}".to_string()),
+            ..Default::default()
+        }];
+        let first = RedactionService::save(
+            root,
+            RedactionProfile {
+                is_default: true,
+                ..RedactionProfile::new(String::new(), "First".to_string(), rules.clone())
+            },
+        )
+        .unwrap();
+        let second = RedactionService::save(
+            root,
+            RedactionProfile {
+                is_default: true,
+                ..RedactionProfile::new(String::new(), "Second".to_string(), rules)
+            },
+        )
+        .unwrap();
+
+        // Only the latest default stays the default
+        let default = RedactionService::default_profile(root).unwrap().unwrap();
+        assert_eq!(default.id, second.id);
+        assert!(!RedactionService::get(root, &first.id).unwrap().is_default);
+
+        let export = ExportService::build(&canvas_path, &ExportOptions::default()).unwrap();
+        assert_eq!(
+            export.workspace.nodes[0].data["content"],
+            Value::from("call [REDACTED]")
+        );
+    }
+}
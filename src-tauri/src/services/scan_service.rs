@@ -0,0 +1,743 @@
+// Scan Service
+//
+// Imports Nmap XML reports onto a canvas: a router node per host, domain
+// nodes for the names it resolved to and a group per subnet. Hosts already
+// on the canvas are updated in place rather than added again.
+
+use crate::core::{self, normalize, MosaicError, MosaicResult};
+use crate::models::{Position, ScanHost, ScanImport, WorkspaceEdge, WorkspaceNode};
+use crate::services::{NormalizeService, WorkspaceService};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Router node fields holding the address, then the MAC, of a host
+const IP_FIELDS: &[&str] = &["ipAddress", "ip"];
+const MAC_FIELDS: &[&str] = &["macAddress", "mac"];
+
+/// Edge from a hostname to the host it resolves to
+const RESOLVES_TO: &str = "resolves to";
+
+/// Size assumed for nodes without one, as when grouping in the editor
+const NODE_WIDTH: f64 = 200.0;
+const NODE_HEIGHT: f64 = 100.0;
+
+/// Group insets, matching groups made in the editor
+const GROUP_PADDING: f64 = 40.0;
+const GROUP_HEADER: f64 = 30.0;
+/// Gap between subnet groups and between them and existing content
+const GROUP_GAP: f64 = 100.0;
+
+/// Hosts per row of a subnet group
+const GROUP_COLUMNS: usize = 3;
+/// A host cell holds the router node with its domain nodes to the right
+const DOMAIN_GAP: f64 = 60.0;
+const CELL_WIDTH: f64 = NODE_WIDTH * 2.0 + DOMAIN_GAP;
+const CELL_GAP: f64 = 40.0;
+const DOMAIN_SPACING_Y: f64 = 130.0;
+
+/// A router node to add, with the domain nodes placed next to it
+struct NewHost {
+    router: WorkspaceNode,
+    domains: Vec<WorkspaceNode>,
+}
+
+pub struct ScanService;
+
+impl ScanService {
+    /// Hosts of an Nmap XML report (`nmap -oX`), sorted by address
+    ///
+    /// Only ports in the "open" state are kept. Hosts without an IP
+    /// address are skipped.
+    pub fn parse_nmap(xml: &str) -> MosaicResult<Vec<ScanHost>> {
+        // Nmap reports declare <!DOCTYPE nmaprun>
+        let options = roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        };
+        let document = roxmltree::Document::parse_with_options(xml, options)
+            .map_err(|e| MosaicError::invalid_format(format!("Invalid Nmap XML: {}", e)))?;
+        let root = document.root_element();
+        if !root.has_tag_name("nmaprun") {
+            return Err(MosaicError::invalid_format("Not an Nmap XML report"));
+        }
+
+        let mut hosts: Vec<ScanHost> = root
+            .children()
+            .filter(|n| n.has_tag_name("host"))
+            .filter_map(|host| Self::parse_host(&host))
+            .collect();
+        hosts.sort_by_key(|h| h.ip.parse::<IpAddr>().ok());
+
+        Ok(hosts)
+    }
+
+    /// Import an Nmap XML report onto a canvas
+    ///
+    /// Hosts are matched to router nodes by IP address, then MAC address,
+    /// then a hostname the router is named after. Names, domains and group
+    /// labels are read from the node files, where the editor keeps them.
+    /// A matched node gets the scan's status, the open ports are added to
+    /// those it lists (a scan may cover only some ports) and its address,
+    /// MAC and vendor are filled in where missing. Other hosts that are up
+    /// become router nodes in a group per /24 (IPv4) or /64 (IPv6) subnet,
+    /// reusing a group of that name left by an earlier import. Hostnames
+    /// become domain nodes unless the canvas has them already.
+    pub fn import_nmap(
+        canvas_path: &Path,
+        file_path: &Path,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<ScanImport> {
+        if !file_path.is_file() {
+            return Err(MosaicError::not_found(&file_path.to_string_lossy()));
+        }
+        let hosts = Self::parse_nmap(&core::fs::read_string(file_path)?)?;
+
+        let mut workspace = WorkspaceService::load(canvas_path)?;
+        let loaded_revision = workspace.revision;
+
+        let mut routers: HashMap<String, String> = HashMap::new();
+        let mut domains: HashMap<String, String> = HashMap::new();
+        for node in &workspace.nodes {
+            match node.node_type.as_str() {
+                "router" => {
+                    for key in router_keys(node) {
+                        routers.entry(key).or_insert_with(|| node.id.clone());
+                    }
+                }
+                "domain" => {
+                    if let Some(domain) =
+                        NormalizeService::value(node, "domain").and_then(normalize::domain)
+                    {
+                        domains.entry(domain).or_insert_with(|| node.id.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut updated_node_ids = Vec::new();
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut subnets: BTreeMap<String, Vec<NewHost>> = BTreeMap::new();
+        // Domain nodes placed beside routers already in a group
+        let mut group_children: HashMap<String, Vec<String>> = HashMap::new();
+        for host in &hosts {
+            let existing = routers
+                .get(&host.ip)
+                .or_else(|| {
+                    host.mac
+                        .as_ref()
+                        .and_then(|mac| routers.get(&format!("mac:{}", mac)))
+                })
+                .or_else(|| {
+                    host.hostnames
+                        .iter()
+                        .find_map(|name| routers.get(&format!("name:{}", name)))
+                });
+
+            match existing.cloned() {
+                Some(router_id) => {
+                    let Some(router) = workspace.nodes.iter_mut().find(|n| n.id == router_id)
+                    else {
+                        continue;
+                    };
+                    let fields = Self::router_updates(&router.data, host);
+                    if !fields.is_empty() {
+                        router.data.extend(fields);
+                        updated_node_ids.push(router_id.clone());
+                    }
+
+                    // Domain nodes go beside the router, in its group if any
+                    let router = router.clone();
+                    let added = Self::link_hostnames(
+                        host,
+                        &router.id,
+                        &workspace.edges,
+                        &mut domains,
+                        &mut edges,
+                    );
+                    for (i, mut domain) in added.into_iter().enumerate() {
+                        domain.parent_id = router.parent_id.clone();
+                        domain.position = Position {
+                            x: router.position.x + router.width.unwrap_or(NODE_WIDTH) + DOMAIN_GAP,
+                            y: router.position.y + i as f64 * DOMAIN_SPACING_Y,
+                        };
+                        if let Some(group_id) = &router.parent_id {
+                            group_children
+                                .entry(group_id.clone())
+                                .or_default()
+                                .push(domain.id.clone());
+                        }
+                        nodes.push(domain);
+                    }
+                }
+                // Hosts that were down are only worth a status update
+                None if host.status != "online" => {}
+                None => {
+                    let router = Self::router_node(host);
+                    routers.insert(host.ip.clone(), router.id.clone());
+                    if let Some(mac) = &host.mac {
+                        routers.insert(format!("mac:{}", mac), router.id.clone());
+                    }
+                    for name in &host.hostnames {
+                        routers.insert(format!("name:{}", name), router.id.clone());
+                    }
+
+                    let hostnames = Self::link_hostnames(
+                        host,
+                        &router.id,
+                        &workspace.edges,
+                        &mut domains,
+                        &mut edges,
+                    );
+                    subnets.entry(subnet(&host.ip)).or_default().push(NewHost {
+                        router,
+                        domains: hostnames,
+                    });
+                }
+            }
+        }
+
+        for (group_id, child_ids) in group_children {
+            let Some(group) = workspace.nodes.iter_mut().find(|n| n.id == group_id) else {
+                continue;
+            };
+            // Grow the group to hold the domain nodes beside its hosts
+            for child in nodes.iter().filter(|n| child_ids.contains(&n.id)) {
+                group.width = Some(
+                    group
+                        .width
+                        .unwrap_or(0.0)
+                        .max(child.position.x + NODE_WIDTH + GROUP_PADDING),
+                );
+                group.height = Some(
+                    group
+                        .height
+                        .unwrap_or(0.0)
+                        .max(child.position.y + NODE_HEIGHT + GROUP_PADDING),
+                );
+            }
+            add_children(group, &child_ids);
+            updated_node_ids.push(group_id);
+        }
+
+        // New groups go in a row below everything on the canvas
+        let top_level = workspace.nodes.iter().filter(|n| n.parent_id.is_none());
+        let mut next_group_x = top_level
+            .clone()
+            .map(|n| n.position.x)
+            .reduce(f64::min)
+            .unwrap_or(0.0);
+        let groups_y = top_level
+            .map(|n| n.position.y + n.height.unwrap_or(NODE_HEIGHT))
+            .reduce(f64::max)
+            .map_or(0.0, |bottom| bottom + GROUP_GAP);
+
+        for (cidr, new_hosts) in subnets {
+            let existing_group = workspace.nodes.iter().position(|n| {
+                n.node_type == "group"
+                    && n.data
+                        .get("label")
+                        .and_then(Value::as_str)
+                        .and_then(normalize::cidr)
+                        .as_ref()
+                        == Some(&cidr)
+            });
+
+            let (index, start_y) = match existing_group {
+                Some(index) => {
+                    let group_id = &workspace.nodes[index].id;
+                    // Below the hosts already in the group
+                    let start_y = workspace
+                        .nodes
+                        .iter()
+                        .filter(|n| n.parent_id.as_ref() == Some(group_id))
+                        .map(|n| n.position.y + n.height.unwrap_or(NODE_HEIGHT) + CELL_GAP)
+                        .reduce(f64::max)
+                        .unwrap_or(GROUP_PADDING + GROUP_HEADER);
+                    (index, start_y)
+                }
+                None => {
+                    let mut group = WorkspaceNode::new(
+                        "group",
+                        Position {
+                            x: next_group_x,
+                            y: groups_y,
+                        },
+                        HashMap::from([
+                            ("title".to_string(), json!(cidr)),
+                            ("label".to_string(), json!(cidr)),
+                            ("description".to_string(), json!("Hosts found by Nmap")),
+                            ("childNodeIds".to_string(), json!([])),
+                        ]),
+                    );
+                    group.z_index = -1;
+                    workspace.nodes.push(group);
+                    (workspace.nodes.len() - 1, GROUP_PADDING + GROUP_HEADER)
+                }
+            };
+
+            let group = &mut workspace.nodes[index];
+            let (children, width, height) = Self::layout(new_hosts, &group.id, start_y);
+            group.width = Some(group.width.unwrap_or(0.0).max(width));
+            group.height = Some(group.height.unwrap_or(0.0).max(height));
+            let child_ids: Vec<String> = children.iter().map(|n| n.id.clone()).collect();
+            add_children(group, &child_ids);
+
+            if existing_group.is_some() {
+                updated_node_ids.push(group.id.clone());
+            } else {
+                next_group_x += width + GROUP_GAP;
+                nodes.push(group.clone());
+            }
+            nodes.extend(children);
+        }
+
+        updated_node_ids.sort();
+        updated_node_ids.dedup();
+        let revision = if nodes.is_empty() && edges.is_empty() && updated_node_ids.is_empty() {
+            None
+        } else {
+            // Groups were added above; their hosts go after them
+            let known: Vec<String> = workspace.nodes.iter().map(|n| n.id.clone()).collect();
+            workspace
+                .nodes
+                .extend(nodes.iter().filter(|n| !known.contains(&n.id)).cloned());
+            workspace.edges.extend(edges.iter().cloned());
+
+            // Without an expected revision, still fail rather than overwrite
+            // a change saved while the report was being read
+            Some(WorkspaceService::save_checked(
+                canvas_path,
+                &workspace,
+                Some(expected_revision.unwrap_or(loaded_revision)),
+            )?)
+        };
+
+        Ok(ScanImport {
+            canvas: None,
+            hosts,
+            nodes,
+            edges,
+            updated_node_ids,
+            revision,
+        })
+    }
+
+    fn parse_host(host: &roxmltree::Node) -> Option<ScanHost> {
+        let status = match host
+            .children()
+            .find(|n| n.has_tag_name("status"))
+            .and_then(|n| n.attribute("state"))
+        {
+            Some("up") => "online",
+            Some("down") => "offline",
+            _ => "unknown",
+        };
+
+        let mut scan_host = ScanHost {
+            status: status.to_string(),
+            ..Default::default()
+        };
+        for address in host.children().filter(|n| n.has_tag_name("address")) {
+            let Some(addr) = address.attribute("addr") else {
+                continue;
+            };
+            match address.attribute("addrtype") {
+                Some("ipv4" | "ipv6") if scan_host.ip.is_empty() => {
+                    scan_host.ip = normalize::ip(addr).unwrap_or_default();
+                }
+                Some("mac") => {
+                    scan_host.mac = normalize::mac(addr);
+                    scan_host.vendor = address.attribute("vendor").map(str::to_string);
+                }
+                _ => {}
+            }
+        }
+        if scan_host.ip.is_empty() {
+            return None;
+        }
+
+        for name in host
+            .descendants()
+            .filter(|n| n.has_tag_name("hostname"))
+            .filter_map(|n| n.attribute("name"))
+            .filter_map(normalize::domain)
+        {
+            if !scan_host.hostnames.contains(&name) {
+                scan_host.hostnames.push(name);
+            }
+        }
+
+        scan_host.ports = host
+            .descendants()
+            .filter(|n| n.has_tag_name("port"))
+            .filter(|port| {
+                port.children()
+                    .any(|n| n.has_tag_name("state") && n.attribute("state") == Some("open"))
+            })
+            .filter_map(|port| port.attribute("portid")?.parse().ok())
+            .collect();
+        scan_host.ports.sort_unstable();
+        scan_host.ports.dedup();
+
+        Some(scan_host)
+    }
+
+    /// Data of a new router node for a host
+    fn router_node(host: &ScanHost) -> WorkspaceNode {
+        let name = host.hostnames.first().unwrap_or(&host.ip);
+        let mut data = HashMap::from([
+            ("title".to_string(), json!(name)),
+            ("name".to_string(), json!(name)),
+            ("ip".to_string(), json!(host.ip)),
+            ("status".to_string(), json!(host.status)),
+            ("ports".to_string(), json!(host.ports)),
+        ]);
+        if let Some(mac) = &host.mac {
+            data.insert("mac".to_string(), json!(mac));
+        }
+        if let Some(vendor) = &host.vendor {
+            data.insert("vendor".to_string(), json!(vendor));
+        }
+
+        WorkspaceNode::new("router", Position::default(), data)
+    }
+
+    /// Fields of a matched router node that the scan changes
+    fn router_updates(data: &HashMap<String, Value>, host: &ScanHost) -> HashMap<String, Value> {
+        let has = |fields: &[&str]| {
+            fields.iter().any(|field| {
+                data.get(*field)
+                    .and_then(Value::as_str)
+                    .is_some_and(|value| !value.trim().is_empty())
+            })
+        };
+
+        let mut ports: Vec<u16> = data
+            .get("ports")
+            .and_then(Value::as_array)
+            .map(|ports| {
+                ports
+                    .iter()
+                    .filter_map(|p| p.as_u64().and_then(|p| u16::try_from(p).ok()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        ports.extend(&host.ports);
+        ports.sort_unstable();
+        ports.dedup();
+
+        let mut fields = HashMap::from([
+            ("status".to_string(), json!(host.status)),
+            ("ports".to_string(), json!(ports)),
+        ]);
+        if !has(IP_FIELDS) {
+            fields.insert("ip".to_string(), json!(host.ip));
+        }
+        if let Some(mac) = host.mac.as_ref().filter(|_| !has(MAC_FIELDS)) {
+            fields.insert("mac".to_string(), json!(mac));
+        }
+        if let Some(vendor) = host
+            .vendor
+            .as_ref()
+            .filter(|_| !has(&["vendor", "manufacturer"]))
+        {
+            fields.insert("vendor".to_string(), json!(vendor));
+        }
+
+        fields.retain(|key, value| data.get(key) != Some(value));
+        fields
+    }
+
+    /// Link a host's hostnames to its router node, returning the domain
+    /// nodes to add for names not yet on the canvas
+    fn link_hostnames(
+        host: &ScanHost,
+        router_id: &str,
+        existing_edges: &[WorkspaceEdge],
+        domains: &mut HashMap<String, String>,
+        edges: &mut Vec<WorkspaceEdge>,
+    ) -> Vec<WorkspaceNode> {
+        let mut added = Vec::new();
+        for hostname in &host.hostnames {
+            let domain_id = match domains.get(hostname) {
+                Some(id) => id.clone(),
+                None => {
+                    let node = WorkspaceNode::new(
+                        "domain",
+                        Position::default(),
+                        HashMap::from([
+                            ("title".to_string(), json!(hostname)),
+                            ("domain".to_string(), json!(hostname)),
+                            ("ip".to_string(), json!(host.ip)),
+                            ("ipAddresses".to_string(), json!([host.ip])),
+                        ]),
+                    );
+                    domains.insert(hostname.clone(), node.id.clone());
+                    let id = node.id.clone();
+                    added.push(node);
+                    id
+                }
+            };
+
+            let linked = existing_edges.iter().chain(edges.iter()).any(|e| {
+                (e.source == domain_id && e.target == router_id)
+                    || (e.source == router_id && e.target == domain_id)
+            });
+            if !linked {
+                edges.push(WorkspaceEdge::new(
+                    &domain_id,
+                    router_id,
+                    Some(RESOLVES_TO.to_string()),
+                ));
+            }
+        }
+        added
+    }
+
+    /// Place new hosts in rows inside a group, starting `start_y` below its
+    /// top; returns the nodes with the width and height the group needs
+    fn layout(hosts: Vec<NewHost>, group_id: &str, start_y: f64) -> (Vec<WorkspaceNode>, f64, f64) {
+        let columns = hosts.len().clamp(1, GROUP_COLUMNS);
+        let width =
+            GROUP_PADDING * 2.0 + columns as f64 * CELL_WIDTH + (columns - 1) as f64 * CELL_GAP;
+
+        let mut nodes = Vec::new();
+        let mut row_y = start_y;
+        let mut row_height: f64 = 0.0;
+        for (i, host) in hosts.into_iter().enumerate() {
+            let column = i % GROUP_COLUMNS;
+            if column == 0 && i > 0 {
+                row_y += row_height + CELL_GAP;
+                row_height = 0.0;
+            }
+            let x = GROUP_PADDING + column as f64 * (CELL_WIDTH + CELL_GAP);
+
+            let mut router = host.router;
+            router.parent_id = Some(group_id.to_string());
+            router.position = Position { x, y: row_y };
+            nodes.push(router);
+
+            let domain_count = host.domains.len();
+            for (j, mut domain) in host.domains.into_iter().enumerate() {
+                domain.parent_id = Some(group_id.to_string());
+                domain.position = Position {
+                    x: x + NODE_WIDTH + DOMAIN_GAP,
+                    y: row_y + j as f64 * DOMAIN_SPACING_Y,
+                };
+                nodes.push(domain);
+            }
+
+            let cell_height =
+                NODE_HEIGHT + domain_count.saturating_sub(1) as f64 * DOMAIN_SPACING_Y;
+            row_height = row_height.max(cell_height);
+        }
+
+        (nodes, width, row_y + row_height + GROUP_PADDING)
+    }
+}
+
+/// Add nodes to a group's `childNodeIds`
+fn add_children(group: &mut WorkspaceNode, child_ids: &[String]) {
+    let mut child_node_ids = group
+        .data
+        .get("childNodeIds")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    child_node_ids.extend(child_ids.iter().map(|id| json!(id)));
+    group
+        .data
+        .insert("childNodeIds".to_string(), Value::Array(child_node_ids));
+}
+
+/// Lookup keys of a router node: its address, "mac:" plus its MAC and
+/// "name:" plus the hostname it is named after
+fn router_keys(node: &WorkspaceNode) -> Vec<String> {
+    // Routers may hold an address with a prefix ("10.0.0.1/24")
+    let ips = IP_FIELDS.iter().filter_map(|field| {
+        let value = node.data.get(*field)?.as_str()?;
+        normalize::ip(value.split('/').next()?)
+    });
+    let macs = MAC_FIELDS.iter().filter_map(|field| {
+        let mac = NormalizeService::value(node, field).and_then(normalize::mac)?;
+        Some(format!("mac:{}", mac))
+    });
+    // A router named after its address counts as having that address
+    let name = NormalizeService::value(node, "name").and_then(|name| {
+        normalize::ip(name).or_else(|| normalize::domain(name).map(|d| format!("name:{}", d)))
+    });
+    ips.chain(macs).chain(name).collect()
+}
+
+/// The /24 (IPv4) or /64 (IPv6) network of an address
+fn subnet(ip: &str) -> String {
+    let prefix = if ip.contains(':') { 64 } else { 24 };
+    normalize::cidr(&format!("{}/{}", ip, prefix)).unwrap_or_else(|| ip.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::CanvasPaths;
+    use crate::services::testing::TempVault;
+    use std::path::PathBuf;
+
+    const REPORT: &str = include_str!("../../tests/fixtures/nmap_scan.xml");
+
+    #[test]
+    fn test_parse_nmap() {
+        let hosts = ScanService::parse_nmap(REPORT).unwrap();
+
+        // The host without an IP address is skipped; the rest sort by address
+        let ips: Vec<&str> = hosts.iter().map(|h| h.ip.as_str()).collect();
+        assert_eq!(ips, ["10.0.0.3", "10.0.0.7", "10.0.0.20"]);
+
+        assert_eq!(hosts[0].status, "online");
+        assert_eq!(hosts[0].ports, [80]);
+        assert!(hosts[0].hostnames.is_empty());
+        assert_eq!(hosts[0].mac, None);
+
+        assert_eq!(hosts[1].status, "offline");
+        assert_eq!(hosts[1].hostnames, ["old.example.com"]);
+        assert!(hosts[1].ports.is_empty());
+
+        // Closed and filtered ports are dropped, names are normalized once
+        let files = &hosts[2];
+        assert_eq!(files.mac.as_deref(), Some("00:1a:2b:3c:4d:5e"));
+        assert_eq!(files.vendor.as_deref(), Some("Acme Networks"));
+        assert_eq!(files.hostnames, ["files.example.com"]);
+        assert_eq!(files.ports, [22, 445]);
+
+        assert!(ScanService::parse_nmap("<nmaprun").is_err());
+        assert!(ScanService::parse_nmap("<report/>").is_err());
+    }
+
+    fn setup() -> (TempVault, PathBuf, PathBuf) {
+        let vault = TempVault::new();
+        let canvas = vault.default_canvas();
+        let report = vault.outside().join("scan.xml");
+        std::fs::write(&report, REPORT).unwrap();
+        (vault, canvas, report)
+    }
+
+    /// Add a node the way the editor stores it, checking that `field` is
+    /// kept in the content file rather than properties.json
+    fn add(canvas: &Path, node: WorkspaceNode, field: &str) -> String {
+        let id = node.id.clone();
+        WorkspaceService::add_node(canvas, node, None).unwrap();
+        let props = CanvasPaths::from_root(canvas)
+            .nodes
+            .join(&id)
+            .join("data")
+            .join("properties.json");
+        let props: Value = core::read_json(&props).unwrap();
+        assert!(props["data"].get(field).is_none());
+        id
+    }
+
+    fn data(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_import_reuses_nodes_from_node_files() {
+        let (_vault, canvas, report) = setup();
+        let group_id = add(
+            &canvas,
+            WorkspaceNode::new(
+                "group",
+                Position::default(),
+                data(&[("label", json!("10.0.0.0/24")), ("childNodeIds", json!([]))]),
+            ),
+            "label",
+        );
+        // Named after its hostname, without an address
+        let mut router = WorkspaceNode::new(
+            "router",
+            Position { x: 40.0, y: 70.0 },
+            data(&[("name", json!("Files.example.com"))]),
+        );
+        router.parent_id = Some(group_id.clone());
+        let router_id = add(&canvas, router, "name");
+        let domain_id = add(
+            &canvas,
+            WorkspaceNode::new(
+                "domain",
+                Position::default(),
+                data(&[("domain", json!("FILES.example.com"))]),
+            ),
+            "domain",
+        );
+
+        let scan = ScanService::import_nmap(&canvas, &report, None).unwrap();
+        assert!(scan.updated_node_ids.contains(&router_id));
+        assert!(scan.updated_node_ids.contains(&group_id));
+        // Only the router for 10.0.0.3 is new, in the existing group
+        assert_eq!(scan.nodes.len(), 1);
+        assert_eq!(scan.nodes[0].parent_id.as_ref(), Some(&group_id));
+        assert_eq!(scan.edges.len(), 1);
+        assert_eq!(scan.edges[0].source, domain_id);
+        assert_eq!(scan.edges[0].target, router_id);
+
+        let workspace = WorkspaceService::load(&canvas).unwrap();
+        assert_eq!(workspace.nodes.len(), 4);
+        let router = workspace.nodes.iter().find(|n| n.id == router_id).unwrap();
+        assert_eq!(router.data["ip"], "10.0.0.20");
+        assert_eq!(router.data["ports"], json!([22, 445]));
+        let group = workspace.nodes.iter().find(|n| n.id == group_id).unwrap();
+        assert_eq!(group.data["childNodeIds"], json!([scan.nodes[0].id]));
+
+        // Importing again changes nothing
+        let again = ScanService::import_nmap(&canvas, &report, None).unwrap();
+        assert!(again.revision.is_none());
+        assert!(again.nodes.is_empty());
+    }
+
+    #[test]
+    fn test_new_domains_join_the_router_group() {
+        let (_vault, canvas, report) = setup();
+        let group_id = add(
+            &canvas,
+            WorkspaceNode::new(
+                "group",
+                Position::default(),
+                data(&[("label", json!("Servers")), ("childNodeIds", json!([]))]),
+            ),
+            "label",
+        );
+        let mut router = WorkspaceNode::new(
+            "router",
+            Position { x: 40.0, y: 70.0 },
+            data(&[("name", json!("nas")), ("ip", json!("10.0.0.20"))]),
+        );
+        router.parent_id = Some(group_id.clone());
+        router.width = Some(NODE_WIDTH);
+        add(&canvas, router, "name");
+
+        let scan = ScanService::import_nmap(&canvas, &report, None).unwrap();
+        let domain = scan.nodes.iter().find(|n| n.node_type == "domain").unwrap();
+        assert_eq!(domain.data["domain"], "files.example.com");
+        assert_eq!(domain.parent_id.as_ref(), Some(&group_id));
+
+        let workspace = WorkspaceService::load(&canvas).unwrap();
+        let group = workspace.nodes.iter().find(|n| n.id == group_id).unwrap();
+        assert_eq!(group.data["childNodeIds"], json!([domain.id]));
+        assert!(group.width.unwrap() >= domain.position.x + NODE_WIDTH);
+        // 10.0.0.3 is in a new group, since "Servers" is not its subnet
+        assert_eq!(
+            workspace
+                .nodes
+                .iter()
+                .filter(|n| n.node_type == "group")
+                .count(),
+            2
+        );
+    }
+}
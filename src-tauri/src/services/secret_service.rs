@@ -0,0 +1,540 @@
+// Secret Service
+//
+// Field-level encryption of sensitive node data (credential passwords and
+// any other configured fields) with a per-vault key kept in the app data
+// directory
+
+use crate::core::{self, crypto, paths::VaultPaths, MosaicError, MosaicResult};
+use crate::models::{SecretFieldConfig, VaultMeta, WorkspaceData, WorkspaceNode};
+use crate::services::WorkspaceService;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Prefix marking a sealed field value, bound to its node ID and field name
+pub const SECRET_PREFIX: &str = "mfsecret:v2:";
+
+/// Prefix of values sealed by older versions without associated data;
+/// still opened, and re-sealed the next time their node is saved
+const LEGACY_PREFIX: &str = "mfsecret:v1:";
+
+/// Directory of per-vault secrets keys inside the app data directory
+const SECRETS_DIR: &str = "secrets";
+
+pub struct SecretService;
+
+impl SecretService {
+    /// Whether a field value is sealed
+    pub fn is_sealed(value: &str) -> bool {
+        value.starts_with(SECRET_PREFIX) || value.starts_with(LEGACY_PREFIX)
+    }
+
+    /// Sensitive field configuration of a vault
+    pub fn config(vault_path: &Path) -> MosaicResult<SecretFieldConfig> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+
+        if !vault_paths.secrets_json.exists() {
+            return Ok(SecretFieldConfig::default());
+        }
+        core::read_json(&vault_paths.secrets_json)
+    }
+
+    /// Replace the sensitive field configuration of a vault
+    ///
+    /// Newly listed fields are sealed the next time each canvas is saved.
+    pub fn set_config(vault_path: &Path, config: &SecretFieldConfig) -> MosaicResult<()> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+        core::ensure_dir(&vault_paths.config)?;
+        core::write_json(&vault_paths.secrets_json, config)
+    }
+
+    /// Seal every plaintext sensitive field of a canvas's workspace
+    ///
+    /// Canvases outside a vault are left untouched.
+    pub fn seal_workspace(canvas_path: &Path, data: &mut WorkspaceData) -> MosaicResult<()> {
+        Self::seal_nodes(canvas_path, data.nodes.iter_mut())
+    }
+
+    /// Seal the plaintext sensitive fields of one node
+    pub fn seal_node(canvas_path: &Path, node: &mut WorkspaceNode) -> MosaicResult<()> {
+        Self::seal_nodes(canvas_path, std::iter::once(node))
+    }
+
+    fn seal_nodes<'a>(
+        canvas_path: &Path,
+        nodes: impl Iterator<Item = &'a mut WorkspaceNode>,
+    ) -> MosaicResult<()> {
+        let Some(vault_path) = core::paths::find_vault_root(canvas_path) else {
+            return Ok(());
+        };
+        let config = Self::config(&vault_path)?;
+
+        let mut key = None;
+        for node in nodes {
+            for field in config.fields_for(&node.node_type) {
+                let Some(Value::String(value)) = node.data.get_mut(field) else {
+                    continue;
+                };
+                if value.is_empty() || value.starts_with(SECRET_PREFIX) {
+                    continue;
+                }
+                let key = match &key {
+                    Some(key) => key,
+                    None => key.insert(Self::vault_key(&vault_path, true)?),
+                };
+                let plaintext = if Self::is_sealed(value) {
+                    Self::open(key, &node.id, field, value)?
+                } else {
+                    value.clone()
+                };
+                *value = Self::seal(key, &node.id, field, &plaintext)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the sealed values of the vault's sensitive fields with their
+    /// plaintext; anything else that happens to be sealed stays sealed
+    pub fn reveal_workspace(vault_path: &Path, data: &mut WorkspaceData) -> MosaicResult<()> {
+        let config = Self::config(vault_path)?;
+
+        let mut key = None;
+        for node in &mut data.nodes {
+            for field in config.fields_for(&node.node_type) {
+                let Some(Value::String(text)) = node.data.get_mut(field) else {
+                    continue;
+                };
+                if !Self::is_sealed(text) {
+                    continue;
+                }
+                let key = match &key {
+                    Some(key) => key,
+                    None => key.insert(Self::vault_key(vault_path, false)?),
+                };
+                *text = Self::open(key, &node.id, field, text)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace every sealed string anywhere in each node's `values` with
+    /// its plaintext
+    ///
+    /// For scanning node files for references, where it does not matter
+    /// which field a value came from. A value stored under an object key is
+    /// opened as that field; one stored on its own (a node's content file)
+    /// as whichever configured sensitive field it was sealed for.
+    pub fn reveal_values(vault_path: &Path, nodes: &mut [(String, Value)]) -> MosaicResult<()> {
+        let config = Self::config(vault_path)?;
+        let mut fields: Vec<&str> = config
+            .fields
+            .values()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        fields.sort_unstable();
+        fields.dedup();
+
+        let mut key = None;
+        for (node_id, value) in nodes {
+            Self::reveal_value(vault_path, node_id, &fields, value, &mut key)?;
+        }
+        Ok(())
+    }
+
+    fn reveal_value(
+        vault_path: &Path,
+        node_id: &str,
+        fields: &[&str],
+        value: &mut Value,
+        key: &mut Option<crypto::VaultKey>,
+    ) -> MosaicResult<()> {
+        match value {
+            Value::String(text) if Self::is_sealed(text) => {
+                let key = match key {
+                    Some(key) => key,
+                    None => key.insert(Self::vault_key(vault_path, false)?),
+                };
+                let opened = fields
+                    .iter()
+                    .find_map(|field| Self::open(key, node_id, field, text).ok());
+                *text = opened.ok_or_else(|| MosaicError::decryption_failed("secret field"))?;
+            }
+            Value::Array(items) => {
+                for item in items {
+                    Self::reveal_value(vault_path, node_id, fields, item, key)?;
+                }
+            }
+            Value::Object(map) => {
+                for (field, item) in map.iter_mut() {
+                    Self::reveal_value(vault_path, node_id, &[field.as_str()], item, key)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Plaintext of one node field (`None` if the field is absent)
+    pub fn reveal(canvas_path: &Path, node_id: &str, field: &str) -> MosaicResult<Option<String>> {
+        let workspace = WorkspaceService::load(canvas_path)?;
+        let node = workspace
+            .nodes
+            .iter()
+            .find(|n| n.id == node_id)
+            .ok_or_else(|| MosaicError::not_found(&format!("Node {}", node_id)))?;
+
+        match node.data.get(field) {
+            Some(Value::String(value)) if Self::is_sealed(value) => {
+                let key = Self::vault_key(&Self::vault_of(canvas_path)?, false)?;
+                Self::open(&key, node_id, field, value).map(Some)
+            }
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(MosaicError::invalid_format(format!(
+                "Field is not a string: {}",
+                field
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Re-seal a canvas copied from another vault under its new vault's key
+    ///
+    /// Every sealed value moves to the new key directly, whatever the
+    /// vaults' field configurations say, so nothing lands on disk in plaintext.
+    pub fn transfer(canvas_path: &Path, source_vault: &Path) -> MosaicResult<()> {
+        let target_vault = Self::vault_of(canvas_path)?;
+        if target_vault == source_vault {
+            return Ok(());
+        }
+
+        let mut workspace = WorkspaceService::load(canvas_path)?;
+        let mut keys = None;
+        for node in &mut workspace.nodes {
+            for (field, value) in node.data.iter_mut() {
+                let Value::String(text) = value else {
+                    continue;
+                };
+                if !Self::is_sealed(text) {
+                    continue;
+                }
+                let (source_key, target_key) = match &keys {
+                    Some(keys) => keys,
+                    None => keys.insert((
+                        Self::vault_key(source_vault, false)?,
+                        Self::vault_key(&target_vault, true)?,
+                    )),
+                };
+                let plaintext = Self::open(source_key, &node.id, field, text)?;
+                *text = Self::seal(target_key, &node.id, field, &plaintext)?;
+            }
+        }
+
+        if keys.is_some() {
+            WorkspaceService::save(canvas_path, &workspace)?;
+        }
+        Ok(())
+    }
+
+    /// Re-seal the values of nodes whose IDs were just regenerated, since
+    /// their associated data names the old ID
+    ///
+    /// `node_ids` maps old IDs to new ones, as `regenerate_ids` returns them.
+    pub fn rebind_nodes(
+        vault_path: &Path,
+        data: &mut WorkspaceData,
+        node_ids: &HashMap<String, String>,
+    ) -> MosaicResult<()> {
+        let old_ids: HashMap<&str, &str> = node_ids
+            .iter()
+            .map(|(old, new)| (new.as_str(), old.as_str()))
+            .collect();
+
+        let mut key = None;
+        for node in &mut data.nodes {
+            let Some(old_id) = old_ids.get(node.id.as_str()) else {
+                continue;
+            };
+            for (field, value) in node.data.iter_mut() {
+                let Value::String(text) = value else {
+                    continue;
+                };
+                if !Self::is_sealed(text) {
+                    continue;
+                }
+                let key = match &key {
+                    Some(key) => key,
+                    None => key.insert(Self::vault_key(vault_path, false)?),
+                };
+                let plaintext = Self::open(key, old_id, field, text)?;
+                *text = Self::seal(key, &node.id, field, &plaintext)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Root of the vault a canvas belongs to
+    pub fn vault_of(canvas_path: &Path) -> MosaicResult<PathBuf> {
+        core::paths::find_vault_root(canvas_path)
+            .ok_or_else(|| MosaicError::vault_not_found(&canvas_path.to_string_lossy()))
+    }
+
+    /// Load the vault's secrets key, generating it on first use if `create`
+    ///
+    /// A key an older version left in the vault is moved out on first use.
+    fn vault_key(vault_path: &Path, create: bool) -> MosaicResult<crypto::VaultKey> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+        if vault_paths.secrets_key.exists() && !Self::key_path(vault_path, SECRETS_DIR)?.exists() {
+            let key = Self::read_key(&vault_paths.secrets_key)?;
+            Self::write_key(&Self::key_path(vault_path, SECRETS_DIR)?, &key)?;
+            core::fs::remove_file(&vault_paths.secrets_key)?;
+            return Ok(key);
+        }
+
+        Self::app_key(vault_path, SECRETS_DIR, create)
+    }
+
+    /// Load a per-vault key kept in `<app data>/<dir>/<vault id>.key`,
+    /// generating it if missing and `create`
+    ///
+    /// Keys live outside the vault, so the vault folder alone never holds
+    /// what opens its secrets or forges its audit log.
+    pub fn app_key(vault_path: &Path, dir: &str, create: bool) -> MosaicResult<crypto::VaultKey> {
+        let key_path = Self::key_path(vault_path, dir)?;
+
+        if key_path.exists() {
+            return Self::read_key(&key_path);
+        }
+        if !create {
+            return Err(MosaicError::not_found(&key_path.to_string_lossy()));
+        }
+
+        let key = crypto::random_key();
+        Self::write_key(&key_path, &key)?;
+        Ok(key)
+    }
+
+    fn key_path(vault_path: &Path, dir: &str) -> MosaicResult<PathBuf> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+        let meta: VaultMeta = core::read_json(&vault_paths.vault_json)?;
+        Ok(core::paths::data_dir()?
+            .join(dir)
+            .join(format!("{}.key", meta.id)))
+    }
+
+    fn write_key(path: &Path, key: &crypto::VaultKey) -> MosaicResult<()> {
+        core::write_string(path, &BASE64.encode(key.as_ref()))
+    }
+
+    fn read_key(path: &Path) -> MosaicResult<crypto::VaultKey> {
+        let encoded = core::read_string(path)?;
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| MosaicError::invalid_format(format!("Invalid secrets key: {}", e)))?;
+        crypto::key_from_bytes(&bytes)
+    }
+
+    /// Associated data binding a sealed value to its node and field, so it
+    /// does not open when pasted into another one
+    fn aad(node_id: &str, field: &str) -> Vec<u8> {
+        serde_json::to_vec(&[node_id, field]).unwrap_or_default()
+    }
+
+    fn seal(
+        key: &crypto::VaultKey,
+        node_id: &str,
+        field: &str,
+        plaintext: &str,
+    ) -> MosaicResult<String> {
+        let sealed = crypto::encrypt(key, &Self::aad(node_id, field), plaintext.as_bytes())?;
+        Ok(format!("{}{}", SECRET_PREFIX, BASE64.encode(sealed)))
+    }
+
+    fn open(
+        key: &crypto::VaultKey,
+        node_id: &str,
+        field: &str,
+        value: &str,
+    ) -> MosaicResult<String> {
+        let (encoded, aad) = match value.strip_prefix(SECRET_PREFIX) {
+            Some(encoded) => (encoded, Self::aad(node_id, field)),
+            None => (
+                value.strip_prefix(LEGACY_PREFIX).unwrap_or(value),
+                Vec::new(),
+            ),
+        };
+        let sealed = BASE64
+            .decode(encoded)
+            .map_err(|_| MosaicError::decryption_failed("secret field"))?;
+        let plaintext = crypto::decrypt(key, &aad, &sealed)
+            .map_err(|_| MosaicError::decryption_failed("secret field"))?;
+        String::from_utf8(plaintext).map_err(|_| MosaicError::decryption_failed("secret field"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::ErrorCode;
+    use crate::core::paths::CanvasPaths;
+    use crate::models::Position;
+    use crate::services::testing::TempVault;
+    use crate::services::NodeFileService;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn credential(password: &str) -> WorkspaceNode {
+        let data = HashMap::from([
+            ("username".to_string(), Value::from("alice")),
+            ("password".to_string(), Value::from(password)),
+        ]);
+        WorkspaceNode::new("credential", Position { x: 0.0, y: 0.0 }, data)
+    }
+
+    #[test]
+    fn test_key_is_kept_out_of_the_vault() {
+        let vault = TempVault::new();
+        let root = &vault.root;
+        let vault_paths = &vault.paths;
+        let canvas = vault.create_canvas("Case");
+        let mut data = WorkspaceData::new();
+        data.nodes.push(credential("hunter2"));
+        SecretService::seal_workspace(&canvas, &mut data).unwrap();
+
+        assert!(SecretService::is_sealed(
+            data.nodes[0].data["password"].as_str().unwrap()
+        ));
+        assert!(!vault_paths.secrets_key.exists());
+
+        SecretService::reveal_workspace(root, &mut data).unwrap();
+        assert_eq!(data.nodes[0].data["password"], "hunter2");
+    }
+
+    #[test]
+    fn test_legacy_key_is_moved() {
+        let vault = TempVault::new();
+        let root = &vault.root;
+        let vault_paths = &vault.paths;
+        let key = crypto::random_key();
+        core::write_string(&vault_paths.secrets_key, &BASE64.encode(key.as_ref())).unwrap();
+
+        // Values sealed back then carry no associated data
+        let legacy = crypto::encrypt(&key, &[], b"hunter2").unwrap();
+        let mut data = WorkspaceData::new();
+        data.nodes.push(credential(&format!(
+            "{}{}",
+            LEGACY_PREFIX,
+            BASE64.encode(legacy)
+        )));
+        SecretService::reveal_workspace(root, &mut data).unwrap();
+
+        assert_eq!(data.nodes[0].data["password"], "hunter2");
+        assert!(!vault_paths.secrets_key.exists());
+    }
+
+    #[test]
+    fn test_legacy_values_are_resealed_for_their_node() {
+        let vault = TempVault::new();
+        let canvas = vault.create_canvas("Case");
+        let key = SecretService::vault_key(&vault.root, true).unwrap();
+        let legacy = crypto::encrypt(&key, &[], b"hunter2").unwrap();
+
+        let mut data = WorkspaceData::new();
+        data.nodes.push(credential(&format!(
+            "{}{}",
+            LEGACY_PREFIX,
+            BASE64.encode(legacy)
+        )));
+        SecretService::seal_workspace(&canvas, &mut data).unwrap();
+
+        let password = data.nodes[0].data["password"].as_str().unwrap();
+        assert!(password.starts_with(SECRET_PREFIX));
+        SecretService::reveal_workspace(&vault.root, &mut data).unwrap();
+        assert_eq!(data.nodes[0].data["password"], "hunter2");
+    }
+
+    #[test]
+    fn test_sealed_values_are_bound_to_node_and_field() {
+        let vault = TempVault::new();
+        let root = &vault.root;
+        let canvas = vault.create_canvas("Case");
+        let mut data = WorkspaceData::new();
+        data.nodes.push(credential("hunter2"));
+        data.nodes.push(credential("other"));
+        SecretService::seal_workspace(&canvas, &mut data).unwrap();
+        let sealed = data.nodes[0].data["password"].clone();
+
+        // Copied onto another node
+        let mut moved = data.clone();
+        moved.nodes[1]
+            .data
+            .insert("password".to_string(), sealed.clone());
+        assert_eq!(
+            SecretService::reveal_workspace(root, &mut moved)
+                .unwrap_err()
+                .code,
+            ErrorCode::DecryptionFailed
+        );
+
+        // Copied into another sensitive field of the same node
+        let mut config = SecretFieldConfig::default();
+        config.fields.insert(
+            "credential".to_string(),
+            vec!["password".into(), "apiKey".into()],
+        );
+        SecretService::set_config(root, &config).unwrap();
+        let mut moved = data.clone();
+        moved.nodes[0].data.insert("apiKey".to_string(), sealed);
+        assert_eq!(
+            SecretService::reveal_workspace(root, &mut moved)
+                .unwrap_err()
+                .code,
+            ErrorCode::DecryptionFailed
+        );
+
+        // Regenerated IDs are re-sealed
+        let node_ids = data.regenerate_ids();
+        SecretService::rebind_nodes(root, &mut data, &node_ids).unwrap();
+        SecretService::reveal_workspace(root, &mut data).unwrap();
+        assert_eq!(data.nodes[0].data["password"], "hunter2");
+    }
+
+    #[test]
+    fn test_reveal_only_configured_fields() {
+        let vault = TempVault::new();
+        let root = &vault.root;
+        let canvas = vault.create_canvas("Case");
+        let mut data = WorkspaceData::new();
+        data.nodes.push(credential("hunter2"));
+        SecretService::seal_workspace(&canvas, &mut data).unwrap();
+
+        // A sealed value pasted into a field that is not sensitive stays sealed
+        let sealed = data.nodes[0].data["password"].clone();
+        data.nodes[0]
+            .data
+            .insert("username".to_string(), sealed.clone());
+        SecretService::reveal_workspace(root, &mut data).unwrap();
+
+        assert_eq!(data.nodes[0].data["password"], "hunter2");
+        assert_eq!(data.nodes[0].data["username"], sealed);
+    }
+
+    #[test]
+    fn test_frontend_node_writes_are_sealed() {
+        let vault = TempVault::new();
+        let canvas = vault.create_canvas("Case");
+        let node = credential("hunter2");
+        let properties = json!({ "position": node.position, "data": node.data }).to_string();
+        NodeFileService::write_node_files(&canvas, &node.id, "credential", None, Some(&properties))
+            .unwrap();
+
+        let canvas_paths = CanvasPaths::from_root(&canvas);
+        let stored = NodeFileService::read_node(&canvas_paths, &node.id, "credential")
+            .unwrap()
+            .unwrap();
+        assert!(SecretService::is_sealed(
+            stored.data["password"].as_str().unwrap()
+        ));
+    }
+}
@@ -0,0 +1,226 @@
+// Template Service
+//
+// Saves canvases as reusable templates and instantiates them
+
+use crate::core::{self, paths::VaultPaths, MosaicError, MosaicResult};
+use crate::models::{CanvasMeta, CanvasTemplate, TemplateInfo, WorkspaceData};
+use crate::services::WorkspaceService;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Matches `{{ variable }}` placeholders
+fn placeholder() -> &'static Regex {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER.get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").unwrap())
+}
+
+pub struct TemplateService;
+
+impl TemplateService {
+    /// Save a canvas's workspace, settings, tags and description as a template
+    pub fn save_from_canvas(
+        vault_path: &Path,
+        canvas_path: &Path,
+        name: &str,
+        description: Option<&str>,
+    ) -> MosaicResult<TemplateInfo> {
+        let canvas_paths = core::CanvasPaths::from_root(canvas_path);
+        let meta: CanvasMeta = core::read_json(&canvas_paths.meta_json)?;
+
+        let mut workspace = WorkspaceService::load(canvas_path)?;
+        workspace.revision = 0;
+        workspace.content_hash = None;
+
+        let mut template = CanvasTemplate::new(core::generate_uuid(), name.to_string(), workspace);
+        template.description = description.map(str::to_string).unwrap_or(meta.description);
+        template.tags = meta.tags;
+        template.variables = Self::find_variables(&template.workspace);
+
+        let path = Self::template_path(vault_path, &template.id)?;
+        core::write_json(&path, &template)?;
+
+        Ok(TemplateInfo::from_template(
+            &template,
+            path.to_string_lossy().to_string(),
+        ))
+    }
+
+    /// List templates in a vault, sorted by name
+    pub fn list(vault_path: &Path) -> MosaicResult<Vec<TemplateInfo>> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+
+        let mut templates: Vec<TemplateInfo> = core::fs::list_dir(&vault_paths.templates)?
+            .into_iter()
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|p| {
+                core::read_json::<CanvasTemplate>(&p)
+                    .ok()
+                    .map(|t| TemplateInfo::from_template(&t, p.to_string_lossy().to_string()))
+            })
+            .collect();
+
+        templates.sort_by_key(|t| t.name.to_lowercase());
+
+        Ok(templates)
+    }
+
+    /// Load a template by ID
+    pub fn get(vault_path: &Path, template_id: &str) -> MosaicResult<CanvasTemplate> {
+        let path = Self::template_path(vault_path, template_id)?;
+
+        if !path.exists() {
+            return Err(MosaicError::not_found(&format!("Template {}", template_id)));
+        }
+
+        core::read_json(&path)
+    }
+
+    /// Delete a template by ID
+    pub fn delete(vault_path: &Path, template_id: &str) -> MosaicResult<()> {
+        let path = Self::template_path(vault_path, template_id)?;
+
+        if !path.exists() {
+            return Err(MosaicError::not_found(&format!("Template {}", template_id)));
+        }
+
+        core::fs::remove_file(&path)
+    }
+
+    /// Build a workspace from a template with fresh IDs and substituted variables
+    ///
+    /// Placeholders without a value are left as-is. Also returns the old ->
+    /// new node ID map, for re-sealing sealed values.
+    pub fn instantiate(
+        template: &CanvasTemplate,
+        variables: &HashMap<String, String>,
+    ) -> (WorkspaceData, HashMap<String, String>) {
+        let mut workspace = template.workspace.clone();
+        let node_ids = workspace.regenerate_ids();
+        workspace.revision = 0;
+        workspace.content_hash = None;
+
+        for node in &mut workspace.nodes {
+            for value in node.data.values_mut() {
+                Self::substitute(value, variables);
+            }
+        }
+        for edge in &mut workspace.edges {
+            if let Some(label) = edge.label.as_mut() {
+                *label = Self::substitute_str(label, variables);
+            }
+        }
+
+        (workspace, node_ids)
+    }
+
+    /// Placeholder names used anywhere in node data or edge labels
+    fn find_variables(workspace: &WorkspaceData) -> Vec<String> {
+        let mut names = BTreeSet::new();
+        let mut collect = |text: &str| {
+            for captures in placeholder().captures_iter(text) {
+                names.insert(captures[1].to_string());
+            }
+        };
+
+        for node in &workspace.nodes {
+            for value in node.data.values() {
+                Self::visit_strings(value, &mut collect);
+            }
+        }
+        for label in workspace.edges.iter().filter_map(|e| e.label.as_deref()) {
+            collect(label);
+        }
+
+        names.into_iter().collect()
+    }
+
+    fn visit_strings(value: &Value, visit: &mut impl FnMut(&str)) {
+        match value {
+            Value::String(s) => visit(s),
+            Value::Array(items) => items.iter().for_each(|v| Self::visit_strings(v, visit)),
+            Value::Object(map) => map.values().for_each(|v| Self::visit_strings(v, visit)),
+            _ => {}
+        }
+    }
+
+    fn substitute(value: &mut Value, variables: &HashMap<String, String>) {
+        match value {
+            Value::String(s) => *s = Self::substitute_str(s, variables),
+            Value::Array(items) => items
+                .iter_mut()
+                .for_each(|v| Self::substitute(v, variables)),
+            Value::Object(map) => map
+                .values_mut()
+                .for_each(|v| Self::substitute(v, variables)),
+            _ => {}
+        }
+    }
+
+    fn substitute_str(text: &str, variables: &HashMap<String, String>) -> String {
+        placeholder()
+            .replace_all(text, |captures: &regex::Captures| {
+                variables
+                    .get(&captures[1])
+                    .cloned()
+                    .unwrap_or_else(|| captures[0].to_string())
+            })
+            .into_owned()
+    }
+
+    /// Path of a template file, rejecting IDs that would escape the templates directory
+    fn template_path(vault_path: &Path, template_id: &str) -> MosaicResult<PathBuf> {
+        if template_id.is_empty()
+            || template_id.contains(['/', '\\'])
+            || template_id.starts_with('.')
+        {
+            return Err(MosaicError::invalid_format(format!(
+                "Invalid template ID: {}",
+                template_id
+            )));
+        }
+
+        let vault_paths = VaultPaths::from_root(vault_path);
+        Ok(vault_paths.templates.join(format!("{}.json", template_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Position, WorkspaceNode};
+
+    fn note(title: &str) -> WorkspaceNode {
+        WorkspaceNode {
+            id: "n1".to_string(),
+            node_type: "note".to_string(),
+            position: Position::default(),
+            width: None,
+            height: None,
+            z_index: 1,
+            parent_id: None,
+            classification: None,
+            data: HashMap::from([("title".to_string(), Value::from(title))]),
+        }
+    }
+
+    #[test]
+    fn test_instantiate_substitutes_variables() {
+        let mut workspace = WorkspaceData::new();
+        workspace.add_node(note("Target: {{ target }} ({{unknown}})"));
+        let mut template = CanvasTemplate::new("t".to_string(), "T".to_string(), workspace);
+        template.variables = TemplateService::find_variables(&template.workspace);
+        assert_eq!(template.variables, vec!["target", "unknown"]);
+
+        let variables = HashMap::from([("target".to_string(), "ACME".to_string())]);
+        let (created, _) = TemplateService::instantiate(&template, &variables);
+
+        assert_ne!(created.nodes[0].id, "n1");
+        assert_eq!(
+            created.nodes[0].data["title"],
+            Value::from("Target: ACME ({{unknown}})")
+        );
+    }
+}
@@ -0,0 +1,82 @@
+// Test Fixtures
+//
+// Vaults and canvases for service tests. Each lives in a temporary directory
+// that is removed on drop, also when the test panics.
+
+use crate::core::paths::{self, VaultPaths};
+use crate::models::WorkspaceData;
+use crate::services::{CanvasService, VaultService, WorkspaceService};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tempfile::TempDir;
+
+/// Point the app data directory at a temporary directory
+///
+/// It can only be set once per process, so all tests share it.
+pub fn init_data_dir() {
+    static DATA_DIR: OnceLock<TempDir> = OnceLock::new();
+    let dir = DATA_DIR.get_or_init(|| TempDir::new().unwrap());
+    paths::set_data_dir(dir.path().to_path_buf());
+}
+
+/// An empty scratch directory
+pub fn temp_dir() -> TempDir {
+    init_data_dir();
+    TempDir::new().unwrap()
+}
+
+/// A vault with its default canvas
+pub struct TempVault {
+    pub root: PathBuf,
+    pub id: String,
+    pub paths: VaultPaths,
+    dir: TempDir,
+}
+
+impl TempVault {
+    pub fn new() -> Self {
+        let dir = temp_dir();
+        let root = dir.path().join("vault");
+        let vault = VaultService::create(&root, "Vault", None).unwrap();
+
+        Self {
+            paths: VaultPaths::from_root(&root),
+            root,
+            id: vault.id,
+            dir,
+        }
+    }
+
+    /// Directory next to the vault, for files that live outside it
+    pub fn outside(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// The canvas created with the vault
+    pub fn default_canvas(&self) -> PathBuf {
+        PathBuf::from(&VaultService::list_canvases(&self.root).unwrap()[0].path)
+    }
+
+    /// Create another canvas at the top of the vault
+    pub fn create_canvas(&self, name: &str) -> PathBuf {
+        let canvas = CanvasService::create(&self.paths.canvases, &self.id, name, None).unwrap();
+        PathBuf::from(canvas.path)
+    }
+}
+
+/// A canvas folder with an empty workspace, outside any vault
+pub struct TempCanvas {
+    pub path: PathBuf,
+    _dir: TempDir,
+}
+
+impl TempCanvas {
+    pub fn new() -> Self {
+        let dir = temp_dir();
+        let path = dir.path().join("canvas");
+        std::fs::create_dir_all(&path).unwrap();
+        WorkspaceService::save(&path, &WorkspaceData::new()).unwrap();
+
+        Self { path, _dir: dir }
+    }
+}
@@ -0,0 +1,701 @@
+// Transform Service
+//
+// Enrichment transforms that take one node and add related nodes and edges
+// ("resolve domain", "look up hash", ...). Each provider declares the node
+// types it accepts and produces; a vault's registry holds the HTTP providers
+// configured in .mosaicflow/transforms.json. Nothing is sent anywhere unless
+// such a provider is configured, its endpoint was saved on this machine, and
+// it is run.
+
+use crate::core::{self, paths::VaultPaths, MosaicError, MosaicResult};
+use crate::models::{
+    HttpTransformConfig, Position, TransformConfig, TransformInfo, TransformInput,
+    TransformInputNode, TransformOutput, TransformOutputEdge, TransformResult, WorkspaceEdge,
+    WorkspaceNode,
+};
+use crate::services::{
+    normalize_service, ConfigService, NormalizeService, SecretService, WorkspaceService,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Edge endpoint naming the node the transform ran on
+pub const INPUT_REF: &str = "$input";
+
+/// Label of edges from the input node when a provider returns none
+const DEFAULT_EDGE_LABEL: &str = "related to";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Largest response read from an HTTP provider
+const MAX_RESPONSE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// App data folder of the per-vault request headers, which may hold API
+/// keys and so are kept out of the vault
+const HEADERS_DIR: &str = "transform-headers";
+
+/// App data folder of the per-vault endpoints approved on this machine;
+/// transforms.json travels with a shared vault, so an endpoint added there
+/// elsewhere is not called until it is saved here
+const ENDPOINTS_DIR: &str = "transform-endpoints";
+
+/// Gap between the input node and the first column of output nodes
+const NODE_OFFSET_X: f64 = 100.0;
+const NODE_SPACING_X: f64 = 320.0;
+const NODE_SPACING_Y: f64 = 160.0;
+const NODE_ROWS: usize = 5;
+
+/// A source of enrichment for nodes of given types
+pub trait TransformProvider: Send + Sync {
+    fn info(&self) -> &TransformInfo;
+
+    /// Nodes and edges related to `input`; may block on I/O
+    fn run(&self, input: &TransformInput) -> MosaicResult<TransformOutput>;
+}
+
+/// Provider that POSTs the input node as JSON to an endpoint and reads
+/// back a `TransformOutput`
+pub struct HttpTransformProvider {
+    config: HttpTransformConfig,
+}
+
+impl HttpTransformProvider {
+    pub fn new(config: HttpTransformConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl TransformProvider for HttpTransformProvider {
+    fn info(&self) -> &TransformInfo {
+        &self.config.info
+    }
+
+    fn run(&self, input: &TransformInput) -> MosaicResult<TransformOutput> {
+        let timeout = self.config.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS);
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(timeout))
+            .build();
+
+        let mut request = agent.post(&self.config.endpoint);
+        for (name, value) in &self.config.headers {
+            request = request.set(name, value);
+        }
+
+        let failed = |reason: String| {
+            MosaicError::invalid_format(format!(
+                "Transform {} failed: {}",
+                self.config.info.id, reason
+            ))
+        };
+        let response = request.send_json(input).map_err(|e| match e {
+            ureq::Error::Status(status, response) => failed(format!(
+                "HTTP {} {}",
+                status,
+                response.into_string().unwrap_or_default().trim()
+            )),
+            e => failed(e.to_string()),
+        })?;
+
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .take(MAX_RESPONSE_SIZE + 1)
+            .read_to_end(&mut body)?;
+        if body.len() as u64 > MAX_RESPONSE_SIZE {
+            return Err(failed(format!(
+                "response larger than {} bytes",
+                MAX_RESPONSE_SIZE
+            )));
+        }
+        serde_json::from_slice(&body).map_err(|e| failed(format!("invalid response: {}", e)))
+    }
+}
+
+/// Providers available to a vault
+#[derive(Default)]
+pub struct TransformRegistry {
+    providers: Vec<Box<dyn TransformProvider>>,
+}
+
+impl TransformRegistry {
+    /// The vault's configured HTTP providers whose endpoints are approved
+    pub fn for_vault(vault_path: &Path) -> MosaicResult<Self> {
+        let mut registry = Self::default();
+        for config in TransformService::full_config(vault_path)?.http {
+            if config.approved {
+                registry.register(Box::new(HttpTransformProvider::new(config)))?;
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn register(&mut self, provider: Box<dyn TransformProvider>) -> MosaicResult<()> {
+        let id = &provider.info().id;
+        if self.get(id).is_some() {
+            return Err(MosaicError::invalid_format(format!(
+                "Duplicate transform ID: {}",
+                id
+            )));
+        }
+        self.providers.push(provider);
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&dyn TransformProvider> {
+        self.providers
+            .iter()
+            .find(|p| p.info().id == id)
+            .map(|p| p.as_ref())
+    }
+
+    /// Transforms accepting a node type, or all of them
+    pub fn list(&self, node_type: Option<&str>) -> Vec<TransformInfo> {
+        self.providers
+            .iter()
+            .map(|p| p.info())
+            .filter(|info| node_type.is_none_or(|t| info.input_types.iter().any(|i| i == t)))
+            .cloned()
+            .collect()
+    }
+}
+
+pub struct TransformService;
+
+impl TransformService {
+    /// HTTP transforms configured for a vault
+    ///
+    /// Header values are left empty: they may be API keys, and are kept on
+    /// this machine only (see `set_config`).
+    pub fn config(vault_path: &Path) -> MosaicResult<TransformConfig> {
+        let mut config = Self::full_config(vault_path)?;
+        for transform in &mut config.http {
+            transform.headers.values_mut().for_each(String::clear);
+        }
+        Ok(config)
+    }
+
+    /// Replace a vault's HTTP transforms
+    ///
+    /// Request headers are stored in the app data directory rather than in
+    /// the vault. An empty header value keeps the value stored for that
+    /// transform and header, so a config read back from `config` can be
+    /// saved again unchanged. Saving approves the endpoints on this machine.
+    pub fn set_config(vault_path: &Path, config: &TransformConfig) -> MosaicResult<()> {
+        let stored = Self::headers(vault_path)?;
+        let mut config = config.clone();
+        for transform in &mut config.http {
+            let kept = stored.get(&transform.info.id);
+            transform.headers.retain(|name, value| {
+                if value.is_empty() {
+                    match kept.and_then(|headers| headers.get(name)) {
+                        Some(kept) => value.clone_from(kept),
+                        None => return false,
+                    }
+                }
+                true
+            });
+        }
+
+        let mut registry = TransformRegistry::default();
+        for transform in &config.http {
+            let info = &transform.info;
+            if info.id.trim().is_empty() || info.input_types.is_empty() {
+                return Err(MosaicError::invalid_format(format!(
+                    "Transform {:?} needs an ID and at least one input type",
+                    info.name
+                )));
+            }
+            if !transform.endpoint.starts_with("http://")
+                && !transform.endpoint.starts_with("https://")
+            {
+                return Err(MosaicError::invalid_format(format!(
+                    "Transform {} endpoint must be an http(s) URL",
+                    info.id
+                )));
+            }
+            // Rejects IDs taken by an earlier entry
+            registry.register(Box::new(HttpTransformProvider::new(transform.clone())))?;
+        }
+
+        let headers: BTreeMap<String, BTreeMap<String, String>> = config
+            .http
+            .iter_mut()
+            .map(|transform| {
+                (
+                    transform.info.id.clone(),
+                    std::mem::take(&mut transform.headers),
+                )
+            })
+            .filter(|(_, headers)| !headers.is_empty())
+            .collect();
+        let headers_file = Self::headers_file(vault_path)?;
+        if !headers.is_empty() {
+            core::write_json(&headers_file, &headers)?;
+        } else if headers_file.exists() {
+            core::fs::remove_file(&headers_file)?;
+        }
+
+        let endpoints: BTreeSet<&str> = config.http.iter().map(|t| t.endpoint.as_str()).collect();
+        core::write_json(&Self::endpoints_file(vault_path)?, &endpoints)?;
+        config.http.iter_mut().for_each(|t| t.approved = false);
+
+        let vault_paths = VaultPaths::from_root(vault_path);
+        core::write_json(&vault_paths.transforms, &config)
+    }
+
+    /// HTTP transforms of a vault with their request headers
+    fn full_config(vault_path: &Path) -> MosaicResult<TransformConfig> {
+        let vault_paths = VaultPaths::from_root(vault_path);
+
+        if !vault_paths.transforms.exists() {
+            return Ok(TransformConfig::default());
+        }
+
+        let mut config: TransformConfig = core::read_json(&vault_paths.transforms)?;
+        let mut headers = Self::headers(vault_path)?;
+        let endpoints = Self::approved_endpoints(vault_path)?;
+        for transform in &mut config.http {
+            if let Some(stored) = headers.remove(&transform.info.id) {
+                transform.headers = stored;
+            }
+            transform.approved = endpoints.contains(&transform.endpoint);
+        }
+        Ok(config)
+    }
+
+    /// Endpoints saved for a vault on this machine
+    fn approved_endpoints(vault_path: &Path) -> MosaicResult<BTreeSet<String>> {
+        let endpoints_file = Self::endpoints_file(vault_path)?;
+
+        if !endpoints_file.exists() {
+            return Ok(BTreeSet::new());
+        }
+
+        core::read_json(&endpoints_file)
+    }
+
+    fn endpoints_file(vault_path: &Path) -> MosaicResult<PathBuf> {
+        ConfigService::vault_file(vault_path, ENDPOINTS_DIR)
+    }
+
+    /// Request headers by transform ID
+    fn headers(vault_path: &Path) -> MosaicResult<BTreeMap<String, BTreeMap<String, String>>> {
+        let headers_file = Self::headers_file(vault_path)?;
+
+        if !headers_file.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        core::read_json(&headers_file)
+    }
+
+    fn headers_file(vault_path: &Path) -> MosaicResult<PathBuf> {
+        ConfigService::vault_file(vault_path, HEADERS_DIR)
+    }
+
+    /// Transforms available in a vault, optionally only those accepting a
+    /// node type
+    pub fn list(vault_path: &Path, node_type: Option<&str>) -> MosaicResult<Vec<TransformInfo>> {
+        Ok(TransformRegistry::for_vault(vault_path)?.list(node_type))
+    }
+
+    /// Run a transform on a node and add its output to the canvas
+    pub fn run(
+        canvas_path: &Path,
+        transform_id: &str,
+        node_id: &str,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<TransformResult> {
+        let vault_path = SecretService::vault_of(canvas_path)?;
+        let registry = TransformRegistry::for_vault(&vault_path)?;
+        let Some(provider) = registry.get(transform_id) else {
+            let config = Self::full_config(&vault_path)?;
+            return Err(
+                match config.http.iter().find(|t| t.info.id == transform_id) {
+                    Some(transform) => MosaicError::invalid_input(format!(
+                        "Transform {} calls {}, which has not been approved on this machine; \
+                     save the vault's transforms to approve it",
+                        transform_id, transform.endpoint
+                    )),
+                    None => MosaicError::not_found(&format!("Transform {}", transform_id)),
+                },
+            );
+        };
+        Self::run_with(canvas_path, provider, node_id, expected_revision)
+    }
+
+    /// Run a given provider on a node and add its output to the canvas
+    ///
+    /// Output nodes equal to a node already on the canvas (same type and
+    /// normalized indicator fields) are linked instead of duplicated. The
+    /// canvas is left untouched when nothing is new.
+    pub fn run_with(
+        canvas_path: &Path,
+        provider: &dyn TransformProvider,
+        node_id: &str,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<TransformResult> {
+        let info = provider.info();
+        let workspace = WorkspaceService::load(canvas_path)?;
+        let source = workspace
+            .nodes
+            .iter()
+            .find(|n| n.id == node_id)
+            .ok_or_else(|| MosaicError::not_found(&format!("Node {}", node_id)))?;
+        if !info.input_types.contains(&source.node_type) {
+            return Err(MosaicError::invalid_format(format!(
+                "Transform {} does not accept {} nodes",
+                info.id, source.node_type
+            )));
+        }
+
+        let input = TransformInput {
+            transform: info.id.clone(),
+            node: TransformInputNode {
+                id: source.id.clone(),
+                node_type: source.node_type.clone(),
+                data: source
+                    .data
+                    .iter()
+                    .filter(|(key, value)| {
+                        key.as_str() != normalize_service::NORMALIZED_FIELD
+                            && !value.as_str().is_some_and(SecretService::is_sealed)
+                    })
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+            },
+        };
+        let output = provider.run(&input)?;
+
+        // The provider may take a while, so the output is matched against
+        // the canvas as it is once the lock is held
+        let mut nodes: Vec<WorkspaceNode> = Vec::new();
+        let mut edges = Vec::new();
+        let mut matched_node_ids = Vec::new();
+        let revision = WorkspaceService::update(canvas_path, expected_revision, |workspace| {
+            let source = workspace
+                .nodes
+                .iter()
+                .find(|n| n.id == node_id)
+                .ok_or_else(|| MosaicError::not_found(&format!("Node {}", node_id)))?;
+
+            // Output refs (and $input) to canvas node IDs
+            let mut ids: HashMap<String, String> =
+                HashMap::from([(INPUT_REF.to_string(), source.id.clone())]);
+            let mut output_ids = Vec::new();
+            let origin_x = source.position.x + source.width.unwrap_or(300.0) + NODE_OFFSET_X;
+
+            for (index, output_node) in output.nodes.into_iter().enumerate() {
+                if !info.output_node_types.contains(&output_node.node_type) {
+                    return Err(MosaicError::invalid_format(format!(
+                        "Transform {} returned an undeclared {} node",
+                        info.id, output_node.node_type
+                    )));
+                }
+
+                let position = Position {
+                    x: origin_x + (index / NODE_ROWS) as f64 * NODE_SPACING_X,
+                    y: source.position.y + (index % NODE_ROWS) as f64 * NODE_SPACING_Y,
+                };
+                let mut node =
+                    WorkspaceNode::new(&output_node.node_type, position, output_node.data);
+                NormalizeService::normalize_node(&mut node);
+
+                let id = match Self::find_equal(workspace.nodes.iter().chain(nodes.iter()), &node) {
+                    Some(existing) => {
+                        if workspace.nodes.iter().any(|n| n.id == existing) {
+                            matched_node_ids.push(existing.clone());
+                        }
+                        existing
+                    }
+                    None => {
+                        let id = node.id.clone();
+                        nodes.push(node);
+                        id
+                    }
+                };
+                if let Some(reference) = output_node.reference {
+                    ids.insert(reference, id.clone());
+                }
+                output_ids.push(id);
+            }
+
+            let output_edges = output.edges.unwrap_or_else(|| {
+                output_ids
+                    .iter()
+                    .map(|id| TransformOutputEdge {
+                        source: INPUT_REF.to_string(),
+                        target: id.clone(),
+                        label: info.edge_labels.first().cloned(),
+                    })
+                    .collect()
+            });
+
+            let mut linked: HashSet<(String, String)> = workspace
+                .edges
+                .iter()
+                .map(|e| (e.source.clone(), e.target.clone()))
+                .collect();
+            for edge in output_edges {
+                // Output IDs are also accepted as endpoints for edges built above
+                let resolve = |endpoint: &str| {
+                    ids.get(endpoint)
+                        .or_else(|| output_ids.iter().find(|id| *id == endpoint))
+                        .cloned()
+                        .ok_or_else(|| {
+                            MosaicError::invalid_format(format!(
+                                "Transform {} returned an edge to unknown node {}",
+                                info.id, endpoint
+                            ))
+                        })
+                };
+                let (edge_source, edge_target) = (resolve(&edge.source)?, resolve(&edge.target)?);
+                if edge_source == edge_target
+                    || !linked.insert((edge_source.clone(), edge_target.clone()))
+                {
+                    continue;
+                }
+
+                let label = edge
+                    .label
+                    .or_else(|| info.edge_labels.first().cloned())
+                    .unwrap_or_else(|| DEFAULT_EDGE_LABEL.to_string());
+                edges.push(WorkspaceEdge::new(&edge_source, &edge_target, Some(label)));
+            }
+
+            for node in &nodes {
+                workspace.add_node(node.clone());
+            }
+            for edge in &edges {
+                workspace.add_edge(edge.clone());
+            }
+            Ok(!nodes.is_empty() || !edges.is_empty())
+        })?;
+
+        matched_node_ids.sort();
+        matched_node_ids.dedup();
+
+        Ok(TransformResult {
+            transform_id: info.id.clone(),
+            nodes,
+            edges,
+            matched_node_ids,
+            revision,
+        })
+    }
+
+    /// Node of the same type with the same normalized indicator fields
+    fn find_equal<'a>(
+        mut candidates: impl Iterator<Item = &'a WorkspaceNode>,
+        node: &WorkspaceNode,
+    ) -> Option<String> {
+        let normalized = node.data.get(normalize_service::NORMALIZED_FIELD)?;
+        candidates
+            .find(|n| {
+                n.node_type == node.node_type
+                    && n.data.get(normalize_service::NORMALIZED_FIELD) == Some(normalized)
+            })
+            .map(|n| n.id.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TransformInfo;
+    use crate::services::testing::TempVault;
+    use serde_json::{json, Value};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Local HTTP server answering one request per response, in order;
+    /// joins to the head and JSON body of each request
+    fn stub_server(
+        responses: Vec<Value>,
+    ) -> (String, std::thread::JoinHandle<Vec<(String, Value)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/transform", listener.local_addr().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|response| {
+                    let mut reader = BufReader::new(listener.accept().unwrap().0);
+                    let mut head = String::new();
+                    while !head.ends_with("\r\n\r\n") {
+                        assert!(reader.read_line(&mut head).unwrap() > 0);
+                    }
+                    let length = head
+                        .lines()
+                        .filter_map(|line| line.split_once(':'))
+                        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                        .map_or(0, |(_, value)| value.trim().parse().unwrap());
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+
+                    let response = response.to_string();
+                    write!(
+                        reader.into_inner(),
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        response.len(),
+                        response
+                    )
+                    .unwrap();
+                    (head.to_lowercase(), serde_json::from_slice(&body).unwrap())
+                })
+                .collect()
+        });
+        (endpoint, handle)
+    }
+
+    #[test]
+    fn test_http_transform_against_stub_server() {
+        let vault = TempVault::new();
+        let vault_path = vault.root.clone();
+        let canvas = vault.default_canvas();
+
+        let domain = |name: &str| {
+            let data = HashMap::from([("domain".to_string(), json!(name))]);
+            WorkspaceNode::new("domain", Position::default(), data)
+        };
+        let (source, mail) = (domain("example.com"), domain("mail.example.com"));
+        WorkspaceService::add_node(&canvas, source.clone(), None).unwrap();
+        WorkspaceService::add_node(&canvas, mail.clone(), None).unwrap();
+
+        // "b" is already on the canvas, written differently
+        let response = json!({
+            "nodes": [
+                { "ref": "a", "type": "domain", "data": { "domain": "10.0.0.1", "ip": "10.0.0.1" } },
+                { "ref": "b", "type": "domain", "data": { "domain": "Mail.Example.COM." } },
+            ],
+            "edges": [
+                { "source": "$input", "target": "a", "label": "resolves to" },
+                { "source": "b", "target": "a" },
+                { "source": "$input", "target": "a" },
+            ],
+        });
+        let (endpoint, server) = stub_server(vec![response.clone(), response]);
+
+        let transform = HttpTransformConfig {
+            info: TransformInfo {
+                id: "stub".to_string(),
+                name: "Stub".to_string(),
+                description: String::new(),
+                input_types: vec!["domain".to_string()],
+                output_node_types: vec!["domain".to_string()],
+                edge_labels: vec!["related to".to_string()],
+            },
+            endpoint,
+            headers: BTreeMap::from([("X-Api-Key".to_string(), "secret-key".to_string())]),
+            timeout_secs: Some(5),
+            approved: false,
+        };
+        TransformService::set_config(
+            &vault_path,
+            &TransformConfig {
+                http: vec![transform],
+            },
+        )
+        .unwrap();
+
+        // The key stays out of the vault and is not handed back, but a
+        // config read back and saved again keeps it
+        let vault_paths = VaultPaths::from_root(&vault_path);
+        assert!(!core::read_string(&vault_paths.transforms)
+            .unwrap()
+            .contains("secret-key"));
+        let config = TransformService::config(&vault_path).unwrap();
+        assert_eq!(config.http[0].headers["X-Api-Key"], "");
+        TransformService::set_config(&vault_path, &config).unwrap();
+        let provider = HttpTransformProvider::new(
+            TransformService::full_config(&vault_path).unwrap().http[0].clone(),
+        );
+        assert_eq!(provider.config.headers["X-Api-Key"], "secret-key");
+
+        let first = TransformService::run_with(&canvas, &provider, &source.id, None).unwrap();
+        assert_eq!(first.nodes.len(), 1);
+        let address = &first.nodes[0];
+        assert_eq!(address.data["ip"], "10.0.0.1");
+        assert_eq!(first.matched_node_ids, vec![mail.id.clone()]);
+        let mut links: Vec<(&str, &str, &str)> = first
+            .edges
+            .iter()
+            .map(|e| {
+                (
+                    e.source.as_str(),
+                    e.target.as_str(),
+                    e.label.as_deref().unwrap(),
+                )
+            })
+            .collect();
+        links.sort();
+        let mut expected = vec![
+            (source.id.as_str(), address.id.as_str(), "resolves to"),
+            (mail.id.as_str(), address.id.as_str(), "related to"),
+        ];
+        expected.sort();
+        assert_eq!(links, expected);
+
+        // Running again finds everything in place and writes nothing
+        let second = TransformService::run_with(
+            &canvas,
+            &provider,
+            &source.id,
+            Some(first.revision.revision),
+        )
+        .unwrap();
+        assert!(second.nodes.is_empty() && second.edges.is_empty());
+        let mut matched = vec![mail.id.clone(), address.id.clone()];
+        matched.sort();
+        assert_eq!(second.matched_node_ids, matched);
+        assert_eq!(second.revision.revision, first.revision.revision);
+        assert_eq!(WorkspaceService::load(&canvas).unwrap().nodes.len(), 3);
+
+        let requests = server.join().unwrap();
+        for (head, body) in &requests {
+            assert!(head.starts_with("post /transform "));
+            assert!(head.contains("x-api-key: secret-key"));
+            assert_eq!(body["transform"], "stub");
+            assert_eq!(body["node"]["id"], json!(source.id));
+            assert_eq!(body["node"]["data"]["domain"], "example.com");
+            assert!(body["node"]["data"].get("normalized").is_none());
+        }
+    }
+
+    #[test]
+    fn test_endpoints_added_to_the_vault_elsewhere_need_approval() {
+        let vault = TempVault::new();
+        let canvas = vault.default_canvas();
+        let node = WorkspaceNode::new("domain", Position::default(), HashMap::new());
+        WorkspaceService::add_node(&canvas, node.clone(), None).unwrap();
+
+        // As synced from another machine: in the vault, never saved here
+        let vault_paths = VaultPaths::from_root(&vault.root);
+        let shared = json!({ "http": [{
+            "id": "planted",
+            "name": "Planted",
+            "input_types": ["domain"],
+            "output_node_types": ["domain"],
+            "endpoint": "http://127.0.0.1:9/collect",
+            "approved": true,
+        }]});
+        core::write_json(&vault_paths.transforms, &shared).unwrap();
+
+        assert!(TransformService::list(&vault.root, None)
+            .unwrap()
+            .is_empty());
+        assert!(!TransformService::config(&vault.root).unwrap().http[0].approved);
+        let err = TransformService::run(&canvas, "planted", &node.id, None).unwrap_err();
+        assert_eq!(err.code, core::error::ErrorCode::InvalidInput);
+
+        // Saving the config here approves it
+        let config = TransformService::config(&vault.root).unwrap();
+        TransformService::set_config(&vault.root, &config).unwrap();
+        assert_eq!(TransformService::list(&vault.root, None).unwrap().len(), 1);
+        assert!(!core::read_string(&vault_paths.transforms)
+            .unwrap()
+            .contains("approved"));
+    }
+}
@@ -4,7 +4,7 @@
 
 use crate::core::{self, paths::VaultPaths, MosaicError, MosaicResult};
 use crate::models::{CanvasInfo, VaultInfo, VaultMeta};
-use crate::services::CanvasService;
+use crate::services::{CanvasService, MigrationService};
 use std::path::Path;
 
 pub struct VaultService;
@@ -12,7 +12,7 @@ pub struct VaultService;
 impl VaultService {
     /// Create a new vault at the specified path
     pub fn create(path: &Path, name: &str, description: Option<&str>) -> MosaicResult<VaultInfo> {
-        let vault_paths = VaultPaths::from_root(&path.to_path_buf());
+        let vault_paths = VaultPaths::from_root(path);
 
         // Check if already exists
         if vault_paths.is_valid() {
@@ -43,23 +43,34 @@ impl VaultService {
             created_at: meta.created_at,
             updated_at: meta.updated_at,
             canvas_count: 1,
+            needs_migration: false,
         })
     }
 
     /// Open an existing vault
     pub fn open(path: &Path) -> MosaicResult<VaultInfo> {
-        let vault_paths = VaultPaths::from_root(&path.to_path_buf());
+        let vault_paths = VaultPaths::from_root(path);
 
         if !vault_paths.is_valid() {
             return Err(MosaicError::vault_not_found(&path.to_string_lossy()));
         }
 
-        // Read vault metadata
-        let meta: VaultMeta = core::read_json(&vault_paths.vault_json)?;
-
         // Count canvases
         let canvas_count = Self::count_canvases(&vault_paths.canvases);
 
+        // Outdated vaults are reported, not rewritten; migrate_vault
+        // upgrades them when asked to
+        if MigrationService::vault_needs_migration(path) {
+            let meta = MigrationService::preview_vault_meta(path)?;
+            let mut info =
+                VaultInfo::from_meta(&meta, path.to_string_lossy().to_string(), canvas_count);
+            info.needs_migration = true;
+            return Ok(info);
+        }
+
+        // Read vault metadata
+        let meta: VaultMeta = core::read_json(&vault_paths.vault_json)?;
+
         Ok(VaultInfo::from_meta(
             &meta,
             path.to_string_lossy().to_string(),
@@ -67,9 +78,21 @@ impl VaultService {
         ))
     }
 
+    /// Open a vault that canvases are added to
+    ///
+    /// Their vault ID must be final, so an outdated vault has to be migrated
+    /// first.
+    pub fn open_migrated(path: &Path) -> MosaicResult<VaultInfo> {
+        let info = Self::open(path)?;
+        if info.needs_migration {
+            return Err(MosaicError::vault_not_migrated(&info.path));
+        }
+        Ok(info)
+    }
+
     /// Rename a vault
     pub fn rename(path: &Path, new_name: &str) -> MosaicResult<VaultInfo> {
-        let vault_paths = VaultPaths::from_root(&path.to_path_buf());
+        let vault_paths = VaultPaths::from_root(path);
 
         if !vault_paths.is_valid() {
             return Err(MosaicError::vault_not_found(&path.to_string_lossy()));
@@ -94,7 +117,7 @@ impl VaultService {
 
     /// Update vault description
     pub fn update_description(path: &Path, description: &str) -> MosaicResult<VaultInfo> {
-        let vault_paths = VaultPaths::from_root(&path.to_path_buf());
+        let vault_paths = VaultPaths::from_root(path);
 
         if !vault_paths.is_valid() {
             return Err(MosaicError::vault_not_found(&path.to_string_lossy()));
@@ -117,12 +140,12 @@ impl VaultService {
 
     /// Check if path is a valid vault
     pub fn is_valid(path: &Path) -> bool {
-        VaultPaths::from_root(&path.to_path_buf()).is_valid()
+        VaultPaths::from_root(path).is_valid()
     }
 
     /// Get vault info without opening
     pub fn get_info(path: &Path) -> MosaicResult<Option<VaultInfo>> {
-        let vault_paths = VaultPaths::from_root(&path.to_path_buf());
+        let vault_paths = VaultPaths::from_root(path);
 
         if !vault_paths.is_valid() {
             return Ok(None);
@@ -140,13 +163,13 @@ impl VaultService {
 
     /// List all canvases in a vault
     pub fn list_canvases(path: &Path) -> MosaicResult<Vec<CanvasInfo>> {
-        let vault_paths = VaultPaths::from_root(&path.to_path_buf());
+        let vault_paths = VaultPaths::from_root(path);
         CanvasService::list(&vault_paths.canvases)
     }
 
     /// Get vault ID from vault.json
     pub fn get_vault_id(path: &Path) -> MosaicResult<Option<String>> {
-        let vault_paths = VaultPaths::from_root(&path.to_path_buf());
+        let vault_paths = VaultPaths::from_root(path);
 
         if !vault_paths.is_valid() {
             return Ok(None);
@@ -158,7 +181,7 @@ impl VaultService {
 
     /// Count canvases in vault
     fn count_canvases(canvases_dir: &Path) -> usize {
-        core::list_subdirs(canvases_dir)
+        core::paths::list_canvas_dirs(canvases_dir)
             .map(|dirs| dirs.len())
             .unwrap_or(0)
     }
@@ -0,0 +1,322 @@
+// Watcher Service
+//
+// Watches the open vault for changes made outside the app (sync tools,
+// scripts, other editors) and turns them into fs:* and workspace events
+
+use crate::core::{self, paths::CanvasPaths, write_log, MosaicError, MosaicResult};
+use crate::events::{EventEmitter, FileChangeKind, FileEvent};
+use crate::models::CanvasMeta;
+use crate::services::{CanvasService, NodeFileService, WorkspaceService};
+use notify_debouncer_full::notify::{
+    event::{ModifyKind, RenameMode},
+    EventKind, RecommendedWatcher, RecursiveMode,
+};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Quiet period before a burst of file events is delivered
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Directories whose contents never produce events: canvas and vault
+/// backups, and VCS metadata anywhere
+const IGNORED_DIRS: &[&[&str]] = &[
+    &[".mosaic", "backups"],
+    &[".mosaicflow", "backups"],
+    &[".git"],
+];
+
+/// Watcher for a single vault
+pub struct VaultWatcher {
+    vault_path: PathBuf,
+    debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+}
+
+/// Tauri-managed slot holding the watcher of the open vault
+#[derive(Default)]
+pub struct WatcherState(Mutex<Option<VaultWatcher>>);
+
+pub struct WatcherService;
+
+impl WatcherService {
+    /// Start watching a vault, replacing any previously watched vault
+    pub fn start(app_handle: &AppHandle, vault_path: &Path) -> MosaicResult<()> {
+        let root = vault_path.canonicalize()?;
+        let state = app_handle.state::<WatcherState>();
+        let mut slot = state
+            .0
+            .lock()
+            .map_err(|_| MosaicError::io_error("Watcher state poisoned"))?;
+
+        if slot.as_ref().is_some_and(|w| w.vault_path == root) {
+            return Ok(());
+        }
+        if let Some(previous) = slot.take() {
+            previous.debouncer.stop();
+        }
+
+        let handle = app_handle.clone();
+        let vault_root = root.clone();
+        let mut debouncer = new_debouncer(DEBOUNCE, None, move |result: DebounceEventResult| {
+            if let Ok(events) = result {
+                let changes = events
+                    .iter()
+                    .flat_map(|event| Self::classify(&event.kind, &event.paths))
+                    .collect();
+                Self::dispatch(&handle, &vault_root, changes);
+            }
+        })
+        .map_err(MosaicError::io_error)?;
+
+        debouncer
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(MosaicError::io_error)?;
+
+        *slot = Some(VaultWatcher {
+            vault_path: root,
+            debouncer,
+        });
+
+        Ok(())
+    }
+
+    /// Stop watching the current vault (no-op if none)
+    pub fn stop(app_handle: &AppHandle) {
+        let state = app_handle.state::<WatcherState>();
+        let previous = state.0.lock().ok().and_then(|mut slot| slot.take());
+        if let Some(watcher) = previous {
+            watcher.debouncer.stop();
+        }
+    }
+
+    /// Map a notify event to created/modified/deleted paths
+    fn classify(kind: &EventKind, paths: &[PathBuf]) -> Vec<(FileChangeKind, PathBuf)> {
+        match kind {
+            EventKind::Create(_) => Self::tag(FileChangeKind::Created, paths),
+            EventKind::Remove(_) => Self::tag(FileChangeKind::Deleted, paths),
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                Self::tag(FileChangeKind::Deleted, paths)
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                Self::tag(FileChangeKind::Created, paths)
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => vec![
+                (FileChangeKind::Deleted, paths[0].clone()),
+                (FileChangeKind::Created, paths[1].clone()),
+            ],
+            EventKind::Modify(ModifyKind::Metadata(_)) => vec![],
+            EventKind::Modify(_) => Self::tag(FileChangeKind::Modified, paths),
+            _ => vec![],
+        }
+    }
+
+    fn tag(kind: FileChangeKind, paths: &[PathBuf]) -> Vec<(FileChangeKind, PathBuf)> {
+        paths.iter().map(|p| (kind, p.clone())).collect()
+    }
+
+    /// Emit events for external changes and reload canvases whose files changed
+    fn dispatch(
+        app_handle: &AppHandle,
+        vault_root: &Path,
+        changes: Vec<(FileChangeKind, PathBuf)>,
+    ) {
+        let emitter = EventEmitter::new(app_handle);
+        let mut seen = HashSet::new();
+        let mut changed_workspaces: HashMap<PathBuf, ChangedIds> = HashMap::new();
+        let mut changed_metas = HashSet::new();
+
+        for (kind, path) in changes {
+            if !seen.insert((kind, path.clone()))
+                || Self::is_ignored(vault_root, &path)
+                || write_log::is_recent(&path)
+            {
+                continue;
+            }
+
+            let canvas_root = Self::find_canvas_root(vault_root, &path);
+            let canvas_paths = canvas_root.as_deref().map(CanvasPaths::from_root);
+
+            if let Some(paths) = canvas_paths.as_ref() {
+                let deleted = kind == FileChangeKind::Deleted;
+                if let Some(node_id) = Self::node_id(&paths.root, &path) {
+                    let changed = changed_workspaces.entry(paths.root.clone()).or_default();
+                    changed.nodes.insert(node_id);
+                } else if let Some(edge_id) = Self::edge_id(&paths.root, &path) {
+                    let changed = changed_workspaces.entry(paths.root.clone()).or_default();
+                    changed.edges.insert(edge_id);
+                } else if path == paths.workspace_json && !deleted {
+                    let changed = changed_workspaces.entry(paths.root.clone()).or_default();
+                    changed.manifest = true;
+                } else if path == paths.meta_json && !deleted {
+                    changed_metas.insert(paths.root.clone());
+                }
+            }
+
+            emitter.file_changed(
+                kind,
+                FileEvent {
+                    path: path.to_string_lossy().to_string(),
+                    vault_path: vault_root.to_string_lossy().to_string(),
+                    canvas_id: canvas_paths.as_ref().and_then(Self::canvas_id),
+                    canvas_path: canvas_root
+                        .as_ref()
+                        .map(|p| p.to_string_lossy().to_string()),
+                    node_id: canvas_root
+                        .as_ref()
+                        .and_then(|root| Self::node_id(root, &path)),
+                },
+            );
+        }
+
+        // Only announce workspaces that parse; sync tools may still be writing
+        for (canvas_root, mut changed) in changed_workspaces {
+            let Ok(data) = WorkspaceService::load(&canvas_root) else {
+                continue;
+            };
+
+            // A single-file workspace.json does not say which nodes changed
+            let workspace_json = CanvasPaths::from_root(&canvas_root).workspace_json;
+            if changed.manifest && !Self::has_node_files(&workspace_json) {
+                changed
+                    .nodes
+                    .extend(data.nodes.iter().map(|n| n.id.clone()));
+                changed
+                    .edges
+                    .extend(data.edges.iter().map(|e| e.id.clone()));
+            }
+
+            emitter.workspace_changed_externally(
+                &canvas_root.to_string_lossy(),
+                changed.nodes.into_iter().collect(),
+                changed.edges.into_iter().collect(),
+            );
+        }
+
+        for canvas_root in changed_metas {
+            if let Ok(canvas) = CanvasService::open(&canvas_root) {
+                emitter.canvas_updated(&canvas.id, &canvas.path, &canvas.name, &canvas.vault_id);
+            }
+        }
+    }
+
+    /// Skip backups, VCS metadata and editor scratch files
+    fn is_ignored(vault_root: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(vault_root).unwrap_or(path);
+        let components: Vec<_> = relative.components().map(|c| c.as_os_str()).collect();
+        let in_ignored_dir = IGNORED_DIRS.iter().any(|dir| {
+            components
+                .windows(dir.len())
+                .any(|w| w.iter().zip(dir.iter()).all(|(c, d)| c == d))
+        });
+
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let scratch = name.ends_with('~')
+            || name.ends_with(".swp")
+            || name.ends_with(".tmp")
+            || name.starts_with(".#");
+
+        in_ignored_dir || scratch
+    }
+
+    /// Nearest ancestor (inside the vault) that is a canvas directory
+    fn find_canvas_root(vault_root: &Path, path: &Path) -> Option<PathBuf> {
+        path.ancestors()
+            .take_while(|p| *p != vault_root)
+            .find(|p| {
+                let canvas_paths = CanvasPaths::from_root(p);
+                canvas_paths.is_valid_v2() || canvas_paths.is_valid_v1()
+            })
+            .map(Path::to_path_buf)
+    }
+
+    fn canvas_id(canvas_paths: &CanvasPaths) -> Option<String> {
+        core::read_json::<CanvasMeta>(&canvas_paths.meta_json)
+            .ok()
+            .map(|m| m.id)
+    }
+
+    /// Node ID for per-node files (nodes/<node_id>/data/... or nodes/<node_id>.<ext>)
+    fn node_id(canvas_root: &Path, path: &Path) -> Option<String> {
+        Self::entry_id(canvas_root, "nodes", path)
+    }
+
+    /// Edge ID for per-edge files (edges/<edge_id>/joined.json)
+    fn edge_id(canvas_root: &Path, path: &Path) -> Option<String> {
+        Self::entry_id(canvas_root, "edges", path)
+    }
+
+    fn entry_id(canvas_root: &Path, dir: &str, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(canvas_root).ok()?;
+        let mut components = relative.components();
+
+        if components.next()?.as_os_str() != dir {
+            return None;
+        }
+
+        components
+            .next()
+            .map(|c| Path::new(c.as_os_str()))
+            .and_then(|p| p.file_stem())
+            .map(|s| s.to_string_lossy().to_string())
+    }
+
+    /// Whether a workspace.json is a manifest for per-node files
+    fn has_node_files(workspace_json: &Path) -> bool {
+        core::read_json::<serde_json::Value>(workspace_json)
+            .is_ok_and(|doc| NodeFileService::is_manifest(&doc))
+    }
+}
+
+/// Nodes and edges of one canvas touched by a batch of external changes
+#[derive(Default)]
+struct ChangedIds {
+    nodes: BTreeSet<String>,
+    edges: BTreeSet<String>,
+    /// workspace.json itself changed
+    manifest: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_ignored_anchors_backups() {
+        let vault = Path::new("/vault");
+        assert!(WatcherService::is_ignored(
+            vault,
+            Path::new("/vault/canvases/a/.mosaic/backups/workspace.json")
+        ));
+        assert!(WatcherService::is_ignored(
+            vault,
+            Path::new("/vault/.mosaicflow/backups/x.json")
+        ));
+        assert!(!WatcherService::is_ignored(
+            vault,
+            Path::new("/vault/canvases/backups/workspace.json")
+        ));
+    }
+
+    #[test]
+    fn test_entry_ids() {
+        let root = Path::new("/vault/canvases/a");
+        assert_eq!(
+            WatcherService::node_id(root, &root.join("nodes/n1/data/content")),
+            Some("n1".to_string())
+        );
+        assert_eq!(
+            WatcherService::edge_id(root, &root.join("edges/e1/joined.json")),
+            Some("e1".to_string())
+        );
+        assert_eq!(
+            WatcherService::node_id(root, &root.join("workspace.json")),
+            None
+        );
+    }
+}
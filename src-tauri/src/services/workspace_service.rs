@@ -2,70 +2,230 @@
 //
 // Handles workspace data operations (nodes, edges)
 
-use crate::core::{self, paths::CanvasPaths, MosaicResult};
-use crate::models::{WorkspaceData, WorkspaceEdge, WorkspaceNode};
-use std::path::Path;
+use crate::core::{self, paths::CanvasPaths, MosaicError, MosaicResult};
+use crate::models::{
+    Tlp, WorkspaceConflict, WorkspaceData, WorkspaceEdge, WorkspaceNode, WorkspaceRevision,
+};
+use crate::services::{NodeFileService, NormalizeService, SecretService};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// One lock per canvas, so read-check-write cycles do not interleave
+fn canvas_lock(canvas_path: &Path) -> MosaicResult<Arc<Mutex<()>>> {
+    static LOCKS: OnceLock<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> = OnceLock::new();
+    let key = canvas_path
+        .canonicalize()
+        .unwrap_or_else(|_| canvas_path.to_path_buf());
+
+    let mut locks = LOCKS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .map_err(|_| MosaicError::io_error("Workspace lock poisoned"))?;
+    Ok(locks.entry(key).or_default().clone())
+}
 
 pub struct WorkspaceService;
 
 impl WorkspaceService {
     /// Load workspace data from canvas
+    ///
+    /// Nodes and edges come from their own files when workspace.json is the
+    /// frontend's manifest. A workspace edited outside the app (content no
+    /// longer matching its stored hash) moves one revision ahead, so stale
+    /// clients conflict.
     pub fn load(canvas_path: &Path) -> MosaicResult<WorkspaceData> {
-        let canvas_paths = CanvasPaths::from_root(&canvas_path.to_path_buf());
+        Self::with_lock(canvas_path, || Self::read(canvas_path))
+    }
+
+    /// Run `f` while holding the lock saves of this canvas take
+    pub fn with_lock<T>(
+        canvas_path: &Path,
+        f: impl FnOnce() -> MosaicResult<T>,
+    ) -> MosaicResult<T> {
+        let lock = canvas_lock(canvas_path)?;
+        let _guard = lock
+            .lock()
+            .map_err(|_| MosaicError::io_error("Workspace lock poisoned"))?;
+        f()
+    }
+
+    /// Load without taking the canvas lock; callers hold it
+    fn read(canvas_path: &Path) -> MosaicResult<WorkspaceData> {
+        let canvas_paths = CanvasPaths::from_root(canvas_path);
+
+        if !canvas_paths.workspace_json.exists() {
+            return Ok(WorkspaceData::new());
+        }
 
-        if canvas_paths.workspace_json.exists() {
-            core::read_json(&canvas_paths.workspace_json)
+        let doc: Value = core::read_json(&canvas_paths.workspace_json)?;
+        let is_manifest = NodeFileService::is_manifest(&doc);
+        let mut data = if is_manifest {
+            NodeFileService::read(&canvas_paths)?
         } else {
-            Ok(WorkspaceData::new())
+            serde_json::from_value(doc)?
+        };
+
+        // Persist the bump so the next load does not count the edit again
+        if data.is_modified_externally() {
+            data.revision += 1;
+            data.content_hash = Some(data.compute_hash());
+            if is_manifest {
+                NodeFileService::write_revision(&canvas_paths, &data)?;
+            } else {
+                core::write_json(&canvas_paths.workspace_json, &data)?;
+            }
         }
+        Ok(data)
     }
 
-    /// Save workspace data to canvas
-    pub fn save(canvas_path: &Path, data: &WorkspaceData) -> MosaicResult<()> {
-        let canvas_paths = CanvasPaths::from_root(&canvas_path.to_path_buf());
-        core::write_json(&canvas_paths.workspace_json, data)
+    /// Save workspace data to canvas, overwriting whatever is on disk
+    pub fn save(canvas_path: &Path, data: &WorkspaceData) -> MosaicResult<WorkspaceRevision> {
+        Self::save_checked(canvas_path, data, None)
+    }
+
+    /// Save workspace data, failing with a conflict if the on-disk revision
+    /// differs from `expected_revision`
+    pub fn save_checked(
+        canvas_path: &Path,
+        data: &WorkspaceData,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        let data = data.clone();
+        Self::modify(canvas_path, expected_revision, |current| {
+            *current = WorkspaceData {
+                revision: current.revision,
+                ..data
+            }
+        })
+    }
+
+    /// Local and on-disk versions of a workspace that failed to save
+    pub fn conflict(
+        canvas_path: &Path,
+        local: WorkspaceData,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceConflict> {
+        Ok(WorkspaceConflict {
+            canvas_path: canvas_path.to_string_lossy().to_string(),
+            expected_revision,
+            local,
+            remote: Self::load(canvas_path)?,
+        })
     }
 
     /// Update nodes only (merge operation)
-    pub fn update_nodes(canvas_path: &Path, nodes: Vec<WorkspaceNode>) -> MosaicResult<()> {
-        let mut data = Self::load(canvas_path)?;
-        data.nodes = nodes;
-        Self::save(canvas_path, &data)
+    pub fn update_nodes(
+        canvas_path: &Path,
+        nodes: Vec<WorkspaceNode>,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::modify(canvas_path, expected_revision, |data| data.nodes = nodes)
     }
 
     /// Update edges only (merge operation)
-    pub fn update_edges(canvas_path: &Path, edges: Vec<WorkspaceEdge>) -> MosaicResult<()> {
-        let mut data = Self::load(canvas_path)?;
-        data.edges = edges;
-        Self::save(canvas_path, &data)
+    pub fn update_edges(
+        canvas_path: &Path,
+        edges: Vec<WorkspaceEdge>,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::modify(canvas_path, expected_revision, |data| data.edges = edges)
     }
 
     /// Add a single node
-    pub fn add_node(canvas_path: &Path, node: WorkspaceNode) -> MosaicResult<()> {
-        let mut data = Self::load(canvas_path)?;
-        data.add_node(node);
-        Self::save(canvas_path, &data)
+    pub fn add_node(
+        canvas_path: &Path,
+        node: WorkspaceNode,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::modify(canvas_path, expected_revision, |data| data.add_node(node))
     }
 
     /// Remove a single node
-    pub fn remove_node(canvas_path: &Path, node_id: &str) -> MosaicResult<()> {
-        let mut data = Self::load(canvas_path)?;
-        data.remove_node(node_id);
-        Self::save(canvas_path, &data)
+    pub fn remove_node(
+        canvas_path: &Path,
+        node_id: &str,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::modify(canvas_path, expected_revision, |data| {
+            data.remove_node(node_id)
+        })
+    }
+
+    /// Set or clear the TLP classification of nodes
+    pub fn classify_nodes(
+        canvas_path: &Path,
+        node_ids: &[String],
+        classification: Option<Tlp>,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::modify(canvas_path, expected_revision, |data| {
+            for node in data.nodes.iter_mut().filter(|n| node_ids.contains(&n.id)) {
+                node.classification = classification;
+            }
+        })
+    }
+
+    /// Merge fields into one node's data, replacing existing values
+    pub fn update_node_data(
+        canvas_path: &Path,
+        node_id: &str,
+        fields: HashMap<String, Value>,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::update(canvas_path, expected_revision, |data| {
+            let node = data
+                .nodes
+                .iter_mut()
+                .find(|n| n.id == node_id)
+                .ok_or_else(|| MosaicError::not_found(&format!("Node {}", node_id)))?;
+            node.data.extend(fields);
+            Ok(true)
+        })
+    }
+
+    /// Merge fields into the data of several nodes at once; a null value
+    /// removes the field. Unknown node IDs are skipped.
+    pub fn update_nodes_data(
+        canvas_path: &Path,
+        fields_by_node: HashMap<String, HashMap<String, Value>>,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::modify(canvas_path, expected_revision, |data| {
+            for node in &mut data.nodes {
+                let Some(fields) = fields_by_node.get(&node.id) else {
+                    continue;
+                };
+                for (key, value) in fields {
+                    if value.is_null() {
+                        node.data.remove(key);
+                    } else {
+                        node.data.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        })
     }
 
     /// Add a single edge
-    pub fn add_edge(canvas_path: &Path, edge: WorkspaceEdge) -> MosaicResult<()> {
-        let mut data = Self::load(canvas_path)?;
-        data.add_edge(edge);
-        Self::save(canvas_path, &data)
+    pub fn add_edge(
+        canvas_path: &Path,
+        edge: WorkspaceEdge,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::modify(canvas_path, expected_revision, |data| data.add_edge(edge))
     }
 
     /// Remove a single edge
-    pub fn remove_edge(canvas_path: &Path, edge_id: &str) -> MosaicResult<()> {
-        let mut data = Self::load(canvas_path)?;
-        data.remove_edge(edge_id);
-        Self::save(canvas_path, &data)
+    pub fn remove_edge(
+        canvas_path: &Path,
+        edge_id: &str,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::modify(canvas_path, expected_revision, |data| {
+            data.remove_edge(edge_id)
+        })
     }
 
     /// Batch update multiple nodes and edges
@@ -75,25 +235,201 @@ impl WorkspaceService {
         nodes_to_remove: Vec<String>,
         edges_to_add: Vec<WorkspaceEdge>,
         edges_to_remove: Vec<String>,
-    ) -> MosaicResult<()> {
-        let mut data = Self::load(canvas_path)?;
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::modify(canvas_path, expected_revision, |data| {
+            // Remove items first
+            for node_id in nodes_to_remove {
+                data.remove_node(&node_id);
+            }
+            for edge_id in edges_to_remove {
+                data.remove_edge(&edge_id);
+            }
 
-        // Remove items first
-        for node_id in nodes_to_remove {
-            data.remove_node(&node_id);
-        }
-        for edge_id in edges_to_remove {
-            data.remove_edge(&edge_id);
-        }
+            // Add new items
+            for node in nodes_to_add {
+                data.add_node(node);
+            }
+            for edge in edges_to_add {
+                data.add_edge(edge);
+            }
+        })
+    }
 
-        // Add new items
-        for node in nodes_to_add {
-            data.add_node(node);
-        }
-        for edge in edges_to_add {
-            data.add_edge(edge);
+    /// Current revision, failing with a conflict if it differs from
+    /// `expected_revision`; for changes that turned out to be empty
+    pub fn revision(
+        canvas_path: &Path,
+        expected_revision: Option<u64>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::with_lock(canvas_path, || {
+            let data = Self::read(canvas_path)?;
+            Self::check_revision(canvas_path, expected_revision, data.revision)?;
+            Ok(WorkspaceRevision {
+                revision: data.revision,
+                content_hash: data
+                    .content_hash
+                    .clone()
+                    .unwrap_or_else(|| data.compute_hash()),
+            })
+        })
+    }
+
+    /// Like `modify`, but `change` may fail and reports whether it changed
+    /// anything; nothing is written when it did not
+    pub fn update(
+        canvas_path: &Path,
+        expected_revision: Option<u64>,
+        change: impl FnOnce(&mut WorkspaceData) -> MosaicResult<bool>,
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::with_lock(canvas_path, || {
+            let mut data = Self::read(canvas_path)?;
+            Self::check_revision(canvas_path, expected_revision, data.revision)?;
+
+            if change(&mut data)? {
+                return Self::write(canvas_path, &mut data);
+            }
+            Ok(WorkspaceRevision {
+                revision: data.revision,
+                content_hash: data
+                    .content_hash
+                    .clone()
+                    .unwrap_or_else(|| data.compute_hash()),
+            })
+        })
+    }
+
+    /// Load, check the revision, apply a change and save, holding the
+    /// canvas lock throughout
+    fn modify(
+        canvas_path: &Path,
+        expected_revision: Option<u64>,
+        change: impl FnOnce(&mut WorkspaceData),
+    ) -> MosaicResult<WorkspaceRevision> {
+        Self::with_lock(canvas_path, || {
+            let mut data = Self::read(canvas_path)?;
+            Self::check_revision(canvas_path, expected_revision, data.revision)?;
+
+            change(&mut data);
+            Self::write(canvas_path, &mut data)
+        })
+    }
+
+    fn check_revision(
+        canvas_path: &Path,
+        expected_revision: Option<u64>,
+        current: u64,
+    ) -> MosaicResult<()> {
+        match expected_revision {
+            Some(expected) if expected != current => Err(MosaicError::conflict(format!(
+                "Workspace changed since revision {} (now at revision {})",
+                expected, current
+            ))
+            .with_context(canvas_path.to_string_lossy().to_string())),
+            _ => Ok(()),
         }
+    }
+
+    /// Bump the revision, stamp the content hash and write the manifest and
+    /// node files
+    fn write(canvas_path: &Path, data: &mut WorkspaceData) -> MosaicResult<WorkspaceRevision> {
+        let canvas_paths = CanvasPaths::from_root(canvas_path);
+
+        // Sensitive fields never reach disk in cleartext
+        SecretService::seal_workspace(canvas_path, data)?;
+        NormalizeService::normalize_workspace(data);
+        data.nodes
+            .iter_mut()
+            .for_each(NodeFileService::canonicalize);
+
+        data.revision += 1;
+        let content_hash = data.compute_hash();
+        data.content_hash = Some(content_hash.clone());
+        NodeFileService::write(&canvas_paths, data)?;
+
+        Ok(WorkspaceRevision {
+            revision: data.revision,
+            content_hash,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Position;
+    use crate::services::testing::TempCanvas;
+
+    fn note(text: &str) -> WorkspaceNode {
+        let data = HashMap::from([("content".to_string(), Value::from(text))]);
+        WorkspaceNode::new("note", Position { x: 0.0, y: 0.0 }, data)
+    }
+
+    #[test]
+    fn test_concurrent_checked_saves() {
+        let temp = TempCanvas::new();
+        let canvas = &temp.path;
+        let revision = WorkspaceService::load(canvas).unwrap().revision;
+
+        // Only one writer may win against the same expected revision
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let canvas = canvas.clone();
+                std::thread::spawn(move || {
+                    WorkspaceService::add_node(&canvas, note(&i.to_string()), Some(revision))
+                })
+            })
+            .collect();
+        let saved = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(Result::is_ok)
+            .count();
+
+        assert_eq!(saved, 1);
+        assert_eq!(WorkspaceService::load(canvas).unwrap().nodes.len(), 1);
+    }
+
+    #[test]
+    fn test_update_node_data_checks_the_node_under_the_lock() {
+        let temp = TempCanvas::new();
+        let canvas = &temp.path;
+        let node = note("before");
+        let id = node.id.clone();
+        let added = WorkspaceService::add_node(canvas, node, None).unwrap();
+
+        let fields = HashMap::from([("content".to_string(), Value::from("after"))]);
+        let err = WorkspaceService::update_node_data(canvas, "missing", fields.clone(), None)
+            .unwrap_err();
+        assert_eq!(err.code, crate::core::error::ErrorCode::NotFound);
+        assert_eq!(
+            WorkspaceService::load(canvas).unwrap().revision,
+            added.revision
+        );
+
+        WorkspaceService::update_node_data(canvas, &id, fields, Some(added.revision)).unwrap();
+        let data = WorkspaceService::load(canvas).unwrap();
+        assert_eq!(data.nodes[0].data["content"], "after");
+    }
+
+    #[test]
+    fn test_external_edit_bump_is_persisted() {
+        let temp = TempCanvas::new();
+        let canvas = &temp.path;
+        WorkspaceService::add_node(canvas, note("before"), None).unwrap();
+        let before = WorkspaceService::load(canvas).unwrap();
+
+        let paths = CanvasPaths::from_root(canvas);
+        let content = paths
+            .nodes
+            .join(&before.nodes[0].id)
+            .join("data")
+            .join("content");
+        std::fs::write(&content, "after").unwrap();
 
-        Self::save(canvas_path, &data)
+        let first = WorkspaceService::load(canvas).unwrap();
+        let second = WorkspaceService::load(canvas).unwrap();
+        assert_eq!(first.revision, before.revision + 1);
+        assert_eq!(second.revision, first.revision);
     }
 }
@@ -12,7 +12,8 @@ import {
   type CanvasEvent,
   type WorkspaceEvent,
   type StateEvent,
-  type HistoryEvent
+  type HistoryEvent,
+  type AuditFailedEvent
 } from './types';
 
 // Event callback types
@@ -21,6 +22,7 @@ export type CanvasEventCallback = (event: CanvasEvent) => void;
 export type WorkspaceEventCallback = (event: WorkspaceEvent) => void;
 export type StateEventCallback = (event: StateEvent) => void;
 export type HistoryEventCallback = (event: HistoryEvent) => void;
+export type AuditFailedEventCallback = (event: AuditFailedEvent) => void;
 
 // Generic event listener
 async function subscribeToEvent<T>(
@@ -92,6 +94,11 @@ export async function onHistoryChanged(callback: HistoryEventCallback): Promise<
   return subscribeToEvent(EventNames.HISTORY_CHANGED, callback);
 }
 
+// Audit event subscriptions
+export async function onAuditFailed(callback: AuditFailedEventCallback): Promise<UnlistenFn> {
+  return subscribeToEvent(EventNames.AUDIT_FAILED, callback);
+}
+
 // Subscribe to all vault events
 export async function onAnyVaultEvent(callback: VaultEventCallback): Promise<UnlistenFn[]> {
   return Promise.all([
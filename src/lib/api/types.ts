@@ -17,6 +17,8 @@ export interface VaultInfo {
   created_at: string;
   updated_at: string;
   canvas_count: number;
+  // vault.json is behind the current schema; migrate_vault upgrades it
+  needs_migration?: boolean;
 }
 
 export interface VaultRef {
@@ -196,6 +198,13 @@ export interface HistoryEvent {
   canvas_count: number;
 }
 
+/** Error of an audit entry that could not be written for a committed operation */
+export interface AuditFailedEvent {
+  code: string;
+  message: string;
+  context?: string;
+}
+
 // ============================================================================
 // EVENT NAMES (matching Rust constants)
 // ============================================================================
@@ -222,4 +231,7 @@ export const EventNames = {
   // State events
   STATE_CHANGED: 'state:changed',
   HISTORY_CHANGED: 'history:changed',
+
+  // Audit events
+  AUDIT_FAILED: 'audit:failed',
 } as const;
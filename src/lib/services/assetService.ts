@@ -0,0 +1,34 @@
+// Asset Service
+// Stable asset://<sha256> references point into the open vault's asset
+// store; the backend resolves them to a blob path the webview can load
+
+import { convertFileSrc, invoke } from '@tauri-apps/api/core';
+
+// A blob in the vault's asset store
+export interface AssetInfo {
+  hash: string;
+  path: string;
+  uri: string;
+  size: number;
+  mime_type: string;
+}
+
+const ASSET_URI = /^asset:\/\/[0-9a-f]{64}\/?$/i;
+
+// Whether a node value is a stable asset reference (not a convertFileSrc URL)
+export function isAssetUri(value: string): boolean {
+  return ASSET_URI.test(value);
+}
+
+// Find the blob an asset:// reference points to in the vault
+export async function resolveAsset(vaultPath: string, uri: string): Promise<AssetInfo> {
+  return invoke<AssetInfo>('resolve_asset', { vaultPath, uri });
+}
+
+// URL to display an image value with: asset references are resolved in the
+// vault and converted for the asset protocol, anything else is kept as is
+export async function resolveImageSrc(vaultPath: string | null, value: string): Promise<string> {
+  if (!vaultPath || !isAssetUri(value)) return value;
+  const asset = await resolveAsset(vaultPath, value);
+  return convertFileSrc(asset.path);
+}
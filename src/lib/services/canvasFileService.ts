@@ -0,0 +1,58 @@
+// Canvas File Service
+// Reads and writes node, edge, manifest and state files through the backend,
+// which encrypts and decrypts them when the vault is encrypted
+
+import { invoke } from '@tauri-apps/api/core';
+
+// While set, writes are dropped: the canvas is showing redacted export data
+// that must never be saved over the real files
+let writesHeld = false;
+
+export function holdWrites(held: boolean) {
+  writesHeld = held;
+}
+
+// Read a canvas file (path relative to the canvas); null if it does not exist
+export async function readCanvasFile(canvasPath: string, relativePath: string): Promise<string | null> {
+  return invoke<string | null>('read_canvas_file', { canvasPath, relativePath });
+}
+
+// Write a canvas file (path relative to the canvas)
+export async function writeCanvasFile(canvasPath: string, relativePath: string, content: string): Promise<void> {
+  if (writesHeld) return;
+  await invoke('write_canvas_file', { canvasPath, relativePath, content });
+}
+
+// Write a node's content and/or properties.json; sensitive fields are sealed
+// before they reach the disk
+export async function writeNodeFiles(
+  canvasPath: string,
+  node: { id: string; type?: string },
+  files: { content?: string; properties?: string }
+): Promise<void> {
+  if (writesHeld) return;
+  await invoke('write_node_files', {
+    canvasPath,
+    nodeId: node.id,
+    nodeType: node.type ?? '',
+    content: files.content ?? null,
+    properties: files.properties ?? null,
+  });
+}
+
+// Canvas data as it leaves the vault: classification limits and the vault's
+// redaction profile applied by the backend
+export interface CanvasExport {
+  format: string;
+  exported_at: string;
+  canvas: Record<string, unknown>;
+  workspace: {
+    nodes: { id: string; type: string; data: Record<string, unknown> }[];
+    edges: { id: string; label?: string | null }[];
+    [key: string]: unknown;
+  };
+}
+
+export async function prepareCanvasExport(canvasPath: string): Promise<CanvasExport> {
+  return invoke<CanvasExport>('prepare_canvas_export', { canvasPath, options: null });
+}
@@ -3,6 +3,7 @@
 // Each edge has its own folder with connection data
 
 import type { MosaicEdge } from '$lib/types';
+import { readCanvasFile, writeCanvasFile } from './canvasFileService';
 import { MarkerType, type EdgeMarker } from '@xyflow/svelte';
 
 // Migration helper: Convert old handle IDs to new format
@@ -42,30 +43,6 @@ function getEdgeFolderPath(edgeId: string): string {
   return `${workspacePath}/edges/${edgeId}`;
 }
 
-// Ensure edge folder structure exists
-async function ensureEdgeFolder(edgeId: string) {
-  if (!workspacePath) return;
-  
-  const { mkdir, exists } = await import('@tauri-apps/plugin-fs');
-  const edgePath = getEdgeFolderPath(edgeId);
-  
-  if (!(await exists(edgePath))) {
-    await mkdir(edgePath, { recursive: true });
-  }
-}
-
-// Ensure edges parent folder exists
-async function ensureEdgesFolder() {
-  if (!workspacePath) return;
-  
-  const { mkdir, exists } = await import('@tauri-apps/plugin-fs');
-  const edgesPath = `${workspacePath}/edges`;
-  
-  if (!(await exists(edgesPath))) {
-    await mkdir(edgesPath, { recursive: true });
-  }
-}
-
 // Extract edge connection data (NDJSON format)
 function extractEdgeData(edge: MosaicEdge): object {
   return {
@@ -83,6 +60,7 @@ function extractEdgeData(edge: MosaicEdge): object {
 // Save edge to file (debounced)
 export function saveEdge(edge: MosaicEdge) {
   if (!workspacePath) return;
+  const canvasPath = workspacePath;
   
   // Clear existing timer
   if (edgeTimers.has(edge.id)) {
@@ -92,15 +70,10 @@ export function saveEdge(edge: MosaicEdge) {
   // Set new debounced timer
   edgeTimers.set(edge.id, setTimeout(async () => {
     try {
-      await ensureEdgesFolder();
-      await ensureEdgeFolder(edge.id);
-      const { writeTextFile } = await import('@tauri-apps/plugin-fs');
-      
       const edgeData = extractEdgeData(edge);
-      const joinedPath = `${getEdgeFolderPath(edge.id)}/joined.json`;
       
       // Write as NDJSON (single line JSON for this edge)
-      await writeTextFile(joinedPath, JSON.stringify(edgeData));
+      await writeCanvasFile(canvasPath, `edges/${edge.id}/joined.json`, JSON.stringify(edgeData));
       edgeTimers.delete(edge.id);
     } catch (error) {
       console.error(`Error saving edge ${edge.id}:`, error);
@@ -113,12 +86,8 @@ export async function saveEdgeImmediate(edge: MosaicEdge) {
   if (!workspacePath) return;
   
   try {
-    await ensureEdgesFolder();
-    await ensureEdgeFolder(edge.id);
-    const { writeTextFile } = await import('@tauri-apps/plugin-fs');
-    
     const edgeData = extractEdgeData(edge);
-    await writeTextFile(`${getEdgeFolderPath(edge.id)}/joined.json`, JSON.stringify(edgeData));
+    await writeCanvasFile(workspacePath, `edges/${edge.id}/joined.json`, JSON.stringify(edgeData));
   } catch (error) {
     console.error(`Error saving edge ${edge.id}:`, error);
   }
@@ -227,16 +196,11 @@ export async function loadEdge(edgeId: string): Promise<MosaicEdge | null> {
   if (!workspacePath) return null;
   
   try {
-    const { readTextFile, exists } = await import('@tauri-apps/plugin-fs');
-    
-    const joinedPath = `${getEdgeFolderPath(edgeId)}/joined.json`;
-    
-    if (!(await exists(joinedPath))) {
+    const content = await readCanvasFile(workspacePath, `edges/${edgeId}/joined.json`);
+    if (content === null) {
       console.warn(`Edge file not found for ${edgeId}`);
       return null;
     }
-    
-    const content = await readTextFile(joinedPath);
     const edgeData = JSON.parse(content);
     
     // Migrate old handle IDs to new format if needed
@@ -306,6 +270,11 @@ export async function loadAllEdges(): Promise<MosaicEdge[]> {
   }
 }
 
+// Whether debounced saves are still waiting to be written
+export function hasPendingSaves(): boolean {
+  return edgeTimers.size > 0;
+}
+
 // Flush all pending saves
 export async function flushPendingSaves() {
   for (const [edgeId, timer] of edgeTimers) {
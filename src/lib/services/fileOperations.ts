@@ -8,6 +8,7 @@ import { toPng, toSvg } from 'html-to-image';
 import { toast } from 'svelte-sonner';
 import { loadAllNodes } from './nodeFileService';
 import { loadAllEdges } from './edgeFileService';
+import { readCanvasFile } from './canvasFileService';
 
 // Workspace manifest format (v2 - minimal)
 interface WorkspaceManifest {
@@ -27,16 +28,13 @@ interface WorkspaceManifest {
 // Load workspace from file (v2 format with individual node/edge files)
 export async function loadWorkspace(path: string): Promise<boolean> {
   try {
-    const { readTextFile, exists } = await import('@tauri-apps/plugin-fs');
-    
     // Load workspace.json (manifest)
-    const workspacePath = `${path}/workspace.json`;
-    if (!(await exists(workspacePath))) {
+    const workspaceContent = await readCanvasFile(path, 'workspace.json');
+    if (workspaceContent === null) {
       console.error('workspace.json not found');
       return false;
     }
     
-    const workspaceContent = await readTextFile(workspacePath);
     const manifest: WorkspaceManifest = JSON.parse(workspaceContent);
     
     // Check version to determine loading strategy
@@ -97,11 +95,9 @@ export async function loadWorkspace(path: string): Promise<boolean> {
     }
     
     // Load state.json if exists (for viewport and selection state)
-    const statePath = `${path}/.mosaic/state.json`;
     try {
-      const stateExists = await exists(statePath);
-      if (stateExists) {
-        const stateContent = await readTextFile(statePath);
+      const stateContent = await readCanvasFile(path, '.mosaic/state.json');
+      if (stateContent !== null) {
         const uiState: UIState = JSON.parse(stateContent);
         workspace.loadUIState(uiState);
       }
@@ -175,7 +171,18 @@ export async function openWorkspaceDialog(): Promise<string | null> {
 }
 
 // Export workspace as ZIP/JSON
+// Built from the redacted export view
 export async function exportAsZip(): Promise<boolean> {
+  return workspace.withExportView(exportViewAsZip).catch(error => {
+    console.error('Error preparing workspace export:', error);
+    toast.error('Failed to export workspace', {
+      description: error instanceof Error ? error.message : String(error)
+    });
+    return false;
+  });
+}
+
+async function exportViewAsZip(): Promise<boolean> {
   // Export full workspace data including all node content
   try {
     // Build full export with all node and edge data
@@ -363,7 +370,18 @@ async function generateSvgDataUrl(): Promise<{ dataUrl: string; svgContent: stri
 
 // Export canvas as high-resolution PNG using DOM capture
 // Strategy: Use fitView to position content correctly, then capture the viewport
+// Captured from the redacted export view
 export async function exportAsPng(): Promise<boolean> {
+  return workspace.withExportView(exportViewAsPng).catch(error => {
+    console.error('Error preparing PNG export:', error);
+    toast.error('Failed to export PNG', {
+      description: error instanceof Error ? error.message : String(error)
+    });
+    return false;
+  });
+}
+
+async function exportViewAsPng(): Promise<boolean> {
   console.log('Starting high-res PNG export...');
   
   // Show loading toast
@@ -698,7 +716,18 @@ export async function exportAsPng(): Promise<boolean> {
 // 1. Calculate bounding box of all nodes
 // 2. Capture the full viewport DOM as SVG  
 // 3. Use viewBox to clip to just the content area
+// Captured from the redacted export view
 export async function exportAsSvg(): Promise<boolean> {
+  return workspace.withExportView(exportViewAsSvg).catch(error => {
+    console.error('Error preparing SVG export:', error);
+    toast.error('Failed to export SVG', {
+      description: error instanceof Error ? error.message : String(error)
+    });
+    return false;
+  });
+}
+
+async function exportViewAsSvg(): Promise<boolean> {
   console.log('Starting SVG export with exact bounds...');
   
   const toastId = toast.loading('Preparing SVG export...', {
@@ -3,6 +3,7 @@
 // Each node has its own folder with content and properties files
 
 import type { MosaicNode, MosaicNodeData, NodeType } from '$lib/types';
+import { readCanvasFile, writeNodeFiles } from './canvasFileService';
 
 // Debounce timers for each node
 const contentTimers = new Map<string, ReturnType<typeof setTimeout>>();
@@ -24,21 +25,9 @@ function getNodeFolderPath(nodeId: string): string {
   return `${workspacePath}/nodes/${nodeId}`;
 }
 
-// Get the data folder path for a node
-function getDataFolderPath(nodeId: string): string {
-  return `${getNodeFolderPath(nodeId)}/data`;
-}
-
-// Ensure node folder structure exists
-async function ensureNodeFolder(nodeId: string) {
-  if (!workspacePath) return;
-  
-  const { mkdir, exists } = await import('@tauri-apps/plugin-fs');
-  const dataPath = getDataFolderPath(nodeId);
-  
-  if (!(await exists(dataPath))) {
-    await mkdir(dataPath, { recursive: true });
-  }
+// Get the data folder path for a node, relative to the workspace
+function getDataFolderRelative(nodeId: string): string {
+  return `nodes/${nodeId}/data`;
 }
 
 // Extract content from node data based on node type
@@ -165,6 +154,7 @@ function extractProperties(node: MosaicNode): object {
     parentId: node.parentId,
     extent: node.extent,
     expandParent: node.expandParent,
+    classification: node.classification,
     data: dataProps,
   };
 }
@@ -172,6 +162,7 @@ function extractProperties(node: MosaicNode): object {
 // Save node content to file (debounced)
 export function saveNodeContent(node: MosaicNode) {
   if (!workspacePath) return;
+  const canvasPath = workspacePath;
   
   // Clear existing timer
   if (contentTimers.has(node.id)) {
@@ -181,13 +172,8 @@ export function saveNodeContent(node: MosaicNode) {
   // Set new debounced timer
   contentTimers.set(node.id, setTimeout(async () => {
     try {
-      await ensureNodeFolder(node.id);
-      const { writeTextFile } = await import('@tauri-apps/plugin-fs');
-      
       const content = extractContent(node.type as NodeType, node.data);
-      const contentPath = `${getDataFolderPath(node.id)}/content`;
-      
-      await writeTextFile(contentPath, content);
+      await writeNodeFiles(canvasPath, node, { content });
       contentTimers.delete(node.id);
     } catch (error) {
       console.error(`Error saving content for node ${node.id}:`, error);
@@ -198,6 +184,7 @@ export function saveNodeContent(node: MosaicNode) {
 // Save node properties to file (debounced)
 export function saveNodeProperties(node: MosaicNode) {
   if (!workspacePath) return;
+  const canvasPath = workspacePath;
   
   // Clear existing timer
   if (propertiesTimers.has(node.id)) {
@@ -207,13 +194,8 @@ export function saveNodeProperties(node: MosaicNode) {
   // Set new debounced timer
   propertiesTimers.set(node.id, setTimeout(async () => {
     try {
-      await ensureNodeFolder(node.id);
-      const { writeTextFile } = await import('@tauri-apps/plugin-fs');
-      
       const properties = extractProperties(node);
-      const propsPath = `${getDataFolderPath(node.id)}/properties.json`;
-      
-      await writeTextFile(propsPath, JSON.stringify(properties, null, 2));
+      await writeNodeFiles(canvasPath, node, { properties: JSON.stringify(properties, null, 2) });
       propertiesTimers.delete(node.id);
     } catch (error) {
       console.error(`Error saving properties for node ${node.id}:`, error);
@@ -226,16 +208,12 @@ export async function saveNodeImmediate(node: MosaicNode) {
   if (!workspacePath) return;
   
   try {
-    await ensureNodeFolder(node.id);
-    const { writeTextFile } = await import('@tauri-apps/plugin-fs');
-    
-    // Save content
     const content = extractContent(node.type as NodeType, node.data);
-    await writeTextFile(`${getDataFolderPath(node.id)}/content`, content);
-    
-    // Save properties
     const properties = extractProperties(node);
-    await writeTextFile(`${getDataFolderPath(node.id)}/properties.json`, JSON.stringify(properties, null, 2));
+    await writeNodeFiles(workspacePath, node, {
+      content,
+      properties: JSON.stringify(properties, null, 2),
+    });
   } catch (error) {
     console.error(`Error saving node ${node.id}:`, error);
   }
@@ -272,27 +250,18 @@ export async function loadNode(nodeId: string, type: NodeType): Promise<MosaicNo
   if (!workspacePath) return null;
   
   try {
-    const { readTextFile, exists } = await import('@tauri-apps/plugin-fs');
+    const dataPath = getDataFolderRelative(nodeId);
     
-    const dataPath = getDataFolderPath(nodeId);
-    const contentPath = `${dataPath}/content`;
-    const propsPath = `${dataPath}/properties.json`;
-    
-    // Check if properties file exists
-    if (!(await exists(propsPath))) {
+    // Load properties
+    const propsContent = await readCanvasFile(workspacePath, `${dataPath}/properties.json`);
+    if (propsContent === null) {
       console.warn(`Properties file not found for node ${nodeId}`);
       return null;
     }
-    
-    // Load properties
-    const propsContent = await readTextFile(propsPath);
     const properties = JSON.parse(propsContent);
     
     // Load content
-    let content = '';
-    if (await exists(contentPath)) {
-      content = await readTextFile(contentPath);
-    }
+    const content = (await readCanvasFile(workspacePath, `${dataPath}/content`)) ?? '';
     
     // Reconstruct node data with content
     const data = { ...properties.data };
@@ -309,6 +278,7 @@ export async function loadNode(nodeId: string, type: NodeType): Promise<MosaicNo
       parentId: properties.parentId,
       extent: properties.extent,
       expandParent: properties.expandParent,
+      classification: properties.classification,
       data: data as MosaicNodeData,
     };
     
@@ -416,6 +386,11 @@ export async function loadAllNodes(nodesManifest: Record<string, { type: NodeTyp
   return nodes;
 }
 
+// Whether debounced saves are still waiting to be written
+export function hasPendingSaves(): boolean {
+  return contentTimers.size > 0 || propertiesTimers.size > 0;
+}
+
 // Flush all pending saves (call before closing)
 export async function flushPendingSaves() {
   // Wait for all pending content saves
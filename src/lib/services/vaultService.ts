@@ -12,6 +12,8 @@ export interface VaultInfo {
   created_at: string;
   updated_at: string;
   canvas_count: number;
+  // vault.json is behind the current schema; migrate_vault upgrades it
+  needs_migration?: boolean;
 }
 
 export interface CanvasInfo {
@@ -23,6 +25,8 @@ export interface CanvasInfo {
   created_at: string;
   updated_at: string;
   tags: string[];
+  // Files are behind the current schema; migrate_canvas upgrades them
+  needs_migration?: boolean;
 }
 
 export interface AppConfig {
@@ -12,6 +12,7 @@
  */
 
 import * as api from '$lib/api';
+import { toast } from 'svelte-sonner';
 import type { 
   AppState, 
   AppHistory, 
@@ -112,6 +113,12 @@ function createAppStore() {
         state.history = await api.history.load();
       });
       unlisteners.push(unlistenHistory);
+
+      // Operations whose audit entry could not be written still went through
+      const unlistenAudit = await api.events.onAuditFailed((error) => {
+        toast.error('Audit entry not recorded', { description: error.message });
+      });
+      unlisteners.push(unlistenAudit);
     } catch (err) {
       console.error('[AppStore] Event subscription error:', err);
     }
@@ -24,6 +24,7 @@ import {
   saveNodeImmediate,
   deleteNodeFolder,
   resetNodeFileService,
+  hasPendingSaves as hasPendingNodeSaves,
 } from '$lib/services/nodeFileService';
 import { 
   getNodeDimensions, 
@@ -36,7 +37,9 @@ import {
   saveEdgeImmediate,
   deleteEdgeFolder,
   resetEdgeFileService,
+  hasPendingSaves as hasPendingEdgeSaves,
 } from '$lib/services/edgeFileService';
+import { holdWrites, prepareCanvasExport, writeCanvasFile } from '$lib/services/canvasFileService';
 
 // Reactive state using Svelte 5 runes
 class WorkspaceStore {
@@ -285,11 +288,17 @@ class WorkspaceStore {
         y: node.position.y + 50,
       };
       
+      const data = JSON.parse(JSON.stringify(node.data)); // Deep clone data
+      // Sealed secrets only open on the node they were sealed for
+      for (const [key, value] of Object.entries(data)) {
+        if (typeof value === 'string' && value.startsWith('mfsecret:')) delete data[key];
+      }
+
       const newNode: MosaicNode = {
         id,
         type: node.type,
         position: newPosition,
-        data: JSON.parse(JSON.stringify(node.data)), // Deep clone data
+        data,
         width: node.width,
         height: node.height,
         zIndex: node.zIndex,
@@ -817,6 +826,38 @@ class WorkspaceStore {
     }
   }
 
+  // Show the canvas as it will be exported (the backend's redacted data)
+  // while `render` captures it, then put the real nodes and edges back.
+  // Nothing is saved in the meantime.
+  async withExportView<T>(render: () => Promise<T>): Promise<T> {
+    if (!this.workspacePath) return render();
+
+    // Let debounced saves land so the backend exports the current state
+    for (let waited = 0; (hasPendingNodeSaves() || hasPendingEdgeSaves()) && waited < 3000; waited += 50) {
+      await new Promise(resolve => setTimeout(resolve, 50));
+    }
+    const exported = await prepareCanvasExport(this.workspacePath);
+    const exportedNodes = new Map(exported.workspace.nodes.map(node => [node.id, node]));
+    const exportedEdges = new Map(exported.workspace.edges.map(edge => [edge.id, edge]));
+
+    const nodes = this.nodes;
+    const edges = this.edges;
+    holdWrites(true);
+    try {
+      this.nodes = nodes
+        .filter(node => exportedNodes.has(node.id))
+        .map(node => ({ ...node, data: exportedNodes.get(node.id)!.data as MosaicNodeData }));
+      this.edges = edges
+        .filter(edge => exportedEdges.has(edge.id))
+        .map(edge => ({ ...edge, label: exportedEdges.get(edge.id)!.label ?? undefined }));
+      return await render();
+    } finally {
+      this.nodes = nodes;
+      this.edges = edges;
+      holdWrites(false);
+    }
+  }
+
   // Mark workspace as modified
   private markModified() {
     this.isModified = true;
@@ -861,8 +902,6 @@ class WorkspaceStore {
     if (!this.workspacePath) return;
     
     try {
-      const { writeTextFile } = await import('@tauri-apps/plugin-fs');
-      
       // Create minimal node manifest (just id and type)
       const nodesManifest: Record<string, { id: string; type: string }> = {};
       this.nodes.forEach(node => {
@@ -892,10 +931,7 @@ class WorkspaceStore {
         edges: edgesManifest,
       };
       
-      await writeTextFile(
-        `${this.workspacePath}/workspace.json`,
-        JSON.stringify(manifest, null, 2)
-      );
+      await writeCanvasFile(this.workspacePath, 'workspace.json', JSON.stringify(manifest, null, 2));
     } catch (error) {
       console.error('Error saving workspace manifest:', error);
     }
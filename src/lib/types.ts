@@ -39,6 +39,8 @@ export interface BaseNodeData {
   showHeader?: boolean;
   locked?: boolean;
   sizeLocked?: boolean;
+  // Canonical forms of indicator fields, kept up to date by the backend on save
+  normalized?: Record<string, string>;
   [key: string]: unknown;
 }
 
@@ -115,6 +117,20 @@ export interface OrganizationNodeData extends BaseNodeData {
   size?: string;
 }
 
+// Offline GeoIP/ASN lookup result, set by the backend on router and domain nodes
+export interface GeoIpInfo {
+  ip: string;
+  country?: string;
+  countryCode?: string;
+  region?: string;
+  city?: string;
+  latitude?: number;
+  longitude?: number;
+  accuracyRadius?: number;
+  asn?: number;
+  organization?: string;
+}
+
 export interface DomainNodeData extends BaseNodeData {
   domain: string;
   registrar?: string;
@@ -127,6 +143,7 @@ export interface DomainNodeData extends BaseNodeData {
   ip?: string;
   created?: string;
   expires?: string;
+  geoip?: GeoIpInfo;
 }
 
 export interface HashNodeData extends BaseNodeData {
@@ -134,6 +151,8 @@ export interface HashNodeData extends BaseNodeData {
   algorithm: 'md5' | 'sha1' | 'sha256' | 'sha512' | 'other';
   filename?: string;
   threatLevel?: 'unknown' | 'safe' | 'suspicious' | 'malicious';
+  // Hash set the threat level was taken from; unset when classified by hand
+  hashSet?: string;
   virusTotalUrl?: string;
   // Alternative properties for component compatibility
   type?: string;
@@ -152,6 +171,8 @@ export interface CredentialNodeData extends BaseNodeData {
   service?: string;
   password?: string;
   compromised?: boolean;
+  // Times the password appears in the vault's breached-password dataset
+  breachCount?: number;
 }
 
 export interface SocialPostNodeData extends BaseNodeData {
@@ -212,6 +233,7 @@ export interface RouterNodeData extends BaseNodeData {
   vendor?: string;
   status?: 'online' | 'offline' | 'unknown';
   ports?: number[];
+  geoip?: GeoIpInfo;
 }
 
 // Link item for LinkListNode
@@ -298,6 +320,8 @@ export interface MosaicNode extends Node {
   data: MosaicNodeData;
   width?: number;
   height?: number;
+  // TLP marking set by the backend (unmarked nodes inherit the canvas's)
+  classification?: 'clear' | 'green' | 'amber' | 'amber+strict' | 'red';
 }
 
 // Edge types